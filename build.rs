@@ -2,64 +2,726 @@
 
 use std::fs::File;
 use std::io::{BufWriter, Write};
+use std::path::Path;
 
+use sha2::{Digest, Sha256};
+use shapefile::dbase::FieldValue;
 use shapefile::PolygonRing;
 
+// Shared with the crate so the data is encoded as it is decoded
+#[allow(dead_code)]
+#[path = "src/blob.rs"]
+mod blob;
+#[allow(dead_code)]
+#[path = "src/geo.rs"]
+mod geo;
+
+use geo::GeoPoint;
+
 const DATA_FILENAME: &str = "src/data.rs";
-const COASTLINE_SHAPEFILE_FILENAME: &str = "data/ne_110m_coastline/ne_110m_coastline.shp";
+// Golden images of rendering, embedded in the `testing` module for tests in the browser
+const GOLDEN_DIRECTORY: &str = "tests/golden";
+const GOLDEN_FILENAME: &str = "golden.rs";
+// Resolutions of the coastline, from the coarsest, with the features selecting them; the
+// finest selected is embedded, and the 110m coastline without any
+const COASTLINE_RESOLUTIONS: &[(&str, &str)] = &[
+    ("110m", "CARGO_FEATURE_COASTLINE_110M"),
+    ("50m", "CARGO_FEATURE_COASTLINE_50M"),
+    ("10m", "CARGO_FEATURE_COASTLINE_10M"),
+];
+// Resolution of the detailed coastline, drawn when zoomed in on a coarser coastline
+const DETAILED_COASTLINE_RESOLUTION: &str = "50m";
+const COUNTRY_SHAPEFILE_FILENAME: &str =
+    "data/ne_110m_admin_0_countries/ne_110m_admin_0_countries.shp";
+const LAND_SHAPEFILE_FILENAME: &str = "data/ne_110m_land/ne_110m_land.shp";
+// Files of a shapefile hashed for the information about its dataset, in order
+const SHAPEFILE_EXTENSIONS: [&str; 4] = ["shp", "shx", "dbf", "prj"];
+const COUNTRY_NAME_FIELD: &str = "NAME";
+// Fields of the localized names of the countries, by language, with the `localized-names`
+// feature
+const COUNTRY_LOCALIZED_NAME_FIELDS: &[(&str, &str)] = &[
+    ("ar", "NAME_AR"),
+    ("bn", "NAME_BN"),
+    ("de", "NAME_DE"),
+    ("el", "NAME_EL"),
+    ("en", "NAME_EN"),
+    ("es", "NAME_ES"),
+    ("fa", "NAME_FA"),
+    ("fr", "NAME_FR"),
+    ("he", "NAME_HE"),
+    ("hi", "NAME_HI"),
+    ("hu", "NAME_HU"),
+    ("id", "NAME_ID"),
+    ("it", "NAME_IT"),
+    ("ja", "NAME_JA"),
+    ("ko", "NAME_KO"),
+    ("nl", "NAME_NL"),
+    ("pl", "NAME_PL"),
+    ("pt", "NAME_PT"),
+    ("ru", "NAME_RU"),
+    ("sv", "NAME_SV"),
+    ("tr", "NAME_TR"),
+    ("uk", "NAME_UK"),
+    ("ur", "NAME_UR"),
+    ("vi", "NAME_VI"),
+    ("zh", "NAME_ZH"),
+    ("zht", "NAME_ZHT"),
+];
+// Fields of the features' records embedded as attributes, for filtering
+const COASTLINE_ATTRIBUTE_FIELDS: &[&str] = &["scalerank", "featurecla", "min_zoom"];
+const COUNTRY_ATTRIBUTE_FIELDS: &[&str] =
+    &["scalerank", "LABELRANK", "CONTINENT", "POP_EST", "GDP_MD"];
+const LAND_ATTRIBUTE_FIELDS: &[&str] = &["scalerank", "featurecla", "min_zoom"];
+// Fractional bits of the fixed-point coordinates; at most 23 for longitudes to fit
+const COORDINATE_SHIFT: u8 = 23;
+// Variables of the environment simplifying the lines and rings of each dataset, by a tolerance
+// (degrees) or to a budget of points, whichever simplifies more
+const SIMPLIFY_TOLERANCE_VARIABLE: &str = "WASM_GLOBE_SIMPLIFY_TOLERANCE";
+const VERTEX_BUDGET_VARIABLE: &str = "WASM_GLOBE_VERTEX_BUDGET";
+// Steps of the search for the tolerance meeting a budget of points
+const TOLERANCE_SEARCH_STEPS: usize = 32;
+
+/// Parts of a feature (degrees, as longitude and latitude), and whether they are closed rings.
+type FeatureParts = (Vec<Vec<(f64, f64)>>, bool);
+
+/// Counts of the data written for a shapefile.
+struct DataCounts {
+    features: usize,
+    points: usize,
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Declaring the variables read replaces the default of rerunning on any change, so the
+    // files read are declared too
+    for path in [
+        "build.rs",
+        "src/blob.rs",
+        "src/geo.rs",
+        "data",
+        GOLDEN_DIRECTORY,
+    ] {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+    for variable in [SIMPLIFY_TOLERANCE_VARIABLE, VERTEX_BUDGET_VARIABLE] {
+        println!("cargo:rerun-if-env-changed={}", variable);
+    }
+
     let file = File::create(DATA_FILENAME)?;
     let mut file = BufWriter::new(file);
 
     file.write_all("// This file is code generated.\n\n".as_bytes())?;
-    write_data(&mut file, COASTLINE_SHAPEFILE_FILENAME, "COASTLINE_POINTS")?;
+    file.write_all("use crate::blob;\n".as_bytes())?;
+    file.write_all("use crate::coords::LonLat;\n".as_bytes())?;
+    file.write_all("use crate::info::{Attribute, DatasetInfo, FeatureBounds};\n\n".as_bytes())?;
+    file.write_all(
+        format!(
+            "/// Polylines of points of the data, decoded on first use.\npub type Polylines = blob::Polylines<{}>;\n\n",
+            COORDINATE_SHIFT
+        )
+        .as_bytes(),
+    )?;
+    let resolution = coastline_resolution();
+    let coastline_filename = coastline_shapefile_filename(resolution);
+    if !Path::new(&coastline_filename).exists() {
+        return Err(format!(
+            "the coastline-{} feature is selected but {} is not present; download ne_{}_coastline from Natural Earth into data, or select another resolution",
+            resolution, coastline_filename, resolution
+        )
+        .into());
+    }
+    let coastline_counts = write_data(
+        &mut file,
+        &coastline_filename,
+        "COASTLINE",
+        None,
+        COASTLINE_ATTRIBUTE_FIELDS,
+    )?;
+    let mut datasets = vec![(coastline_filename, coastline_counts)];
+
+    // The detailed coastline, drawn when zoomed in, is optional, and only embedded when
+    // finer than the coastline; empty data is written when it is not, with a warning when it
+    // is only missing
+    let detailed_filename = coastline_shapefile_filename(DETAILED_COASTLINE_RESOLUTION);
+    let detailed = is_finer(DETAILED_COASTLINE_RESOLUTION, resolution);
+    if detailed && !Path::new(&detailed_filename).exists() {
+        warn_missing(
+            &detailed_filename,
+            "the coastline will not gain detail as the view zooms in",
+        );
+    }
+    if detailed && Path::new(&detailed_filename).exists() {
+        let detailed_counts = write_data(
+            &mut file,
+            &detailed_filename,
+            "DETAILED_COASTLINE",
+            None,
+            COASTLINE_ATTRIBUTE_FIELDS,
+        )?;
+        datasets.push((detailed_filename, detailed_counts));
+    } else {
+        file.write_all(
+            "pub static DETAILED_COASTLINE_POINTS: Polylines = Polylines::new(&[]);\n".as_bytes(),
+        )?;
+        file.write_all(
+            "pub const DETAILED_COASTLINE_BOUNDS: &[FeatureBounds] = &[];\n".as_bytes(),
+        )?;
+        file.write_all("pub const DETAILED_COASTLINE_FIELDS: &[&str] = &[];\n".as_bytes())?;
+        file.write_all(
+            "pub const DETAILED_COASTLINE_ATTRIBUTES: &[&[Attribute]] = &[];\n".as_bytes(),
+        )?;
+    }
+
+    // The admin-0 countries are only embedded with the `countries` feature, which requires
+    // their shapefile; empty data is written without it
+    let countries = std::env::var_os("CARGO_FEATURE_COUNTRIES").is_some();
+    if countries {
+        require_shapefile(COUNTRY_SHAPEFILE_FILENAME, "countries")?;
+        let country_counts = write_data(
+            &mut file,
+            COUNTRY_SHAPEFILE_FILENAME,
+            "COUNTRY",
+            Some(COUNTRY_NAME_FIELD),
+            COUNTRY_ATTRIBUTE_FIELDS,
+        )?;
+        datasets.push((COUNTRY_SHAPEFILE_FILENAME.to_string(), country_counts));
+    } else {
+        file.write_all("pub static COUNTRY_POINTS: Polylines = Polylines::new(&[]);\n".as_bytes())?;
+        file.write_all("pub const COUNTRY_BOUNDS: &[FeatureBounds] = &[];\n".as_bytes())?;
+        file.write_all("pub const COUNTRY_NAMES: &[&str] = &[];\n".as_bytes())?;
+        file.write_all("pub const COUNTRY_FIELDS: &[&str] = &[];\n".as_bytes())?;
+        file.write_all("pub const COUNTRY_ATTRIBUTES: &[&[Attribute]] = &[];\n".as_bytes())?;
+    }
+
+    // Localized names of the countries are only embedded with the `localized-names` feature,
+    // as they add to the size of the module
+    if countries && std::env::var_os("CARGO_FEATURE_LOCALIZED_NAMES").is_some() {
+        write_localized_names(
+            &mut file,
+            COUNTRY_SHAPEFILE_FILENAME,
+            "COUNTRY",
+            COUNTRY_LOCALIZED_NAME_FIELDS,
+        )?;
+    } else {
+        file.write_all("pub const COUNTRY_NAME_LANGUAGES: &[&str] = &[];\n".as_bytes())?;
+        file.write_all("pub const COUNTRY_LOCALIZED_NAMES: &[&[&str]] = &[];\n".as_bytes())?;
+    }
+
+    // The land polygons are only embedded with the `land` feature, which requires their
+    // shapefile; empty data is written without it
+    if std::env::var_os("CARGO_FEATURE_LAND").is_some() {
+        require_shapefile(LAND_SHAPEFILE_FILENAME, "land")?;
+        let land_counts = write_data(
+            &mut file,
+            LAND_SHAPEFILE_FILENAME,
+            "LAND",
+            None,
+            LAND_ATTRIBUTE_FIELDS,
+        )?;
+        datasets.push((LAND_SHAPEFILE_FILENAME.to_string(), land_counts));
+    } else {
+        file.write_all("pub static LAND_POINTS: Polylines = Polylines::new(&[]);\n".as_bytes())?;
+        file.write_all("pub const LAND_BOUNDS: &[FeatureBounds] = &[];\n".as_bytes())?;
+        file.write_all("pub const LAND_FIELDS: &[&str] = &[];\n".as_bytes())?;
+        file.write_all("pub const LAND_ATTRIBUTES: &[&[Attribute]] = &[];\n".as_bytes())?;
+    }
+
+    write_info(&mut file, &datasets)?;
+
+    write_golden_images()?;
+
+    Ok(())
+}
+
+/// Fail the build when the shapefile of a dataset selected by a feature is not present.
+fn require_shapefile(
+    shapefile_filename: &str,
+    feature: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if Path::new(shapefile_filename).exists() {
+        return Ok(());
+    }
+    Err(format!(
+        "the {} feature is selected but {} is not present; download it from Natural Earth into data, or build without the feature",
+        feature, shapefile_filename
+    )
+    .into())
+}
+
+/// Warn that the shapefile of an optional dataset is not present, and what is left empty.
+fn warn_missing(shapefile_filename: &str, consequence: &str) {
+    println!(
+        "cargo:warning={} is not present, so {}; download it from Natural Earth",
+        shapefile_filename, consequence
+    );
+}
+
+/// Resolution of the coastline selected by the features, the finest of those selected.
+fn coastline_resolution() -> &'static str {
+    COASTLINE_RESOLUTIONS
+        .iter()
+        .rev()
+        .find(|(_, feature)| std::env::var_os(feature).is_some())
+        .map_or(COASTLINE_RESOLUTIONS[0].0, |(resolution, _)| resolution)
+}
+
+/// Whether a resolution of the coastline is finer than another.
+fn is_finer(resolution: &str, other: &str) -> bool {
+    let rank = |resolution: &str| {
+        COASTLINE_RESOLUTIONS
+            .iter()
+            .position(|(candidate, _)| *candidate == resolution)
+    };
+    rank(resolution) > rank(other)
+}
+
+fn coastline_shapefile_filename(resolution: &str) -> String {
+    format!(
+        "data/ne_{}_coastline/ne_{}_coastline.shp",
+        resolution, resolution
+    )
+}
+
+/// Write the golden images checked in as `GOLDEN_IMAGES`, by their names, into the output
+/// directory; none are written without the `testing` feature.
+fn write_golden_images() -> Result<(), Box<dyn std::error::Error>> {
+    let mut images = Vec::new();
+    if std::env::var_os("CARGO_FEATURE_TESTING").is_some() && Path::new(GOLDEN_DIRECTORY).exists() {
+        for entry in std::fs::read_dir(GOLDEN_DIRECTORY)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "png") {
+                let name = path.file_stem().unwrap().to_string_lossy().to_string();
+                images.push((name, std::fs::canonicalize(&path)?));
+            }
+        }
+    }
+    images.sort();
+
+    let out_dir = std::env::var("OUT_DIR")?;
+    let file = File::create(Path::new(&out_dir).join(GOLDEN_FILENAME))?;
+    let mut file = BufWriter::new(file);
+    file.write_all("// This file is code generated.\n\n".as_bytes())?;
+    file.write_all("/// Golden images checked in, by name.\n".as_bytes())?;
+    file.write_all("pub const GOLDEN_IMAGES: &[(&str, &[u8])] = &[\n".as_bytes())?;
+    for (name, path) in images {
+        file.write_all(format!("    ({:?}, include_bytes!({:?})),\n", name, path).as_bytes())?;
+    }
+    file.write_all("];\n".as_bytes())?;
 
     Ok(())
 }
 
+/// Write the points of each feature in a shapefile as `<name>_POINTS`, encoded in a blob in
+/// the output directory, and their bounds as `<name>_BOUNDS`, with their names as `<name>_NAMES` if a field is given,
+/// and the values of the attribute fields as `<name>_ATTRIBUTES` of `<name>_FIELDS`.
 fn write_data(
     file: &mut BufWriter<File>,
     shapefile_filename: &str,
     name: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    file.write_all(format!("pub const {}: &[&[(f64, f64)]] = &[\n", name).as_bytes())?;
-
+    name_field: Option<&str>,
+    attribute_fields: &[&str],
+) -> Result<DataCounts, Box<dyn std::error::Error>> {
+    let mut counts = DataCounts {
+        features: 0,
+        points: 0,
+    };
+    let mut features = Vec::new();
+    let mut names = Vec::new();
+    let mut attributes = Vec::new();
     let mut reader = shapefile::Reader::from_path(shapefile_filename)?;
     for shape_record in reader.iter_shapes_and_records() {
-        let (shape, _record) = shape_record?;
-        match shape {
-            shapefile::Shape::Polyline(polyline) => {
-                file.write_all("    &[\n".as_bytes())?;
-                for part in polyline.parts() {
-                    for point in part {
-                        file.write_all(
-                            format!("        ({}f64, {}f64),\n", point.x, point.y).as_bytes(),
-                        )?;
-                    }
-                }
-                file.write_all("    ],\n".as_bytes())?;
+        let (shape, record) = shape_record?;
+        // Parts of the feature, and whether they are closed rings
+        let (parts, closed) = match &shape {
+            shapefile::Shape::Polyline(polyline) => (polyline.parts().iter().collect(), false),
+            shapefile::Shape::Polygon(polygon) => (
+                polygon
+                    .rings()
+                    .iter()
+                    .filter_map(|ring| match ring {
+                        PolygonRing::Outer(points) => Some(points),
+                        PolygonRing::Inner(_) => None,
+                    })
+                    .collect::<Vec<_>>(),
+                true,
+            ),
+            _ => {
+                return Err(format!("unsupported shape {} in {}", shape, shapefile_filename).into())
             }
-            shapefile::Shape::Polygon(polygon) => {
-                file.write_all("    &[\n".as_bytes())?;
-                for ring in polygon.rings() {
-                    match ring {
-                        PolygonRing::Outer(points) => {
-                            for point in points {
-                                file.write_all(
-                                    format!("        ({}f64, {}f64),\n", point.x, point.y)
-                                        .as_bytes(),
-                                )?;
-                            }
-                        }
-                        PolygonRing::Inner(_) => {}
-                    }
-                }
-                file.write_all("    ],\n".as_bytes())?;
+        };
+
+        let parts = parts
+            .iter()
+            .map(|part| part.iter().map(|point| (point.x, point.y)).collect())
+            .collect::<Vec<Vec<_>>>();
+        features.push((parts, closed));
+        if let Some(name_field) = name_field {
+            names.push(match record.get(name_field) {
+                Some(FieldValue::Character(Some(name))) => name.trim().to_string(),
+                _ => String::new(),
+            });
+        }
+        attributes.push(
+            attribute_fields
+                .iter()
+                .map(|field| attribute(record.get(field)))
+                .collect::<Vec<_>>(),
+        );
+        counts.features += 1;
+    }
+
+    let tolerance = simplify_tolerance(&features)?;
+    let mut polylines = Vec::new();
+    let mut bounds = Vec::new();
+    for (parts, closed) in &features {
+        let parts = parts
+            .iter()
+            .map(|part| simplify(part, tolerance, *closed))
+            .collect::<Vec<_>>();
+        let polyline = parts
+            .iter()
+            .flatten()
+            .map(|(lon, lat)| GeoPoint::<COORDINATE_SHIFT>::from_degrees(*lon, *lat).raw())
+            .collect::<Vec<_>>();
+        counts.points += polyline.len();
+        polylines.push(polyline);
+        bounds.push(feature_bounds(&parts, *closed));
+    }
+
+    let blob_filename = format!("{}_points.bin", name.to_lowercase());
+    let out_dir = std::env::var("OUT_DIR")?;
+    std::fs::write(
+        Path::new(&out_dir).join(&blob_filename),
+        blob::encode(&polylines),
+    )?;
+    file.write_all(
+        format!(
+            "pub static {}_POINTS: Polylines =\n    Polylines::new(include_bytes!(concat!(env!(\"OUT_DIR\"), {:?})));\n",
+            name,
+            format!("/{}", blob_filename)
+        )
+        .as_bytes(),
+    )?;
+
+    file.write_all(format!("\npub const {}_BOUNDS: &[FeatureBounds] = &[\n", name).as_bytes())?;
+    for ((lon, lat), radius) in bounds {
+        file.write_all(
+            format!(
+                "    FeatureBounds {{ centroid: LonLat {{ lon: {}f64, lat: {}f64 }}, radius: {}f64 }},\n",
+                lon, lat, radius
+            )
+            .as_bytes(),
+        )?;
+    }
+    file.write_all("];\n".as_bytes())?;
+
+    if name_field.is_some() {
+        file.write_all(format!("\npub const {}_NAMES: &[&str] = &[\n", name).as_bytes())?;
+        for feature_name in names {
+            file.write_all(format!("    {:?},\n", feature_name).as_bytes())?;
+        }
+        file.write_all("];\n".as_bytes())?;
+    }
+
+    file.write_all(format!("\npub const {}_FIELDS: &[&str] = &[\n", name).as_bytes())?;
+    for field in attribute_fields {
+        file.write_all(format!("    {:?},\n", field).as_bytes())?;
+    }
+    file.write_all("];\n".as_bytes())?;
+    file.write_all(format!("\npub const {}_ATTRIBUTES: &[&[Attribute]] = &[\n", name).as_bytes())?;
+    for feature_attributes in attributes {
+        file.write_all(format!("    &[{}],\n", feature_attributes.join(", ")).as_bytes())?;
+    }
+    file.write_all("];\n".as_bytes())?;
+
+    Ok(counts)
+}
+
+/// Tolerance (degrees) the lines and rings of features, with whether they are closed rings,
+/// are simplified by: the tolerance set in the environment, raised to meet any budget of
+/// points set in the environment.
+fn simplify_tolerance(features: &[FeatureParts]) -> Result<f64, Box<dyn std::error::Error>> {
+    let tolerance = match std::env::var(SIMPLIFY_TOLERANCE_VARIABLE) {
+        Ok(tolerance) => tolerance.parse::<f64>()?.max(0.0),
+        Err(_) => 0.0,
+    };
+    let Ok(budget) = std::env::var(VERTEX_BUDGET_VARIABLE) else {
+        return Ok(tolerance);
+    };
+    let budget = budget.parse::<usize>()?;
+    let points = |tolerance: f64| {
+        features
+            .iter()
+            .flat_map(|(parts, closed)| parts.iter().map(move |part| (part, *closed)))
+            .map(|(part, closed)| simplify(part, tolerance, closed).len())
+            .sum::<usize>()
+    };
+    if points(tolerance) <= budget {
+        return Ok(tolerance);
+    }
+    // The number of points kept falls as the tolerance rises, so the least tolerance within
+    // the budget is searched for by bisection
+    let (mut within, mut beyond) = (tolerance.max(1.0), tolerance);
+    while points(within) > budget && within < 180.0 {
+        (beyond, within) = (within, within * 2.0);
+    }
+    for _ in 0..TOLERANCE_SEARCH_STEPS {
+        let middle = (within + beyond) / 2.0;
+        if points(middle) <= budget {
+            within = middle;
+        } else {
+            beyond = middle;
+        }
+    }
+    Ok(within)
+}
+
+/// Points of a line kept by Douglas–Peucker simplification within a tolerance (degrees),
+/// measured in the plane of longitude and latitude. The ends are always kept, and rings
+/// simplified to fewer than four points, so no longer enclosing an area, are kept whole.
+fn simplify(points: &[(f64, f64)], tolerance: f64, closed: bool) -> Vec<(f64, f64)> {
+    if tolerance <= 0.0 || points.len() <= 2 {
+        return points.to_vec();
+    }
+    let mut kept = vec![false; points.len()];
+    kept[0] = true;
+    kept[points.len() - 1] = true;
+    let mut spans = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = spans.pop() {
+        let farthest = (first + 1..last)
+            .map(|index| {
+                let distance = segment_distance(points[index], points[first], points[last]);
+                (index, distance)
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        if let Some((index, distance)) = farthest {
+            if distance > tolerance {
+                kept[index] = true;
+                spans.push((first, index));
+                spans.push((index, last));
             }
-            _ => file.write_all(format!("!!!ERROR({})!!!", shape).as_bytes())?,
         }
     }
+    let simplified = points
+        .iter()
+        .zip(&kept)
+        .filter(|(_, kept)| **kept)
+        .map(|(point, _)| *point)
+        .collect::<Vec<_>>();
+    if closed && simplified.len() < 4 {
+        points.to_vec()
+    } else {
+        simplified
+    }
+}
+
+/// Distance in the plane from a point to a segment.
+fn segment_distance((x, y): (f64, f64), (x0, y0): (f64, f64), (x1, y1): (f64, f64)) -> f64 {
+    let (dx, dy) = (x1 - x0, y1 - y0);
+    let length_squared = dx * dx + dy * dy;
+    // Rings start and end at the same point
+    let t = if length_squared > 0.0 {
+        (((x - x0) * dx + (y - y0) * dy) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (x - (x0 + t * dx)).hypot(y - (y0 + t * dy))
+}
+
+/// Write the languages of the localized name fields of a shapefile as `<name>_NAME_LANGUAGES`
+/// and the names of each feature in each language as `<name>_LOCALIZED_NAMES`, with empty
+/// names where a field is missing or blank.
+fn write_localized_names(
+    file: &mut BufWriter<File>,
+    shapefile_filename: &str,
+    name: &str,
+    fields: &[(&str, &str)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut names = vec![Vec::new(); fields.len()];
+    let mut reader = shapefile::Reader::from_path(shapefile_filename)?;
+    for shape_record in reader.iter_shapes_and_records() {
+        let (_, record) = shape_record?;
+        for ((_, field), names) in fields.iter().zip(&mut names) {
+            names.push(match record.get(field) {
+                Some(FieldValue::Character(Some(name))) => name.trim().to_string(),
+                _ => String::new(),
+            });
+        }
+    }
+
+    file.write_all(format!("\npub const {}_NAME_LANGUAGES: &[&str] = &[\n", name).as_bytes())?;
+    for (language, _) in fields {
+        file.write_all(format!("    {:?},\n", language).as_bytes())?;
+    }
+    file.write_all("];\n".as_bytes())?;
+    file.write_all(format!("\npub const {}_LOCALIZED_NAMES: &[&[&str]] = &[\n", name).as_bytes())?;
+    for language_names in names {
+        file.write_all("    &[\n".as_bytes())?;
+        for feature_name in language_names {
+            file.write_all(format!("        {:?},\n", feature_name).as_bytes())?;
+        }
+        file.write_all("    ],\n".as_bytes())?;
+    }
+    file.write_all("];\n".as_bytes())?;
+
+    Ok(())
+}
+
+/// Code constructing the attribute of a field's value in a record.
+fn attribute(value: Option<&FieldValue>) -> String {
+    let number = match value {
+        Some(FieldValue::Character(Some(text))) => {
+            return format!("Attribute::Text({:?})", text.trim());
+        }
+        Some(FieldValue::Numeric(Some(number))) | Some(FieldValue::Double(number)) => *number,
+        Some(FieldValue::Float(Some(number))) => *number as f64,
+        Some(FieldValue::Integer(number)) => *number as f64,
+        _ => return "Attribute::Null".to_string(),
+    };
+    format!("Attribute::Number({:?}f64)", number)
+}
+
+/// Spherical centroid (degrees, as longitude and latitude) of a feature and the
+/// radius (degrees) of the cap about it bounding the feature's points.
+///
+/// The centroid of closed rings is weighted by the area they enclose, and the
+/// centroid of lines by their length; both are on the surface of the sphere.
+fn feature_bounds(parts: &[Vec<(f64, f64)>], closed: bool) -> ((f64, f64), f64) {
+    let mut sum = [0.0; 3];
+    let mut vertex_sum = [0.0; 3];
+    for part in parts {
+        let points = part
+            .iter()
+            .map(|(lon, lat)| to_cartesian(*lon, *lat))
+            .collect::<Vec<_>>();
+        for point in &points {
+            vertex_sum = add(vertex_sum, *point);
+        }
+        for edge in points.windows(2) {
+            let (a, b) = (edge[0], edge[1]);
+            let angle = dot(a, b).clamp(-1.0, 1.0).acos();
+            // The area-weighted mean position of a region is proportional to the sum, over
+            // the edges of its boundary, of the edge's angle along its great circle's normal
+            let direction = if closed { cross(a, b) } else { add(a, b) };
+            sum = add(sum, scale(normalise(direction), angle));
+        }
+    }
+    // Rings may be wound either way, so the centroid is taken on the side of their points
+    if dot(sum, vertex_sum) < 0.0 {
+        sum = scale(sum, -1.0);
+    }
+    if norm(sum) == 0.0 {
+        sum = vertex_sum;
+    }
+    let centroid = normalise(sum);
+
+    let radius = parts
+        .iter()
+        .flatten()
+        .map(|(lon, lat)| {
+            dot(centroid, to_cartesian(*lon, *lat))
+                .clamp(-1.0, 1.0)
+                .acos()
+        })
+        .fold(0.0, f64::max);
+
+    (
+        (
+            centroid[1].atan2(centroid[0]).to_degrees(),
+            centroid[2].clamp(-1.0, 1.0).asin().to_degrees(),
+        ),
+        radius.to_degrees(),
+    )
+}
+
+fn to_cartesian(lon: f64, lat: f64) -> [f64; 3] {
+    let (sin_lon, cos_lon) = lon.to_radians().sin_cos();
+    let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+    [cos_lat * cos_lon, cos_lat * sin_lon, sin_lat]
+}
+
+fn add(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale(a: [f64; 3], factor: f64) -> [f64; 3] {
+    [a[0] * factor, a[1] * factor, a[2] * factor]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn norm(a: [f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Scale a vector to unit length, leaving zero vectors unchanged.
+fn normalise(a: [f64; 3]) -> [f64; 3] {
+    let length = norm(a);
+    if length > 0.0 {
+        scale(a, 1.0 / length)
+    } else {
+        a
+    }
+}
+
+/// Write information about the datasets, including a hash of the content of each shapefile's
+/// files: its geometry, index, attributes and projection.
+fn write_info(
+    file: &mut BufWriter<File>,
+    datasets: &[(String, DataCounts)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    file.write_all("\npub const DATASETS: &[DatasetInfo] = &[\n".as_bytes())?;
+
+    for (shapefile_filename, counts) in datasets {
+        let path = Path::new(shapefile_filename);
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or("shapefile should have a name")?;
+        // Natural Earth names are of the form "ne_<resolution>_<theme>"
+        let source = if name.starts_with("ne_") {
+            "Natural Earth"
+        } else {
+            "unknown"
+        };
+        let resolution = name.split('_').nth(1).unwrap_or("unknown");
+        let version = std::fs::read_to_string(path.with_extension("VERSION.txt"))
+            .map(|version| version.trim().to_string())
+            .unwrap_or_else(|_| "unknown".to_string());
+        let mut hasher = Sha256::new();
+        for extension in SHAPEFILE_EXTENSIONS {
+            let component = path.with_extension(extension);
+            // The projection is the only part a shapefile can be read without
+            if extension == "prj" && !component.exists() {
+                continue;
+            }
+            hasher.update(std::fs::read(&component)?);
+        }
+        let sha256 = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+
+        file.write_all("    DatasetInfo {\n".as_bytes())?;
+        file.write_all(format!("        name: {:?},\n", name).as_bytes())?;
+        file.write_all(format!("        source: {:?},\n", source).as_bytes())?;
+        file.write_all(format!("        resolution: {:?},\n", resolution).as_bytes())?;
+        file.write_all(format!("        version: {:?},\n", version).as_bytes())?;
+        file.write_all(format!("        features: {},\n", counts.features).as_bytes())?;
+        file.write_all(format!("        points: {},\n", counts.points).as_bytes())?;
+        file.write_all(format!("        sha256: {:?},\n", sha256).as_bytes())?;
+        file.write_all("    },\n".as_bytes())?;
+    }
     file.write_all("];\n".as_bytes())?;
 
     Ok(())
@@ -0,0 +1,7 @@
+//! Fuzzing of the clipping, simplification and projection of the globe, run with
+//! `cargo fuzz run geometry`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| wasm_globe::fuzzing::fuzz_geometry(data));
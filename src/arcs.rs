@@ -0,0 +1,209 @@
+// Great-circle arcs between pairs of locations, as for flight routes, drawn as polylines
+// sampled along the great circle and split where they pass behind the globe.
+
+use wasm_bindgen::prelude::*;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::coords::LonLat;
+use crate::measure;
+use crate::view::{Frame, Side};
+
+const ARC_DEFAULT_COLOR: &str = "rgba(255, 140, 0, 1.0)";
+const ARC_DEFAULT_BACK_COLOR: &str = "rgba(0, 0, 0, 0.25)";
+const ARC_DEFAULT_WIDTH: f64 = 0.004;
+// Angles are in degrees
+const ARC_SAMPLE_ANGLE: f64 = 2.0;
+// Locations further apart than this are too near antipodal for a single great circle
+const MAX_ARC_ANGLE: f64 = 179.999;
+
+/// Style of an arc.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArcStyle {
+    /// CSS color of the arc on the front of the globe.
+    #[wasm_bindgen(getter_with_clone)]
+    pub color: String,
+    /// CSS color of the arc where it shows through on the back of the globe, or `None` for
+    /// the back to be hidden.
+    #[wasm_bindgen(getter_with_clone)]
+    pub back_color: Option<String>,
+    /// Line width, relative to the radius of the globe.
+    pub width: f64,
+}
+
+impl Default for ArcStyle {
+    fn default() -> Self {
+        Self {
+            color: ARC_DEFAULT_COLOR.to_string(),
+            back_color: Some(ARC_DEFAULT_BACK_COLOR.to_string()),
+            width: ARC_DEFAULT_WIDTH,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl ArcStyle {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ArcStyle {
+        ArcStyle::default()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Arc {
+    id: u32,
+    /// Ends (degrees).
+    from: LonLat,
+    to: LonLat,
+    style: ArcStyle,
+}
+
+#[derive(Debug, Default)]
+pub struct Arcs {
+    arcs: Vec<Arc>,
+    next_id: u32,
+}
+
+impl Arcs {
+    /// Add an arc between locations (degrees), returning its identifier.
+    pub fn add(&mut self, from: LonLat, to: LonLat, style: ArcStyle) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.arcs.push(Arc {
+            id,
+            from,
+            to,
+            style,
+        });
+        id
+    }
+
+    /// Remove an arc, returning whether it existed.
+    pub fn remove(&mut self, id: u32) -> bool {
+        let len = self.arcs.len();
+        self.arcs.retain(|arc| arc.id != id);
+        self.arcs.len() != len
+    }
+
+    /// Remove all arcs.
+    pub fn clear(&mut self) {
+        self.arcs.clear();
+    }
+
+    /// Draw the arcs onto the canvas, each part on the back before those in front; arcs
+    /// between antipodes, which have no single great circle, are not drawn.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        for arc in &self.arcs {
+            let angle = measure::central_angle(arc.from, arc.to);
+            if angle.is_nan() || angle > MAX_ARC_ANGLE {
+                continue;
+            }
+            let samples = ((angle / ARC_SAMPLE_ANGLE).ceil() as usize).max(1);
+            let points = (0..=samples)
+                .map(|i| {
+                    let location =
+                        measure::interpolate(arc.from, arc.to, i as f64 / samples as f64);
+                    frame.project(location.lon, location.lat)
+                })
+                .collect::<Vec<_>>();
+            let runs = split_runs(&points, frame.projection.shows_back(), |prev, point| {
+                frame.segment_side(prev, point)
+            });
+
+            context.set_line_width(frame.line_width(arc.style.width));
+            for side in [Side::Back, Side::Front] {
+                let style = match side {
+                    Side::Front => &arc.style.color,
+                    // Runs on the culled back are not drawn
+                    _ if !frame.draws_back() => continue,
+                    _ => match &arc.style.back_color {
+                        Some(color) => color,
+                        None => continue,
+                    },
+                };
+                context.set_stroke_style_str(style);
+                context.begin_path();
+                for (_, run) in runs.iter().filter(|(run_side, _)| *run_side == side) {
+                    for (i, (y, z)) in run.iter().enumerate() {
+                        if i == 0 {
+                            context.move_to(*y, *z);
+                        } else {
+                            context.line_to(*y, *z);
+                        }
+                    }
+                }
+                context.stroke();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs of plotted coordinates of a polyline of projected points on each side of the globe.
+/// Segments are on the side given for them, or not drawn; where the back shows through,
+/// segments crossing the horizon are split where they cross it.
+fn split_runs(
+    points: &[(f64, f64, f64)],
+    shows_back: bool,
+    segment_side: impl Fn((f64, f64, f64), (f64, f64, f64)) -> Option<Side>,
+) -> Vec<(Side, Vec<(f64, f64)>)> {
+    let mut runs: Vec<(Side, Vec<(f64, f64)>)> = Vec::new();
+    let mut push = |side: Side, from: (f64, f64), to: (f64, f64)| match runs.last_mut() {
+        Some((run_side, run)) if *run_side == side && run.last() == Some(&from) => run.push(to),
+        _ => runs.push((side, vec![from, to])),
+    };
+    for segment in points.windows(2) {
+        let (prev, point) = (segment[0], segment[1]);
+        let ((x_prev, y_prev, z_prev), (x, y, z)) = (prev, point);
+        if shows_back && (x_prev >= 0.0) != (x >= 0.0) {
+            // The segment crosses the horizon where its depth is zero, on the limb
+            let t = x_prev / (x_prev - x);
+            let (cy, cz) = (y_prev + (y - y_prev) * t, z_prev + (z - z_prev) * t);
+            let r = cy.hypot(cz);
+            let crossing = if r > 0.0 { (cy / r, cz / r) } else { (cy, cz) };
+            let (first, second) = if x_prev >= 0.0 {
+                (Side::Front, Side::Back)
+            } else {
+                (Side::Back, Side::Front)
+            };
+            push(first, (y_prev, z_prev), crossing);
+            push(second, crossing, (y, z));
+            continue;
+        }
+        if let Some(side) = segment_side(prev, point) {
+            push(side, (y_prev, z_prev), (y, z));
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projection::Projection;
+
+    #[test]
+    fn splits_at_the_horizon() {
+        let projection = Projection::default();
+        let points = [
+            (0.6, 0.8, 0.0),
+            (0.0, 1.0, 0.0),
+            (-0.6, 0.8, 0.0),
+            (-0.8, 0.6, 0.0),
+        ];
+        let runs = split_runs(&points, true, |a, b| projection.segment_side(a, b));
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].0, Side::Front);
+        assert_eq!(runs[0].1.last(), Some(&(1.0, 0.0)));
+        assert_eq!(runs[1].0, Side::Back);
+        assert_eq!(runs[1].1.len(), 3);
+
+        // Straddling segments are split at their crossing
+        let runs = split_runs(&[(0.5, 0.5, 0.0), (-0.5, 0.5, 0.0)], true, |a, b| {
+            projection.segment_side(a, b)
+        });
+        assert_eq!(runs[0].1, vec![(0.5, 0.0), (1.0, 0.0)]);
+        assert_eq!(runs[1].1, vec![(1.0, 0.0), (0.5, 0.0)]);
+    }
+}
@@ -0,0 +1,218 @@
+// The moon, as a companion to the day/night terminator: its phase is shown in a widget in a
+// corner of the canvas, and the sub-lunar point, where it is overhead, optionally marked on
+// the globe. Both follow the time the night side is shaded at, or else the clock.
+
+use std::cell::Cell;
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::coords::LonLat;
+use crate::overlay::{self, Corner};
+use crate::terminator;
+use crate::view::Frame;
+
+const MOON_LIT_FILL_STYLE: &str = "rgba(245, 240, 220, 1.0)";
+const MOON_DARK_FILL_STYLE: &str = "rgba(40, 40, 50, 0.85)";
+const MOON_OUTLINE_STYLE: &str = "rgba(255, 255, 255, 0.5)";
+// Sizes are in canvas pixels
+const MOON_WIDGET_RADIUS: f64 = 16.0;
+const MOON_WIDGET_MARGIN: f64 = 8.0;
+const MOON_LINE_WIDTH: f64 = 1.0;
+const SUBLUNAR_RADIUS: f64 = 5.0;
+// The moon moves by about 0.004 degrees a second over the globe, so it is redrawn at most this
+// often (milliseconds) while following the clock
+const MOON_UPDATE_INTERVAL: f64 = 1000.0;
+
+#[derive(Debug)]
+pub struct Moon {
+    /// Whether the phase is shown in a widget, and the corner of the canvas it is shown in.
+    pub widget: bool,
+    pub corner: Corner,
+    /// Whether the sub-lunar point is marked on the globe.
+    pub sublunar: bool,
+    /// Time of the clock the moon was last drawn at.
+    drawn: Cell<Option<f64>>,
+}
+
+impl Default for Moon {
+    fn default() -> Self {
+        Self {
+            widget: false,
+            corner: Corner::BottomLeft,
+            sublunar: false,
+            drawn: Cell::new(None),
+        }
+    }
+}
+
+impl Moon {
+    /// Whether the moon is shown following the clock and has moved since it was drawn.
+    pub fn animating(&self, clock: f64, following: bool) -> bool {
+        (self.widget || self.sublunar)
+            && following
+            && self
+                .drawn
+                .get()
+                .is_none_or(|drawn| clock - drawn >= MOON_UPDATE_INTERVAL)
+    }
+
+    /// Mark the sub-lunar point at a time (milliseconds since the Unix epoch) onto the canvas,
+    /// when it is on the front of the globe.
+    pub fn draw_sublunar(
+        &self,
+        context: &CanvasRenderingContext2d,
+        frame: &Frame,
+        clock: f64,
+        time: f64,
+    ) -> Result<(), JsValue> {
+        if !self.sublunar {
+            return Ok(());
+        }
+        self.drawn.set(Some(clock));
+        let sublunar = sublunar_point(time);
+        let (x, y, z) = frame.project(sublunar.lon, sublunar.lat);
+        if x < 0.0 {
+            return Ok(());
+        }
+        // The mark is a constant size on screen
+        context.begin_path();
+        context.arc(
+            y,
+            z,
+            SUBLUNAR_RADIUS / frame.scale,
+            0.0,
+            std::f64::consts::TAU,
+        )?;
+        context.set_fill_style_str(MOON_LIT_FILL_STYLE);
+        context.fill();
+        context.set_line_width(MOON_LINE_WIDTH / frame.scale);
+        context.set_stroke_style_str(MOON_DARK_FILL_STYLE);
+        context.stroke();
+        Ok(())
+    }
+
+    /// Draw the widget of the phase at a time (milliseconds since the Unix epoch) onto the
+    /// canvas, if shown, lit on the side the sun is on as seen from the north.
+    pub fn draw_widget(
+        &self,
+        context: &CanvasRenderingContext2d,
+        pixel_ratio: f64,
+        clock: f64,
+        time: f64,
+    ) -> Result<(), JsValue> {
+        if !self.widget {
+            return Ok(());
+        }
+        self.drawn.set(Some(clock));
+        let (illuminated, waxing) = phase(time);
+
+        // The widget is drawn in canvas pixels rather than unit sphere coordinates
+        context.save();
+        let canvas_size = overlay::canvas_pixels(context, pixel_ratio)?;
+        let size = 2.0 * MOON_WIDGET_RADIUS;
+        let (left, top) = self
+            .corner
+            .place((size, size), canvas_size, MOON_WIDGET_MARGIN);
+        context.translate(left + MOON_WIDGET_RADIUS, top + MOON_WIDGET_RADIUS)?;
+
+        let radius = MOON_WIDGET_RADIUS;
+        context.begin_path();
+        context.arc(0.0, 0.0, radius, 0.0, std::f64::consts::TAU)?;
+        context.set_fill_style_str(MOON_DARK_FILL_STYLE);
+        context.fill();
+
+        // The lit part is bounded by the limb on the sun's side and by the terminator, an
+        // ellipse bulging towards the sun for crescents and away from it past half lit
+        if !waxing {
+            context.scale(-1.0, 1.0)?;
+        }
+        let half = std::f64::consts::FRAC_PI_2;
+        context.begin_path();
+        context.arc(0.0, 0.0, radius, -half, half)?;
+        context.ellipse_with_anticlockwise(
+            0.0,
+            0.0,
+            radius * (1.0 - 2.0 * illuminated).abs(),
+            radius,
+            0.0,
+            half,
+            if illuminated < 0.5 { -half } else { 3.0 * half },
+            illuminated < 0.5,
+        )?;
+        context.set_fill_style_str(MOON_LIT_FILL_STYLE);
+        context.fill();
+
+        context.begin_path();
+        context.arc(0.0, 0.0, radius, 0.0, std::f64::consts::TAU)?;
+        context.set_line_width(MOON_LINE_WIDTH);
+        context.set_stroke_style_str(MOON_OUTLINE_STYLE);
+        context.stroke();
+        context.restore();
+
+        Ok(())
+    }
+}
+
+/// Ecliptic longitude and latitude (degrees) of the moon a number of days since the J2000
+/// epoch, from the low precision lunar coordinates of the Astronomical Almanac, good to about
+/// a third of a degree.
+fn lunar_position(days: f64) -> (f64, f64) {
+    let t = days / 36_525.0;
+    let term =
+        |amplitude: f64, phase: f64, rate: f64| amplitude * (phase + rate * t).to_radians().sin();
+    let longitude = 218.32
+        + 481_267.881 * t
+        + term(6.29, 135.0, 477_198.87)
+        + term(-1.27, 259.3, -413_335.36)
+        + term(0.66, 235.7, 890_534.22)
+        + term(0.21, 269.9, 954_397.74)
+        + term(-0.19, 357.5, 35_999.05)
+        + term(-0.11, 186.5, 966_404.03);
+    let latitude = term(5.13, 93.3, 483_202.02)
+        + term(0.28, 228.2, 960_400.89)
+        + term(-0.28, 318.3, 6_003.15)
+        + term(-0.17, 217.6, -407_332.21);
+    (longitude, latitude)
+}
+
+/// Location (degrees) where the moon is overhead at a time (milliseconds since the Unix
+/// epoch), as seen from the centre of the Earth.
+pub fn sublunar_point(time: f64) -> LonLat {
+    let days = terminator::days_since_j2000(time);
+    let (longitude, latitude) = lunar_position(days);
+    terminator::overhead_point(days, longitude, latitude)
+}
+
+/// Phase of the moon at a time (milliseconds since the Unix epoch): the fraction of its disc
+/// lit, and whether it is waxing.
+pub fn phase(time: f64) -> (f64, bool) {
+    let days = terminator::days_since_j2000(time);
+    let (longitude, latitude) = lunar_position(days);
+    let separation = (longitude - terminator::solar_longitude(days)).rem_euclid(360.0);
+    // The sun is far enough that the phase angle is the supplement of the elongation
+    let elongation = (latitude.to_radians().cos() * separation.to_radians().cos()).acos();
+    ((1.0 - elongation.cos()) / 2.0, separation < 180.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_the_phases_of_the_moon() {
+        // The full moon of the 23rd of April 2024, at 23:49 UTC
+        let (illuminated, _) = phase(1_713_916_140_000.0);
+        assert!(illuminated > 0.99);
+        // The new moon of the 8th of April 2024, at 18:21 UTC, eclipsing the sun, which is
+        // overhead near the moon
+        let eclipse = 1_712_600_460_000.0;
+        let (illuminated, _) = phase(eclipse);
+        assert!(illuminated < 0.01);
+        let (moon, sun) = (sublunar_point(eclipse), terminator::subsolar_point(eclipse));
+        assert!((moon.lat - sun.lat).abs() < 1.0 && (moon.lon - sun.lon).abs() < 1.0);
+        // A week after the new moon it is waxing and about half lit
+        let (illuminated, waxing) = phase(eclipse + 7.4 * 86_400_000.0);
+        assert!(waxing && (illuminated - 0.5).abs() < 0.1);
+    }
+}
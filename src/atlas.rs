@@ -0,0 +1,161 @@
+// Atlas of the icons of markers: the images of the icons are packed onto a single canvas as
+// they are added, in shelves of rows, and markers are drawn from their regions of it, so
+// drawing many markers draws from one image rather than switching between many.
+
+use wasm_bindgen::prelude::*;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement};
+
+use crate::tiles;
+
+// Sizes are in pixels of the images
+const ATLAS_WIDTH: u32 = 1024;
+const ATLAS_MAX_HEIGHT: u32 = 4096;
+// Icons are spaced apart so drawing one scaled does not sample its neighbours
+const ICON_PADDING: u32 = 1;
+
+/// Region of the atlas holding an icon.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    /// Width used along the shelf.
+    used: u32,
+}
+
+/// Packing of rectangles into shelves across the atlas, each rectangle placed on the
+/// shortest shelf it fits on, or a new shelf beneath the others.
+#[derive(Debug, Default)]
+struct Shelves {
+    shelves: Vec<Shelf>,
+    /// Height of the shelves together.
+    height: u32,
+}
+
+impl Shelves {
+    /// Place a rectangle, returning the position of its top left corner, or `None` when
+    /// the atlas has no room for it.
+    fn place(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (width, height) = (width + ICON_PADDING, height + ICON_PADDING);
+        if width > ATLAS_WIDTH {
+            return None;
+        }
+        if let Some(shelf) = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= height && ATLAS_WIDTH - shelf.used >= width)
+            .min_by_key(|shelf| shelf.height)
+        {
+            let x = shelf.used;
+            shelf.used += width;
+            return Some((x, shelf.y));
+        }
+        if self.height + height > ATLAS_MAX_HEIGHT {
+            return None;
+        }
+        let y = self.height;
+        self.shelves.push(Shelf {
+            y,
+            height,
+            used: width,
+        });
+        self.height += height;
+        Some((0, y))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct IconAtlas {
+    /// Icons by name, with their images, kept to draw them again as the atlas grows.
+    icons: Vec<(String, HtmlImageElement, Region)>,
+    shelves: Shelves,
+    canvas: Option<(HtmlCanvasElement, CanvasRenderingContext2d)>,
+}
+
+impl IconAtlas {
+    /// Add an icon by name from a loaded image, replacing any icon of the same name.
+    pub fn add(&mut self, name: &str, image: &HtmlImageElement) -> Result<(), JsValue> {
+        let (width, height) = (image.natural_width(), image.natural_height());
+        if width == 0 || height == 0 {
+            return Err(JsValue::from_str("icon image is not loaded"));
+        }
+        // Replaced icons leave their regions unused until the atlas is cleared
+        self.icons.retain(|(icon, _, _)| icon != name);
+        let (x, y) = self
+            .shelves
+            .place(width, height)
+            .ok_or_else(|| JsValue::from_str("icon atlas is full"))?;
+        let region = Region {
+            x,
+            y,
+            width,
+            height,
+        };
+        self.icons.push((name.to_string(), image.clone(), region));
+
+        let (canvas, context) = tiles::scratch(&mut self.canvas)?;
+        if canvas.width() != ATLAS_WIDTH || canvas.height() < self.shelves.height {
+            // Resizing clears the canvas, so it grows by doubling and all icons are drawn again
+            canvas.set_width(ATLAS_WIDTH);
+            canvas.set_height(self.shelves.height.next_power_of_two());
+            for (_, image, region) in &self.icons {
+                draw_icon(&context, image, region)?;
+            }
+        } else {
+            draw_icon(&context, image, &region)?;
+        }
+        Ok(())
+    }
+
+    /// Remove all icons.
+    pub fn clear(&mut self) {
+        self.icons.clear();
+        self.shelves = Shelves::default();
+        if let Some((canvas, _)) = &self.canvas {
+            canvas.set_height(0);
+        }
+    }
+
+    /// Atlas and the region of it holding an icon, by name.
+    pub fn get(&self, name: &str) -> Option<(&HtmlCanvasElement, Region)> {
+        let (canvas, _) = self.canvas.as_ref()?;
+        self.icons
+            .iter()
+            .find(|(icon, _, _)| icon == name)
+            .map(|(_, _, region)| (canvas, *region))
+    }
+}
+
+fn draw_icon(
+    context: &CanvasRenderingContext2d,
+    image: &HtmlImageElement,
+    region: &Region,
+) -> Result<(), JsValue> {
+    context.draw_image_with_html_image_element(image, region.x as f64, region.y as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_icons_onto_shelves() {
+        let mut shelves = Shelves::default();
+        assert_eq!(shelves.place(32, 32), Some((0, 0)));
+        assert_eq!(shelves.place(32, 16), Some((33, 0)));
+        // Taller icons start a new shelf, and shorter ones fill the shortest shelf they fit
+        assert_eq!(shelves.place(10, 64), Some((0, 33)));
+        assert_eq!(shelves.place(10, 40), Some((11, 33)));
+        assert_eq!(shelves.height, 33 + 65);
+
+        assert_eq!(shelves.place(ATLAS_WIDTH, 8), None);
+        assert_eq!(shelves.place(8, ATLAS_MAX_HEIGHT), None);
+    }
+}
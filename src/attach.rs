@@ -0,0 +1,135 @@
+// Event listeners and the animation loop of a globe on its canvas, removed and stopped when
+// the globe is dropped so host pages can create and remove globes.
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm_bindgen::convert::FromWasmAbi;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    AddEventListenerOptions, DedicatedWorkerGlobalScope, Element, EventTarget, ResizeObserver,
+};
+
+/// Callback of the animation frames, requesting the next frame each time it is called.
+type FrameCallback = Rc<RefCell<Option<Closure<dyn FnMut()>>>>;
+
+/// Listener added to a target, with the closure it calls kept alive.
+struct Listener {
+    target: EventTarget,
+    event_type: &'static str,
+    function: js_sys::Function,
+    _closure: Box<dyn Any>,
+}
+
+/// Observer of the size of an element, with the closure it calls kept alive.
+struct ResizeObservation {
+    observer: ResizeObserver,
+    _closure: Closure<dyn FnMut(js_sys::Array)>,
+}
+
+#[derive(Default)]
+pub struct Attachment {
+    listeners: Vec<Listener>,
+    frame: Option<FrameCallback>,
+    /// Identifier of the animation frame last requested.
+    frame_request: Rc<Cell<Option<i32>>>,
+    resize_observations: Vec<ResizeObservation>,
+}
+
+impl Attachment {
+    /// Add a listener for events of a type on a target, with any options.
+    pub fn listen<E: FromWasmAbi + 'static>(
+        &mut self,
+        target: &EventTarget,
+        event_type: &'static str,
+        closure: Closure<dyn FnMut(E)>,
+        options: Option<&AddEventListenerOptions>,
+    ) -> Result<(), JsValue> {
+        let function = closure.as_ref().unchecked_ref::<js_sys::Function>().clone();
+        match options {
+            Some(options) => target
+                .add_event_listener_with_callback_and_add_event_listener_options(
+                    event_type, &function, options,
+                )?,
+            None => target.add_event_listener_with_callback(event_type, &function)?,
+        }
+        self.listeners.push(Listener {
+            target: target.clone(),
+            event_type,
+            function,
+            _closure: Box::new(closure),
+        });
+        Ok(())
+    }
+
+    /// Call a closure with the entries of a resize observer each time a target is resized.
+    pub fn observe_resize(
+        &mut self,
+        target: &Element,
+        closure: Closure<dyn FnMut(js_sys::Array)>,
+    ) -> Result<(), JsValue> {
+        let observer = ResizeObserver::new(closure.as_ref().unchecked_ref())?;
+        observer.observe(target);
+        self.resize_observations.push(ResizeObservation {
+            observer,
+            _closure: closure,
+        });
+        Ok(())
+    }
+
+    /// Call a callback every animation frame until dropped.
+    pub fn animate(&mut self, mut callback: impl FnMut() + 'static) {
+        let frame: FrameCallback = Rc::new(RefCell::new(None));
+        let next_frame = frame.clone();
+        let frame_request = self.frame_request.clone();
+        *frame.borrow_mut() = Some(Closure::new(move || {
+            callback();
+            // The callback is gone when the globe was dropped during it
+            if let Some(next_frame) = next_frame.borrow().as_ref() {
+                frame_request.set(Some(request_animation_frame(next_frame)));
+            }
+        }));
+        self.frame_request.set(Some(request_animation_frame(
+            frame.borrow().as_ref().unwrap(),
+        )));
+        self.frame = Some(frame);
+    }
+}
+
+impl Drop for Attachment {
+    fn drop(&mut self) {
+        for listener in &self.listeners {
+            let _ = listener
+                .target
+                .remove_event_listener_with_callback(listener.event_type, &listener.function);
+        }
+        for observation in &self.resize_observations {
+            observation.observer.disconnect();
+        }
+        if let Some(frame_request) = self.frame_request.take() {
+            let _ = match web_sys::window() {
+                Some(window) => window.cancel_animation_frame(frame_request),
+                None => worker_scope().cancel_animation_frame(frame_request),
+            };
+        }
+        // The callback holds itself to request the next frame
+        if let Some(frame) = self.frame.take() {
+            frame.borrow_mut().take();
+        }
+    }
+}
+
+fn request_animation_frame(f: &Closure<dyn FnMut()>) -> i32 {
+    // Globes drawn in a worker request frames of the worker
+    match web_sys::window() {
+        Some(window) => window.request_animation_frame(f.as_ref().unchecked_ref()),
+        None => worker_scope().request_animation_frame(f.as_ref().unchecked_ref()),
+    }
+    .expect("should register request animation frame callback")
+}
+
+fn worker_scope() -> DedicatedWorkerGlobalScope {
+    js_sys::global().unchecked_into()
+}
@@ -0,0 +1,154 @@
+// Attract mode for kiosks: once the globe has been left idle, a slow tour turns it and flies
+// between a list of stops, until it is touched again.
+
+use crate::coords::LonLat;
+
+// Times are in milliseconds
+const ATTRACT_DEFAULT_DELAY: f64 = 30000.0;
+const ATTRACT_DEFAULT_DWELL: f64 = 8000.0;
+// Degrees per second
+const ATTRACT_DEFAULT_SPEED: f64 = 6.0;
+// Longer gaps between frames, as when the page is hidden, are shortened
+const MAX_FRAME_INTERVAL: f64 = 100.0;
+
+/// Step of the tour for a frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TourStep {
+    /// Turn the globe about its polar axis by an angle (degrees).
+    Rotate(f64),
+    /// Fly to a stop.
+    FlyTo(LonLat),
+}
+
+#[derive(Debug)]
+pub struct Attract {
+    pub enabled: bool,
+    /// Time idle before the tour starts.
+    pub delay: f64,
+    /// Speed (degrees per second) the globe turns at between stops.
+    pub speed: f64,
+    /// Time turning between flying to each stop.
+    pub dwell: f64,
+    /// Stops flown to in turn.
+    stops: Vec<LonLat>,
+    next_stop: usize,
+    /// Time of the next flight to a stop, while touring.
+    next_flight: Option<f64>,
+    /// Time of the latest interaction.
+    interacted: Option<f64>,
+    /// Time of the previous frame.
+    time_prev: Option<f64>,
+    touring: bool,
+}
+
+impl Default for Attract {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            delay: ATTRACT_DEFAULT_DELAY,
+            speed: ATTRACT_DEFAULT_SPEED,
+            dwell: ATTRACT_DEFAULT_DWELL,
+            stops: Vec::new(),
+            next_stop: 0,
+            next_flight: None,
+            interacted: None,
+            time_prev: None,
+            touring: false,
+        }
+    }
+}
+
+impl Attract {
+    /// Set the stops of the tour from the latitude and longitude (degrees) of each in turn;
+    /// returns whether the number of values is even.
+    pub fn set_stops(&mut self, coordinates: &[f64]) -> bool {
+        if !coordinates.len().is_multiple_of(2) {
+            return false;
+        }
+        self.stops = coordinates
+            .chunks_exact(2)
+            .map(|pair| LonLat::new(pair[1], pair[0]))
+            .collect();
+        self.next_stop = 0;
+        true
+    }
+
+    /// Record an interaction at a time, stopping any tour until idle again.
+    pub fn interrupt(&mut self, time: f64) {
+        self.interacted = Some(time);
+        self.touring = false;
+        self.next_flight = None;
+    }
+
+    /// Whether the tour is playing.
+    pub fn touring(&self) -> bool {
+        self.touring
+    }
+
+    /// Step of the tour for the frame at a time, once idle for the delay; frames that are not
+    /// idle are interactions, and the tour waits while the camera is flying.
+    pub fn tick(&mut self, time: f64, idle: bool, flying: bool) -> Option<TourStep> {
+        let interval = (time - self.time_prev.unwrap_or(time)).clamp(0.0, MAX_FRAME_INTERVAL);
+        self.time_prev = Some(time);
+        if !idle {
+            self.interrupt(time);
+            return None;
+        }
+        let interacted = *self.interacted.get_or_insert(time);
+        self.touring = self.enabled && time - interacted >= self.delay;
+        if !self.touring || flying {
+            return None;
+        }
+        let next_flight = *self.next_flight.get_or_insert(time + self.dwell);
+        if time >= next_flight && !self.stops.is_empty() {
+            let stop = self.stops[self.next_stop % self.stops.len()];
+            self.next_stop = (self.next_stop + 1) % self.stops.len();
+            self.next_flight = Some(time + self.dwell);
+            return Some(TourStep::FlyTo(stop));
+        }
+        Some(TourStep::Rotate(self.speed * interval / 1000.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tours_once_idle() {
+        let mut attract = Attract {
+            enabled: true,
+            delay: 1000.0,
+            dwell: 500.0,
+            speed: 10.0,
+            ..Attract::default()
+        };
+        assert!(attract.set_stops(&[10.0, 20.0, -30.0, 40.0]));
+        assert!(!attract.set_stops(&[10.0]));
+        assert_eq!(attract.tick(0.0, true, false), None);
+        assert_eq!(attract.tick(900.0, true, false), None);
+        assert_eq!(
+            attract.tick(1000.0, true, false),
+            Some(TourStep::Rotate(1.0))
+        );
+        assert_eq!(
+            attract.tick(1500.0, true, false),
+            Some(TourStep::FlyTo(LonLat::new(20.0, 10.0)))
+        );
+        // The tour waits for each flight to finish
+        assert_eq!(attract.tick(1550.0, true, true), None);
+        assert_eq!(
+            attract.tick(2000.0, true, false),
+            Some(TourStep::FlyTo(LonLat::new(40.0, -30.0)))
+        );
+
+        // Touching the globe stops the tour until idle for the delay again
+        assert_eq!(attract.tick(2050.0, false, true), None);
+        assert!(!attract.touring());
+        assert_eq!(attract.tick(2100.0, true, false), None);
+        assert_eq!(
+            attract.tick(3050.0, true, false),
+            Some(TourStep::Rotate(1.0))
+        );
+    }
+}
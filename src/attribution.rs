@@ -0,0 +1,103 @@
+// Attribution of the data shown on the globe, drawn in a corner of the canvas.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::data;
+use crate::overlay::{self, Corner, Rect};
+
+const ATTRIBUTION_FONT: &str = "11px sans-serif";
+const ATTRIBUTION_FILL_STYLE: &str = "rgba(0, 0, 0, 0.75)";
+const ATTRIBUTION_BACKGROUND_STYLE: &str = "rgba(255, 255, 255, 0.5)";
+const ATTRIBUTION_HEIGHT: f64 = 11.0;
+const ATTRIBUTION_PADDING: f64 = 3.0;
+const ATTRIBUTION_MARGIN: f64 = 2.0;
+const ATTRIBUTION_SEPARATOR: &str = " | ";
+
+#[derive(Debug)]
+pub struct Attribution {
+    pub visible: bool,
+    pub corner: Corner,
+    /// Sources of data added at runtime.
+    sources: Vec<String>,
+}
+
+impl Default for Attribution {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            corner: Corner::BottomRight,
+            sources: Vec::new(),
+        }
+    }
+}
+
+impl Attribution {
+    /// Add the attribution of a source of data, unless already present.
+    pub fn add(&mut self, source: &str) {
+        if !self.sources.iter().any(|s| s == source) {
+            self.sources.push(source.to_string());
+        }
+    }
+
+    /// Remove the attribution of a source of data, returning whether it was present.
+    pub fn remove(&mut self, source: &str) -> bool {
+        let len = self.sources.len();
+        self.sources.retain(|s| s != source);
+        self.sources.len() != len
+    }
+
+    /// Text attributing the embedded datasets and the sources added at runtime.
+    pub fn text(&self) -> String {
+        let mut sources = Vec::<&str>::new();
+        for source in data::DATASETS
+            .iter()
+            .map(|dataset| dataset.source)
+            .chain(self.sources.iter().map(String::as_str))
+        {
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+        sources.join(ATTRIBUTION_SEPARATOR)
+    }
+
+    /// Draw the attribution onto the canvas, returning the rectangle it takes.
+    pub fn draw(
+        &self,
+        context: &CanvasRenderingContext2d,
+        pixel_ratio: f64,
+    ) -> Result<Option<Rect>, JsValue> {
+        let text = self.text();
+        if !self.visible || text.is_empty() {
+            return Ok(None);
+        }
+
+        // The attribution is drawn in canvas pixels rather than unit sphere coordinates
+        context.save();
+        let canvas_size = overlay::canvas_pixels(context, pixel_ratio)?;
+        context.set_font(ATTRIBUTION_FONT);
+        context.set_text_align("left");
+        context.set_text_baseline("top");
+
+        let size = (
+            context.measure_text(&text)?.width() + 2.0 * ATTRIBUTION_PADDING,
+            ATTRIBUTION_HEIGHT + 2.0 * ATTRIBUTION_PADDING,
+        );
+        let (left, top) = self.corner.place(size, canvas_size, ATTRIBUTION_MARGIN);
+
+        context.set_fill_style_str(ATTRIBUTION_BACKGROUND_STYLE);
+        context.fill_rect(left, top, size.0, size.1);
+        context.set_fill_style_str(ATTRIBUTION_FILL_STYLE);
+        context.fill_text(&text, left + ATTRIBUTION_PADDING, top + ATTRIBUTION_PADDING)?;
+
+        context.restore();
+
+        Ok(Some(Rect {
+            left,
+            top,
+            width: size.0,
+            height: size.1,
+        }))
+    }
+}
@@ -0,0 +1,86 @@
+// Slow spinning of the globe once it has been left idle.
+
+// Times are in milliseconds
+const AUTO_ROTATE_DEFAULT_DELAY: f64 = 5000.0;
+// Longer gaps between frames, as when the page is hidden, are shortened
+const MAX_FRAME_INTERVAL: f64 = 100.0;
+
+#[derive(Debug)]
+pub struct AutoRotate {
+    /// Speed (degrees per second) of rotation about the polar axis, or `None` for no rotation.
+    pub speed: Option<f64>,
+    /// Time idle before rotating.
+    pub delay: f64,
+    /// Time of the latest interaction.
+    interacted: Option<f64>,
+    /// Time of the previous frame.
+    time_prev: Option<f64>,
+    rotating: bool,
+}
+
+impl Default for AutoRotate {
+    fn default() -> Self {
+        Self {
+            speed: None,
+            delay: AUTO_ROTATE_DEFAULT_DELAY,
+            interacted: None,
+            time_prev: None,
+            rotating: false,
+        }
+    }
+}
+
+impl AutoRotate {
+    /// Record an interaction at a time, pausing any rotation until idle again.
+    pub fn interrupt(&mut self, time: f64) {
+        self.interacted = Some(time);
+        self.rotating = false;
+    }
+
+    /// Whether the globe is rotating.
+    pub fn rotating(&self) -> bool {
+        self.rotating
+    }
+
+    /// Rotation (degrees) of the globe for the frame at a time, when it has been idle for
+    /// the delay; frames that are not idle are interactions.
+    pub fn tick(&mut self, time: f64, idle: bool) -> Option<f64> {
+        let interval = (time - self.time_prev.unwrap_or(time)).clamp(0.0, MAX_FRAME_INTERVAL);
+        self.time_prev = Some(time);
+        if !idle {
+            self.interrupt(time);
+            return None;
+        }
+        let interacted = *self.interacted.get_or_insert(time);
+        self.rotating = match self.speed {
+            Some(speed) => speed != 0.0 && time - interacted >= self.delay,
+            None => false,
+        };
+        self.rotating
+            .then(|| self.speed.unwrap_or(0.0) * interval / 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_once_idle() {
+        let mut auto_rotate = AutoRotate {
+            speed: Some(10.0),
+            delay: 1000.0,
+            ..AutoRotate::default()
+        };
+        assert_eq!(auto_rotate.tick(0.0, true), None);
+        assert_eq!(auto_rotate.tick(900.0, true), None);
+        assert_eq!(auto_rotate.tick(1000.0, true), Some(1.0));
+        assert!(auto_rotate.rotating());
+
+        // Interacting pauses the rotation until idle for the delay again
+        assert_eq!(auto_rotate.tick(1050.0, false), None);
+        assert!(!auto_rotate.rotating());
+        assert_eq!(auto_rotate.tick(2000.0, true), None);
+        assert!(auto_rotate.tick(2050.0, true).is_some());
+    }
+}
@@ -0,0 +1,153 @@
+// Compact binary encoding of the polylines of the embedded data, written by the build script
+// and decoded on first use, so the coordinates are not compiled as Rust source.
+//
+// A blob holds the number of polylines, then for each its number of points and the
+// differences of each point's fixed-point longitude and latitude from the previous point's,
+// as zigzag-encoded variable-length integers of 7 bits a byte.
+
+use std::ops::Deref;
+use std::sync::OnceLock;
+
+use crate::geo::GeoPoint;
+
+/// Polylines of fixed-point locations decoded from an embedded blob on first use.
+pub struct Polylines<const SHIFT: u8> {
+    bytes: &'static [u8],
+    decoded: OnceLock<Vec<Vec<GeoPoint<SHIFT>>>>,
+}
+
+impl<const SHIFT: u8> Polylines<SHIFT> {
+    pub const fn new(bytes: &'static [u8]) -> Self {
+        Self {
+            bytes,
+            decoded: OnceLock::new(),
+        }
+    }
+
+    /// Whether the polylines have been decoded.
+    pub fn decoded(&self) -> bool {
+        self.decoded.get().is_some()
+    }
+}
+
+impl<const SHIFT: u8> Deref for Polylines<SHIFT> {
+    type Target = [Vec<GeoPoint<SHIFT>>];
+
+    fn deref(&self) -> &Self::Target {
+        self.decoded.get_or_init(|| {
+            decode(self.bytes)
+                .expect("embedded data should decode")
+                .into_iter()
+                .map(|polyline| {
+                    polyline
+                        .into_iter()
+                        .map(|(lon, lat)| GeoPoint::from_raw(lon, lat))
+                        .collect()
+                })
+                .collect()
+        })
+    }
+}
+
+impl<const SHIFT: u8> std::fmt::Debug for Polylines<SHIFT> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Polylines")
+            .field("bytes", &self.bytes.len())
+            .field("decoded", &self.decoded.get().is_some())
+            .finish()
+    }
+}
+
+/// Blob of polylines of fixed-point longitudes and latitudes.
+// Used by the build script to encode the data
+#[allow(dead_code)]
+pub fn encode(polylines: &[Vec<(i32, i32)>]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_unsigned(&mut bytes, polylines.len() as u64);
+    let mut previous = (0, 0);
+    for polyline in polylines {
+        write_unsigned(&mut bytes, polyline.len() as u64);
+        for &(lon, lat) in polyline {
+            write_signed(&mut bytes, lon as i64 - previous.0 as i64);
+            write_signed(&mut bytes, lat as i64 - previous.1 as i64);
+            previous = (lon, lat);
+        }
+    }
+    bytes
+}
+
+/// Polylines of fixed-point longitudes and latitudes in a blob, or `None` when it is cut
+/// short; an empty blob holds no polylines.
+pub fn decode(bytes: &[u8]) -> Option<Vec<Vec<(i32, i32)>>> {
+    if bytes.is_empty() {
+        return Some(Vec::new());
+    }
+    let mut bytes = bytes.iter().copied();
+    let count = read_unsigned(&mut bytes)?;
+    let mut previous = (0i64, 0i64);
+    let mut polylines = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let points = read_unsigned(&mut bytes)?;
+        let mut polyline = Vec::with_capacity(points as usize);
+        for _ in 0..points {
+            previous.0 += read_signed(&mut bytes)?;
+            previous.1 += read_signed(&mut bytes)?;
+            polyline.push((previous.0 as i32, previous.1 as i32));
+        }
+        polylines.push(polyline);
+    }
+    Some(polylines)
+}
+
+#[allow(dead_code)]
+fn write_unsigned(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+// Zigzag encoding interleaves negative and positive values, so small differences of either
+// sign take few bytes
+#[allow(dead_code)]
+fn write_signed(bytes: &mut Vec<u8>, value: i64) {
+    write_unsigned(bytes, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+fn read_unsigned(bytes: &mut impl Iterator<Item = u8>) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = bytes.next()?;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn read_signed(bytes: &mut impl Iterator<Item = u8>) -> Option<i64> {
+    let value = read_unsigned(bytes)?;
+    Some((value >> 1) as i64 ^ -((value & 1) as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_polylines() {
+        let polylines = vec![
+            vec![(0, 0), (1, -1), (i32::MAX, i32::MIN)],
+            vec![],
+            vec![(i32::MIN, i32::MAX), (-1_509_949_440, 754_974_720)],
+        ];
+        let bytes = encode(&polylines);
+        assert_eq!(decode(&bytes), Some(polylines));
+        assert_eq!(decode(&bytes[..bytes.len() - 1]), None);
+        assert_eq!(decode(&[]), Some(Vec::new()));
+        // Nearby points take a byte a coordinate
+        assert_eq!(encode(&[vec![(0, 0), (3, -3)]]).len(), 6);
+    }
+}
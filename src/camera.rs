@@ -0,0 +1,248 @@
+// Animation of the camera between orientations of the globe.
+
+use wasm_bindgen::prelude::*;
+
+use crate::quaternion::Quaternion;
+use crate::view::Orientation;
+
+// Times are in milliseconds
+const DEFAULT_DURATION: f64 = 1000.0;
+
+/// Curve of the progress of an animation over its duration.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    /// Starting slowly.
+    In,
+    /// Finishing slowly.
+    Out,
+    /// Starting and finishing slowly.
+    #[default]
+    InOut,
+    /// Starting and finishing more slowly, and faster between.
+    InOutCubic,
+}
+
+impl Easing {
+    /// Progress over the fraction of an animation elapsed.
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::In => t * t,
+            Easing::Out => t * (2.0 - t),
+            Easing::InOut => ease(t),
+            Easing::InOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (2.0 - 2.0 * t).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+pub struct CameraAnimation {
+    from: Orientation,
+    to: Orientation,
+    start: f64,
+    duration: f64,
+    easing: Easing,
+    /// Rotations slerped between along the great circle, for flights.
+    arc: Option<(Quaternion, Quaternion)>,
+    /// Zoom factors zoomed between along with the rotation.
+    zoom: Option<(f64, f64)>,
+    /// Function called with whether the animation finished, rather than being interrupted.
+    on_end: Option<js_sys::Function>,
+}
+
+impl CameraAnimation {
+    /// Animate from one orientation to another, rotating the shortest way about the polar axis.
+    pub fn new(from: Orientation, to: Orientation, start: f64) -> Self {
+        let to = Orientation {
+            rotation: from.rotation + wrap(to.rotation - from.rotation),
+            roll: from.roll + wrap(to.roll - from.roll),
+            ..to
+        };
+        Self {
+            from,
+            to,
+            start,
+            duration: DEFAULT_DURATION,
+            easing: Easing::InOut,
+            arc: None,
+            zoom: None,
+            on_end: None,
+        }
+    }
+
+    /// Fly from one orientation to another over any duration with an easing, turning at a
+    /// constant rate about a fixed axis, so the view travels along the great circle between
+    /// the centres; a function given is called with whether the flight finished.
+    pub fn fly(
+        from: Orientation,
+        to: Orientation,
+        start: f64,
+        duration: Option<f64>,
+        easing: Easing,
+        on_end: Option<js_sys::Function>,
+    ) -> Self {
+        let to = Orientation {
+            rotation: from.rotation + wrap(to.rotation - from.rotation),
+            roll: from.roll + wrap(to.roll - from.roll),
+            ..to
+        };
+        Self {
+            from,
+            to,
+            start,
+            duration: duration.unwrap_or(DEFAULT_DURATION).max(0.0),
+            easing,
+            arc: Some((Quaternion::from(from), Quaternion::from(to))),
+            zoom: None,
+            on_end,
+        }
+    }
+
+    /// Zoom from one factor to another along with the rotation.
+    pub fn zooming(mut self, from: f64, to: f64) -> Self {
+        self.zoom = Some((from, to));
+        self
+    }
+
+    /// Orientation at a time, and whether the animation has finished.
+    pub fn at(&self, time: f64) -> (Orientation, bool) {
+        let (t, s) = self.progress(time);
+        if t >= 1.0 {
+            return (self.to, true);
+        }
+        if let Some((from, to)) = self.arc {
+            return (from.slerp(to, s).into(), false);
+        }
+        let lerp = |from: f64, to: f64| from + (to - from) * s;
+        (
+            Orientation {
+                rotation: lerp(self.from.rotation, self.to.rotation),
+                tilt: lerp(self.from.tilt, self.to.tilt),
+                roll: lerp(self.from.roll, self.to.roll),
+            },
+            false,
+        )
+    }
+
+    /// Zoom factor at a time, for animations that zoom.
+    pub fn zoom_at(&self, time: f64) -> Option<f64> {
+        let (from, to) = self.zoom?;
+        // Zooming geometrically keeps the apparent rate of zoom constant
+        let (_, s) = self.progress(time);
+        Some(from * (to / from).powf(s))
+    }
+
+    /// Fraction of the duration elapsed at a time, and the progress eased over it.
+    fn progress(&self, time: f64) -> (f64, f64) {
+        let t = if self.duration > 0.0 {
+            ((time - self.start) / self.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        (t, self.easing.apply(t))
+    }
+
+    /// End the animation as finished.
+    pub fn finish(mut self) {
+        self.end(true);
+    }
+
+    fn end(&mut self, finished: bool) {
+        if let Some(on_end) = self.on_end.take() {
+            // Errors thrown by the host application should not stop the globe
+            if let Err(error) = on_end.call1(&JsValue::NULL, &finished.into()) {
+                web_sys::console::error_1(&error);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for CameraAnimation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CameraAnimation")
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("start", &self.start)
+            .field("duration", &self.duration)
+            .field("easing", &self.easing)
+            .field("zoom", &self.zoom)
+            .finish()
+    }
+}
+
+// Animations replaced or stopped before finishing end as interrupted
+impl Drop for CameraAnimation {
+    fn drop(&mut self) {
+        self.end(false);
+    }
+}
+
+/// Orientation a fraction of the way from one orientation towards another, rotating and
+/// rolling the shortest way, for following a moving target smoothly.
+pub fn approach(from: Orientation, to: Orientation, fraction: f64) -> Orientation {
+    Orientation {
+        rotation: from.rotation + wrap(to.rotation - from.rotation) * fraction,
+        tilt: from.tilt + (to.tilt - from.tilt) * fraction,
+        roll: from.roll + wrap(to.roll - from.roll) * fraction,
+    }
+}
+
+/// Ease in and out over the fraction of an animation elapsed.
+pub fn ease(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Wrap an angle (degrees) to the range [-180, 180).
+fn wrap(angle: f64) -> f64 {
+    (angle + 180.0).rem_euclid(360.0) - 180.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::LonLat;
+
+    #[test]
+    fn flies_along_the_great_circle() {
+        for easing in [
+            Easing::Linear,
+            Easing::In,
+            Easing::Out,
+            Easing::InOut,
+            Easing::InOutCubic,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert_eq!(Easing::InOutCubic.apply(0.5), 0.5);
+
+        let from = Orientation::default();
+        let to = from.centred_on(LonLat::new(90.0, 0.0));
+        let flight = CameraAnimation::fly(from, to, 0.0, Some(2000.0), Easing::Linear, None);
+        // Halfway along the equator, the centre of the view is at 45 degrees east
+        let (halfway, finished) = flight.at(1000.0);
+        assert!(!finished);
+        let (x, y, z) = halfway.project(45.0, 0.0);
+        assert!((x - 1.0).abs() < 1e-9 && y.abs() < 1e-9 && z.abs() < 1e-9);
+        assert_eq!(flight.at(2500.0), (to, true));
+    }
+
+    #[test]
+    fn zooms_with_the_rotation() {
+        let from = Orientation::default();
+        let animation = CameraAnimation::new(from, from.centred_on(LonLat::new(20.0, 10.0)), 0.0);
+        assert_eq!(animation.zoom_at(500.0), None);
+        let animation = animation.zooming(1.0, 4.0);
+        assert_eq!(animation.zoom_at(0.0), Some(1.0));
+        // Halfway through, the zoom is halfway in ratio
+        assert!((animation.zoom_at(500.0).unwrap() - 2.0).abs() < 1e-9);
+        assert_eq!(animation.zoom_at(1500.0), Some(4.0));
+    }
+}
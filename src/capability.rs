@@ -0,0 +1,214 @@
+// Probing the capabilities of the device to select a rendering profile, so the globe stays
+// usable on low-end hardware.
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, Document, HtmlCanvasElement, Window};
+
+// Devices reporting less memory (gigabytes) than this are low-end
+const LOW_DEVICE_MEMORY: f64 = 2.0;
+// Frames taking longer (milliseconds) than this to draw are too slow
+const SLOW_FRAME_TIME: f64 = 50.0;
+// Canvas pixels per backing store pixel for the reduced profile
+const REDUCED_PIXEL_RATIO: f64 = 0.5;
+// Every this many points of the coastlines are drawn for the reduced profile
+const REDUCED_GEOMETRY_STRIDE: usize = 3;
+// Frames without changes before the globe is at rest and supersampled
+const SUPERSAMPLE_IDLE_FRAMES: u32 = 10;
+const SUPERSAMPLE_FACTOR: f64 = 2.0;
+// Greatest ratio of backing store to canvas pixels supersampling goes to, bounding the memory
+// of the backing store on high-density displays
+const MAX_SUPERSAMPLE_PIXEL_RATIO: f64 = 3.0;
+
+/// Profile of the quality of rendering.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderProfile {
+    /// Full geometry and resolution, with post-processing effects.
+    #[default]
+    Full,
+    /// Simplified geometry at a lower resolution, without post-processing effects.
+    Reduced,
+}
+
+#[derive(Debug)]
+pub struct RenderSettings {
+    /// Profile selected by probing the capabilities of the device.
+    pub probed: RenderProfile,
+    /// Profile set through the API, in place of the probed profile.
+    pub overridden: Option<RenderProfile>,
+    /// Whether frames are supersampled while the globe is at rest.
+    pub supersampling: bool,
+    /// Whether segments of lines on the back of the globe are skipped rather than stroked.
+    pub back_culling: bool,
+    /// Device pixels per CSS pixel of the display the canvas is on.
+    device_pixel_ratio: f64,
+    /// Whether the frame is supersampled, for crisper lines.
+    supersampled: bool,
+    /// Frames drawn since the last change.
+    idle_frames: u32,
+    /// Ratio of backing store to canvas pixels the canvas was last sized for.
+    applied_pixel_ratio: Option<f64>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            probed: RenderProfile::Full,
+            overridden: None,
+            supersampling: true,
+            back_culling: false,
+            device_pixel_ratio: 1.0,
+            supersampled: false,
+            idle_frames: 0,
+            applied_pixel_ratio: None,
+        }
+    }
+}
+
+impl RenderSettings {
+    pub fn profile(&self) -> RenderProfile {
+        self.overridden.unwrap_or(self.probed)
+    }
+
+    /// Ratio of backing store to canvas pixels, matching the pixels of the display for the full
+    /// profile.
+    pub fn pixel_ratio(&self) -> f64 {
+        let pixel_ratio = self.device_pixel_ratio
+            * match self.profile() {
+                RenderProfile::Full => 1.0,
+                RenderProfile::Reduced => REDUCED_PIXEL_RATIO,
+            };
+        if self.supersampled {
+            (pixel_ratio * SUPERSAMPLE_FACTOR).min(MAX_SUPERSAMPLE_PIXEL_RATIO.max(pixel_ratio))
+        } else {
+            pixel_ratio
+        }
+    }
+
+    /// Follow the device pixel ratio of the display, as when the window moves to another
+    /// monitor or the page is zoomed; the canvas is resized at the next frame on a change.
+    pub fn set_device_pixel_ratio(&mut self, ratio: f64) {
+        if ratio.is_finite() && ratio > 0.0 {
+            self.device_pixel_ratio = ratio;
+        }
+    }
+
+    /// Track whether the frame changed; once the globe has been at rest for a few frames
+    /// it is supersampled, until it changes again.
+    pub fn settle(&mut self, changed: bool) {
+        if changed {
+            self.idle_frames = 0;
+            self.supersampled = false;
+        } else if self.idle_frames < SUPERSAMPLE_IDLE_FRAMES {
+            self.idle_frames += 1;
+        } else {
+            self.supersampled = self.supersampling;
+        }
+    }
+
+    /// Whether the globe has been at rest long enough to be supersampled, and the canvas is
+    /// sized for the ratio of its pixels, so frames at rest change nothing.
+    pub fn settled(&self) -> bool {
+        self.idle_frames >= SUPERSAMPLE_IDLE_FRAMES
+            && self.supersampled == self.supersampling
+            && self.applied_pixel_ratio == Some(self.pixel_ratio())
+    }
+
+    /// Step between the points of the coastlines drawn.
+    pub fn geometry_stride(&self) -> usize {
+        match self.profile() {
+            RenderProfile::Full => 1,
+            RenderProfile::Reduced => REDUCED_GEOMETRY_STRIDE,
+        }
+    }
+
+    /// Whether post-processing effects are applied.
+    pub fn post_effects(&self) -> bool {
+        self.profile() == RenderProfile::Full
+    }
+
+    /// Resize the canvas at the next frame, as for a change of its size.
+    pub fn resize(&mut self) {
+        self.applied_pixel_ratio = None;
+    }
+
+    /// Ratio of backing store to canvas pixels when the canvas needs resizing for it.
+    pub fn take_resize(&mut self) -> Option<f64> {
+        let pixel_ratio = self.pixel_ratio();
+        (self.applied_pixel_ratio != Some(pixel_ratio)).then(|| {
+            self.applied_pixel_ratio = Some(pixel_ratio);
+            pixel_ratio
+        })
+    }
+}
+
+/// Capabilities of the device relevant to rendering.
+#[derive(Debug)]
+pub struct Capabilities {
+    /// Whether a canvas of the size drawn can be drawn on.
+    pub canvas_size: bool,
+    /// Approximate memory (gigabytes) of the device, where reported.
+    pub device_memory: Option<f64>,
+    /// Time (milliseconds) taken to draw the first frame.
+    pub first_frame: Option<f64>,
+}
+
+impl Capabilities {
+    /// Probe the limits of canvases and the hints of the browser about the device.
+    pub fn probe(
+        window: &Window,
+        document: &Document,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, JsValue> {
+        // Not all browsers report the memory of the device
+        let device_memory =
+            js_sys::Reflect::get(&window.navigator(), &"deviceMemory".into())?.as_f64();
+        Ok(Self {
+            canvas_size: canvas_size_supported(document, width, height)?,
+            device_memory,
+            first_frame: None,
+        })
+    }
+
+    /// Capabilities assumed where they cannot be probed, as in a worker without a document.
+    pub fn unprobed() -> Self {
+        Self {
+            canvas_size: true,
+            device_memory: None,
+            first_frame: None,
+        }
+    }
+
+    /// Profile suited to the capabilities.
+    pub fn profile(&self) -> RenderProfile {
+        let low_memory = self
+            .device_memory
+            .is_some_and(|memory| memory < LOW_DEVICE_MEMORY);
+        let slow = self.first_frame.is_some_and(|time| time > SLOW_FRAME_TIME);
+        if !self.canvas_size || low_memory || slow {
+            RenderProfile::Reduced
+        } else {
+            RenderProfile::Full
+        }
+    }
+}
+
+/// Whether a canvas of a size (pixels) can be drawn on; browsers silently fail to draw on
+/// canvases beyond their limits, so a pixel in the far corner is drawn and read back.
+fn canvas_size_supported(document: &Document, width: u32, height: u32) -> Result<bool, JsValue> {
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(width);
+    canvas.set_height(height);
+    let Some(context) = canvas.get_context("2d")? else {
+        return Ok(false);
+    };
+    let context = context.dyn_into::<CanvasRenderingContext2d>()?;
+    let (x, y) = (width as f64 - 1.0, height as f64 - 1.0);
+    context.fill_rect(x, y, 1.0, 1.0);
+    let pixel = context.get_image_data(x, y, 1.0, 1.0)?.data();
+    Ok(pixel.get(3) == Some(&255))
+}
@@ -0,0 +1,174 @@
+// Experimental non-contiguous cartogram, scaling each country about its centroid by a value.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::camera::ease;
+use crate::coords::LonLat;
+use crate::view::Frame;
+use crate::{data, measure};
+
+const CARTOGRAM_FILL_STYLE: &str = "rgba(200, 60, 60, 0.6)";
+const CARTOGRAM_STROKE_STYLE: &str = "rgba(120, 0, 0, 1.0)";
+const CARTOGRAM_OUTLINE_STYLE: &str = "rgba(0, 0, 0, 0.2)";
+const CARTOGRAM_LINE_WIDTH: f64 = 0.002;
+// Times are in milliseconds
+const CARTOGRAM_TRANSITION: f64 = 750.0;
+
+#[derive(Debug, Default)]
+pub struct Cartogram {
+    /// Value of each country, by feature index.
+    values: Vec<Option<f64>>,
+    /// Scale of each country when the values last changed, transitioning to their new scales.
+    from: Vec<f64>,
+    changed: f64,
+    transitioning: bool,
+}
+
+impl Cartogram {
+    /// Set the value of a country, by feature index, transitioning from the
+    /// current scales at a time; returns whether the country exists.
+    pub fn set_value(&mut self, country: usize, value: Option<f64>, time: f64) -> bool {
+        if country >= data::COUNTRY_POINTS.len() {
+            return false;
+        }
+        self.from = (0..data::COUNTRY_POINTS.len())
+            .map(|i| self.scale(i, time))
+            .collect();
+        self.changed = time;
+        self.transitioning = true;
+        self.values.resize(data::COUNTRY_POINTS.len(), None);
+        self.values[country] = value;
+        true
+    }
+
+    /// Remove the values of all countries, transitioning from the current scales at a time.
+    pub fn clear(&mut self, time: f64) {
+        self.from = (0..data::COUNTRY_POINTS.len())
+            .map(|i| self.scale(i, time))
+            .collect();
+        self.changed = time;
+        self.transitioning = true;
+        self.values.clear();
+    }
+
+    /// Whether the scales are transitioning, or have yet to be drawn once finished.
+    pub fn transitioning(&self) -> bool {
+        self.transitioning
+    }
+
+    /// Whether the scales are to be redrawn at a time while transitioning,
+    /// including once when the transition has finished.
+    pub fn animating(&mut self, time: f64) -> bool {
+        if time - self.changed >= CARTOGRAM_TRANSITION {
+            return std::mem::take(&mut self.transitioning);
+        }
+        self.transitioning
+    }
+
+    /// Scale of a country after transitioning, with area proportional to its value
+    /// relative to the largest value; countries without values have no area.
+    fn target(&self, country: usize) -> f64 {
+        let max_value = self
+            .values
+            .iter()
+            .flatten()
+            .map(|value| value.abs())
+            .fold(0.0, f64::max);
+        match self.values.get(country).copied().flatten() {
+            Some(value) if max_value > 0.0 => (value.abs() / max_value).sqrt(),
+            _ => 0.0,
+        }
+    }
+
+    /// Scale of a country at a time.
+    fn scale(&self, country: usize, time: f64) -> f64 {
+        let from = self.from.get(country).copied().unwrap_or(0.0);
+        let t = ((time - self.changed) / CARTOGRAM_TRANSITION).clamp(0.0, 1.0);
+        from + (self.target(country) - from) * ease(t)
+    }
+
+    /// Draw the outlines of the countries and the countries scaled about their centroids,
+    /// at a time, onto the canvas.
+    pub fn draw(
+        &self,
+        context: &CanvasRenderingContext2d,
+        frame: &Frame,
+        time: f64,
+    ) -> Result<(), JsValue> {
+        if self.from.is_empty() {
+            return Ok(());
+        }
+
+        context.set_line_width(frame.line_width(CARTOGRAM_LINE_WIDTH));
+        for (country, (points, bounds)) in data::COUNTRY_POINTS
+            .iter()
+            .zip(data::COUNTRY_BOUNDS)
+            .enumerate()
+        {
+            if !frame.shows("countries", country) {
+                continue;
+            }
+            let centroid = bounds.centroid;
+
+            context.begin_path();
+            for point in points {
+                let (lon, lat) = point.to_degrees();
+                let (y, z) = frame.project_to_disc(lon, lat);
+                context.line_to(y, z);
+            }
+            context.close_path();
+            context.set_stroke_style_str(CARTOGRAM_OUTLINE_STYLE);
+            context.stroke();
+
+            let scale = self.scale(country, time);
+            if scale <= 0.0 {
+                continue;
+            }
+            // Points are moved towards the centroid along great circles
+            context.begin_path();
+            for point in points {
+                let (lon, lat) = point.to_degrees();
+                let location = LonLat::new(lon, lat);
+                let angle = measure::central_angle(centroid, location);
+                let bearing = measure::bearing(centroid, location);
+                let moved = measure::destination(centroid, bearing, angle * scale);
+                let (y, z) = frame.project_to_disc(moved.lon, moved.lat);
+                context.line_to(y, z);
+            }
+            context.close_path();
+            context.set_fill_style_str(CARTOGRAM_FILL_STYLE);
+            context.fill();
+            context.set_stroke_style_str(CARTOGRAM_STROKE_STYLE);
+            context.stroke();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_area_with_value() {
+        let mut cartogram = Cartogram {
+            values: vec![Some(4.0), Some(-1.0), None],
+            transitioning: true,
+            ..Cartogram::default()
+        };
+        assert!(!cartogram.set_value(usize::MAX, Some(1.0), 0.0));
+        // Areas are proportional to absolute values, so scales to their square roots
+        assert_eq!(cartogram.target(0), 1.0);
+        assert_eq!(cartogram.target(1), 0.5);
+        assert_eq!(cartogram.target(2), 0.0);
+        // Scales transition from nothing, halfway at half the transition
+        let halfway = CARTOGRAM_TRANSITION / 2.0;
+        assert_eq!(cartogram.scale(0, halfway), 0.5);
+        assert_eq!(cartogram.scale(1, CARTOGRAM_TRANSITION), 0.5);
+        assert!(cartogram.animating(halfway));
+        assert!(cartogram.animating(CARTOGRAM_TRANSITION));
+        assert!(!cartogram.animating(CARTOGRAM_TRANSITION + 16.0));
+    }
+}
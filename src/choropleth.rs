@@ -0,0 +1,216 @@
+// Countries filled by color according to a time series of values, played back over time.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::data;
+use crate::legend::{Legend, LegendSymbols};
+use crate::view::Frame;
+
+// Colors (red, green, blue) of the lowest and highest values
+const CHOROPLETH_LOW_COLOR: [f64; 3] = [255.0, 255.0, 204.0];
+const CHOROPLETH_HIGH_COLOR: [f64; 3] = [189.0, 0.0, 38.0];
+const CHOROPLETH_ALPHA: f64 = 0.8;
+const CHOROPLETH_DEFAULT_SPEED: f64 = 1.0;
+const CHOROPLETH_DEFAULT_LEGEND_TITLE: &str = "Value";
+
+#[derive(Debug)]
+pub struct Choropleth {
+    /// Values of each country, by feature index, at each time step; `NaN` where there is no value.
+    series: Vec<Vec<f64>>,
+    /// Range of the values, mapped to the color ramp.
+    range: (f64, f64),
+    /// Position in the series, in time steps.
+    position: f64,
+    pub playing: bool,
+    /// Time steps played per second.
+    pub speed: f64,
+    /// Time (milliseconds) playback was last advanced.
+    last_tick: Option<f64>,
+    pub legend_title: String,
+}
+
+impl Default for Choropleth {
+    fn default() -> Self {
+        Self {
+            series: Vec::new(),
+            range: (0.0, 0.0),
+            position: 0.0,
+            playing: false,
+            speed: CHOROPLETH_DEFAULT_SPEED,
+            last_tick: None,
+            legend_title: CHOROPLETH_DEFAULT_LEGEND_TITLE.to_string(),
+        }
+    }
+}
+
+impl Choropleth {
+    /// Set the series of values, given as the values of every country for each time
+    /// step in turn, returning whether the number of values matches the countries.
+    pub fn set_series(&mut self, values: &[f64]) -> bool {
+        let countries = data::COUNTRY_POINTS.len();
+        if countries == 0 || !values.len().is_multiple_of(countries) {
+            return false;
+        }
+        self.series = values.chunks(countries).map(|step| step.to_vec()).collect();
+        let finite = values.iter().copied().filter(|value| value.is_finite());
+        self.range = finite.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+            (min.min(value), max.max(value))
+        });
+        self.seek(self.position);
+        true
+    }
+
+    /// Number of time steps in the series.
+    pub fn steps(&self) -> usize {
+        self.series.len()
+    }
+
+    /// Position in the series, in time steps.
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    /// Move to a position in the series, in time steps.
+    pub fn seek(&mut self, position: f64) {
+        let last = self.steps().saturating_sub(1) as f64;
+        self.position = position.clamp(0.0, last);
+    }
+
+    /// Advance playback to a time (milliseconds), returning whether the position changed;
+    /// playback pauses at the end of the series.
+    pub fn tick(&mut self, time: f64) -> bool {
+        if !self.playing {
+            self.last_tick = None;
+            return false;
+        }
+        let elapsed = self.last_tick.map_or(0.0, |last_tick| time - last_tick);
+        self.last_tick = Some(time);
+
+        let previous = self.position;
+        self.seek(self.position + elapsed / 1000.0 * self.speed);
+        if self.position >= self.steps().saturating_sub(1) as f64 {
+            self.playing = false;
+        }
+        self.position != previous
+    }
+
+    /// Color (red, green, blue) of a value on the ramp.
+    fn color(&self, value: f64) -> [f64; 3] {
+        let (min, max) = self.range;
+        let t = if max > min {
+            ((value - min) / (max - min)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        lerp_color(CHOROPLETH_LOW_COLOR, CHOROPLETH_HIGH_COLOR, t)
+    }
+
+    /// Fill style of a country at the current position, interpolating between the colors of
+    /// the time steps either side; countries without values at both steps are not filled.
+    fn fill_style(&self, country: usize) -> Option<String> {
+        let step = self.position.floor() as usize;
+        let next = (step + 1).min(self.steps() - 1);
+        let (value, next_value) = (self.series[step][country], self.series[next][country]);
+        let color = match (value.is_finite(), next_value.is_finite()) {
+            (true, true) => lerp_color(
+                self.color(value),
+                self.color(next_value),
+                self.position - step as f64,
+            ),
+            (true, false) => self.color(value),
+            (false, true) => self.color(next_value),
+            (false, false) => return None,
+        };
+        Some(color_style(color))
+    }
+
+    /// Legend of the color ramp over the range of the values, when shown.
+    pub fn legend(&self) -> Option<Legend> {
+        let (min, max) = self.range;
+        if self.series.is_empty() || min > max {
+            return None;
+        }
+        Some(Legend {
+            title: self.legend_title.clone(),
+            symbols: LegendSymbols::Ramp {
+                low: color_style(CHOROPLETH_LOW_COLOR),
+                high: color_style(CHOROPLETH_HIGH_COLOR),
+                min: min.to_string(),
+                max: max.to_string(),
+            },
+        })
+    }
+
+    /// Fill the countries onto the canvas.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        if self.series.is_empty() {
+            return Ok(());
+        }
+
+        for (country, points) in data::COUNTRY_POINTS.iter().enumerate() {
+            if !frame.shows("countries", country) {
+                continue;
+            }
+            let Some(fill_style) = self.fill_style(country) else {
+                continue;
+            };
+            context.begin_path();
+            for point in points {
+                let (lon, lat) = point.to_degrees();
+                let (y, z) = frame.project_to_disc(lon, lat);
+                context.line_to(y, z);
+            }
+            context.close_path();
+            context.set_fill_style_str(&fill_style);
+            context.fill();
+        }
+
+        Ok(())
+    }
+}
+
+fn color_style(color: [f64; 3]) -> String {
+    format!(
+        "rgba({:.0}, {:.0}, {:.0}, {})",
+        color[0], color[1], color[2], CHOROPLETH_ALPHA
+    )
+}
+
+fn lerp_color(from: [f64; 3], to: [f64; 3], t: f64) -> [f64; 3] {
+    [0, 1, 2].map(|i| from[i] + (to[i] - from[i]) * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_between_frames() {
+        let mut choropleth = Choropleth {
+            series: vec![vec![0.0, f64::NAN, f64::NAN], vec![10.0, 10.0, f64::NAN]],
+            range: (0.0, 10.0),
+            playing: true,
+            speed: 2.0,
+            ..Choropleth::default()
+        };
+        // Playback starts at the first tick, and pauses at the end of the series
+        assert!(!choropleth.tick(1000.0));
+        assert!(choropleth.tick(1250.0));
+        assert_eq!(choropleth.position(), 0.5);
+        assert!(choropleth.playing);
+
+        // Colors are interpolated between time steps, or held where a value is missing
+        let halfway = lerp_color(CHOROPLETH_LOW_COLOR, CHOROPLETH_HIGH_COLOR, 0.5);
+        assert_eq!(choropleth.fill_style(0), Some(color_style(halfway)));
+        assert_eq!(
+            choropleth.fill_style(1),
+            Some(color_style(CHOROPLETH_HIGH_COLOR))
+        );
+        assert_eq!(choropleth.fill_style(2), None);
+
+        assert!(choropleth.tick(2000.0));
+        assert_eq!(choropleth.position(), 1.0);
+        assert!(!choropleth.playing);
+    }
+}
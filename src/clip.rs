@@ -0,0 +1,84 @@
+// Clipping of everything drawn to a shape, so the globe can be inset into non-rectangular
+// elements of a page.
+
+use wasm_bindgen::JsValue;
+use web_sys::{CanvasRenderingContext2d, Path2d};
+
+use crate::view::Frame;
+
+/// Shape (canvas pixels) everything drawn is clipped to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClipShape {
+    Circle {
+        x: f64,
+        y: f64,
+        radius: f64,
+    },
+    /// Rectangle from its top left corner, with corners rounded to a radius.
+    RoundedRect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        radius: f64,
+    },
+    Path(Path2d),
+}
+
+impl ClipShape {
+    /// Clip subsequent drawing to the shape; the clip is removed by restoring the context.
+    pub fn apply(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        // The shape is traced in canvas pixels, keeping the transform to the unit sphere
+        let unit = context.get_transform()?;
+        context.set_transform(frame.pixel_ratio, 0.0, 0.0, frame.pixel_ratio, 0.0, 0.0)?;
+        match self {
+            ClipShape::Circle { x, y, radius } => {
+                context.begin_path();
+                context.arc(*x, *y, radius.max(0.0), 0.0, std::f64::consts::TAU)?;
+                context.clip();
+            }
+            ClipShape::RoundedRect {
+                x,
+                y,
+                width,
+                height,
+                radius,
+            } => {
+                let (right, bottom) = (x + width, y + height);
+                let radius = corner_radius(*width, *height, *radius);
+                context.begin_path();
+                context.move_to(x + radius, *y);
+                context.arc_to(right, *y, right, bottom, radius)?;
+                context.arc_to(right, bottom, *x, bottom, radius)?;
+                context.arc_to(*x, bottom, *x, *y, radius)?;
+                context.arc_to(*x, *y, right, *y, radius)?;
+                context.close_path();
+                context.clip();
+            }
+            ClipShape::Path(path) => context.clip_with_path_2d(path),
+        }
+        context.set_transform(unit.a(), unit.b(), unit.c(), unit.d(), unit.e(), unit.f())?;
+
+        Ok(())
+    }
+}
+
+/// Radius (canvas pixels) of the corners of a rounded rectangle, at most half its shorter
+/// side so the corners meet rather than overlap.
+fn corner_radius(width: f64, height: f64, radius: f64) -> f64 {
+    radius.clamp(0.0, width.min(height).max(0.0) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_the_corners_of_rounded_rects() {
+        assert_eq!(corner_radius(100.0, 50.0, 10.0), 10.0);
+        // Corners larger than the rectangle make a stadium
+        assert_eq!(corner_radius(100.0, 50.0, 40.0), 25.0);
+        assert_eq!(corner_radius(100.0, 50.0, -5.0), 0.0);
+        assert_eq!(corner_radius(-100.0, 50.0, 10.0), 0.0);
+    }
+}
@@ -0,0 +1,184 @@
+// Configuration of a globe given declaratively, as the query string of the script loading it,
+// the `data-*` attributes of the element hosting it, or the options of its constructor, so
+// simple embeds need no script of their own. Keys are written in kebab case, as in
+// `data-day-night`, or in camel case, as in `dayNight`.
+
+use web_sys::Element;
+
+/// Attribute marking the script and the element hosting the globe created at start up.
+#[cfg(feature = "autostart")]
+const HOST_ATTRIBUTE: &str = "data-globe";
+
+/// Initial configuration of a globe, each setting left as it is when not given.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    /// Location (degrees) at the centre of the view.
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub zoom: Option<f64>,
+    /// Name of the style of the base layers.
+    pub theme: Option<String>,
+    pub graticule: Option<bool>,
+    pub country_labels: Option<bool>,
+    pub day_night: Option<bool>,
+    /// Whether the globe responds to dragging and the wheel.
+    pub interactive: Option<bool>,
+    pub inertia: Option<bool>,
+    /// Speed (degrees per second) the globe turns at while idle.
+    pub auto_rotate: Option<f64>,
+    pub responsive: Option<bool>,
+    pub hash: Option<bool>,
+}
+
+impl Config {
+    /// Set a setting by key from a value written as text, unsetting it when the value is
+    /// invalid; returns whether the key is known. Flags given without a value, as attributes
+    /// can be, are set.
+    pub fn set(&mut self, key: &str, value: &str) -> bool {
+        let value = value.trim();
+        let number = || value.parse::<f64>().ok().filter(|value| value.is_finite());
+        let flag = || match value {
+            "" | "true" | "1" | "yes" | "on" => Some(true),
+            "false" | "0" | "no" | "off" => Some(false),
+            _ => None,
+        };
+        match kebab_case(key).as_str() {
+            "lat" => self.lat = number().filter(|lat| lat.abs() <= 90.0),
+            "lon" => self.lon = number(),
+            "zoom" => self.zoom = number().filter(|zoom| *zoom > 0.0),
+            "theme" => self.theme = Some(value.to_string()).filter(|theme| !theme.is_empty()),
+            "graticule" => self.graticule = flag(),
+            "country-labels" | "labels" => self.country_labels = flag(),
+            "day-night" => self.day_night = flag(),
+            "interactive" => self.interactive = flag(),
+            "inertia" => self.inertia = flag(),
+            "auto-rotate" => self.auto_rotate = number(),
+            "responsive" => self.responsive = flag(),
+            "hash" => self.hash = flag(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Set the settings of the parameters of a query string, with or without its leading
+    /// `?`, skipping unknown keys.
+    #[cfg(feature = "autostart")]
+    pub fn set_query(&mut self, query: &str) {
+        for pair in query.trim_start_matches('?').split('&') {
+            if pair.is_empty() {
+                continue;
+            }
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let decoded = |text: &str| {
+                js_sys::decode_uri_component(&text.replace('+', " "))
+                    .map(String::from)
+                    .unwrap_or_else(|_| text.to_string())
+            };
+            self.set(&decoded(key), &decoded(value));
+        }
+    }
+
+    /// Set the settings of the `data-*` attributes of an element, skipping unknown keys.
+    pub fn set_attributes(&mut self, element: &Element) {
+        for name in element.get_attribute_names() {
+            let Some(name) = name.as_string() else {
+                continue;
+            };
+            if let (Some(key), Some(value)) =
+                (name.strip_prefix("data-"), element.get_attribute(&name))
+            {
+                self.set(key, &value);
+            }
+        }
+    }
+
+    /// Set the settings of the properties of an object, with numbers, booleans or text as
+    /// values, skipping unknown keys.
+    pub fn set_object(&mut self, object: &js_sys::Object) {
+        for entry in js_sys::Object::entries(object) {
+            let entry = js_sys::Array::from(&entry);
+            let key = entry.get(0).as_string().unwrap_or_default();
+            let value = entry.get(1);
+            let text = match (value.as_bool(), value.as_f64(), value.as_string()) {
+                (Some(flag), _, _) => flag.to_string(),
+                (_, Some(number), _) => number.to_string(),
+                (_, _, Some(text)) => text,
+                _ => continue,
+            };
+            self.set(&key, &text);
+        }
+    }
+
+    /// Configuration of the page for the globe created at start up, from the query string of
+    /// the script marked with `data-globe`, or the current script, then the attributes of the
+    /// element marked with `data-globe` hosting the globe, which is returned.
+    #[cfg(feature = "autostart")]
+    pub fn from_page(
+        document: &web_sys::Document,
+    ) -> Result<(Config, Option<Element>), wasm_bindgen::JsValue> {
+        use wasm_bindgen::JsCast;
+
+        let mut config = Config::default();
+        // Scripts run as modules have no current script
+        let script = match document.current_script() {
+            Some(script) => Some(script),
+            None => document.query_selector(&format!("script[{}]", HOST_ATTRIBUTE))?,
+        };
+        let script = script.and_then(|script| script.dyn_into::<web_sys::HtmlScriptElement>().ok());
+        if let Some(script) = script {
+            let src = script.src();
+            if let Some((_, query)) = src.split_once('?') {
+                config.set_query(query.split('#').next().unwrap_or_default());
+            }
+        }
+        let host = document.query_selector(&format!("[{}]:not(script)", HOST_ATTRIBUTE))?;
+        if let Some(host) = &host {
+            config.set_attributes(host);
+        }
+        Ok((config, host))
+    }
+}
+
+/// Key written in kebab case, as from camel case.
+fn kebab_case(key: &str) -> String {
+    let mut kebab = String::with_capacity(key.len());
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            kebab.push('-');
+            kebab.push(c.to_ascii_lowercase());
+        } else {
+            kebab.push(c);
+        }
+    }
+    kebab
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_settings_written_as_text() {
+        let mut config = Config::default();
+        assert!(config.set("lat", "51.5"));
+        assert!(config.set("dayNight", ""));
+        assert!(config.set("country-labels", "false"));
+        assert!(config.set("autoRotate", "10"));
+        assert!(config.set("theme", "dark"));
+        assert!(!config.set("colour", "red"));
+        // Invalid values leave the setting unset
+        assert!(config.set("zoom", "-1"));
+        assert!(config.set("graticule", "maybe"));
+        assert_eq!(
+            config,
+            Config {
+                lat: Some(51.5),
+                theme: Some("dark".to_string()),
+                country_labels: Some(false),
+                day_night: Some(true),
+                auto_rotate: Some(10.0),
+                ..Config::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,345 @@
+// Contour lines of gridded data, such as temperature or pressure, traced by marching squares.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::coords::LonLat;
+use crate::labels::Label;
+use crate::view::{Frame, Side};
+
+const CONTOUR_STROKE_STYLE: &str = "rgba(127, 0, 63, 0.8)";
+const CONTOUR_LINE_WIDTH: f64 = 0.003;
+const CONTOUR_DEFAULT_INTERVAL: f64 = 10.0;
+// Levels beyond this many are not traced, bounding the work for small intervals
+const CONTOUR_MAX_LEVELS: usize = 200;
+// Lines are labelled every this many segments
+const CONTOUR_LABEL_SPACING: usize = 40;
+// Contour labels give way to labels of other layers
+const CONTOUR_LABEL_PRIORITY: f64 = -1.0;
+
+/// Values at the points of a grid regularly spaced in latitude and longitude.
+#[derive(Debug)]
+struct Grid {
+    /// Values by row from north to south, each from west to east; `NaN` where missing.
+    values: Vec<f64>,
+    columns: usize,
+    rows: usize,
+    /// Bounds (degrees) of the grid, at the outer points.
+    west: f64,
+    south: f64,
+    east: f64,
+    north: f64,
+}
+
+impl Grid {
+    fn value(&self, row: usize, column: usize) -> f64 {
+        self.values[row * self.columns + column]
+    }
+
+    /// Value at a location (degrees), interpolated bilinearly between the surrounding grid
+    /// points, or `None` outside the grid or next to a missing value.
+    fn sample(&self, lon: f64, lat: f64) -> Option<f64> {
+        // Longitudes are taken the way around the globe that falls within the grid
+        let lon = self.west + (lon - self.west).rem_euclid(360.0);
+        let column = (lon - self.west) / (self.east - self.west) * (self.columns - 1) as f64;
+        let row = (self.north - lat) / (self.north - self.south) * (self.rows - 1) as f64;
+        let (max_row, max_column) = ((self.rows - 1) as f64, (self.columns - 1) as f64);
+        if !(0.0..=max_row).contains(&row) || !(0.0..=max_column).contains(&column) {
+            return None;
+        }
+        // The last row and column are sampled from the squares before them
+        let (row0, column0) = (
+            row.floor().min(max_row - 1.0),
+            column.floor().min(max_column - 1.0),
+        );
+        let (dr, dc) = (row - row0, column - column0);
+        let mut value = 0.0;
+        for (r, c, weight) in [
+            (0, 0, (1.0 - dr) * (1.0 - dc)),
+            (0, 1, (1.0 - dr) * dc),
+            (1, 0, dr * (1.0 - dc)),
+            (1, 1, dr * dc),
+        ] {
+            // Points without weight do not count, so values on the grid are exact
+            if weight > 0.0 {
+                let point = self.value(row0 as usize + r, column0 as usize + c);
+                if !point.is_finite() {
+                    return None;
+                }
+                value += weight * point;
+            }
+        }
+        Some(value)
+    }
+
+    /// Location (degrees, as longitude and latitude) at fractional grid coordinates.
+    fn location(&self, row: f64, column: f64) -> (f64, f64) {
+        (
+            self.west + (self.east - self.west) * column / (self.columns - 1) as f64,
+            self.north - (self.north - self.south) * row / (self.rows - 1) as f64,
+        )
+    }
+}
+
+/// Contour line at a level, as segments between locations (degrees, as longitude and latitude).
+#[derive(Debug)]
+struct Contour {
+    level: f64,
+    segments: Vec<[(f64, f64); 2]>,
+}
+
+#[derive(Debug)]
+pub struct Contours {
+    grid: Option<Grid>,
+    /// Difference between the values of neighbouring levels.
+    interval: f64,
+    /// Value of a level, from which the others are spaced by the interval.
+    base: f64,
+    contours: Vec<Contour>,
+}
+
+impl Default for Contours {
+    fn default() -> Self {
+        Self {
+            grid: None,
+            interval: CONTOUR_DEFAULT_INTERVAL,
+            base: 0.0,
+            contours: Vec::new(),
+        }
+    }
+}
+
+impl Contours {
+    /// Set the gridded values, by row from north to south, each from west to east, with
+    /// the bounds (degrees) of the grid; returns whether the number of values matches.
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_grid(
+        &mut self,
+        values: &[f64],
+        columns: usize,
+        rows: usize,
+        west: f64,
+        south: f64,
+        east: f64,
+        north: f64,
+    ) -> bool {
+        if columns < 2 || rows < 2 || values.len() != columns * rows {
+            return false;
+        }
+        self.grid = Some(Grid {
+            values: values.to_vec(),
+            columns,
+            rows,
+            west,
+            south,
+            east,
+            north,
+        });
+        self.trace();
+        true
+    }
+
+    /// Set the levels traced, spaced by an interval from a base level.
+    pub fn set_levels(&mut self, interval: f64, base: f64) {
+        self.interval = interval;
+        self.base = base;
+        self.trace();
+    }
+
+    /// Gridded value at a location (degrees), interpolated between the grid points, or `None`
+    /// where there is no value.
+    pub fn sample(&self, location: LonLat) -> Option<f64> {
+        self.grid.as_ref()?.sample(location.lon, location.lat)
+    }
+
+    /// Remove the gridded values.
+    pub fn clear(&mut self) {
+        self.grid = None;
+        self.contours.clear();
+    }
+
+    fn trace(&mut self) {
+        self.contours.clear();
+        let Some(grid) = &self.grid else {
+            return;
+        };
+        if self.interval.is_nan() || self.interval <= 0.0 {
+            return;
+        }
+        let (min, max) = grid
+            .values
+            .iter()
+            .filter(|value| value.is_finite())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                (min.min(*value), max.max(*value))
+            });
+        if min > max {
+            return;
+        }
+        let first = ((min - self.base) / self.interval).ceil() as i64;
+        let last = ((max - self.base) / self.interval).floor() as i64;
+        self.contours = (first..=last)
+            .take(CONTOUR_MAX_LEVELS)
+            .map(|step| {
+                let level = self.base + step as f64 * self.interval;
+                Contour {
+                    level,
+                    segments: march(grid, level),
+                }
+            })
+            .collect();
+    }
+
+    /// Labels of the levels at intervals along their lines on the front of the globe.
+    pub fn labels(&self, frame: &Frame) -> Vec<Label> {
+        let mut labels = Vec::new();
+        for contour in &self.contours {
+            for [(lon, lat), _] in contour.segments.iter().step_by(CONTOUR_LABEL_SPACING) {
+                let (x, y, z) = frame.project(*lon, *lat);
+                if x < 0.0 {
+                    continue;
+                }
+                let (x, y) = frame.to_canvas(y, z);
+                labels.push(Label {
+                    text: format!("{}", contour.level),
+                    x,
+                    y,
+                    priority: CONTOUR_LABEL_PRIORITY,
+                    allow_overlap: false,
+                });
+            }
+        }
+        labels
+    }
+
+    /// Draw the contour lines onto the canvas.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        context.set_line_width(frame.line_width(CONTOUR_LINE_WIDTH));
+        context.set_stroke_style_str(CONTOUR_STROKE_STYLE);
+        for contour in &self.contours {
+            context.begin_path();
+            for [(lon_from, lat_from), (lon_to, lat_to)] in &contour.segments {
+                let from = frame.project(*lon_from, *lat_from);
+                let to = frame.project(*lon_to, *lat_to);
+                if frame.segment_side(from, to) != Some(Side::Front) {
+                    continue;
+                }
+                context.move_to(from.1, from.2);
+                context.line_to(to.1, to.2);
+            }
+            context.stroke();
+        }
+
+        Ok(())
+    }
+}
+
+/// Segments of the contour at a level through each square of neighbouring grid points
+/// with values either side of it; squares with missing values are skipped.
+fn march(grid: &Grid, level: f64) -> Vec<[(f64, f64); 2]> {
+    let mut segments = Vec::new();
+    for row in 0..grid.rows - 1 {
+        for column in 0..grid.columns - 1 {
+            // Corners clockwise from the north west, as grid coordinates and values
+            let corners = [(0, 0), (0, 1), (1, 1), (1, 0)].map(|(dr, dc)| {
+                let (row, column) = (row + dr, column + dc);
+                (row as f64, column as f64, grid.value(row, column))
+            });
+            if corners.iter().any(|(_, _, value)| !value.is_finite()) {
+                continue;
+            }
+            let above = corners.map(|(_, _, value)| value >= level);
+
+            // Crossing of the level along each edge, from the edge's corner clockwise
+            let crossing = |edge: usize| {
+                let ((r0, c0, v0), (r1, c1, v1)) = (corners[edge], corners[(edge + 1) % 4]);
+                let t = (level - v0) / (v1 - v0);
+                grid.location(r0 + (r1 - r0) * t, c0 + (c1 - c0) * t)
+            };
+            let crossed = (0..4)
+                .filter(|edge| above[*edge] != above[(edge + 1) % 4])
+                .collect::<Vec<_>>();
+            match crossed.as_slice() {
+                [a, b] => segments.push([crossing(*a), crossing(*b)]),
+                // Saddles are resolved by the value at the centre of the square
+                [_, _, _, _] => {
+                    let centre = corners.iter().map(|(_, _, value)| value).sum::<f64>() / 4.0;
+                    let pairs = if (centre >= level) == above[0] {
+                        // The north west and south east corners are joined through the centre
+                        [(0, 1), (2, 3)]
+                    } else {
+                        [(3, 0), (1, 2)]
+                    };
+                    for (a, b) in pairs {
+                        segments.push([crossing(a), crossing(b)]);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traces_levels_between_values() {
+        let mut contours = Contours::default();
+        // Values rise from 0 in the west to 30 in the east
+        let values = [0.0, 15.0, 30.0].repeat(3);
+        assert!(contours.set_grid(&values, 3, 3, 0.0, 0.0, 20.0, 20.0));
+        let levels = contours
+            .contours
+            .iter()
+            .map(|contour| contour.level)
+            .collect::<Vec<_>>();
+        assert_eq!(levels, [0.0, 10.0, 20.0, 30.0]);
+
+        // The level of 20 runs north to south a third of the way across the eastern squares
+        let contour = &contours.contours[2];
+        assert_eq!(contour.segments.len(), 2);
+        for segment in &contour.segments {
+            for (lon, _) in segment {
+                assert!((lon - 10.0 - 10.0 / 3.0).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn resolves_saddles() {
+        let grid = Grid {
+            values: vec![1.0, 0.0, 0.0, 1.0],
+            columns: 2,
+            rows: 2,
+            west: 0.0,
+            south: 0.0,
+            east: 1.0,
+            north: 1.0,
+        };
+        assert_eq!(march(&grid, 0.5).len(), 2);
+    }
+
+    #[test]
+    fn samples_between_grid_points() {
+        let mut contours = Contours::default();
+        assert_eq!(contours.sample(LonLat::new(5.0, 5.0)), None);
+        let values = [0.0, 15.0, 30.0, 0.0, 15.0, 30.0, 0.0, f64::NAN, 30.0];
+        assert!(contours.set_grid(&values, 3, 3, 0.0, 0.0, 20.0, 20.0));
+        assert_eq!(contours.sample(LonLat::new(5.0, 15.0)), Some(7.5));
+        assert_eq!(contours.sample(LonLat::new(20.0, 20.0)), Some(30.0));
+        // Longitudes are wrapped onto the grid
+        assert_eq!(contours.sample(LonLat::new(-340.0, 10.0)), Some(30.0));
+        assert_eq!(contours.sample(LonLat::new(25.0, 10.0)), None);
+        // Values next to missing ones are unknown, but grid points beside them are not
+        assert_eq!(contours.sample(LonLat::new(5.0, 5.0)), None);
+        assert_eq!(contours.sample(LonLat::new(0.0, 0.0)), Some(0.0));
+    }
+
+    #[test]
+    fn rejects_mismatched_grids() {
+        assert!(!Contours::default().set_grid(&[0.0; 5], 2, 3, 0.0, 0.0, 1.0, 1.0));
+    }
+}
@@ -0,0 +1,706 @@
+// Pointer controls for rotating the globe.
+
+use wasm_bindgen::prelude::*;
+use web_sys::DomMatrix;
+
+use crate::cartesian_to_unit_spherical;
+use crate::coords::Vec3;
+use crate::projection::Projection;
+use crate::quaternion::Quaternion;
+use crate::view::Orientation;
+use crate::zoom::Zoom;
+
+// Velocities of drags are smoothed, weighting the latest by this
+const VELOCITY_SMOOTHING: f64 = 0.5;
+// Times are in milliseconds; longer gaps between frames, as when the page is hidden, are shortened
+const MAX_FRAME_INTERVAL: f64 = 100.0;
+// The globe stops coasting below this speed (degrees per millisecond)
+const MIN_COASTING_SPEED: f64 = 1e-4;
+const DEFAULT_FRICTION: f64 = 3.0;
+const DEFAULT_DRAG_SMOOTHING: f64 = 30.0;
+// The rotation applied reaches the pointer once within this distance (pixels) of it
+const SMOOTHING_SNAP_DISTANCE: f64 = 0.1;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct ControlData {
+    pub pressed: bool,
+    pub position: Position,
+    pub position_prev: Position,
+    pub orientation: Orientation,
+    pub zoom: Zoom,
+    /// Pressed pointers, by pointer identifier.
+    pointers: Vec<(i32, Position)>,
+    /// Angle (degrees) of the line between two pressed pointers when last applied.
+    twist_prev: Option<f64>,
+    /// Distance (pixels) between two pressed pointers when last applied.
+    pinch_prev: Option<f64>,
+    /// Velocity of the globe while dragged, continued once released.
+    velocity: Velocity,
+    /// Times of the previous frames dragging and coasting the globe.
+    drag_time: Option<f64>,
+    coast_time: Option<f64>,
+}
+
+/// Angular velocity (degrees per millisecond) of the globe.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Velocity {
+    rotation: f64,
+    tilt: f64,
+    /// Rotation about an axis in view coordinates, as the axis scaled by its rate, for the
+    /// arcball scheme.
+    spin: Vec3,
+}
+
+impl Velocity {
+    fn speed(&self) -> f64 {
+        self.rotation
+            .abs()
+            .max(self.tilt.abs())
+            .max(self.spin.length())
+    }
+
+    fn scaled(self, factor: f64) -> Velocity {
+        Velocity {
+            rotation: self.rotation * factor,
+            tilt: self.tilt * factor,
+            spin: self.spin * factor,
+        }
+    }
+
+    /// Velocity smoothed towards another.
+    fn towards(self, other: Velocity, weight: f64) -> Velocity {
+        Velocity {
+            rotation: self.rotation + (other.rotation - self.rotation) * weight,
+            tilt: self.tilt + (other.tilt - self.tilt) * weight,
+            spin: self.spin + (other.spin - self.spin) * weight,
+        }
+    }
+}
+
+/// Scheme mapping pointer drags to rotation of the globe.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlScheme {
+    /// Rotate about the polar axis only.
+    Turntable,
+    /// Rotate about the polar axis and tilt towards or away from the viewer.
+    Trackball,
+    /// Rotate freely about any axis, turning the globe over the poles.
+    Arcball,
+}
+
+/// Behaviour of drags tilting the globe past a pole, for the trackball scheme.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PoleCrossing {
+    /// Stop tilting at the pole, keeping north upwards.
+    #[default]
+    Clamp,
+    /// Keep turning over the pole, the view rolling upside down so the drag continues smoothly.
+    Continuous,
+}
+
+impl ControlScheme {
+    /// Whether vertical drags rotate the globe, rather than being free to scroll the page.
+    pub fn drags_vertically(&self) -> bool {
+        *self != ControlScheme::Turntable
+    }
+}
+
+#[derive(Debug)]
+pub struct ControlSettings {
+    /// Degrees of rotation per pixel dragged, or `None` for the surface to follow the pointer.
+    pub sensitivity: Option<f64>,
+    pub invert_x: bool,
+    pub invert_y: bool,
+    pub scheme: ControlScheme,
+    pub pole_crossing: PoleCrossing,
+    /// Whether the globe keeps rotating once released mid-drag.
+    pub inertia: bool,
+    /// Rate (per second) at which the velocity of the released globe decays exponentially.
+    pub friction: f64,
+    /// Time constant (milliseconds) over which the rotation applied follows the pointer,
+    /// spreading the steps between sparse pointer samples over the frames between them, or
+    /// `None` for each sample to be applied at once.
+    pub smoothing: Option<f64>,
+}
+
+impl Default for ControlSettings {
+    fn default() -> Self {
+        Self {
+            sensitivity: None,
+            invert_x: false,
+            invert_y: false,
+            scheme: ControlScheme::Turntable,
+            pole_crossing: PoleCrossing::default(),
+            inertia: true,
+            friction: DEFAULT_FRICTION,
+            smoothing: Some(DEFAULT_DRAG_SMOOTHING),
+        }
+    }
+}
+
+impl ControlData {
+    /// Handle a pointer being pressed; a single pointer drags the globe
+    /// and a second pointer switches to two pointer gestures.
+    pub fn pointer_down(&mut self, id: i32, position: Position) {
+        self.pointers.retain(|(pointer_id, _)| *pointer_id != id);
+        self.pointers.push((id, position.clone()));
+        // Pressing catches the globe
+        self.stop();
+        self.drag_time = None;
+        match self.pointers.len() {
+            1 => {
+                self.pressed = true;
+                self.position = position;
+                self.position_prev = self.position.clone();
+            }
+            _ => {
+                self.pressed = false;
+                self.twist_prev = self.twist_angle();
+                self.pinch_prev = self.pinch_distance();
+            }
+        }
+    }
+
+    /// Handle a pointer moving, returning whether it is pressed.
+    pub fn pointer_move(&mut self, id: i32, position: Position) -> bool {
+        let Some((_, pointer_position)) = self
+            .pointers
+            .iter_mut()
+            .find(|(pointer_id, _)| *pointer_id == id)
+        else {
+            return false;
+        };
+        *pointer_position = position.clone();
+        if self.pressed {
+            self.position = position;
+        }
+        true
+    }
+
+    /// Handle a pointer being released; when a single pointer remains it resumes dragging.
+    pub fn pointer_up(&mut self, id: i32, position: Position) {
+        self.pointers.retain(|(pointer_id, _)| *pointer_id != id);
+        self.twist_prev = None;
+        self.pinch_prev = None;
+        match self.pointers.as_slice() {
+            [] => {
+                if self.pressed {
+                    self.position = position;
+                }
+                self.pressed = false;
+            }
+            [(_, remaining)] => {
+                self.pressed = true;
+                self.position = remaining.clone();
+                self.position_prev = self.position.clone();
+            }
+            _ => {
+                self.twist_prev = self.twist_angle();
+                self.pinch_prev = self.pinch_distance();
+            }
+        }
+    }
+
+    /// Handle all pointers being cancelled.
+    pub fn pointer_cancel(&mut self) {
+        self.pointers.clear();
+        self.twist_prev = None;
+        self.pinch_prev = None;
+        self.pressed = false;
+    }
+
+    /// Whether any pointer is pressed.
+    pub fn interacting(&self) -> bool {
+        !self.pointers.is_empty()
+    }
+
+    /// Whether the rotation applied is still catching up with the pointer.
+    pub fn following(&self) -> bool {
+        self.position != self.position_prev
+    }
+
+    /// Whether the released globe is coasting.
+    pub fn coasting(&self) -> bool {
+        !self.interacting() && self.velocity.speed() > 0.0
+    }
+
+    /// Stop the globe coasting.
+    pub fn stop(&mut self) {
+        self.velocity = Velocity::default();
+    }
+
+    /// Keep rotating the released globe at the velocity of the drag at a time (milliseconds),
+    /// decaying exponentially with the friction, returning whether the orientation changed.
+    pub fn coast(&mut self, settings: &ControlSettings, time: f64) -> bool {
+        let interval = (time - self.coast_time.unwrap_or(time)).clamp(0.0, MAX_FRAME_INTERVAL);
+        self.coast_time = Some(time);
+        if self.interacting() {
+            return false;
+        }
+        if !settings.inertia || self.velocity.speed() < MIN_COASTING_SPEED {
+            self.stop();
+            return false;
+        }
+
+        let velocity = self.velocity;
+        self.orientation.rotation += velocity.rotation * interval;
+        self.tilt_by(velocity.tilt * interval, settings.pole_crossing);
+        if velocity.spin.length() > 0.0 {
+            let spin =
+                Quaternion::from_axis_angle(velocity.spin, velocity.spin.length() * interval);
+            self.orientation = spin.then_after(Quaternion::from(self.orientation)).into();
+        }
+        self.velocity = self
+            .velocity
+            .scaled((-settings.friction * interval / 1000.0).exp());
+        true
+    }
+
+    /// Tilt the globe, clamped at the poles or turning over them, returning whether it turned
+    /// over a pole; the velocity of the globe is reversed with the view.
+    fn tilt_by(&mut self, delta: f64, pole_crossing: PoleCrossing) -> bool {
+        let tilt = self.orientation.tilt + delta;
+        if tilt.abs() <= 90.0 || pole_crossing == PoleCrossing::Clamp {
+            self.orientation.tilt = tilt.clamp(-90.0, 90.0);
+            return false;
+        }
+        // Past a pole the globe is seen from the other side of it, turned half a turn
+        // about the polar axis and rolled upside down
+        self.orientation = Orientation {
+            rotation: self.orientation.rotation + 180.0,
+            tilt: tilt.signum() * 180.0 - tilt,
+            roll: (self.orientation.roll + 360.0).rem_euclid(360.0) - 180.0,
+        };
+        self.velocity.rotation = -self.velocity.rotation;
+        self.velocity.tilt = -self.velocity.tilt;
+        true
+    }
+
+    /// Whether more than one pointer is pressed.
+    pub fn multiple_pointers(&self) -> bool {
+        self.pointers.len() > 1
+    }
+
+    /// Angle (degrees, clockwise on the canvas) of the line between the first two pressed pointers.
+    fn twist_angle(&self) -> Option<f64> {
+        match self.pointers.as_slice() {
+            [(_, a), (_, b), ..] => Some((b.y - a.y).atan2(b.x - a.x).to_degrees()),
+            _ => None,
+        }
+    }
+
+    /// Roll the globe about the view axis by the twist of two pressed
+    /// pointers since last applied, returning whether the orientation changed.
+    pub fn twist(&mut self) -> bool {
+        let (Some(angle), Some(angle_prev)) = (self.twist_angle(), self.twist_prev) else {
+            return false;
+        };
+        let delta = (angle - angle_prev + 180.0).rem_euclid(360.0) - 180.0;
+        if delta == 0.0 {
+            return false;
+        }
+        // Roll is anticlockwise on the canvas
+        self.orientation.roll -= delta;
+        self.twist_prev = Some(angle);
+        true
+    }
+
+    /// Distance (pixels) between the first two pressed pointers.
+    fn pinch_distance(&self) -> Option<f64> {
+        match self.pointers.as_slice() {
+            [(_, a), (_, b), ..] => Some((b.x - a.x).hypot(b.y - a.y)),
+            _ => None,
+        }
+    }
+
+    /// Zoom the view by the ratio of the distances between two pressed pointers
+    /// now and when last applied, returning whether the zoom changed.
+    pub fn pinch(&mut self) -> bool {
+        let (Some(distance), Some(distance_prev)) = (self.pinch_distance(), self.pinch_prev) else {
+            return false;
+        };
+        if distance == distance_prev || distance_prev == 0.0 {
+            return false;
+        }
+        self.pinch_prev = Some(distance);
+        self.zoom.by(distance / distance_prev)
+    }
+
+    /// Position the drag is applied up to after an interval (milliseconds): the pointer's
+    /// position, or with smoothing a position approaching it exponentially from the position
+    /// last applied.
+    fn smoothed_position(&self, smoothing: Option<f64>, interval: f64) -> Position {
+        let Some(smoothing) = smoothing.filter(|smoothing| *smoothing > 0.0) else {
+            return self.position.clone();
+        };
+        let (dx, dy) = (
+            self.position.x - self.position_prev.x,
+            self.position.y - self.position_prev.y,
+        );
+        if dx.hypot(dy) < SMOOTHING_SNAP_DISTANCE {
+            return self.position.clone();
+        }
+        let fraction = 1.0 - (-interval / smoothing).exp();
+        Position {
+            x: self.position_prev.x + dx * fraction,
+            y: self.position_prev.y + dy * fraction,
+        }
+    }
+
+    /// Rotate the globe by the drag from the position last applied towards the current
+    /// pointer position at a time (milliseconds), smoothed by the settings, tracking its
+    /// velocity; returns whether the orientation changed.
+    pub fn drag(
+        &mut self,
+        settings: &ControlSettings,
+        projection: &Projection,
+        transform: &DomMatrix,
+        time: f64,
+    ) -> bool {
+        let interval = (time - self.drag_time.unwrap_or(time)).clamp(0.0, MAX_FRAME_INTERVAL);
+        self.drag_time = Some(time);
+        let track = |data: &mut ControlData, velocity: Velocity| {
+            if interval > 0.0 {
+                data.velocity = data
+                    .velocity
+                    .towards(velocity.scaled(1.0 / interval), VELOCITY_SMOOTHING);
+            }
+        };
+        if self.position == self.position_prev {
+            // Holding the pointer still slows the globe to be released
+            if self.pressed {
+                track(self, Velocity::default());
+            }
+            return false;
+        }
+        let to = self.smoothed_position(settings.smoothing, interval);
+        if settings.scheme == ControlScheme::Arcball {
+            let drag = self.arcball_drag(settings, projection, transform, &to);
+            let spin = drag.rotation_vector();
+            track(
+                self,
+                Velocity {
+                    spin,
+                    ..Velocity::default()
+                },
+            );
+            self.position_prev = to;
+            return true;
+        }
+        let orientation_prev = self.orientation;
+
+        let (mut rotation, mut tilt) = match settings.sensitivity {
+            Some(sensitivity) => {
+                // Drags are along the rolled view; canvas y is downwards
+                let (dy, dz) = self
+                    .orientation
+                    .unroll(to.x - self.position_prev.x, self.position_prev.y - to.y);
+                (dy * sensitivity, -dz * sensitivity)
+            }
+            None => {
+                let unrolled = |position: &Position| {
+                    let (y, z) = canvas_to_unit_coords(position.x, position.y, transform);
+                    self.orientation.unroll(y, z)
+                };
+                // Polar views rotate about their centre, where the pole is
+                match projection.polar_rotation(unrolled(&self.position_prev), unrolled(&to)) {
+                    Some(rotation) => (rotation, 0.0),
+                    None => surface_delta(
+                        projection,
+                        &self.orientation,
+                        &self.position_prev,
+                        &to,
+                        transform,
+                    ),
+                }
+            }
+        };
+        if settings.invert_x {
+            rotation = -rotation;
+        }
+        if settings.invert_y {
+            tilt = -tilt;
+        }
+
+        self.orientation.rotation += rotation;
+        let crossed = settings.scheme == ControlScheme::Trackball
+            && self.tilt_by(tilt, settings.pole_crossing);
+        self.position_prev = to;
+        // Over a pole, continuing the drag reverses the rotation and tilt of the view
+        let velocity = if crossed {
+            Velocity {
+                rotation: -rotation,
+                tilt: -tilt,
+                ..Velocity::default()
+            }
+        } else {
+            Velocity {
+                rotation: self.orientation.rotation - orientation_prev.rotation,
+                tilt: self.orientation.tilt - orientation_prev.tilt,
+                ..Velocity::default()
+            }
+        };
+        track(self, velocity);
+
+        true
+    }
+
+    /// Rotate the globe about the axis perpendicular to the drag to a position, composing the
+    /// rotation of the drag with the orientation as quaternions; returns the rotation.
+    fn arcball_drag(
+        &mut self,
+        settings: &ControlSettings,
+        projection: &Projection,
+        transform: &DomMatrix,
+        to: &Position,
+    ) -> Quaternion {
+        let drag = match settings.sensitivity {
+            Some(sensitivity) => {
+                // Canvas y is downwards; dragging moves the front of the globe with the pointer
+                let dy = to.x - self.position_prev.x;
+                let dz = self.position_prev.y - to.y;
+                Quaternion::from_axis_angle(Vec3::new(0.0, -dz, dy), dy.hypot(dz) * sensitivity)
+            }
+            None => {
+                let view_point = |position: &Position| {
+                    let (y, z) = canvas_to_unit_coords(position.x, position.y, transform);
+                    let (y, z) = projection.orthographic_equivalent(y, z);
+                    let r = y.hypot(z);
+                    if r > 1.0 {
+                        Vec3::new(0.0, y / r, z / r)
+                    } else {
+                        Vec3::new(third_coord_val(y, z), y, z)
+                    }
+                };
+                Quaternion::between(view_point(&self.position_prev), view_point(to))
+            }
+        };
+        // Inverting reverses the rotations moving the globe horizontally or vertically
+        let drag = Quaternion {
+            y: if settings.invert_y { -drag.y } else { drag.y },
+            z: if settings.invert_x { -drag.z } else { drag.z },
+            ..drag
+        };
+        self.orientation = drag.then_after(Quaternion::from(self.orientation)).into();
+        drag
+    }
+}
+
+/// Rotation and tilt (degrees) that keep the surface under the pointer.
+fn surface_delta(
+    projection: &Projection,
+    orientation: &Orientation,
+    from: &Position,
+    to: &Position,
+    transform: &DomMatrix,
+) -> (f64, f64) {
+    unit_surface_delta(
+        projection,
+        orientation,
+        canvas_to_unit_coords(from.x, from.y, transform),
+        canvas_to_unit_coords(to.x, to.y, transform),
+    )
+}
+
+/// Rotation and tilt (degrees) that keep the surface under the pointer as it moves between
+/// unit circle coordinates. Off the sphere, where every position would be taken to the limb,
+/// drags rotate by a radian per radius moved, as at the centre of the view, so they neither
+/// stall nor flip as they follow the limb.
+fn unit_surface_delta(
+    projection: &Projection,
+    orientation: &Orientation,
+    from: (f64, f64),
+    to: (f64, f64),
+) -> (f64, f64) {
+    let (Some((x, y, z)), Some((x_prev, y_prev, z_prev))) = (
+        canvas_to_sphere(projection, orientation, to),
+        canvas_to_sphere(projection, orientation, from),
+    ) else {
+        let (y, z) = orientation.unroll(to.0, to.1);
+        let (y_prev, z_prev) = orientation.unroll(from.0, from.1);
+        return ((y - y_prev).to_degrees(), (z_prev - z).to_degrees());
+    };
+
+    // Rotation is undefined at the top and bottom of the limb
+    let (_, phi) = cartesian_to_unit_spherical(x, y, z);
+    let (_, phi_prev) = cartesian_to_unit_spherical(x_prev, y_prev, z_prev);
+    let rotation = if phi.is_nan() || phi_prev.is_nan() {
+        0.0
+    } else {
+        phi - phi_prev
+    };
+
+    // Dragging the surface upwards brings southern latitudes to the centre of the view
+    let elevation = z.atan2(x).to_degrees();
+    let elevation_prev = z_prev.atan2(x_prev).to_degrees();
+
+    (rotation, elevation_prev - elevation)
+}
+
+/// Position on the unit sphere under unit circle coordinates of the canvas, with the view's
+/// roll reversed, or `None` off the sphere or on its limb.
+fn canvas_to_sphere(
+    projection: &Projection,
+    orientation: &Orientation,
+    (y, z): (f64, f64),
+) -> Option<(f64, f64, f64)> {
+    let (y, z) = projection.orthographic_equivalent(y, z);
+    let (y, z) = orientation.unroll(y, z);
+    if y * y + z * z >= 1.0 {
+        return None;
+    }
+    Some((third_coord_val(y, z), y, z))
+}
+
+/// Tranform from canvas coordinates to unit circle
+/// coordinates by reversing the context transform.
+pub fn canvas_to_unit_coords(x: f64, y: f64, reverse_transform: &DomMatrix) -> (f64, f64) {
+    (
+        (x - reverse_transform.e()) / reverse_transform.a(),
+        (y - reverse_transform.f()) / reverse_transform.d(),
+    )
+}
+
+/// Calculate the (positive) third coordinate value on
+/// a unit sphere given the other two coordinate values.
+pub fn third_coord_val(first: f64, second: f64) -> f64 {
+    // Clamped as rounding can take points on the limb just outside the sphere
+    (1.0 - first * first - second * second).max(0.0).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projection::ProjectionKind;
+
+    #[test]
+    fn coasts_with_decaying_velocity() {
+        let settings = ControlSettings::default();
+        let mut data = ControlData {
+            velocity: Velocity {
+                rotation: 0.1,
+                ..Velocity::default()
+            },
+            ..ControlData::default()
+        };
+        assert!(data.coast(&settings, 0.0));
+        assert!(data.coast(&settings, 10.0));
+        assert!((data.orientation.rotation - 1.0).abs() < 1e-9);
+        assert!(data.velocity.rotation < 0.1);
+
+        // The velocity decays until the globe stops
+        let mut time = 10.0;
+        while data.coast(&settings, time) {
+            time += 16.0;
+        }
+        assert!(!data.coasting());
+    }
+
+    #[test]
+    fn stops_without_inertia() {
+        let settings = ControlSettings {
+            inertia: false,
+            ..ControlSettings::default()
+        };
+        let mut data = ControlData {
+            velocity: Velocity {
+                tilt: 0.1,
+                ..Velocity::default()
+            },
+            ..ControlData::default()
+        };
+        assert!(!data.coast(&settings, 0.0));
+        assert!(!data.coasting());
+    }
+
+    #[test]
+    fn spreads_pointer_steps_over_frames() {
+        let mut data = ControlData {
+            position: Position { x: 100.0, y: 0.0 },
+            ..ControlData::default()
+        };
+        assert_eq!(data.smoothed_position(None, 16.0), data.position);
+        // Each frame covers the same fraction of the remaining step
+        let first = data.smoothed_position(Some(30.0), 16.0);
+        assert!(first.x > 0.0 && first.x < 100.0 && first.y == 0.0);
+        data.position_prev = first.clone();
+        let second = data.smoothed_position(Some(30.0), 16.0);
+        assert!(((second.x - first.x) - first.x * (100.0 - first.x) / 100.0).abs() < 1e-9);
+        // Close to the pointer the rest of the step is taken at once
+        data.position_prev = Position { x: 99.95, y: 0.0 };
+        assert_eq!(data.smoothed_position(Some(30.0), 16.0), data.position);
+    }
+
+    #[test]
+    fn turns_over_the_pole_continuously() {
+        let mut data = ControlData::default();
+        data.orientation.tilt = 89.99;
+        assert!(!data.tilt_by(0.02, PoleCrossing::Clamp));
+        assert_eq!(data.orientation.tilt, 90.0);
+
+        data.orientation.tilt = 89.99;
+        let before = data.orientation.project(30.0, 60.0);
+        data.velocity.tilt = 0.1;
+        assert!(data.tilt_by(0.02, PoleCrossing::Continuous));
+        assert!((data.orientation.tilt - 89.99).abs() < 1e-9);
+        assert_eq!(data.velocity.tilt, -0.1);
+        // The view barely moves across the pole
+        let after = data.orientation.project(30.0, 60.0);
+        assert!((before.1 - after.1).hypot(before.2 - after.2) < 1e-3);
+    }
+
+    #[test]
+    fn finds_the_point_on_the_sphere_under_the_canvas() {
+        let projection = Projection::default();
+        let orientation = Orientation::default();
+        let (x, y, z) = canvas_to_sphere(&projection, &orientation, (0.6, 0.0)).unwrap();
+        assert!((x - 0.8).abs() < 1e-9 && (y - 0.6).abs() < 1e-9 && z == 0.0);
+        // The roll of the view is reversed
+        let rolled = Orientation {
+            roll: 90.0,
+            ..orientation
+        };
+        let (_, y, z) = canvas_to_sphere(&projection, &rolled, (0.6, 0.0)).unwrap();
+        assert!(y.abs() < 1e-9 && (z + 0.6).abs() < 1e-9);
+        assert_eq!(
+            canvas_to_sphere(&projection, &orientation, (1.2, 0.5)),
+            None
+        );
+        // The outer half of the azimuthal equidistant view is the back of the globe
+        let mut projection = projection;
+        projection.kind = ProjectionKind::AzimuthalEquidistant;
+        assert!(canvas_to_sphere(&projection, &orientation, (0.4, 0.0)).is_some());
+        assert_eq!(
+            canvas_to_sphere(&projection, &orientation, (0.7, 0.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn keeps_rotating_drags_off_the_disc() {
+        let projection = Projection::default();
+        let orientation = Orientation::default();
+        let delta = |from, to| unit_surface_delta(&projection, &orientation, from, to);
+        // Horizontal drags above the disc rotate the globe as those across it, crossing the
+        // centreline without flipping
+        let (rotation, tilt) = delta((-0.1, -1.5), (0.1, -1.5));
+        assert!((rotation - 0.2f64.to_degrees()).abs() < 1e-9 && tilt.abs() < 1e-9);
+        assert!(delta((-0.1, 0.0), (0.1, 0.0)).0 > 0.0);
+        let (rotation, _) = delta((0.1, 1.5), (0.3, 1.5));
+        assert!((rotation - 0.2f64.to_degrees()).abs() < 1e-9);
+        // Vertical drags beside the disc tilt it as those across it
+        let (rotation, tilt) = delta((1.5, -0.1), (1.5, 0.1));
+        assert!(rotation.abs() < 1e-9 && (tilt + 0.2f64.to_degrees()).abs() < 1e-9);
+        assert!(delta((0.0, -0.1), (0.0, 0.1)).1 < 0.0);
+        // Leaving the disc carries on in the same direction
+        assert!(delta((0.95, 0.0), (1.05, 0.0)).0 > 0.0);
+    }
+}
@@ -0,0 +1,203 @@
+// Coordinate types of locations, points and orientations of the globe.
+
+use wasm_bindgen::prelude::*;
+
+use crate::view::Orientation;
+use crate::{cartesian_to_unit_spherical, unit_spherical_to_cartesian};
+
+/// Location (degrees) on the globe.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LonLat {
+    pub lon: f64,
+    pub lat: f64,
+}
+
+#[wasm_bindgen]
+impl LonLat {
+    #[wasm_bindgen(constructor)]
+    pub fn new(lon: f64, lat: f64) -> LonLat {
+        LonLat { lon, lat }
+    }
+}
+
+impl LonLat {
+    /// Point on the unit sphere at the location, with the z axis through the north pole
+    /// and the x axis through longitude zero.
+    pub fn to_vec3(self) -> Vec3 {
+        unit_spherical_to_cartesian(90.0 - self.lat, self.lon).into()
+    }
+
+    /// Location of the direction of a point from the centre of the globe.
+    pub fn from_vec3(point: Vec3) -> LonLat {
+        let point = point.normalised();
+        let (theta, phi) = cartesian_to_unit_spherical(point.x, point.y, point.z);
+        // Longitude is undefined at the poles
+        LonLat {
+            lon: if phi.is_nan() { 0.0 } else { phi },
+            lat: 90.0 - theta,
+        }
+    }
+
+    /// Location with the longitude wrapped to the range [-180, 180).
+    pub fn wrapped(self) -> LonLat {
+        LonLat {
+            lon: (self.lon + 180.0).rem_euclid(360.0) - 180.0,
+            lat: self.lat,
+        }
+    }
+}
+
+/// Point in three dimensions.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vec3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+#[wasm_bindgen]
+impl Vec3 {
+    #[wasm_bindgen(constructor)]
+    pub fn new(x: f64, y: f64, z: f64) -> Vec3 {
+        Vec3 { x, y, z }
+    }
+}
+
+impl Vec3 {
+    pub fn dot(self, other: Vec3) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(self, other: Vec3) -> Vec3 {
+        Vec3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    /// Point in the same direction at unit distance, or the point itself at the origin.
+    pub fn normalised(self) -> Vec3 {
+        let length = self.length();
+        if length > 0.0 {
+            Vec3::new(self.x / length, self.y / length, self.z / length)
+        } else {
+            self
+        }
+    }
+}
+
+impl std::ops::Add for Vec3 {
+    type Output = Vec3;
+
+    fn add(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl std::ops::Sub for Vec3 {
+    type Output = Vec3;
+
+    fn sub(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl std::ops::Mul<f64> for Vec3 {
+    type Output = Vec3;
+
+    fn mul(self, factor: f64) -> Vec3 {
+        Vec3::new(self.x * factor, self.y * factor, self.z * factor)
+    }
+}
+
+impl From<(f64, f64, f64)> for Vec3 {
+    fn from((x, y, z): (f64, f64, f64)) -> Self {
+        Vec3 { x, y, z }
+    }
+}
+
+impl From<Vec3> for (f64, f64, f64) {
+    fn from(point: Vec3) -> Self {
+        (point.x, point.y, point.z)
+    }
+}
+
+/// Orientation (degrees) of the globe relative to the viewer.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rotation {
+    /// Rotation about the polar axis; the longitude at the centre of the view is its negation.
+    pub rotation: f64,
+    /// Latitude at the centre of the view.
+    pub tilt: f64,
+    /// Rotation about the view axis, anticlockwise on the canvas.
+    pub roll: f64,
+}
+
+#[wasm_bindgen]
+impl Rotation {
+    #[wasm_bindgen(constructor)]
+    pub fn new(rotation: f64, tilt: f64, roll: f64) -> Rotation {
+        Rotation {
+            rotation,
+            tilt,
+            roll,
+        }
+    }
+
+    /// Location at the centre of the view.
+    pub fn centre(&self) -> LonLat {
+        LonLat::new(-self.rotation, self.tilt).wrapped()
+    }
+}
+
+impl From<Orientation> for Rotation {
+    fn from(orientation: Orientation) -> Self {
+        Rotation::new(orientation.rotation, orientation.tilt, orientation.roll)
+    }
+}
+
+impl From<Rotation> for Orientation {
+    fn from(rotation: Rotation) -> Self {
+        Orientation {
+            rotation: rotation.rotation,
+            tilt: rotation.tilt,
+            roll: rotation.roll,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_locations_through_points() {
+        for location in [
+            LonLat::new(0.0, 0.0),
+            LonLat::new(-120.0, 45.0),
+            LonLat::new(179.0, -80.0),
+        ] {
+            let converted = LonLat::from_vec3(location.to_vec3());
+            assert!((converted.lon - location.lon).abs() < 1e-9);
+            assert!((converted.lat - location.lat).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn centres_rotations() {
+        let centre = Rotation::new(-200.0, 30.0, 0.0).centre();
+        assert!((centre.lon - -160.0).abs() < 1e-9);
+        assert_eq!(centre.lat, 30.0);
+    }
+}
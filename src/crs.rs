@@ -0,0 +1,188 @@
+// Coordinate reference systems of data loaded onto the globe: data that is not in longitude
+// and latitude on WGS84, such as Web Mercator metres from tiled services, is transformed to
+// latitude and longitude as it is loaded, so layers from mixed sources line up.
+
+use std::borrow::Cow;
+
+// Radius (metres) of the sphere of Web Mercator and of the equidistant cylindrical projection
+const WEB_MERCATOR_RADIUS: f64 = 6_378_137.0;
+
+/// Layers loading their locations in bulk, which can be given in another reference system.
+pub const CRS_LAYERS: [&str; 2] = ["hexbins", "voronoi"];
+
+/// Coordinate reference system of locations, all on WGS84 or its sphere.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Crs {
+    /// Latitude and longitude (degrees), as the globe takes them and EPSG:4326 orders them.
+    #[default]
+    Geographic,
+    /// Longitude and latitude (degrees), as OGC:CRS84 and the `.prj` files of shapefiles
+    /// order them.
+    GeographicLonLat,
+    /// Easting and northing (metres) of Web Mercator, as used by tiled maps (EPSG:3857).
+    WebMercator,
+    /// Easting and northing (metres) of the equidistant cylindrical projection (EPSG:4087).
+    EquidistantCylindrical,
+}
+
+impl Crs {
+    /// Reference system from an identifier, such as "EPSG:3857", or the well-known text of a
+    /// shapefile's `.prj` file.
+    pub fn parse(definition: &str) -> Result<Crs, String> {
+        let definition = definition.trim();
+        let code = definition
+            .rsplit([':', '/'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        match code.as_str() {
+            "4326" | "4258" | "4269" => return Ok(Crs::Geographic),
+            "CRS84" => return Ok(Crs::GeographicLonLat),
+            "3857" | "900913" | "102100" | "102113" => return Ok(Crs::WebMercator),
+            "4087" | "32662" => return Ok(Crs::EquidistantCylindrical),
+            _ => {}
+        }
+
+        // Well-known text is recognised by the outermost definition and its projection
+        let text = definition.to_ascii_uppercase().replace([' ', '-'], "_");
+        if text.starts_with("GEOGCS[") || text.starts_with("GEOGCRS[") {
+            // Longitude comes first unless the first axis declared is the latitude
+            let latitude_first = text
+                .split("AXIS[")
+                .nth(1)
+                .is_some_and(|axis| axis.split(']').next().unwrap_or_default().contains("LAT"));
+            Ok(if latitude_first {
+                Crs::Geographic
+            } else {
+                Crs::GeographicLonLat
+            })
+        } else if (text.starts_with("PROJCS[") || text.starts_with("PROJCRS["))
+            && (text.contains("PSEUDO_MERCATOR")
+                || text.contains("MERCATOR_AUXILIARY_SPHERE")
+                || text.contains("POPULAR_VISUALISATION"))
+        {
+            Ok(Crs::WebMercator)
+        } else if (text.starts_with("PROJCS[") || text.starts_with("PROJCRS["))
+            && (text.contains("EQUIDISTANT_CYLINDRICAL") || text.contains("PLATE_CARREE"))
+        {
+            Ok(Crs::EquidistantCylindrical)
+        } else {
+            Err(format!(
+                "unsupported coordinate reference system {:?}",
+                definition
+            ))
+        }
+    }
+
+    /// Latitude and longitude (degrees) of a location given by a pair of coordinates: in the
+    /// order of the axes for geographic systems, and the easting and northing for projected
+    /// systems.
+    pub fn to_lat_lon(self, first: f64, second: f64) -> (f64, f64) {
+        match self {
+            Crs::Geographic => (first, second),
+            Crs::GeographicLonLat => (second, first),
+            Crs::WebMercator => (
+                (second / WEB_MERCATOR_RADIUS)
+                    .exp()
+                    .atan()
+                    .mul_add(2.0, -std::f64::consts::FRAC_PI_2)
+                    .to_degrees(),
+                (first / WEB_MERCATOR_RADIUS).to_degrees(),
+            ),
+            Crs::EquidistantCylindrical => (
+                (second / WEB_MERCATOR_RADIUS).to_degrees(),
+                (first / WEB_MERCATOR_RADIUS).to_degrees(),
+            ),
+        }
+    }
+
+    /// Latitudes and longitudes (degrees) of locations given by pairs of coordinates in turn,
+    /// with any odd coordinate left at the end.
+    pub fn transform<'a>(self, coordinates: &'a [f64]) -> Cow<'a, [f64]> {
+        if self == Crs::Geographic {
+            return Cow::Borrowed(coordinates);
+        }
+        let pairs = coordinates.chunks_exact(2);
+        let remainder = pairs.remainder();
+        let mut transformed = pairs
+            .flat_map(|pair| {
+                let (lat, lon) = self.to_lat_lon(pair[0], pair[1]);
+                [lat, lon]
+            })
+            .collect::<Vec<_>>();
+        transformed.extend_from_slice(remainder);
+        Cow::Owned(transformed)
+    }
+}
+
+/// Reference systems of the locations loaded into layers.
+#[derive(Debug, Default)]
+pub struct LayerCrs {
+    systems: Vec<(&'static str, Crs)>,
+}
+
+impl LayerCrs {
+    /// Set the reference system of the locations loaded into a layer, by its identifier.
+    pub fn set(&mut self, layer: &str, crs: Crs) -> Result<(), String> {
+        let layer = CRS_LAYERS
+            .iter()
+            .find(|candidate| **candidate == layer)
+            .ok_or_else(|| format!("layer {:?} does not load locations in bulk", layer))?;
+        self.systems.retain(|(set, _)| set != layer);
+        self.systems.push((layer, crs));
+        Ok(())
+    }
+
+    /// Reference system of the locations loaded into a layer.
+    pub fn get(&self, layer: &str) -> Crs {
+        self.systems
+            .iter()
+            .find(|(set, _)| *set == layer)
+            .map(|(_, crs)| *crs)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transforms_projected_coordinates() {
+        let near = |(lat, lon): (f64, f64), (expected_lat, expected_lon): (f64, f64)| {
+            (lat - expected_lat).abs() < 1e-6 && (lon - expected_lon).abs() < 1e-6
+        };
+        let mercator = Crs::parse("EPSG:3857").unwrap();
+        assert!(near(mercator.to_lat_lon(0.0, 0.0), (0.0, 0.0)));
+        assert!(near(
+            mercator.to_lat_lon(20_037_508.342_789_244, 20_037_508.342_789_244),
+            (85.051_128_779_806_6, 180.0)
+        ));
+        assert_eq!(
+            Crs::parse(r#"PROJCS["WGS 84 / Pseudo-Mercator",GEOGCS["WGS 84"]]"#),
+            Ok(Crs::WebMercator)
+        );
+        assert_eq!(
+            Crs::parse(r#"GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984"]]"#),
+            Ok(Crs::GeographicLonLat)
+        );
+        assert_eq!(
+            Crs::parse(r#"GEOGCRS["WGS 84",AXIS["geodetic latitude (Lat)",north]]"#),
+            Ok(Crs::Geographic)
+        );
+        assert!(Crs::parse("EPSG:27700").is_err());
+
+        assert_eq!(*Crs::Geographic.transform(&[1.0, 2.0]), [1.0, 2.0]);
+        // CRS84 gives the longitude first
+        let crs84 = Crs::parse("urn:ogc:def:crs:OGC:1.3:CRS84").unwrap();
+        assert_eq!(crs84, Crs::GeographicLonLat);
+        assert_eq!(crs84.to_lat_lon(-0.1, 51.5), (51.5, -0.1));
+        assert_eq!(*crs84.transform(&[-0.1, 51.5, 5.0]), [51.5, -0.1, 5.0]);
+        let transformed = Crs::EquidistantCylindrical.transform(&[0.0, 6_378_137.0, 5.0]);
+        assert!(near(
+            (transformed[0], transformed[1]),
+            (57.295_779_513, 0.0)
+        ));
+        assert_eq!(transformed[2], 5.0);
+    }
+}
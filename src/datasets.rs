@@ -0,0 +1,169 @@
+// The datasets embedded during the build, for Rust crates using the globe to analyse the same
+// data it draws: each dataset is iterated as features with their locations, bounds, names
+// and attributes.
+
+use std::sync::OnceLock;
+
+use crate::coords::LonLat;
+use crate::data::{self, Polylines};
+use crate::info::FeatureBounds;
+
+pub use crate::info::{Attribute, DatasetInfo};
+
+/// Feature of an embedded dataset.
+#[derive(Clone, Copy, Debug)]
+pub struct Feature {
+    /// Locations of the points of the feature's lines, or of the outer rings of its polygons,
+    /// one after another.
+    pub points: &'static [LonLat],
+    /// Spherical centroid of the feature.
+    pub centroid: LonLat,
+    /// Radius (degrees) of the cap about the centroid containing the feature's points.
+    pub radius: f64,
+    /// Name of the feature, for datasets with names.
+    pub name: Option<&'static str>,
+    fields: &'static [&'static str],
+    attributes: &'static [Attribute],
+}
+
+impl Feature {
+    /// Value of an attribute of the feature by the name of its field, such as "scalerank".
+    pub fn attribute(&self, field: &str) -> Option<Attribute> {
+        let index = self.fields.iter().position(|name| *name == field)?;
+        self.attributes.get(index).copied()
+    }
+
+    /// Names of the attribute fields of the feature's dataset.
+    pub fn fields(&self) -> &'static [&'static str] {
+        self.fields
+    }
+}
+
+/// Embedded dataset, with the locations of its points converted from the fixed-point data on
+/// first use.
+struct Dataset {
+    points: &'static Polylines,
+    bounds: &'static [FeatureBounds],
+    names: &'static [&'static str],
+    fields: &'static [&'static str],
+    attributes: &'static [&'static [Attribute]],
+    locations: OnceLock<Vec<Vec<LonLat>>>,
+}
+
+impl Dataset {
+    fn features(&'static self) -> impl Iterator<Item = Feature> {
+        let locations = self.locations.get_or_init(|| {
+            self.points
+                .iter()
+                .map(|polyline| {
+                    polyline
+                        .iter()
+                        .map(|point| {
+                            let (lon, lat) = point.to_degrees();
+                            LonLat { lon, lat }
+                        })
+                        .collect()
+                })
+                .collect()
+        });
+        locations
+            .iter()
+            .zip(self.bounds)
+            .zip(self.attributes)
+            .enumerate()
+            .map(|(index, ((points, bounds), attributes))| Feature {
+                points,
+                centroid: bounds.centroid,
+                radius: bounds.radius,
+                name: self.names.get(index).copied(),
+                fields: self.fields,
+                attributes,
+            })
+    }
+}
+
+static COASTLINE: Dataset = Dataset {
+    points: &data::COASTLINE_POINTS,
+    bounds: data::COASTLINE_BOUNDS,
+    names: &[],
+    fields: data::COASTLINE_FIELDS,
+    attributes: data::COASTLINE_ATTRIBUTES,
+    locations: OnceLock::new(),
+};
+
+static DETAILED_COASTLINE: Dataset = Dataset {
+    points: &data::DETAILED_COASTLINE_POINTS,
+    bounds: data::DETAILED_COASTLINE_BOUNDS,
+    names: &[],
+    fields: data::DETAILED_COASTLINE_FIELDS,
+    attributes: data::DETAILED_COASTLINE_ATTRIBUTES,
+    locations: OnceLock::new(),
+};
+
+static COUNTRIES: Dataset = Dataset {
+    points: &data::COUNTRY_POINTS,
+    bounds: data::COUNTRY_BOUNDS,
+    names: data::COUNTRY_NAMES,
+    fields: data::COUNTRY_FIELDS,
+    attributes: data::COUNTRY_ATTRIBUTES,
+    locations: OnceLock::new(),
+};
+
+static LAND: Dataset = Dataset {
+    points: &data::LAND_POINTS,
+    bounds: data::LAND_BOUNDS,
+    names: &[],
+    fields: data::LAND_FIELDS,
+    attributes: data::LAND_ATTRIBUTES,
+    locations: OnceLock::new(),
+};
+
+/// Lines of the coastline drawn on the globe.
+pub fn coastlines() -> impl Iterator<Item = Feature> {
+    COASTLINE.features()
+}
+
+/// Lines of the detailed coastline drawn when zoomed in, where embedded.
+pub fn detailed_coastlines() -> impl Iterator<Item = Feature> {
+    DETAILED_COASTLINE.features()
+}
+
+/// Outer rings of the countries, with their names, where embedded.
+pub fn countries() -> impl Iterator<Item = Feature> {
+    COUNTRIES.features()
+}
+
+/// Outer rings of the land polygons, where embedded.
+pub fn land() -> impl Iterator<Item = Feature> {
+    LAND.features()
+}
+
+/// Descriptions of the datasets embedded, with their names, resolutions, versions, feature
+/// and point counts, and hashes of their source content.
+pub fn info() -> &'static [DatasetInfo] {
+    data::DATASETS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iterates_the_embedded_coastline() {
+        let info = &info()[0];
+        assert_eq!(coastlines().count(), info.features);
+        assert_eq!(
+            coastlines()
+                .map(|feature| feature.points.len())
+                .sum::<usize>(),
+            info.points
+        );
+        let feature = coastlines().next().unwrap();
+        assert!(feature
+            .points
+            .iter()
+            .all(|point| point.lat.abs() <= 90.0 && point.lon.abs() <= 180.0));
+        assert!(feature.attribute("scalerank").is_some());
+        assert_eq!(feature.attribute("population"), None);
+    }
+}
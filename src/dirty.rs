@@ -0,0 +1,127 @@
+// Partial redraws of the regions of the canvas changed since the frame was last drawn, so
+// small animations, such as a ping pulsing or the status badge changing, are nearly free:
+// the frame is drawn again clipped to the dirty rectangles, clearing and drawing only them.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::overlay::{self, Rect};
+
+/// Most rectangles drawn apart, beyond which they are drawn as their bounds.
+const MAX_DIRTY_RECTS: usize = 8;
+
+#[derive(Debug, Default)]
+pub struct DirtyRegion {
+    /// Rectangles (canvas pixels) changed since the frame being drawn started.
+    changed: Vec<Rect>,
+    /// Rectangles the frame being drawn is clipped to, or `None` for the whole canvas.
+    drawing: Option<Vec<Rect>>,
+}
+
+impl DirtyRegion {
+    /// Mark a rectangle (canvas pixels) of the canvas as changed.
+    pub fn add(&mut self, rect: Rect) {
+        self.changed = merged(std::mem::take(&mut self.changed), rect);
+    }
+
+    /// Whether rectangles changed since the frame being drawn started.
+    pub fn pending(&self) -> bool {
+        !self.changed.is_empty()
+    }
+
+    /// Start drawing the whole canvas, which covers the rectangles changed.
+    pub fn start_full(&mut self) {
+        self.changed.clear();
+        self.drawing = None;
+    }
+
+    /// Start drawing the rectangles changed, with those of a partial frame still being drawn.
+    pub fn start_partial(&mut self) {
+        let mut rects = self.drawing.take().unwrap_or_default();
+        for rect in std::mem::take(&mut self.changed) {
+            rects = merged(rects, rect);
+        }
+        self.drawing = Some(rects);
+    }
+
+    /// Rectangles the frame being drawn is clipped to, or `None` for the whole canvas.
+    pub fn drawing(&self) -> Option<&[Rect]> {
+        self.drawing.as_deref()
+    }
+}
+
+/// Rectangles with another added, merged with those it overlaps.
+fn merged(mut rects: Vec<Rect>, mut rect: Rect) -> Vec<Rect> {
+    // Rectangles grown by a merge may overlap others in turn
+    while let Some(index) = rects.iter().position(|other| other.intersects(&rect)) {
+        rect = rect.union(&rects.swap_remove(index));
+    }
+    rects.push(rect);
+    if rects.len() > MAX_DIRTY_RECTS {
+        let bounds = rects
+            .iter()
+            .skip(1)
+            .fold(rects[0], |bounds, rect| bounds.union(rect));
+        rects = vec![bounds];
+    }
+    rects
+}
+
+/// Clip subsequent drawing to rectangles (canvas pixels), rounded out to whole pixels of the
+/// backing store so their edges are not blended; the clip is removed by restoring the context.
+pub fn clip(
+    context: &CanvasRenderingContext2d,
+    rects: &[Rect],
+    pixel_ratio: f64,
+) -> Result<(), JsValue> {
+    overlay::canvas_pixels(context, pixel_ratio)?;
+    context.begin_path();
+    for rect in rects {
+        let left = (rect.left * pixel_ratio).floor() / pixel_ratio;
+        let top = (rect.top * pixel_ratio).floor() / pixel_ratio;
+        let right = ((rect.left + rect.width) * pixel_ratio).ceil() / pixel_ratio;
+        let bottom = ((rect.top + rect.height) * pixel_ratio).ceil() / pixel_ratio;
+        context.rect(left, top, right - left, bottom - top);
+    }
+    context.clip();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_overlapping_rectangles() {
+        let rect = |left, top, width, height| Rect {
+            left,
+            top,
+            width,
+            height,
+        };
+        let mut region = DirtyRegion::default();
+        region.add(rect(0.0, 0.0, 10.0, 10.0));
+        region.add(rect(50.0, 50.0, 10.0, 10.0));
+        // Bridging the two merges all three
+        region.add(rect(5.0, 5.0, 50.0, 50.0));
+        assert!(region.pending());
+        region.start_partial();
+        assert_eq!(region.drawing(), Some(&[rect(0.0, 0.0, 60.0, 60.0)][..]));
+
+        // Rectangles changed while drawing are added to those being drawn
+        region.add(rect(100.0, 0.0, 10.0, 10.0));
+        region.start_partial();
+        assert_eq!(region.drawing().map(<[Rect]>::len), Some(2));
+        region.start_full();
+        assert_eq!(region.drawing(), None);
+
+        for i in 0..=MAX_DIRTY_RECTS {
+            region.add(rect(20.0 * i as f64, 0.0, 10.0, 10.0));
+        }
+        region.start_partial();
+        assert_eq!(
+            region.drawing(),
+            Some(&[rect(0.0, 0.0, 20.0 * MAX_DIRTY_RECTS as f64 + 10.0, 10.0)][..])
+        );
+    }
+}
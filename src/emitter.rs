@@ -0,0 +1,225 @@
+// Events dispatched as the state of the globe changes, so host applications can follow it
+// without polling: the view is compared with that of the previous frame, the drawing of each
+// frame is reported as it starts and ends, and clicks on the globe report what they picked.
+// Events are dispatched on the canvas and passed to the functions registered for them.
+
+use wasm_bindgen::JsValue;
+
+use crate::coords::{LonLat, Rotation};
+use crate::view::Orientation;
+
+/// Types of the events functions can be registered for.
+const EVENT_TYPES: [&str; 5] = [
+    "rotationchange",
+    "zoomchange",
+    "renderstart",
+    "renderend",
+    "pick",
+];
+
+/// Change of the state of the globe.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GlobeEvent {
+    RotationChange(Orientation),
+    ZoomChange(f64),
+    RenderStart,
+    /// Drawing of a frame ended, after a time (milliseconds) from its start.
+    RenderEnd(f64),
+    /// Click on the globe at a canvas position and location, with the marker and the country
+    /// there, if any.
+    Pick {
+        x: f64,
+        y: f64,
+        location: LonLat,
+        marker: Option<u32>,
+        country: Option<usize>,
+    },
+}
+
+impl GlobeEvent {
+    /// Type of the event dispatched on the canvas.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            GlobeEvent::RotationChange(_) => "rotationchange",
+            GlobeEvent::ZoomChange(_) => "zoomchange",
+            GlobeEvent::RenderStart => "renderstart",
+            GlobeEvent::RenderEnd(_) => "renderend",
+            GlobeEvent::Pick { .. } => "pick",
+        }
+    }
+
+    /// Detail of the event: the location at the centre of the view and the rotation, tilt
+    /// and roll (degrees) of rotations, the zoom of zooms, the duration of frames drawn, and
+    /// the position, location, marker and country of picks.
+    pub fn detail(&self) -> Result<JsValue, JsValue> {
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| js_sys::Reflect::set(&object, &key.into(), &value);
+        match self {
+            GlobeEvent::RotationChange(orientation) => {
+                let rotation = Rotation::from(*orientation);
+                let centre = rotation.centre();
+                set("lat", centre.lat.into())?;
+                set("lon", centre.lon.into())?;
+                set("rotation", rotation.rotation.into())?;
+                set("tilt", rotation.tilt.into())?;
+                set("roll", rotation.roll.into())?;
+            }
+            GlobeEvent::ZoomChange(zoom) => {
+                set("zoom", (*zoom).into())?;
+            }
+            GlobeEvent::RenderStart => return Ok(JsValue::NULL),
+            GlobeEvent::RenderEnd(duration) => {
+                set("duration", (*duration).into())?;
+            }
+            GlobeEvent::Pick {
+                x,
+                y,
+                location,
+                marker,
+                country,
+            } => {
+                set("x", (*x).into())?;
+                set("y", (*y).into())?;
+                set("lat", location.lat.into())?;
+                set("lon", location.lon.into())?;
+                set("marker", marker.map_or(JsValue::NULL, JsValue::from))?;
+                let country = country.map_or(JsValue::NULL, |country| (country as u32).into());
+                set("country", country)?;
+            }
+        }
+        Ok(object.into())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Emitter {
+    /// Functions registered for each type of event.
+    listeners: Vec<(&'static str, js_sys::Function)>,
+    /// View as of the previous frame.
+    orientation: Option<Orientation>,
+    zoom: Option<f64>,
+    /// Time (milliseconds) the frame being drawn started.
+    render_start: Option<f64>,
+    /// Events not yet dispatched.
+    events: Vec<GlobeEvent>,
+}
+
+impl Emitter {
+    /// Register a function called with the detail of each event of a type; returns whether
+    /// the type is known.
+    pub fn on(&mut self, event_type: &str, callback: js_sys::Function) -> bool {
+        let Some(event_type) = EVENT_TYPES.into_iter().find(|known| *known == event_type) else {
+            return false;
+        };
+        self.listeners.push((event_type, callback));
+        true
+    }
+
+    /// Remove a function registered for a type of event, returning whether it was registered.
+    pub fn off(&mut self, event_type: &str, callback: &js_sys::Function) -> bool {
+        let count = self.listeners.len();
+        self.listeners.retain(|(registered_type, registered)| {
+            *registered_type != event_type || JsValue::from(registered) != JsValue::from(callback)
+        });
+        self.listeners.len() != count
+    }
+
+    /// Compare the view of a frame with that of the previous frame, queuing events for its
+    /// changes.
+    pub fn view(&mut self, orientation: Orientation, zoom: f64) {
+        if self
+            .orientation
+            .is_some_and(|previous| previous != orientation)
+        {
+            self.events.push(GlobeEvent::RotationChange(orientation));
+        }
+        if self.zoom.is_some_and(|previous| previous != zoom) {
+            self.events.push(GlobeEvent::ZoomChange(zoom));
+        }
+        self.orientation = Some(orientation);
+        self.zoom = Some(zoom);
+    }
+
+    /// Queue the start of drawing a frame at a time (milliseconds); a frame started before
+    /// the previous one ended replaces it.
+    pub fn render_start(&mut self, time: f64) {
+        if self.render_start.is_none() {
+            self.events.push(GlobeEvent::RenderStart);
+        }
+        self.render_start = Some(time);
+    }
+
+    /// Queue the end of drawing the frame started, at a time (milliseconds).
+    pub fn render_end(&mut self, time: f64) {
+        if let Some(start) = self.render_start.take() {
+            self.events.push(GlobeEvent::RenderEnd(time - start));
+        }
+    }
+
+    /// Queue an event.
+    pub fn push(&mut self, event: GlobeEvent) {
+        self.events.push(event);
+    }
+
+    /// Take the events not yet dispatched, with the functions registered for each.
+    pub fn take(&mut self) -> Vec<(GlobeEvent, Vec<js_sys::Function>)> {
+        std::mem::take(&mut self.events)
+            .into_iter()
+            .map(|event| {
+                let callbacks = self
+                    .listeners
+                    .iter()
+                    .filter(|(event_type, _)| *event_type == event.event_type())
+                    .map(|(_, callback)| callback.clone())
+                    .collect();
+                (event, callbacks)
+            })
+            .collect()
+    }
+
+    /// Whether events are waiting to be dispatched.
+    pub fn pending(&self) -> bool {
+        !self.events.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queues_changes_of_the_view_and_frames() {
+        let mut emitter = Emitter::default();
+        let orientation = Orientation::default();
+        emitter.view(orientation, 1.0);
+        emitter.view(orientation, 1.0);
+        assert!(!emitter.pending());
+
+        let turned = Orientation {
+            rotation: 10.0,
+            ..orientation
+        };
+        emitter.view(turned, 2.0);
+        emitter.render_start(100.0);
+        // Frames restarted before they end are reported once
+        emitter.render_start(110.0);
+        emitter.render_end(125.0);
+        emitter.render_end(130.0);
+        let events = emitter
+            .take()
+            .into_iter()
+            .map(|(event, _)| event)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                GlobeEvent::RotationChange(turned),
+                GlobeEvent::ZoomChange(2.0),
+                GlobeEvent::RenderStart,
+                GlobeEvent::RenderEnd(15.0),
+            ]
+        );
+        assert_eq!(events[3].event_type(), "renderend");
+        assert!(!emitter.pending());
+    }
+}
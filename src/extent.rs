@@ -0,0 +1,93 @@
+// Locator frame outlining the extent of a map shown elsewhere on the page.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::coords::LonLat;
+use crate::selection::Bounds;
+use crate::view::{Frame, Side};
+
+const EXTENT_STROKE_STYLE: &str = "rgba(223, 0, 0, 1.0)";
+const EXTENT_HALO_STROKE_STYLE: &str = "rgba(255, 255, 255, 0.8)";
+const EXTENT_LINE_WIDTH: f64 = 0.005;
+const EXTENT_HALO_LINE_WIDTH: f64 = 0.011;
+
+#[derive(Debug, Default)]
+pub struct Extent {
+    bounds: Option<Bounds>,
+}
+
+impl Extent {
+    /// Show the extent between its south-west and north-east corners, eastwards from the
+    /// west, so extents crossing the antimeridian have the greater longitude in the west;
+    /// returns whether the extent is valid.
+    pub fn show(&mut self, south_west: LonLat, north_east: LonLat) -> bool {
+        let bounds = Bounds {
+            west: south_west.lon,
+            south: south_west.lat,
+            east: north_east.lon,
+            north: north_east.lat,
+        };
+        let valid = [bounds.west, bounds.south, bounds.east, bounds.north]
+            .iter()
+            .all(|value| value.is_finite())
+            && (-90.0..=90.0).contains(&bounds.south)
+            && (-90.0..=90.0).contains(&bounds.north)
+            && bounds.south <= bounds.north;
+        if valid {
+            self.bounds = Some(bounds);
+        }
+        valid
+    }
+
+    /// Remove the extent.
+    pub fn hide(&mut self) {
+        self.bounds = None;
+    }
+
+    /// Outline the extent on the front of the globe onto the canvas, over a halo so it
+    /// stands out from the layers beneath.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        let Some(bounds) = self.bounds else {
+            return Ok(());
+        };
+        let boundary = bounds.boundary();
+        let points = boundary
+            .iter()
+            .chain(boundary.first())
+            .map(|(lon, lat)| frame.project(*lon, *lat))
+            .collect::<Vec<_>>();
+
+        context.begin_path();
+        for segment in points.windows(2) {
+            if frame.segment_side(segment[0], segment[1]) != Some(Side::Front) {
+                continue;
+            }
+            let ((_, y_prev, z_prev), (_, y, z)) = (segment[0], segment[1]);
+            context.move_to(y_prev, z_prev);
+            context.line_to(y, z);
+        }
+        context.set_line_width(frame.line_width(EXTENT_HALO_LINE_WIDTH));
+        context.set_stroke_style_str(EXTENT_HALO_STROKE_STYLE);
+        context.stroke();
+        context.set_line_width(frame.line_width(EXTENT_LINE_WIDTH));
+        context.set_stroke_style_str(EXTENT_STROKE_STYLE);
+        context.stroke();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_extents() {
+        let mut extent = Extent::default();
+        assert!(extent.show(LonLat::new(170.0, -10.0), LonLat::new(-170.0, 10.0)));
+        assert!(!extent.show(LonLat::new(0.0, 10.0), LonLat::new(10.0, -10.0)));
+        assert!(!extent.show(LonLat::new(0.0, -100.0), LonLat::new(10.0, 0.0)));
+        assert!(!extent.show(LonLat::new(f64::NAN, 0.0), LonLat::new(10.0, 10.0)));
+    }
+}
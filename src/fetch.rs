@@ -0,0 +1,449 @@
+// Requests of the network layers for their data, queued by the priority the layers give them
+// with a limit to the requests in flight, cached in memory and in IndexedDB, and aborted when
+// the layers no longer want them, such as tiles scrolled out of view.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    AbortController, AbortSignal, IdbDatabase, IdbOpenDbRequest, IdbRequest, IdbTransactionMode,
+    RequestInit, Response, WorkerGlobalScope,
+};
+
+const DEFAULT_MAX_REQUESTS: usize = 6;
+// Bytes of responses kept in memory
+const MEMORY_CACHE_SIZE: usize = 32 * 1024 * 1024;
+// Age (milliseconds) beyond which responses kept in IndexedDB are fetched again
+const PERSISTENT_CACHE_MAX_AGE: f64 = 7.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+const DATABASE_NAME: &str = "wasm-globe";
+const DATABASE_STORE: &str = "responses";
+
+/// Body of a response, shared between the cache and the layers.
+pub type Body = Rc<[u8]>;
+
+/// Request queued or in flight.
+struct Entry {
+    /// Layer the response is for.
+    client: &'static str,
+    /// Priority of the request, the highest sent first.
+    priority: f64,
+    /// Round of the client's requests the request was last made in.
+    round: u64,
+    /// Controller aborting the request, once it is in flight.
+    controller: Option<AbortController>,
+}
+
+/// Responses by their URLs, evicting the least recently used beyond a number of bytes.
+#[derive(Default)]
+struct MemoryCache {
+    responses: HashMap<String, Body>,
+    /// URLs from the least to the most recently used.
+    order: VecDeque<String>,
+    size: usize,
+}
+
+impl MemoryCache {
+    fn get(&mut self, url: &str) -> Option<Body> {
+        let body = self.responses.get(url)?.clone();
+        self.order.retain(|used| used != url);
+        self.order.push_back(url.to_string());
+        Some(body)
+    }
+
+    fn insert(&mut self, url: &str, body: Body) {
+        if let Some(previous) = self.responses.insert(url.to_string(), body.clone()) {
+            self.size -= previous.len();
+            self.order.retain(|used| used != url);
+        }
+        self.size += body.len();
+        self.order.push_back(url.to_string());
+        while self.size > MEMORY_CACHE_SIZE {
+            let Some(url) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(body) = self.responses.remove(&url) {
+                self.size -= body.len();
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Database of the responses kept between visits.
+#[derive(Default)]
+enum Database {
+    #[default]
+    Closed,
+    Opening,
+    Open(IdbDatabase),
+    /// IndexedDB is unavailable, such as in private browsing, or its use was turned off.
+    Unavailable,
+}
+
+struct Queue {
+    max_requests: usize,
+    entries: HashMap<String, Entry>,
+    /// Current round of the requests of each client.
+    rounds: HashMap<&'static str, u64>,
+    /// Responses not yet received by their clients, with their URLs.
+    responses: Vec<(&'static str, String, Result<Body, String>)>,
+    memory: MemoryCache,
+    persistent: bool,
+    database: Database,
+    /// Requests made since the queue was last empty.
+    requested: usize,
+}
+
+impl Default for Queue {
+    fn default() -> Self {
+        Self {
+            max_requests: DEFAULT_MAX_REQUESTS,
+            entries: HashMap::new(),
+            rounds: HashMap::new(),
+            responses: Vec::new(),
+            memory: MemoryCache::default(),
+            persistent: true,
+            database: Database::default(),
+            requested: 0,
+        }
+    }
+}
+
+impl Queue {
+    /// URL of the queued request of highest priority, if another can be in flight.
+    fn next(&self) -> Option<String> {
+        let in_flight = self
+            .entries
+            .values()
+            .filter(|entry| entry.controller.is_some())
+            .count();
+        if in_flight >= self.max_requests {
+            return None;
+        }
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.controller.is_none())
+            .max_by(|(_, a), (_, b)| a.priority.total_cmp(&b.priority))
+            .map(|(url, _)| url.clone())
+    }
+}
+
+/// Requests shared by the layers of a globe, which each frame request the data they want with
+/// `request`, cancel the rest with `sweep` and take what arrived with `receive`.
+#[derive(Clone, Default)]
+pub struct Fetcher(Rc<RefCell<Queue>>);
+
+impl std::fmt::Debug for Fetcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let queue = self.0.borrow();
+        f.debug_struct("Fetcher")
+            .field("max_requests", &queue.max_requests)
+            .field("requests", &queue.entries.len())
+            .field("cached", &queue.memory.responses.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Fetcher {
+    /// Request a URL for a client with a priority, the highest sent first; a request already
+    /// queued or in flight takes the new priority, and a response cached in memory is received
+    /// without a request.
+    pub fn request(&self, client: &'static str, url: &str, priority: f64) {
+        let mut queue = self.0.borrow_mut();
+        let round = queue.rounds.get(client).copied().unwrap_or_default();
+        if let Some(entry) = queue.entries.get_mut(url) {
+            entry.priority = priority;
+            entry.round = round;
+            return;
+        }
+        if let Some(body) = queue.memory.get(url) {
+            queue.responses.push((client, url.to_string(), Ok(body)));
+            return;
+        }
+        if queue.entries.is_empty() {
+            queue.requested = 0;
+        }
+        queue.requested += 1;
+        queue.entries.insert(
+            url.to_string(),
+            Entry {
+                client,
+                priority,
+                round,
+                controller: None,
+            },
+        );
+    }
+
+    /// Cancel the requests of a client not made since it was last swept, aborting those in
+    /// flight, and send the requests queued as others finish.
+    pub fn sweep(&self, client: &'static str) {
+        let aborted = {
+            let mut queue = self.0.borrow_mut();
+            let round = queue.rounds.entry(client).or_default();
+            let current = *round;
+            *round += 1;
+            let unwanted = queue
+                .entries
+                .iter()
+                .filter(|(_, entry)| entry.client == client && entry.round < current)
+                .map(|(url, _)| url.clone())
+                .collect::<Vec<_>>();
+            unwanted
+                .iter()
+                .filter_map(|url| queue.entries.remove(url)?.controller)
+                .collect::<Vec<_>>()
+        };
+        for controller in aborted {
+            controller.abort();
+        }
+        self.dispatch();
+    }
+
+    /// Take the responses to a client's requests received since it last took them, with their
+    /// URLs.
+    pub fn receive(&self, client: &'static str) -> Vec<(String, Result<Body, String>)> {
+        let mut queue = self.0.borrow_mut();
+        let (received, others) = std::mem::take(&mut queue.responses)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(response_client, _, _)| *response_client == client);
+        queue.responses = others;
+        received
+            .into_iter()
+            .map(|(_, url, body)| (url, body))
+            .collect()
+    }
+
+    /// Progress (0 to 1) of the requests made since the queue was last empty, by the number
+    /// finished or cancelled, or `None` when no requests are queued or in flight.
+    pub fn progress(&self) -> Option<f64> {
+        let queue = self.0.borrow();
+        (!queue.entries.is_empty())
+            .then(|| (queue.requested - queue.entries.len()) as f64 / queue.requested.max(1) as f64)
+    }
+
+    /// Set the greatest number of requests in flight at once.
+    pub fn set_max_requests(&self, max_requests: usize) {
+        self.0.borrow_mut().max_requests = max_requests.max(1);
+        self.dispatch();
+    }
+
+    /// Set whether responses are kept in IndexedDB between visits.
+    pub fn set_persistent(&self, persistent: bool) {
+        let mut queue = self.0.borrow_mut();
+        queue.persistent = persistent;
+        if !persistent {
+            queue.database = Database::Unavailable;
+        } else if matches!(queue.database, Database::Unavailable) {
+            queue.database = Database::Closed;
+        }
+    }
+
+    /// Discard the responses cached in memory and in IndexedDB.
+    pub fn clear(&self) -> Result<(), JsValue> {
+        let database = {
+            let mut queue = self.0.borrow_mut();
+            queue.memory.clear();
+            match &queue.database {
+                Database::Open(database) => Some(database.clone()),
+                _ => None,
+            }
+        };
+        if let Some(database) = database {
+            database
+                .transaction_with_str_and_mode(DATABASE_STORE, IdbTransactionMode::Readwrite)?
+                .object_store(DATABASE_STORE)?
+                .clear()?;
+        }
+        Ok(())
+    }
+
+    /// Send the queued requests of highest priority while fewer than the limit are in flight.
+    fn dispatch(&self) {
+        self.open_database();
+        loop {
+            let (url, signal) = {
+                let mut queue = self.0.borrow_mut();
+                let Some(url) = queue.next() else {
+                    return;
+                };
+                let Ok(controller) = AbortController::new() else {
+                    return;
+                };
+                let signal = controller.signal();
+                queue.entries.get_mut(&url).unwrap().controller = Some(controller);
+                (url, signal)
+            };
+            wasm_bindgen_futures::spawn_local(self.clone().load(url, signal));
+        }
+    }
+
+    /// Load a response, from IndexedDB or else the network, into the responses for its client.
+    async fn load(self, url: String, signal: AbortSignal) {
+        let database = match &self.0.borrow().database {
+            Database::Open(database) => Some(database.clone()),
+            _ => None,
+        };
+        let stored = match &database {
+            Some(database) => read(database, &url).await.ok().flatten(),
+            None => None,
+        };
+        let result = match stored {
+            Some(body) => Ok(body),
+            None => fetch(&url, &signal).await.map_err(|error| {
+                error
+                    .as_string()
+                    .unwrap_or_else(|| format!("failed to fetch {}", url))
+            }),
+        };
+        {
+            let mut queue = self.0.borrow_mut();
+            // Requests swept while in flight are dropped
+            let Some(entry) = queue.entries.remove(&url) else {
+                return;
+            };
+            if let Ok(body) = &result {
+                queue.memory.insert(&url, body.clone());
+            }
+            queue
+                .responses
+                .push((entry.client, url.clone(), result.clone()));
+        }
+        if let (Some(database), Ok(body)) = (database, result) {
+            let _ = write(&database, &url, &body);
+        }
+        self.dispatch();
+    }
+
+    /// Open the database of responses kept between visits, if not yet opened.
+    fn open_database(&self) {
+        {
+            let mut queue = self.0.borrow_mut();
+            if !queue.persistent || !matches!(queue.database, Database::Closed) {
+                return;
+            }
+            queue.database = Database::Opening;
+        }
+        let fetcher = self.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let database = open().await.ok();
+            let mut queue = fetcher.0.borrow_mut();
+            // Turning off the persistent cache while opening leaves it unused
+            if matches!(queue.database, Database::Opening) {
+                queue.database = database.map_or(Database::Unavailable, Database::Open);
+            }
+        });
+    }
+}
+
+/// Body of a response from the network, failing for statuses other than success.
+async fn fetch(url: &str, signal: &AbortSignal) -> Result<Body, JsValue> {
+    let init = RequestInit::new();
+    init.set_signal(Some(signal));
+    // Globes drawn in a worker fetch from the worker
+    let request = match web_sys::window() {
+        Some(window) => window.fetch_with_str_and_init(url, &init),
+        None => worker_scope().fetch_with_str_and_init(url, &init),
+    };
+    let response = JsFuture::from(request).await?.dyn_into::<Response>()?;
+    if !response.ok() {
+        return Err(JsValue::from_str(&format!(
+            "{} fetching {}",
+            response.status(),
+            url
+        )));
+    }
+    let buffer = JsFuture::from(response.array_buffer()?).await?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec().into())
+}
+
+fn worker_scope() -> WorkerGlobalScope {
+    js_sys::global().unchecked_into()
+}
+
+/// Result of a request to IndexedDB, once it succeeds.
+async fn complete(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+    JsFuture::from(promise).await?;
+    request.result()
+}
+
+async fn open() -> Result<IdbDatabase, JsValue> {
+    let factory = match web_sys::window() {
+        Some(window) => window.indexed_db()?,
+        None => worker_scope().indexed_db()?,
+    }
+    .ok_or_else(|| JsValue::from_str("no IndexedDB"))?;
+    let request: IdbOpenDbRequest = factory.open_with_u32(DATABASE_NAME, 1)?;
+    let upgraded = request.clone();
+    // Called once, when the database is created
+    let upgrade = Closure::once_into_js(move || {
+        if let Ok(database) = upgraded.result() {
+            let _ = database
+                .unchecked_into::<IdbDatabase>()
+                .create_object_store(DATABASE_STORE);
+        }
+    });
+    request.set_onupgradeneeded(Some(upgrade.unchecked_ref()));
+    Ok(complete(&request).await?.unchecked_into())
+}
+
+/// Body of a response kept in the database, unless missing or too old.
+async fn read(database: &IdbDatabase, url: &str) -> Result<Option<Body>, JsValue> {
+    let request = database
+        .transaction_with_str(DATABASE_STORE)?
+        .object_store(DATABASE_STORE)?
+        .get(&url.into())?;
+    let stored = complete(&request).await?;
+    if stored.is_undefined() {
+        return Ok(None);
+    }
+    let time = js_sys::Reflect::get(&stored, &"time".into())?
+        .as_f64()
+        .unwrap_or_default();
+    if js_sys::Date::now() - time > PERSISTENT_CACHE_MAX_AGE {
+        return Ok(None);
+    }
+    let body = js_sys::Reflect::get(&stored, &"body".into())?;
+    Ok(Some(body.dyn_into::<js_sys::Uint8Array>()?.to_vec().into()))
+}
+
+/// Keep the body of a response in the database, with the time it was fetched.
+fn write(database: &IdbDatabase, url: &str, body: &[u8]) -> Result<(), JsValue> {
+    let stored = js_sys::Object::new();
+    js_sys::Reflect::set(&stored, &"time".into(), &js_sys::Date::now().into())?;
+    js_sys::Reflect::set(&stored, &"body".into(), &js_sys::Uint8Array::from(body))?;
+    database
+        .transaction_with_str_and_mode(DATABASE_STORE, IdbTransactionMode::Readwrite)?
+        .object_store(DATABASE_STORE)?
+        .put_with_key(&stored, &url.into())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used_responses() {
+        let mut cache = MemoryCache::default();
+        let body = |len: usize| -> Body { vec![0; len].into() };
+        cache.insert("a", body(MEMORY_CACHE_SIZE / 2));
+        cache.insert("b", body(MEMORY_CACHE_SIZE / 4));
+        assert!(cache.get("a").is_some());
+        cache.insert("c", body(MEMORY_CACHE_SIZE / 2));
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+        assert_eq!(cache.size, MEMORY_CACHE_SIZE);
+    }
+}
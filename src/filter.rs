@@ -0,0 +1,353 @@
+// Filtering the features of layers by predicates on their attributes.
+//
+// Expressions compare attribute fields with numbers or quoted text, as in
+// `scalerank <= 3 && featurecla == "Coastline"`, and combine comparisons
+// with `&&`, `||`, `!` and parentheses.
+
+use crate::info::{self, Attribute};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    Number(f64),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Identifier(String),
+    Literal(Literal),
+    Comparison(Comparison),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+/// Predicate on the attributes of features.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+    Compare {
+        /// Index of the field, in the order of the layer's fields.
+        field: usize,
+        comparison: Comparison,
+        literal: Literal,
+    },
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+impl Filter {
+    /// Parse an expression for the fields of a layer.
+    pub fn parse(expression: &str, fields: &[&str]) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenise(expression)?,
+            position: 0,
+            fields,
+        };
+        let filter = parser.or()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(filter),
+            Some(token) => Err(format!("unexpected {:?}", token)),
+        }
+    }
+
+    /// Whether a feature's attributes, in the order of the layer's fields, match.
+    pub fn matches(&self, attributes: &[Attribute]) -> bool {
+        match self {
+            Filter::Compare {
+                field,
+                comparison,
+                literal,
+            } => {
+                let ordering = match (attributes.get(*field), literal) {
+                    (Some(Attribute::Number(value)), Literal::Number(number)) => {
+                        value.partial_cmp(number)
+                    }
+                    (Some(Attribute::Text(value)), Literal::Text(text)) => {
+                        Some((*value).cmp(text.as_str()))
+                    }
+                    // Values of other types, or missing values, do not compare
+                    _ => None,
+                };
+                ordering.is_some_and(|ordering| match comparison {
+                    Comparison::Equal => ordering.is_eq(),
+                    Comparison::NotEqual => ordering.is_ne(),
+                    Comparison::Less => ordering.is_lt(),
+                    Comparison::LessOrEqual => ordering.is_le(),
+                    Comparison::Greater => ordering.is_gt(),
+                    Comparison::GreaterOrEqual => ordering.is_ge(),
+                })
+            }
+            Filter::Not(filter) => !filter.matches(attributes),
+            Filter::And(a, b) => a.matches(attributes) && b.matches(attributes),
+            Filter::Or(a, b) => a.matches(attributes) || b.matches(attributes),
+        }
+    }
+}
+
+/// Filters of the embedded layers.
+#[derive(Debug, Default)]
+pub struct LayerFilters {
+    coastline: Option<Filter>,
+    countries: Option<Filter>,
+    land: Option<Filter>,
+}
+
+impl LayerFilters {
+    /// Set the filter of a layer from an expression, or remove it when the expression is empty.
+    pub fn set(&mut self, layer: &str, expression: &str) -> Result<(), String> {
+        let (fields, _) =
+            info::layer_attributes(layer).ok_or_else(|| format!("unknown layer {:?}", layer))?;
+        let filter = if expression.trim().is_empty() {
+            None
+        } else {
+            Some(Filter::parse(expression, fields)?)
+        };
+        match layer {
+            "coastline" => self.coastline = filter,
+            "land" => self.land = filter,
+            _ => self.countries = filter,
+        }
+        Ok(())
+    }
+
+    /// Whether a feature of a layer is shown, by layer identifier and feature index.
+    pub fn shows(&self, layer: &str, feature: usize) -> bool {
+        let Some(filter) = self.filter(layer) else {
+            return true;
+        };
+        info::layer_attributes(layer)
+            .and_then(|(_, attributes)| attributes.get(feature))
+            .is_some_and(|attributes| filter.matches(attributes))
+    }
+
+    /// Whether a feature of a layer with attributes, in the order of the layer's fields,
+    /// is shown; for features of the layer's other levels of detail.
+    pub fn shows_attributes(&self, layer: &str, attributes: &[Attribute]) -> bool {
+        self.filter(layer)
+            .is_none_or(|filter| filter.matches(attributes))
+    }
+
+    fn filter(&self, layer: &str) -> Option<&Filter> {
+        match layer {
+            "coastline" => self.coastline.as_ref(),
+            "countries" => self.countries.as_ref(),
+            "land" => self.land.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+fn tokenise(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            '&' | '|' => {
+                chars.next();
+                if chars.next() != Some(c) {
+                    return Err(format!("expected {}{}", c, c));
+                }
+                tokens.push(if c == '&' { Token::And } else { Token::Or });
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let equals = chars.next_if_eq(&'=').is_some();
+                tokens.push(match (c, equals) {
+                    ('=', true) => Token::Comparison(Comparison::Equal),
+                    ('!', true) => Token::Comparison(Comparison::NotEqual),
+                    ('!', false) => Token::Not,
+                    ('<', false) => Token::Comparison(Comparison::Less),
+                    ('<', true) => Token::Comparison(Comparison::LessOrEqual),
+                    ('>', false) => Token::Comparison(Comparison::Greater),
+                    ('>', true) => Token::Comparison(Comparison::GreaterOrEqual),
+                    _ => return Err("expected ==".to_string()),
+                });
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some(char) => text.push(char),
+                        None => return Err("unterminated text".to_string()),
+                    }
+                }
+                tokens.push(Token::Literal(Literal::Text(text)));
+            }
+            _ if c.is_ascii_digit() || c == '-' || c == '.' => {
+                let mut number = String::new();
+                while let Some(char) = chars
+                    .next_if(|char| char.is_ascii_alphanumeric() || matches!(char, '-' | '+' | '.'))
+                {
+                    number.push(char);
+                }
+                let number = number
+                    .parse()
+                    .map_err(|_| format!("invalid number {:?}", number))?;
+                tokens.push(Token::Literal(Literal::Number(number)));
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let mut identifier = String::new();
+                while let Some(char) = chars.next_if(|char| char.is_alphanumeric() || *char == '_')
+                {
+                    identifier.push(char);
+                }
+                tokens.push(Token::Identifier(identifier));
+            }
+            _ => return Err(format!("unexpected {:?}", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser of expressions, with `||` binding loosest and `!` tightest.
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    fields: &'a [&'a str],
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn next_if(&mut self, token: &Token) -> bool {
+        let matches = self.tokens.get(self.position) == Some(token);
+        if matches {
+            self.position += 1;
+        }
+        matches
+    }
+
+    fn or(&mut self) -> Result<Filter, String> {
+        let mut filter = self.and()?;
+        while self.next_if(&Token::Or) {
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter, String> {
+        let mut filter = self.not()?;
+        while self.next_if(&Token::And) {
+            filter = Filter::And(Box::new(filter), Box::new(self.not()?));
+        }
+        Ok(filter)
+    }
+
+    fn not(&mut self) -> Result<Filter, String> {
+        if self.next_if(&Token::Not) {
+            return Ok(Filter::Not(Box::new(self.not()?)));
+        }
+        if self.next_if(&Token::Open) {
+            let filter = self.or()?;
+            if !self.next_if(&Token::Close) {
+                return Err("expected )".to_string());
+            }
+            return Ok(filter);
+        }
+        self.compare()
+    }
+
+    fn compare(&mut self) -> Result<Filter, String> {
+        let Some(Token::Identifier(name)) = self.next() else {
+            return Err("expected a field".to_string());
+        };
+        let field = self
+            .fields
+            .iter()
+            .position(|field| *field == name)
+            .ok_or_else(|| format!("unknown field {:?}", name))?;
+        let Some(Token::Comparison(comparison)) = self.next() else {
+            return Err("expected a comparison".to_string());
+        };
+        let Some(Token::Literal(literal)) = self.next() else {
+            return Err("expected a number or text".to_string());
+        };
+        Ok(Filter::Compare {
+            field,
+            comparison,
+            literal,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELDS: &[&str] = &["scalerank", "featurecla"];
+
+    fn matches(expression: &str, attributes: &[Attribute]) -> bool {
+        Filter::parse(expression, FIELDS)
+            .unwrap()
+            .matches(attributes)
+    }
+
+    #[test]
+    fn compares_numbers_and_text() {
+        let attributes = [Attribute::Number(1.0), Attribute::Text("Coastline")];
+        assert!(matches("scalerank <= 3", &attributes));
+        assert!(!matches("scalerank > 1", &attributes));
+        assert!(matches("featurecla == 'Coastline'", &attributes));
+        assert!(!matches("featurecla != \"Coastline\"", &attributes));
+    }
+
+    #[test]
+    fn combines_with_precedence() {
+        let attributes = [Attribute::Number(5.0), Attribute::Text("Coastline")];
+        assert!(matches(
+            "scalerank < 3 || scalerank > 4 && featurecla == 'Coastline'",
+            &attributes
+        ));
+        assert!(!matches(
+            "(scalerank < 3 || scalerank > 4) && !(featurecla == 'Coastline')",
+            &attributes
+        ));
+    }
+
+    #[test]
+    fn does_not_match_other_types() {
+        assert!(!matches(
+            "scalerank == 'one'",
+            &[Attribute::Number(1.0), Attribute::Null]
+        ));
+        assert!(!matches(
+            "featurecla != 'Coastline'",
+            &[Attribute::Number(1.0), Attribute::Null]
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        assert!(Filter::parse("rank <= 3", FIELDS).is_err());
+        assert!(Filter::parse("scalerank <=", FIELDS).is_err());
+        assert!(Filter::parse("scalerank = 3", FIELDS).is_err());
+        assert!(Filter::parse("(scalerank < 3", FIELDS).is_err());
+        assert!(Filter::parse("scalerank < 3 scalerank", FIELDS).is_err());
+    }
+}
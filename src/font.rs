@@ -0,0 +1,333 @@
+// Stroked vector font, so text is drawn the same by every backend, including renderers
+// without text support such as the server-side renderer.
+
+// Glyphs are drawn on a grid in font units, with the baseline at 0 and y up
+const CAP_HEIGHT: f64 = 6.0;
+// Lowercase letters are drawn as small capitals scaled by this
+const SMALL_CAPS_SCALE: f64 = 0.75;
+// Space between glyphs, and the width of a space, in font units
+const GLYPH_SPACING: f64 = 1.5;
+const SPACE_WIDTH: f64 = 3.0;
+
+type Stroke = &'static [(i8, i8)];
+
+/// Glyph with its width and strokes in font units.
+#[derive(Clone, Copy)]
+struct Glyph {
+    width: i8,
+    strokes: &'static [Stroke],
+}
+
+const fn glyph(width: i8, strokes: &'static [Stroke]) -> Glyph {
+    Glyph { width, strokes }
+}
+
+// Drawn for characters without a glyph
+const MISSING: Glyph = glyph(4, &[&[(0, 0), (4, 0), (4, 6), (0, 6), (0, 0)]]);
+
+fn glyph_of(c: char) -> Glyph {
+    const O: Stroke = &[
+        (1, 0),
+        (3, 0),
+        (4, 1),
+        (4, 5),
+        (3, 6),
+        (1, 6),
+        (0, 5),
+        (0, 1),
+        (1, 0),
+    ];
+    const P: Stroke = &[(0, 0), (0, 6), (3, 6), (4, 5), (4, 4), (3, 3), (0, 3)];
+    match c {
+        '0' => glyph(4, &[O, &[(0, 1), (4, 5)]]),
+        '1' => glyph(4, &[&[(1, 5), (2, 6), (2, 0)], &[(1, 0), (3, 0)]]),
+        '2' => glyph(
+            4,
+            &[&[(0, 5), (1, 6), (3, 6), (4, 5), (4, 4), (0, 0), (4, 0)]],
+        ),
+        '3' => glyph(
+            4,
+            &[
+                &[
+                    (0, 5),
+                    (1, 6),
+                    (3, 6),
+                    (4, 5),
+                    (4, 4),
+                    (3, 3),
+                    (4, 2),
+                    (4, 1),
+                    (3, 0),
+                    (1, 0),
+                    (0, 1),
+                ],
+                &[(1, 3), (3, 3)],
+            ],
+        ),
+        '4' => glyph(4, &[&[(3, 0), (3, 6), (0, 2), (4, 2)]]),
+        '5' => glyph(
+            4,
+            &[&[
+                (4, 6),
+                (0, 6),
+                (0, 3),
+                (3, 3),
+                (4, 2),
+                (4, 1),
+                (3, 0),
+                (0, 0),
+            ]],
+        ),
+        '6' => glyph(
+            4,
+            &[&[
+                (4, 5),
+                (3, 6),
+                (1, 6),
+                (0, 5),
+                (0, 1),
+                (1, 0),
+                (3, 0),
+                (4, 1),
+                (4, 2),
+                (3, 3),
+                (0, 3),
+            ]],
+        ),
+        '7' => glyph(4, &[&[(0, 6), (4, 6), (1, 0)]]),
+        '8' => glyph(
+            4,
+            &[&[
+                (1, 3),
+                (0, 4),
+                (0, 5),
+                (1, 6),
+                (3, 6),
+                (4, 5),
+                (4, 4),
+                (3, 3),
+                (1, 3),
+                (0, 2),
+                (0, 1),
+                (1, 0),
+                (3, 0),
+                (4, 1),
+                (4, 2),
+                (3, 3),
+            ]],
+        ),
+        '9' => glyph(
+            4,
+            &[&[
+                (4, 3),
+                (1, 3),
+                (0, 4),
+                (0, 5),
+                (1, 6),
+                (3, 6),
+                (4, 5),
+                (4, 1),
+                (3, 0),
+                (1, 0),
+                (0, 1),
+            ]],
+        ),
+        'A' => glyph(
+            4,
+            &[&[(0, 0), (0, 4), (2, 6), (4, 4), (4, 0)], &[(0, 2), (4, 2)]],
+        ),
+        'B' => glyph(4, &[P, &[(3, 3), (4, 2), (4, 1), (3, 0), (0, 0)]]),
+        'C' => glyph(
+            4,
+            &[&[
+                (4, 5),
+                (3, 6),
+                (1, 6),
+                (0, 5),
+                (0, 1),
+                (1, 0),
+                (3, 0),
+                (4, 1),
+            ]],
+        ),
+        'D' => glyph(
+            4,
+            &[&[(0, 0), (0, 6), (3, 6), (4, 5), (4, 1), (3, 0), (0, 0)]],
+        ),
+        'E' => glyph(4, &[&[(4, 6), (0, 6), (0, 0), (4, 0)], &[(0, 3), (3, 3)]]),
+        'F' => glyph(4, &[&[(4, 6), (0, 6), (0, 0)], &[(0, 3), (3, 3)]]),
+        'G' => glyph(
+            4,
+            &[&[
+                (4, 5),
+                (3, 6),
+                (1, 6),
+                (0, 5),
+                (0, 1),
+                (1, 0),
+                (3, 0),
+                (4, 1),
+                (4, 3),
+                (2, 3),
+            ]],
+        ),
+        'H' => glyph(
+            4,
+            &[&[(0, 0), (0, 6)], &[(4, 0), (4, 6)], &[(0, 3), (4, 3)]],
+        ),
+        'I' => glyph(
+            2,
+            &[&[(0, 6), (2, 6)], &[(1, 6), (1, 0)], &[(0, 0), (2, 0)]],
+        ),
+        'J' => glyph(4, &[&[(4, 6), (4, 1), (3, 0), (1, 0), (0, 1)]]),
+        'K' => glyph(
+            4,
+            &[&[(0, 0), (0, 6)], &[(4, 6), (0, 2)], &[(1, 3), (4, 0)]],
+        ),
+        'L' => glyph(4, &[&[(0, 6), (0, 0), (4, 0)]]),
+        'M' => glyph(4, &[&[(0, 0), (0, 6), (2, 3), (4, 6), (4, 0)]]),
+        'N' => glyph(4, &[&[(0, 0), (0, 6), (4, 0), (4, 6)]]),
+        'O' => glyph(4, &[O]),
+        'P' => glyph(4, &[P]),
+        'Q' => glyph(4, &[O, &[(2, 2), (4, 0)]]),
+        'R' => glyph(4, &[P, &[(2, 3), (4, 0)]]),
+        'S' => glyph(
+            4,
+            &[&[
+                (4, 5),
+                (3, 6),
+                (1, 6),
+                (0, 5),
+                (0, 4),
+                (1, 3),
+                (3, 3),
+                (4, 2),
+                (4, 1),
+                (3, 0),
+                (1, 0),
+                (0, 1),
+            ]],
+        ),
+        'T' => glyph(4, &[&[(0, 6), (4, 6)], &[(2, 6), (2, 0)]]),
+        'U' => glyph(4, &[&[(0, 6), (0, 1), (1, 0), (3, 0), (4, 1), (4, 6)]]),
+        'V' => glyph(4, &[&[(0, 6), (2, 0), (4, 6)]]),
+        'W' => glyph(4, &[&[(0, 6), (1, 0), (2, 3), (3, 0), (4, 6)]]),
+        'X' => glyph(4, &[&[(0, 0), (4, 6)], &[(0, 6), (4, 0)]]),
+        'Y' => glyph(4, &[&[(0, 6), (2, 3), (4, 6)], &[(2, 3), (2, 0)]]),
+        'Z' => glyph(4, &[&[(0, 6), (4, 6), (0, 0), (4, 0)]]),
+        '.' => glyph(0, &[&[(0, 0), (0, 1)]]),
+        ',' => glyph(1, &[&[(1, 1), (0, -1)]]),
+        ':' => glyph(0, &[&[(0, 1), (0, 2)], &[(0, 4), (0, 5)]]),
+        '-' => glyph(3, &[&[(0, 3), (3, 3)]]),
+        '+' => glyph(4, &[&[(0, 3), (4, 3)], &[(2, 1), (2, 5)]]),
+        '/' => glyph(4, &[&[(0, 0), (4, 6)]]),
+        '(' => glyph(2, &[&[(2, 6), (0, 4), (0, 2), (2, 0)]]),
+        ')' => glyph(2, &[&[(0, 6), (2, 4), (2, 2), (0, 0)]]),
+        '°' => glyph(2, &[&[(1, 6), (2, 5), (1, 4), (0, 5), (1, 6)]]),
+        '\'' | '′' => glyph(0, &[&[(0, 6), (0, 4)]]),
+        '"' | '″' => glyph(2, &[&[(0, 6), (0, 4)], &[(2, 6), (2, 4)]]),
+        '%' => glyph(
+            4,
+            &[
+                &[(0, 0), (4, 6)],
+                &[(0, 6), (1, 6), (1, 5), (0, 5), (0, 6)],
+                &[(3, 1), (4, 1), (4, 0), (3, 0), (3, 1)],
+            ],
+        ),
+        '?' => glyph(
+            4,
+            &[
+                &[(0, 5), (1, 6), (3, 6), (4, 5), (4, 4), (2, 3), (2, 2)],
+                &[(2, 0), (2, 1)],
+            ],
+        ),
+        '!' => glyph(0, &[&[(0, 6), (0, 2)], &[(0, 0), (0, 1)]]),
+        _ => MISSING,
+    }
+}
+
+/// Glyph of a character with the scale it is drawn at, or `None` for whitespace.
+fn scaled_glyph(c: char) -> Option<(Glyph, f64)> {
+    if c.is_whitespace() {
+        None
+    } else if c.is_lowercase() {
+        let mut upper = c.to_uppercase();
+        match (upper.next(), upper.next()) {
+            (Some(upper), None) => Some((glyph_of(upper), SMALL_CAPS_SCALE)),
+            _ => Some((MISSING, SMALL_CAPS_SCALE)),
+        }
+    } else {
+        Some((glyph_of(c), 1.0))
+    }
+}
+
+/// Width of text drawn with a cap height (pixels).
+pub fn width(text: &str, cap_height: f64) -> f64 {
+    let mut width = 0.0;
+    for (i, c) in text.chars().enumerate() {
+        if i > 0 {
+            width += GLYPH_SPACING;
+        }
+        width += match scaled_glyph(c) {
+            Some((glyph, scale)) => glyph.width as f64 * scale,
+            None => SPACE_WIDTH,
+        };
+    }
+    width * cap_height / CAP_HEIGHT
+}
+
+/// Strokes (pixels, with y down) of text drawn with a cap height, centred above an anchor.
+pub fn strokes(text: &str, cap_height: f64, (x, y): (f64, f64)) -> Vec<Vec<(f64, f64)>> {
+    let unit = cap_height / CAP_HEIGHT;
+    let mut left = x - width(text, cap_height) / 2.0;
+    let mut strokes = Vec::new();
+    for c in text.chars() {
+        match scaled_glyph(c) {
+            Some((glyph, scale)) => {
+                strokes.extend(glyph.strokes.iter().map(|stroke| {
+                    stroke
+                        .iter()
+                        .map(|&(u, v)| {
+                            (left + u as f64 * scale * unit, y - v as f64 * scale * unit)
+                        })
+                        .collect::<Vec<_>>()
+                }));
+                left += glyph.width as f64 * scale * unit;
+            }
+            None => left += SPACE_WIDTH * unit,
+        }
+        left += GLYPH_SPACING * unit;
+    }
+    strokes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn centres_text_above_anchor() {
+        let strokes = strokes("TH", 12.0, (100.0, 50.0));
+        let points = strokes.iter().flatten().collect::<Vec<_>>();
+        let left = points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+        let right = points.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+        let top = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        let bottom = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+        assert_eq!((left + right) / 2.0, 100.0);
+        assert_eq!(right - left, width("TH", 12.0));
+        assert_eq!((top, bottom), (38.0, 50.0));
+    }
+
+    #[test]
+    fn draws_lowercase_as_small_capitals() {
+        assert!(width("abc", 6.0) < width("ABC", 6.0));
+        assert_eq!(
+            strokes("a", 6.0, (0.0, 0.0)).len(),
+            glyph_of('A').strokes.len()
+        );
+        assert_eq!(
+            width("A B", 6.0),
+            width("AB", 6.0) + SPACE_WIDTH + GLYPH_SPACING
+        );
+    }
+}
@@ -0,0 +1,211 @@
+// Formatting of numbers and coordinates for display, following the conventions of a locale.
+
+use crate::coords::LonLat;
+
+/// How coordinates are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CoordinateFormat {
+    /// Decimal degrees, such as 51.48° N.
+    #[default]
+    Decimal,
+    /// Degrees, minutes and seconds, such as 51°28′40″ N.
+    Dms,
+}
+
+impl CoordinateFormat {
+    /// Coordinate format by the identifier used in the API.
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "decimal" => Some(Self::Decimal),
+            "dms" => Some(Self::Dms),
+            _ => None,
+        }
+    }
+}
+
+/// Conventions for writing numbers and coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Locale {
+    decimal_separator: char,
+    /// Separator between groups of thousands.
+    group_separator: char,
+    /// Suffixes of the north, south, east and west hemispheres.
+    hemispheres: [&'static str; 4],
+    pub coordinates: CoordinateFormat,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            group_separator: ',',
+            hemispheres: ["N", "S", "E", "W"],
+            coordinates: CoordinateFormat::default(),
+        }
+    }
+}
+
+impl Locale {
+    /// Locale of a BCP 47 language tag, such as "de-CH", by its primary language; returns
+    /// `None` for languages without conventions here.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_']).next()?.to_ascii_lowercase();
+        let (decimal_separator, group_separator, hemispheres) = match language.as_str() {
+            "en" => ('.', ',', ["N", "S", "E", "W"]),
+            "de" => (',', '.', ["N", "S", "O", "W"]),
+            "es" | "it" => (',', '.', ["N", "S", "E", "O"]),
+            "fr" => (',', '\u{202f}', ["N", "S", "E", "O"]),
+            "nl" => (',', '.', ["N", "Z", "O", "W"]),
+            "pt" => (',', '.', ["N", "S", "L", "O"]),
+            _ => return None,
+        };
+        Some(Self {
+            decimal_separator,
+            group_separator,
+            hemispheres,
+            coordinates: CoordinateFormat::default(),
+        })
+    }
+
+    /// A number with a number of decimal places, with its thousands grouped.
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        let text = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let mut formatted = String::new();
+        // Rounding may leave no digits other than zeros, which are not negative
+        if value < 0.0 && text.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            formatted.push('-');
+        }
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                formatted.push(self.group_separator);
+            }
+            formatted.push(digit);
+        }
+        if !fraction.is_empty() {
+            formatted.push(self.decimal_separator);
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+
+    /// A latitude (degrees), to a number of decimal places of degrees or of seconds.
+    pub fn latitude(&self, lat: f64, decimals: usize) -> String {
+        // The hemisphere is that of the value written, so values rounding to zero have none
+        let lat = self.rounded(lat, decimals);
+        let suffix = if lat < 0.0 {
+            self.hemispheres[1]
+        } else {
+            self.hemispheres[0]
+        };
+        self.coordinate(lat, decimals, if lat == 0.0 { "" } else { suffix })
+    }
+
+    /// A longitude (degrees), to a number of decimal places of degrees or of seconds.
+    pub fn longitude(&self, lon: f64, decimals: usize) -> String {
+        let lon = self.rounded((lon + 180.0).rem_euclid(360.0) - 180.0, decimals);
+        let suffix = if lon < 0.0 {
+            self.hemispheres[3]
+        } else {
+            self.hemispheres[2]
+        };
+        // The equator and the prime and antimeridians are in neither hemisphere
+        let suffix = if lon == 0.0 || lon.abs() == 180.0 {
+            ""
+        } else {
+            suffix
+        };
+        self.coordinate(lon.abs(), decimals, suffix)
+    }
+
+    /// A location (degrees), as its latitude then longitude.
+    pub fn location(&self, location: LonLat, decimals: usize) -> String {
+        format!(
+            "{}, {}",
+            self.latitude(location.lat, decimals),
+            self.longitude(location.lon, decimals)
+        )
+    }
+
+    /// A coordinate (degrees) rounded to the precision it is written with, to a number of
+    /// decimal places of degrees or of seconds.
+    fn rounded(&self, value: f64, decimals: usize) -> f64 {
+        let scale = match self.coordinates {
+            CoordinateFormat::Decimal => 10_f64.powi(decimals as i32),
+            CoordinateFormat::Dms => 3600.0 * 10_f64.powi(decimals as i32),
+        };
+        (value * scale).round() / scale
+    }
+
+    /// The magnitude of a coordinate with its hemisphere; in degrees, minutes and seconds,
+    /// trailing zero minutes and seconds are left out.
+    fn coordinate(&self, value: f64, decimals: usize, suffix: &str) -> String {
+        let value = value.abs();
+        let text = match self.coordinates {
+            CoordinateFormat::Decimal => format!("{}°", self.number(value, decimals)),
+            CoordinateFormat::Dms => {
+                // Seconds are counted in units of the last decimal place, to carry exactly
+                let scale = 10_u64.pow(decimals as u32);
+                let total = (value * 3600.0 * scale as f64).round() as u64;
+                let degrees = total / (3600 * scale);
+                let minutes = total / (60 * scale) % 60;
+                let seconds = total % (60 * scale);
+                if seconds != 0 {
+                    let padding = if seconds < 10 * scale { "0" } else { "" };
+                    let seconds = self.number(seconds as f64 / scale as f64, decimals);
+                    format!("{}°{:02}′{}{}″", degrees, minutes, padding, seconds)
+                } else if minutes != 0 {
+                    format!("{}°{:02}′", degrees, minutes)
+                } else {
+                    format!("{}°", degrees)
+                }
+            }
+        };
+        if suffix.is_empty() {
+            text
+        } else {
+            format!("{} {}", text, suffix)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_numbers_by_locale() {
+        let english = Locale::default();
+        assert_eq!(english.number(1234567.891, 2), "1,234,567.89");
+        assert_eq!(english.number(-0.001, 1), "0.0");
+        let german = Locale::from_tag("de-CH").unwrap();
+        assert_eq!(german.number(-1234.5, 1), "-1.234,5");
+        assert!(Locale::from_tag("xx").is_none());
+    }
+
+    #[test]
+    fn formats_coordinates_by_locale() {
+        let mut english = Locale::default();
+        assert_eq!(
+            english.location(LonLat::new(-0.0014, 51.4778), 3),
+            "51.478° N, 0.001° W"
+        );
+        assert_eq!(english.longitude(190.0, 0), "170° W");
+        // Values rounding to the equator or a meridian between hemispheres have none
+        assert_eq!(english.latitude(-0.0001, 1), "0.0°");
+        assert_eq!(english.longitude(-0.0001, 1), "0.0°");
+        assert_eq!(english.longitude(179.9999, 2), "180.00°");
+        english.coordinates = CoordinateFormat::Dms;
+        assert_eq!(english.latitude(-33.5, 0), "33°30′ S");
+        assert_eq!(english.latitude(51.4778, 0), "51°28′40″ N");
+        assert_eq!(english.latitude(-0.0001, 0), "0°");
+        let french = Locale {
+            coordinates: CoordinateFormat::Dms,
+            ..Locale::from_tag("fr").unwrap()
+        };
+        assert_eq!(french.longitude(-2.3526, 1), "2°21′09,4″ O");
+    }
+}
@@ -0,0 +1,217 @@
+// Entry points into the geometry of the globe for property-based tests and fuzzing natively:
+// each checks the invariants of a routine for its inputs and panics when they are broken.
+// Generators of degenerate inputs cover the cases real data rarely exercises, such as
+// duplicate points, the poles and the antimeridian.
+
+use crate::coords::{LonLat, Vec3};
+use crate::horizon;
+use crate::lod;
+use crate::projection::{Projection, ProjectionKind};
+use crate::view::Orientation;
+
+// Greatest distance (globe radii) between a location and the location projected and
+// unprojected again
+const ROUND_TRIP_TOLERANCE: f64 = 1e-6;
+// Points on the disc may lie beyond its edge by rounding
+const DISC_TOLERANCE: f64 = 1e-9;
+// Locations this close (depth) to the edge of the disc may be unprojected off it by rounding
+const EDGE_MARGIN: f64 = 1e-6;
+// Locations this close (degrees) to the antipode of the centre of the azimuthal equidistant
+// view are unprojected imprecisely
+const ANTIPODE_MARGIN: f64 = 1.0;
+
+/// Every projection, for choosing one from fuzzed input.
+pub const PROJECTIONS: [ProjectionKind; 4] = [
+    ProjectionKind::Orthographic,
+    ProjectionKind::NorthPolarStereographic,
+    ProjectionKind::SouthPolarStereographic,
+    ProjectionKind::AzimuthalEquidistant,
+];
+
+/// Latitudes (degrees) at and about the poles and the equator.
+pub const SPECIAL_LATITUDES: [f64; 7] = [-90.0, -89.999_999, -45.0, 0.0, 45.0, 89.999_999, 90.0];
+
+/// Longitudes (degrees) at and about the antimeridian and the prime meridian.
+pub const SPECIAL_LONGITUDES: [f64; 7] =
+    [-180.0, -179.999_999, -90.0, 0.0, 90.0, 179.999_999, 180.0];
+
+/// Rings (longitude and latitude, degrees) that are degenerate or cross the poles or the
+/// antimeridian.
+pub fn degenerate_rings() -> Vec<Vec<(f64, f64)>> {
+    vec![
+        Vec::new(),
+        vec![(0.0, 0.0)],
+        vec![(10.0, 10.0); 4],
+        // Duplicate points within a ring
+        vec![
+            (0.0, 0.0),
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (10.0, 10.0),
+        ],
+        // Around the north pole, and through both poles
+        vec![(0.0, 80.0), (90.0, 80.0), (180.0, 80.0), (-90.0, 80.0)],
+        vec![(0.0, 90.0), (0.0, 0.0), (0.0, -90.0), (180.0, 0.0)],
+        // Across the antimeridian, by either of its longitudes
+        vec![
+            (170.0, 10.0),
+            (-170.0, 10.0),
+            (-170.0, -10.0),
+            (170.0, -10.0),
+        ],
+        vec![(180.0, 10.0), (-180.0, 0.0), (180.0, -10.0)],
+        // Between antipodes
+        vec![(0.0, 0.0), (180.0, 0.0), (0.0, 0.0)],
+    ]
+}
+
+/// Check the clipping of a ring (longitude and latitude, degrees) at the horizon of the
+/// orthographic view centred on a location: the outline lies within the disc, and is empty
+/// only when the whole ring is behind.
+pub fn clip_ring(ring: &[(f64, f64)], centre: LonLat) -> Vec<(f64, f64)> {
+    let orientation = Orientation::default().centred_on(centre);
+    let ring = ring
+        .iter()
+        .map(|(lon, lat)| Vec3::from(orientation.project(*lon, *lat)))
+        .collect::<Vec<_>>();
+    let outline = horizon::clip_ring(&ring);
+    for (y, z) in &outline {
+        assert!(
+            y.is_finite() && z.is_finite(),
+            "outline point ({}, {})",
+            y,
+            z
+        );
+        assert!(
+            y.hypot(*z) <= 1.0 + DISC_TOLERANCE,
+            "outline point ({}, {}) off the disc",
+            y,
+            z
+        );
+    }
+    assert_eq!(
+        outline.is_empty(),
+        ring.iter().all(|point| point.x < 0.0),
+        "outline {:?} of ring {:?}",
+        outline,
+        ring
+    );
+    outline
+}
+
+/// Check the simplification of a polyline by a stride: the points kept are in order, include
+/// both ends and are every few points.
+pub fn thin(polyline: &[(f64, f64)], stride: usize) -> Vec<(f64, f64)> {
+    let thinned = lod::thin(polyline, stride).copied().collect::<Vec<_>>();
+    assert_eq!(thinned.first(), polyline.first());
+    assert_eq!(thinned.last(), polyline.last());
+    let expected = polyline.len().div_ceil(stride.max(1))
+        + usize::from(polyline.len() > 1 && !(polyline.len() - 1).is_multiple_of(stride.max(1)));
+    assert_eq!(thinned.len(), expected, "thinned by {}", stride);
+    thinned
+}
+
+/// Check the projection of a location (degrees) in a view centred on another: the projected
+/// point is finite, and a location shown is unprojected back to itself. Returns the projected
+/// view coordinates.
+pub fn project(kind: ProjectionKind, centre: LonLat, location: LonLat) -> (f64, f64, f64) {
+    let mut projection = Projection::default();
+    projection.kind = kind;
+    let orientation = Orientation::default().centred_on(centre);
+    let (x, y, z) = projection.project(&orientation, location.lon, location.lat);
+    assert!(
+        x.is_finite() && y.is_finite() && z.is_finite(),
+        "{:?} projected to ({}, {}, {})",
+        location,
+        x,
+        y,
+        z
+    );
+
+    // Locations are shown in front, or within the latitude limit of the polar views
+    let shown = match kind {
+        ProjectionKind::AzimuthalEquidistant => {
+            let angle = centre
+                .to_vec3()
+                .dot(location.to_vec3())
+                .clamp(-1.0, 1.0)
+                .acos()
+                .to_degrees();
+            angle < 180.0 - ANTIPODE_MARGIN
+        }
+        _ => x > EDGE_MARGIN,
+    };
+    if shown {
+        let unprojected = projection
+            .unproject(&orientation, y, z)
+            .unwrap_or_else(|| panic!("{:?} shown at ({}, {}) off the globe", location, y, z));
+        let distance = (unprojected.to_vec3() - location.to_vec3()).length();
+        assert!(
+            distance <= ROUND_TRIP_TOLERANCE,
+            "{:?} unprojected as {:?}",
+            location,
+            unprojected
+        );
+    }
+    (x, y, z)
+}
+
+/// Run the checks of the geometry on arbitrary bytes, as from a fuzzer: the first selects the
+/// projection and the second the stride, and the rest are read as coordinates (degrees) of
+/// the centre of the view followed by the points of a ring.
+pub fn fuzz_geometry(data: &[u8]) {
+    let [kind, stride, rest @ ..] = data else {
+        return;
+    };
+    let kind = PROJECTIONS[*kind as usize % PROJECTIONS.len()];
+    let coordinates = rest
+        .chunks_exact(8)
+        .map(|chunk| f64::from_le_bytes(chunk.try_into().expect("should be 8 bytes")))
+        .collect::<Vec<_>>();
+    let mut locations = coordinates
+        .chunks_exact(2)
+        .map(|pair| location(pair[0], pair[1]));
+    let Some(centre) = locations.next() else {
+        return;
+    };
+    let ring = locations
+        .map(|location| (location.lon, location.lat))
+        .collect::<Vec<_>>();
+
+    clip_ring(&ring, centre);
+    thin(&ring, *stride as usize);
+    for (lon, lat) in &ring {
+        project(kind, centre, LonLat::new(*lon, *lat));
+    }
+}
+
+/// Location (degrees) from arbitrary numbers, with the longitude wrapped and the latitude
+/// clamped, and numbers that are not finite taken as zero.
+pub fn location(lon: f64, lat: f64) -> LonLat {
+    let finite = |value: f64| if value.is_finite() { value } else { 0.0 };
+    LonLat::new(finite(lon), finite(lat).clamp(-90.0, 90.0)).wrapped()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_degenerate_rings() {
+        for ring in degenerate_rings() {
+            for (lat, lon) in SPECIAL_LATITUDES.iter().zip(SPECIAL_LONGITUDES) {
+                let centre = LonLat::new(lon, *lat);
+                clip_ring(&ring, centre);
+                for kind in PROJECTIONS {
+                    for (lon, lat) in &ring {
+                        project(kind, centre, LonLat::new(*lon, *lat));
+                    }
+                }
+            }
+            for stride in 0..4 {
+                thin(&ring, stride);
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+// Compact fixed-point storage of geographic coordinates.
+
+/// Location (degrees, as longitude and latitude) stored as fixed-point
+/// integers with `SHIFT` fractional bits, decoded on the fly.
+///
+/// Longitudes of up to ±180 degrees fit when `SHIFT` is at most 23, giving a resolution of
+/// about 1.2e-7 degrees in half the size of a pair of `f64`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GeoPoint<const SHIFT: u8> {
+    lon: i32,
+    lat: i32,
+}
+
+impl<const SHIFT: u8> GeoPoint<SHIFT> {
+    /// Fixed-point units per degree.
+    const SCALE: f64 = (1u64 << SHIFT) as f64;
+
+    /// Location from fixed-point longitude and latitude, as generated during the build.
+    pub const fn from_raw(lon: i32, lat: i32) -> Self {
+        Self { lon, lat }
+    }
+
+    /// Location from a longitude and latitude (degrees), rounded to the nearest fixed-point
+    /// value; values out of the representable range saturate.
+    // Used by the build script to encode the data
+    #[allow(dead_code)]
+    pub fn from_degrees(lon: f64, lat: f64) -> Self {
+        Self {
+            lon: (lon * Self::SCALE).round() as i32,
+            lat: (lat * Self::SCALE).round() as i32,
+        }
+    }
+
+    /// Fixed-point longitude and latitude.
+    #[allow(dead_code)]
+    pub fn raw(self) -> (i32, i32) {
+        (self.lon, self.lat)
+    }
+
+    /// Longitude and latitude (degrees).
+    pub fn to_degrees(self) -> (f64, f64) {
+        (self.lon as f64 / Self::SCALE, self.lat as f64 / Self::SCALE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_within_resolution() {
+        let resolution = 1.0 / (1u64 << 23) as f64;
+        for (lon, lat) in [
+            (0.0, 0.0),
+            (-0.5, 0.25),
+            (151.2093, -33.8688),
+            (-179.999_999, 89.999_999),
+            (180.0, -90.0),
+        ] {
+            let (decoded_lon, decoded_lat) = GeoPoint::<23>::from_degrees(lon, lat).to_degrees();
+            assert!((decoded_lon - lon).abs() <= resolution / 2.0);
+            assert!((decoded_lat - lat).abs() <= resolution / 2.0);
+        }
+    }
+
+    #[test]
+    fn raw_values_scale_by_shift() {
+        let point = GeoPoint::<4>::from_raw(-32, 8);
+        assert_eq!(point.to_degrees(), (-2.0, 0.5));
+        assert_eq!(GeoPoint::<4>::from_degrees(-2.0, 0.5), point);
+        assert_eq!(GeoPoint::<0>::from_raw(-32, 8).to_degrees(), (-32.0, 8.0));
+    }
+
+    #[test]
+    fn rounds_to_nearest() {
+        assert_eq!(GeoPoint::<1>::from_degrees(0.74, -0.76).raw(), (1, -2));
+        assert_eq!(GeoPoint::<1>::from_degrees(0.2, -0.2).raw(), (0, 0));
+    }
+
+    #[test]
+    fn full_longitude_range_fits_largest_shift() {
+        let point = GeoPoint::<23>::from_degrees(-180.0, 180.0);
+        assert_eq!(point.to_degrees(), (-180.0, 180.0));
+    }
+
+    #[test]
+    fn out_of_range_saturates() {
+        let point = GeoPoint::<30>::from_degrees(180.0, -180.0);
+        assert_eq!(point.raw(), (i32::MAX, i32::MIN));
+    }
+}
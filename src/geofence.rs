@@ -0,0 +1,212 @@
+// Geofences: polygons on the globe that tracked entities are watched entering and leaving. The
+// latest position of each track is tested against each fence on the sphere as it arrives, and
+// crossings are queued as events for the next frame to dispatch.
+
+use wasm_bindgen::JsValue;
+
+use crate::coords::{LonLat, Vec3};
+
+#[derive(Debug)]
+struct Geofence {
+    id: String,
+    /// Vertices of the polygon on the unit sphere, its edges along great circles.
+    vertices: Vec<Vec3>,
+}
+
+impl Geofence {
+    /// Whether the fence contains a point on the unit sphere, by the winding of the polygon
+    /// about the point: the angles its edges subtend about the point add to a full turn for
+    /// points inside and cancel for points outside, taking the smaller region as the inside.
+    fn contains(&self, point: Vec3) -> bool {
+        let previous = self.vertices.iter().cycle().skip(self.vertices.len() - 1);
+        let winding = previous
+            .zip(&self.vertices)
+            .map(|(&a, &b)| {
+                let sine = a.cross(b).dot(point);
+                let cosine = a.dot(b) - a.dot(point) * b.dot(point);
+                sine.atan2(cosine)
+            })
+            .sum::<f64>();
+        winding.abs() > std::f64::consts::PI
+    }
+}
+
+/// Entity entering or leaving a fence.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeofenceEvent {
+    pub entered: bool,
+    pub fence: String,
+    pub entity: String,
+    /// Time (milliseconds) and location (degrees) of the position crossing the fence.
+    pub time: f64,
+    pub location: LonLat,
+}
+
+impl GeofenceEvent {
+    /// Type of the event dispatched on the canvas.
+    pub fn event_type(&self) -> &'static str {
+        if self.entered {
+            "geofenceenter"
+        } else {
+            "geofenceexit"
+        }
+    }
+
+    /// The event as an object with fence, entity, time, lat and lon properties.
+    pub fn to_object(&self) -> Result<JsValue, JsValue> {
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| js_sys::Reflect::set(&object, &key.into(), &value);
+        set("fence", self.fence.as_str().into())?;
+        set("entity", self.entity.as_str().into())?;
+        set("time", self.time.into())?;
+        set("lat", self.location.lat.into())?;
+        set("lon", self.location.lon.into())?;
+        Ok(object.into())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Geofences {
+    fences: Vec<Geofence>,
+    /// Fences each entity is inside, by entity, as of its latest position.
+    inside: Vec<(String, Vec<String>)>,
+    /// Crossings not yet dispatched.
+    events: Vec<GeofenceEvent>,
+}
+
+impl Geofences {
+    /// Add a fence, replacing any fence with the same identifier, as the latitude and
+    /// longitude (degrees) of each vertex of its polygon in turn; returns whether the polygon
+    /// has at least three vertices. Entities are tested against the fence as their positions
+    /// arrive.
+    pub fn add(&mut self, id: &str, coordinates: &[f64]) -> bool {
+        if !coordinates.len().is_multiple_of(2) || coordinates.len() < 6 {
+            return false;
+        }
+        let vertices = coordinates
+            .chunks_exact(2)
+            .map(|pair| LonLat::new(pair[1], pair[0]).to_vec3())
+            .collect();
+        self.remove(id);
+        self.fences.push(Geofence {
+            id: id.to_string(),
+            vertices,
+        });
+        true
+    }
+
+    /// Remove a fence, returning whether it existed; entities inside it leave it silently.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let count = self.fences.len();
+        self.fences.retain(|fence| fence.id != id);
+        for (_, fences) in &mut self.inside {
+            fences.retain(|fence| fence != id);
+        }
+        self.fences.len() != count
+    }
+
+    /// Remove all fences.
+    pub fn clear(&mut self) {
+        self.fences.clear();
+        self.inside.clear();
+    }
+
+    /// Forget the fences an entity is inside, as when its track is removed.
+    pub fn forget(&mut self, entity: Option<&str>) {
+        match entity {
+            Some(entity) => self.inside.retain(|(inside, _)| inside != entity),
+            None => self.inside.clear(),
+        }
+    }
+
+    /// Test the latest position (degrees) of an entity at a time (milliseconds) against the
+    /// fences, queuing an event for each fence it entered or left since its previous position.
+    pub fn update(&mut self, entity: &str, time: f64, location: LonLat) {
+        if self.fences.is_empty() && self.inside.is_empty() {
+            return;
+        }
+        let point = location.to_vec3();
+        let now_inside = self
+            .fences
+            .iter()
+            .filter(|fence| fence.contains(point))
+            .map(|fence| fence.id.clone())
+            .collect::<Vec<_>>();
+        let index = match self.inside.iter().position(|(inside, _)| inside == entity) {
+            Some(index) => index,
+            None => {
+                self.inside.push((entity.to_string(), Vec::new()));
+                self.inside.len() - 1
+            }
+        };
+        let was_inside = std::mem::replace(&mut self.inside[index].1, now_inside);
+        let now_inside = &self.inside[index].1;
+
+        let event = |entered: bool, fence: &String| GeofenceEvent {
+            entered,
+            fence: fence.clone(),
+            entity: entity.to_string(),
+            time,
+            location,
+        };
+        self.events.extend(
+            was_inside
+                .iter()
+                .filter(|fence| !now_inside.contains(fence))
+                .map(|fence| event(false, fence)),
+        );
+        self.events.extend(
+            now_inside
+                .iter()
+                .filter(|fence| !was_inside.contains(fence))
+                .map(|fence| event(true, fence)),
+        );
+    }
+
+    /// Take the crossings not yet dispatched.
+    pub fn take_events(&mut self) -> Vec<GeofenceEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Whether crossings are waiting to be dispatched.
+    pub fn pending(&self) -> bool {
+        !self.events.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_entities_crossing_fences() {
+        let mut fences = Geofences::default();
+        assert!(!fences.add("harbour", &[0.0, 0.0, 0.0, 1.0]));
+        // A square across the antimeridian
+        assert!(fences.add(
+            "strait",
+            &[-5.0, 175.0, 5.0, 175.0, 5.0, -175.0, -5.0, -175.0]
+        ));
+
+        fences.update("ship", 0.0, LonLat::new(170.0, 0.0));
+        assert!(!fences.pending());
+        fences.update("ship", 1.0, LonLat::new(179.5, 1.0));
+        fences.update("ship", 2.0, LonLat::new(-179.0, 2.0));
+        fences.update("ship", 3.0, LonLat::new(-179.0, 10.0));
+        let events = fences.take_events();
+        assert_eq!(events.len(), 2);
+        assert!(events[0].entered && events[0].time == 1.0);
+        assert_eq!(events[1].event_type(), "geofenceexit");
+        assert_eq!(
+            (events[1].fence.as_str(), events[1].location.lat),
+            ("strait", 10.0)
+        );
+
+        // Removing the fence forgets entities inside it without events
+        fences.update("ship", 4.0, LonLat::new(180.0, 0.0));
+        assert!(fences.remove("strait"));
+        assert_eq!(fences.take_events().len(), 1);
+        fences.update("ship", 5.0, LonLat::new(180.0, 10.0));
+        assert!(!fences.pending());
+    }
+}
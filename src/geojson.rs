@@ -0,0 +1,326 @@
+// Overlays of GeoJSON loaded at runtime, so users can show their own data without rebuilding
+// the crate. Points, line strings and polygons, and their multi-part forms, of features,
+// feature collections and geometry collections are parsed into a layer of the stack, drawn
+// with one style. Edges are straight in longitude and latitude, as GeoJSON defines them, and
+// are sampled so they curve with the globe.
+
+use wasm_bindgen::prelude::*;
+use web_sys::{CanvasRenderingContext2d, CanvasWindingRule};
+
+use crate::layers::Layer;
+use crate::view::{Frame, Side};
+
+const GEOJSON_DEFAULT_STROKE: &str = "rgba(30, 100, 200, 1.0)";
+const GEOJSON_DEFAULT_FILL: &str = "rgba(30, 100, 200, 0.3)";
+const GEOJSON_DEFAULT_WIDTH: f64 = 0.003;
+// Sizes are in canvas pixels and angles in degrees
+const GEOJSON_DEFAULT_RADIUS: f64 = 4.0;
+const GEOJSON_SAMPLE_ANGLE: f64 = 2.0;
+// Edges span at most a turn in longitude between valid positions
+const GEOJSON_MAX_EDGE_SAMPLES: usize = 180;
+
+/// Style of a GeoJSON overlay.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeoJsonStyle {
+    /// CSS color of lines, the outlines of polygons and points.
+    #[wasm_bindgen(getter_with_clone)]
+    pub stroke: String,
+    /// CSS color polygons are filled with, or `None` for polygons to be outlined only.
+    #[wasm_bindgen(getter_with_clone)]
+    pub fill: Option<String>,
+    /// Line width, relative to the radius of the globe.
+    pub width: f64,
+    /// Radius (canvas pixels) of points.
+    pub radius: f64,
+}
+
+impl Default for GeoJsonStyle {
+    fn default() -> Self {
+        Self {
+            stroke: GEOJSON_DEFAULT_STROKE.to_string(),
+            fill: Some(GEOJSON_DEFAULT_FILL.to_string()),
+            width: GEOJSON_DEFAULT_WIDTH,
+            radius: GEOJSON_DEFAULT_RADIUS,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl GeoJsonStyle {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> GeoJsonStyle {
+        GeoJsonStyle::default()
+    }
+}
+
+/// Geometry of an overlay, with locations (degrees) as longitude and latitude, and lines and
+/// rings sampled along their edges.
+#[derive(Clone, Debug, PartialEq)]
+enum Geometry {
+    Point((f64, f64)),
+    Line(Vec<(f64, f64)>),
+    /// Rings of a polygon, the first its exterior and the others its holes.
+    Polygon(Vec<Vec<(f64, f64)>>),
+}
+
+/// Geometry projected with the view of a frame.
+#[derive(Clone, Debug)]
+enum Projected {
+    Point((f64, f64, f64)),
+    Line(Vec<(f64, f64, f64)>),
+    /// Rings projected for filling, with locations that are not visible taken to the edge of
+    /// the disc, and for outlining.
+    Polygon(Vec<Vec<(f64, f64)>>, Vec<Vec<(f64, f64, f64)>>),
+}
+
+/// Layer of the geometries of a GeoJSON document.
+#[derive(Debug)]
+pub struct GeoJsonLayer {
+    geometries: Vec<Geometry>,
+    style: GeoJsonStyle,
+    projected: Vec<Projected>,
+}
+
+impl GeoJsonLayer {
+    /// Layer of a GeoJSON document: a geometry, feature, or collection of either. Throws when
+    /// the document is not JSON or has a geometry of an unknown type or invalid coordinates.
+    pub fn new(json: &str, style: GeoJsonStyle) -> Result<GeoJsonLayer, JsValue> {
+        let document = js_sys::JSON::parse(json)?;
+        let mut geometries = Vec::new();
+        parse_object(&document, &mut geometries)?;
+        Ok(GeoJsonLayer {
+            geometries,
+            style,
+            projected: Vec::new(),
+        })
+    }
+}
+
+impl Layer for GeoJsonLayer {
+    fn project(&mut self, frame: &Frame) -> Result<(), JsValue> {
+        let project = |points: &[(f64, f64)]| {
+            points
+                .iter()
+                .map(|(lon, lat)| frame.project(*lon, *lat))
+                .collect::<Vec<_>>()
+        };
+        self.projected = self
+            .geometries
+            .iter()
+            .map(|geometry| match geometry {
+                Geometry::Point((lon, lat)) => Projected::Point(frame.project(*lon, *lat)),
+                Geometry::Line(points) => Projected::Line(project(points)),
+                Geometry::Polygon(rings) => Projected::Polygon(
+                    rings
+                        .iter()
+                        .map(|ring| {
+                            ring.iter()
+                                .map(|(lon, lat)| frame.project_to_disc(*lon, *lat))
+                                .collect()
+                        })
+                        .collect(),
+                    rings.iter().map(|ring| project(ring)).collect(),
+                ),
+            })
+            .collect();
+        Ok(())
+    }
+
+    fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        // Only the front is stroked, and fills are clipped to the visible globe
+        let stroke_line = |points: &[(f64, f64, f64)]| {
+            context.begin_path();
+            for segment in points.windows(2) {
+                let ((_, y_prev, z_prev), (_, y, z)) = (segment[0], segment[1]);
+                if frame.segment_side(segment[0], segment[1]) == Some(Side::Front) {
+                    context.move_to(y_prev, z_prev);
+                    context.line_to(y, z);
+                }
+            }
+            context.stroke();
+        };
+        context.set_line_width(frame.line_width(self.style.width));
+        context.set_stroke_style_str(&self.style.stroke);
+        for projected in &self.projected {
+            match projected {
+                Projected::Point(_) => {}
+                Projected::Line(points) => stroke_line(points),
+                Projected::Polygon(filled, outlines) => {
+                    if let Some(fill) = &self.style.fill {
+                        context.begin_path();
+                        for ring in filled {
+                            for (i, (y, z)) in ring.iter().enumerate() {
+                                if i == 0 {
+                                    context.move_to(*y, *z);
+                                } else {
+                                    context.line_to(*y, *z);
+                                }
+                            }
+                            context.close_path();
+                        }
+                        context.set_fill_style_str(fill);
+                        context.fill_with_canvas_winding_rule(CanvasWindingRule::Evenodd);
+                    }
+                    for ring in outlines {
+                        stroke_line(ring);
+                    }
+                }
+            }
+        }
+
+        // Points are a constant size on screen
+        let radius = self.style.radius / frame.scale;
+        context.set_fill_style_str(&self.style.stroke);
+        for projected in &self.projected {
+            let Projected::Point((x, y, z)) = projected else {
+                continue;
+            };
+            if *x < 0.0 {
+                continue;
+            }
+            context.begin_path();
+            context.arc(*y, *z, radius, 0.0, std::f64::consts::TAU)?;
+            context.fill();
+        }
+
+        Ok(())
+    }
+}
+
+/// Add the geometries of a GeoJSON object: a geometry, feature, or collection of either.
+fn parse_object(object: &JsValue, geometries: &mut Vec<Geometry>) -> Result<(), JsValue> {
+    let get = |key: &str| js_sys::Reflect::get(object, &key.into());
+    let kind = get("type")?.as_string().unwrap_or_default();
+    let coordinates = || get("coordinates");
+    match kind.as_str() {
+        "FeatureCollection" => {
+            for feature in array(&get("features")?)? {
+                parse_object(&feature, geometries)?;
+            }
+        }
+        // Features without geometry have none to draw
+        "Feature" => {
+            let geometry = get("geometry")?;
+            if !geometry.is_null() && !geometry.is_undefined() {
+                parse_object(&geometry, geometries)?;
+            }
+        }
+        "GeometryCollection" => {
+            for geometry in array(&get("geometries")?)? {
+                parse_object(&geometry, geometries)?;
+            }
+        }
+        "Point" => geometries.push(Geometry::Point(position(&coordinates()?)?)),
+        "MultiPoint" => {
+            for point in array(&coordinates()?)? {
+                geometries.push(Geometry::Point(position(&point)?));
+            }
+        }
+        "LineString" => geometries.push(Geometry::Line(line(&coordinates()?)?)),
+        "MultiLineString" => {
+            for points in array(&coordinates()?)? {
+                geometries.push(Geometry::Line(line(&points)?));
+            }
+        }
+        "Polygon" => geometries.push(Geometry::Polygon(rings(&coordinates()?)?)),
+        "MultiPolygon" => {
+            for polygon in array(&coordinates()?)? {
+                geometries.push(Geometry::Polygon(rings(&polygon)?));
+            }
+        }
+        _ => {
+            return Err(JsValue::from_str(&format!(
+                "unknown GeoJSON type {:?}",
+                kind
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn array(value: &JsValue) -> Result<js_sys::Array, JsValue> {
+    value
+        .clone()
+        .dyn_into::<js_sys::Array>()
+        .map_err(|_| JsValue::from_str("GeoJSON should have an array"))
+}
+
+/// Location (degrees, as longitude and latitude) of a position.
+fn position(value: &JsValue) -> Result<(f64, f64), JsValue> {
+    let position = array(value)?;
+    location(position.get(0).as_f64(), position.get(1).as_f64()).ok_or_else(|| {
+        JsValue::from_str("GeoJSON positions should be a longitude and latitude in range")
+    })
+}
+
+/// Location (degrees) of a longitude and latitude, unless either is missing or out of range.
+fn location(lon: Option<f64>, lat: Option<f64>) -> Option<(f64, f64)> {
+    match (lon, lat) {
+        (Some(lon), Some(lat)) if lon.abs() <= 180.0 && lat.abs() <= 90.0 => Some((lon, lat)),
+        _ => None,
+    }
+}
+
+/// Line sampled along its edges from an array of positions.
+fn line(value: &JsValue) -> Result<Vec<(f64, f64)>, JsValue> {
+    let points = array(value)?
+        .iter()
+        .map(|point| position(&point))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(densify(&points))
+}
+
+/// Rings of a polygon from an array of arrays of positions.
+fn rings(value: &JsValue) -> Result<Vec<Vec<(f64, f64)>>, JsValue> {
+    array(value)?.iter().map(|ring| line(&ring)).collect()
+}
+
+/// Points of a line (degrees, as longitude and latitude) with edges sampled straight in
+/// longitude and latitude at most the sample angle apart, so they curve with the globe.
+fn densify(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut densified = points.first().copied().into_iter().collect::<Vec<_>>();
+    for edge in points.windows(2) {
+        let ((lon_from, lat_from), (lon_to, lat_to)) = (edge[0], edge[1]);
+        let angle = (lon_to - lon_from).abs().max((lat_to - lat_from).abs());
+        let samples =
+            ((angle / GEOJSON_SAMPLE_ANGLE).ceil() as usize).clamp(1, GEOJSON_MAX_EDGE_SAMPLES);
+        densified.extend((1..=samples).map(|i| {
+            let t = i as f64 / samples as f64;
+            (
+                lon_from + (lon_to - lon_from) * t,
+                lat_from + (lat_to - lat_from) * t,
+            )
+        }));
+    }
+    densified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_long_edges() {
+        assert_eq!(densify(&[]), []);
+        assert_eq!(densify(&[(1.0, 2.0)]), [(1.0, 2.0)]);
+        let points = densify(&[(0.0, 0.0), (1.0, 1.0), (1.0, 7.0)]);
+        assert_eq!(
+            points,
+            [(0.0, 0.0), (1.0, 1.0), (1.0, 3.0), (1.0, 5.0), (1.0, 7.0)]
+        );
+        // Edges far longer than any between positions in range are still sampled finitely
+        let points = densify(&[(0.0, 0.0), (1e12, 0.0)]);
+        assert_eq!(points.len(), GEOJSON_MAX_EDGE_SAMPLES + 1);
+    }
+
+    #[test]
+    fn rejects_positions_out_of_range() {
+        assert_eq!(location(Some(-180.0), Some(90.0)), Some((-180.0, 90.0)));
+        assert_eq!(location(Some(180.5), Some(0.0)), None);
+        assert_eq!(location(Some(0.0), Some(-90.5)), None);
+        assert_eq!(location(Some(f64::NAN), Some(0.0)), None);
+        assert_eq!(location(Some(f64::INFINITY), Some(0.0)), None);
+        assert_eq!(location(None, Some(0.0)), None);
+    }
+}
@@ -0,0 +1,264 @@
+// Recognition of tap, double tap, long press and hover intent gestures from pointer
+// events, shared by mouse, touch and pen input.
+
+use crate::controls::Position;
+
+// Distances are in canvas pixels and times in milliseconds
+const TAP_MAX_DISTANCE: f64 = 6.0;
+const LONG_PRESS_DELAY: f64 = 500.0;
+const DOUBLE_TAP_INTERVAL: f64 = 300.0;
+const HOVER_INTENT_DELAY: f64 = 400.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GestureKind {
+    Tap,
+    DoubleTap,
+    LongPress,
+    Hover,
+}
+
+impl GestureKind {
+    /// Type of the event dispatched when the gesture is recognised.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            GestureKind::Tap => "globe-tap",
+            GestureKind::DoubleTap => "globe-doubletap",
+            GestureKind::LongPress => "globe-longpress",
+            GestureKind::Hover => "globe-hover",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gesture {
+    pub kind: GestureKind,
+    pub position: Position,
+    pub pointer_type: String,
+}
+
+#[derive(Debug)]
+struct Press {
+    position: Position,
+    time: f64,
+    pointer_type: String,
+    moved: bool,
+    long_pressed: bool,
+}
+
+#[derive(Debug)]
+struct Hover {
+    position: Position,
+    time: f64,
+    pointer_type: String,
+    recognised: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct GestureRecognizer {
+    press: Option<Press>,
+    last_tap: Option<(Position, f64)>,
+    hover: Option<Hover>,
+}
+
+impl GestureRecognizer {
+    pub fn pointer_down(&mut self, position: Position, time: f64, pointer_type: &str) {
+        self.hover = None;
+        self.press = Some(Press {
+            position,
+            time,
+            pointer_type: pointer_type.to_string(),
+            moved: false,
+            long_pressed: false,
+        });
+    }
+
+    pub fn pointer_move(&mut self, position: Position, time: f64, pointer_type: &str) {
+        match &mut self.press {
+            Some(press) => {
+                if distance(&press.position, &position) > TAP_MAX_DISTANCE {
+                    press.moved = true;
+                }
+            }
+            None => {
+                // Small movements do not restart the hover intent delay
+                let settled = self
+                    .hover
+                    .as_ref()
+                    .is_some_and(|hover| distance(&hover.position, &position) <= TAP_MAX_DISTANCE);
+                if !settled {
+                    self.hover = Some(Hover {
+                        position,
+                        time,
+                        pointer_type: pointer_type.to_string(),
+                        recognised: false,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Handle the pointer being released, returning any tap gesture recognised.
+    pub fn pointer_up(&mut self, time: f64) -> Option<Gesture> {
+        let press = self.press.take()?;
+        if press.moved || press.long_pressed {
+            self.last_tap = None;
+            return None;
+        }
+
+        if let Some((position, tap_time)) = self.last_tap.take() {
+            if time - tap_time <= DOUBLE_TAP_INTERVAL
+                && distance(&position, &press.position) <= TAP_MAX_DISTANCE
+            {
+                return Some(Gesture {
+                    kind: GestureKind::DoubleTap,
+                    position: press.position,
+                    pointer_type: press.pointer_type,
+                });
+            }
+        }
+
+        self.last_tap = Some((press.position.clone(), time));
+        Some(Gesture {
+            kind: GestureKind::Tap,
+            position: press.position,
+            pointer_type: press.pointer_type,
+        })
+    }
+
+    /// Handle the pointer leaving the canvas.
+    pub fn pointer_leave(&mut self) {
+        self.hover = None;
+    }
+
+    /// Handle the pointer being cancelled by the browser.
+    pub fn pointer_cancel(&mut self) {
+        self.press = None;
+        self.hover = None;
+    }
+
+    /// Whether a press or hover may yet be recognised as a gesture by the passage of time.
+    pub fn pending(&self) -> bool {
+        self.press
+            .as_ref()
+            .is_some_and(|press| !press.moved && !press.long_pressed)
+            || self.hover.as_ref().is_some_and(|hover| !hover.recognised)
+    }
+
+    /// Check for gestures recognised by the passage of time, returning any recognised.
+    pub fn tick(&mut self, time: f64) -> Option<Gesture> {
+        if let Some(press) = &mut self.press {
+            if !press.moved && !press.long_pressed && time - press.time >= LONG_PRESS_DELAY {
+                press.long_pressed = true;
+                return Some(Gesture {
+                    kind: GestureKind::LongPress,
+                    position: press.position.clone(),
+                    pointer_type: press.pointer_type.clone(),
+                });
+            }
+        }
+        if let Some(hover) = &mut self.hover {
+            if !hover.recognised && time - hover.time >= HOVER_INTENT_DELAY {
+                hover.recognised = true;
+                return Some(Gesture {
+                    kind: GestureKind::Hover,
+                    position: hover.position.clone(),
+                    pointer_type: hover.pointer_type.clone(),
+                });
+            }
+        }
+        None
+    }
+}
+
+fn distance(a: &Position, b: &Position) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: f64, y: f64) -> Position {
+        Position { x, y }
+    }
+
+    fn kind(gesture: Option<Gesture>) -> Option<GestureKind> {
+        gesture.map(|gesture| gesture.kind)
+    }
+
+    #[test]
+    fn recognises_long_presses_that_stay_still() {
+        let mut gestures = GestureRecognizer::default();
+        gestures.pointer_down(at(0.0, 0.0), 0.0, "touch");
+        // Movements within the tolerance keep the press
+        gestures.pointer_move(at(TAP_MAX_DISTANCE, 0.0), 100.0, "touch");
+        assert_eq!(gestures.tick(LONG_PRESS_DELAY - 1.0), None);
+        assert_eq!(
+            kind(gestures.tick(LONG_PRESS_DELAY)),
+            Some(GestureKind::LongPress)
+        );
+        assert_eq!(gestures.tick(LONG_PRESS_DELAY + 100.0), None);
+        // Releasing a long press is not a tap
+        assert_eq!(gestures.pointer_up(LONG_PRESS_DELAY + 200.0), None);
+
+        gestures.pointer_down(at(0.0, 0.0), 1000.0, "touch");
+        gestures.pointer_move(at(TAP_MAX_DISTANCE + 1.0, 0.0), 1100.0, "touch");
+        assert!(!gestures.pending());
+        assert_eq!(gestures.tick(1000.0 + LONG_PRESS_DELAY), None);
+        assert_eq!(gestures.pointer_up(1000.0 + LONG_PRESS_DELAY), None);
+    }
+
+    #[test]
+    fn recognises_double_taps_within_the_interval() {
+        let mut gestures = GestureRecognizer::default();
+        gestures.pointer_down(at(0.0, 0.0), 0.0, "mouse");
+        assert_eq!(kind(gestures.pointer_up(50.0)), Some(GestureKind::Tap));
+        gestures.pointer_down(at(2.0, 2.0), 100.0, "mouse");
+        let gesture = gestures.pointer_up(50.0 + DOUBLE_TAP_INTERVAL).unwrap();
+        assert_eq!(gesture.kind, GestureKind::DoubleTap);
+        assert_eq!(gesture.position, at(2.0, 2.0));
+        assert_eq!(gesture.pointer_type, "mouse");
+
+        // Taps too far apart in time are single taps
+        gestures.pointer_down(at(0.0, 0.0), 1000.0, "mouse");
+        assert_eq!(kind(gestures.pointer_up(1050.0)), Some(GestureKind::Tap));
+        gestures.pointer_down(at(0.0, 0.0), 1300.0, "mouse");
+        assert_eq!(
+            kind(gestures.pointer_up(1051.0 + DOUBLE_TAP_INTERVAL)),
+            Some(GestureKind::Tap)
+        );
+    }
+
+    #[test]
+    fn recognises_hovers_that_dwell() {
+        let mut gestures = GestureRecognizer::default();
+        gestures.pointer_move(at(0.0, 0.0), 0.0, "mouse");
+        assert!(gestures.pending());
+        // Small movements do not restart the delay, but larger ones do
+        gestures.pointer_move(at(3.0, 0.0), 200.0, "mouse");
+        assert_eq!(
+            kind(gestures.tick(HOVER_INTENT_DELAY)),
+            Some(GestureKind::Hover)
+        );
+        assert_eq!(gestures.tick(HOVER_INTENT_DELAY + 100.0), None);
+        gestures.pointer_move(at(50.0, 0.0), 1000.0, "mouse");
+        assert_eq!(gestures.tick(1000.0 + HOVER_INTENT_DELAY - 1.0), None);
+        let gesture = gestures.tick(1000.0 + HOVER_INTENT_DELAY).unwrap();
+        assert_eq!(gesture.position, at(50.0, 0.0));
+
+        gestures.pointer_move(at(0.0, 0.0), 2000.0, "mouse");
+        gestures.pointer_leave();
+        assert_eq!(gestures.tick(2000.0 + HOVER_INTENT_DELAY), None);
+    }
+
+    #[test]
+    fn cancels_presses_for_a_second_pointer() {
+        let mut gestures = GestureRecognizer::default();
+        gestures.pointer_down(at(0.0, 0.0), 0.0, "touch");
+        // A second pointer going down starts a pinch rather than a gesture
+        gestures.pointer_cancel();
+        assert!(!gestures.pending());
+        assert_eq!(gestures.tick(LONG_PRESS_DELAY), None);
+        assert_eq!(gestures.pointer_up(LONG_PRESS_DELAY), None);
+    }
+}
@@ -0,0 +1,131 @@
+// Lines of constant latitude and longitude.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::labels::Label;
+use crate::view::{Frame, Side};
+
+pub const GRATICULE_FRONT_STROKE_STYLE: &str = "rgba(0, 0, 127, 0.3)";
+pub const GRATICULE_BACK_STROKE_STYLE: &str = "rgba(0, 0, 0, 0.1)";
+pub const GRATICULE_FRONT_LINE_WIDTH: f64 = 0.002;
+pub const GRATICULE_BACK_LINE_WIDTH: f64 = 0.001;
+// Angles are in degrees
+const MERIDIAN_INTERVAL: f64 = 30.0;
+const PARALLEL_INTERVAL: f64 = 15.0;
+const SAMPLE_INTERVAL: f64 = 2.0;
+// Meridians stop short of the poles, where they converge
+const MERIDIAN_LIMIT: f64 = 80.0;
+// Labels are placed beneath those of the data layers
+const GRATICULE_LABEL_PRIORITY: f64 = -2.0;
+
+#[derive(Debug, Default)]
+pub struct Graticule {
+    pub visible: bool,
+}
+
+impl Graticule {
+    /// Draw the meridians and parallels onto the canvas.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        if !self.visible {
+            return Ok(());
+        }
+
+        for line in lines() {
+            let line = line
+                .into_iter()
+                .map(|(lon, lat)| frame.project(lon, lat))
+                .collect::<Vec<_>>();
+            for segment in line.windows(2) {
+                let ((_, y_prev, z_prev), (_, y, z)) = (segment[0], segment[1]);
+                match frame.segment_side(segment[0], segment[1]) {
+                    Some(Side::Front) => {
+                        context.set_line_width(frame.line_width(GRATICULE_FRONT_LINE_WIDTH));
+                        context.set_stroke_style_str(GRATICULE_FRONT_STROKE_STYLE);
+                    }
+                    Some(_) => {
+                        context.set_line_width(frame.line_width(GRATICULE_BACK_LINE_WIDTH));
+                        context.set_stroke_style_str(GRATICULE_BACK_STROKE_STYLE);
+                    }
+                    None => continue,
+                }
+                context.begin_path();
+                context.move_to(y_prev, z_prev);
+                context.line_to(y, z);
+                context.stroke();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Labels of the meridians along the parallel nearest the centre of the view, and of the
+    /// parallels along the meridian nearest it, written in the frame's locale.
+    pub fn labels(&self, frame: &Frame) -> Vec<Label> {
+        if !self.visible {
+            return Vec::new();
+        }
+        let lat = nearest(frame.orientation.tilt, PARALLEL_INTERVAL)
+            .clamp(-90.0 + PARALLEL_INTERVAL, 90.0 - PARALLEL_INTERVAL);
+        let lon = nearest(-frame.orientation.rotation, MERIDIAN_INTERVAL);
+        let meridians = steps(-180.0, 180.0 - MERIDIAN_INTERVAL, MERIDIAN_INTERVAL)
+            .map(|lon| (lon, lat, frame.locale.longitude(lon, 0)));
+        let parallels = steps(
+            -90.0 + PARALLEL_INTERVAL,
+            90.0 - PARALLEL_INTERVAL,
+            PARALLEL_INTERVAL,
+        )
+        .filter(|parallel| *parallel != lat)
+        .map(|lat| (lon, lat, frame.locale.latitude(lat, 0)));
+
+        meridians
+            .chain(parallels)
+            .filter_map(|(lon, lat, text)| {
+                let (x, y, z) = frame.project(lon, lat);
+                if x < 0.0 {
+                    return None;
+                }
+                let (x, y) = frame.to_canvas(y, z);
+                Some(Label {
+                    text,
+                    x,
+                    y,
+                    priority: GRATICULE_LABEL_PRIORITY,
+                    allow_overlap: false,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Multiple of an interval nearest a value.
+fn nearest(value: f64, interval: f64) -> f64 {
+    (value / interval).round() * interval
+}
+
+/// Meridians then parallels, as locations (degrees, as longitude and latitude) sampled along
+/// each line.
+pub fn lines() -> impl Iterator<Item = Vec<(f64, f64)>> {
+    let meridians = steps(-180.0, 180.0 - MERIDIAN_INTERVAL, MERIDIAN_INTERVAL).map(|lon| {
+        steps(-MERIDIAN_LIMIT, MERIDIAN_LIMIT, SAMPLE_INTERVAL)
+            .map(|lat| (lon, lat))
+            .collect::<Vec<_>>()
+    });
+    let parallels = steps(
+        -90.0 + PARALLEL_INTERVAL,
+        90.0 - PARALLEL_INTERVAL,
+        PARALLEL_INTERVAL,
+    )
+    .map(|lat| {
+        steps(-180.0, 180.0, SAMPLE_INTERVAL)
+            .map(|lon| (lon, lat))
+            .collect::<Vec<_>>()
+    });
+    meridians.chain(parallels)
+}
+
+/// Values from `first` to `last` inclusive at an interval.
+fn steps(first: f64, last: f64, interval: f64) -> impl Iterator<Item = f64> {
+    let count = ((last - first) / interval).round() as usize;
+    (0..=count).map(move |i| first + i as f64 * interval)
+}
@@ -0,0 +1,400 @@
+// Aggregation of points into hexagonal bins over the globe, filled by color according to the
+// count or total value of the points in each.
+//
+// The bins are the cells of a geodesic grid: the vertices of an icosahedron whose faces are
+// subdivided into triangles are the centres of the bins, each bounded by the centroids of the
+// triangles around it, giving hexagons everywhere but at the 12 vertices of the icosahedron,
+// which are pentagons.
+
+use std::collections::HashMap;
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::coords::{LonLat, Vec3};
+use crate::legend::{Legend, LegendSymbols};
+use crate::view::Frame;
+use crate::voronoi;
+
+// Colors (red, green, blue) of the lowest and highest totals
+const HEXBIN_LOW_COLOR: [f64; 3] = [237.0, 248.0, 177.0];
+const HEXBIN_HIGH_COLOR: [f64; 3] = [37.0, 52.0, 148.0];
+const HEXBIN_ALPHA: f64 = 0.75;
+const HEXBIN_DEFAULT_RESOLUTION: u32 = 8;
+const HEXBIN_MAX_RESOLUTION: u32 = 64;
+const HEXBIN_DEFAULT_LEGEND_TITLE: &str = "Count";
+// Coordinates of grid vertices are rounded to this many parts of the radius, to find
+// the vertices shared by neighbouring faces
+const VERTEX_QUANTUM: f64 = 1e6;
+
+/// Geodesic grid at a resolution, subdividing each edge of the icosahedron into that many parts.
+#[derive(Debug)]
+struct Grid {
+    resolution: u32,
+    /// Corners of each face of the icosahedron.
+    faces: Vec<[Vec3; 3]>,
+    /// Index of each grid vertex of each face, by its steps along the face's edges.
+    face_vertices: Vec<HashMap<(u32, u32), usize>>,
+    /// Centre of each bin.
+    centres: Vec<Vec3>,
+    /// Boundary of each bin, around its centre.
+    cells: Vec<Vec<Vec3>>,
+}
+
+impl Grid {
+    fn new(resolution: u32) -> Self {
+        let resolution = resolution.clamp(1, HEXBIN_MAX_RESOLUTION);
+        let faces = icosahedron_faces();
+        let mut face_vertices = Vec::new();
+        let mut centres = Vec::new();
+        let mut indices = HashMap::new();
+        for face in &faces {
+            let mut vertices = HashMap::new();
+            for i in 0..=resolution {
+                for j in 0..=resolution - i {
+                    let point = face_point(face, resolution, i as f64, j as f64);
+                    let key = [point.x, point.y, point.z]
+                        .map(|coordinate| (coordinate * VERTEX_QUANTUM).round() as i64);
+                    let index = *indices.entry(key).or_insert_with(|| {
+                        centres.push(point);
+                        centres.len() - 1
+                    });
+                    vertices.insert((i, j), index);
+                }
+            }
+            face_vertices.push(vertices);
+        }
+
+        // Each bin is bounded by the centroids of the triangles around its centre
+        let mut corners = vec![Vec::new(); centres.len()];
+        for vertices in &face_vertices {
+            for i in 0..resolution {
+                for j in 0..resolution - i {
+                    let mut triangles = vec![[(i, j), (i + 1, j), (i, j + 1)]];
+                    if i + j + 1 < resolution {
+                        triangles.push([(i + 1, j), (i + 1, j + 1), (i, j + 1)]);
+                    }
+                    for triangle in triangles {
+                        let triangle = triangle.map(|step| vertices[&step]);
+                        let centroid =
+                            (centres[triangle[0]] + centres[triangle[1]] + centres[triangle[2]])
+                                .normalised();
+                        for index in triangle {
+                            corners[index].push(centroid);
+                        }
+                    }
+                }
+            }
+        }
+        let cells = corners
+            .into_iter()
+            .zip(&centres)
+            .map(|(corners, centre)| around(*centre, corners))
+            .collect();
+
+        Self {
+            resolution,
+            faces,
+            face_vertices,
+            centres,
+            cells,
+        }
+    }
+
+    /// Index of the bin containing a point on the unit sphere.
+    fn bin(&self, point: Vec3) -> usize {
+        let face = (0..self.faces.len())
+            .max_by(|a, b| {
+                let centre =
+                    |face: usize| self.faces[face][0] + self.faces[face][1] + self.faces[face][2];
+                point.dot(centre(*a)).total_cmp(&point.dot(centre(*b)))
+            })
+            .unwrap_or(0);
+        let [a, b, c] = self.faces[face];
+
+        // Steps along the face's edges of the point where it meets the face's plane
+        let normal = (b - a).cross(c - a);
+        let on_face = point * (normal.dot(a) / normal.dot(point));
+        let (u, v) = barycentric(on_face - a, b - a, c - a);
+        let resolution = self.resolution as f64;
+        let (i, j) = (
+            (u * resolution).clamp(0.0, resolution),
+            (v * resolution).clamp(0.0, resolution),
+        );
+        let (i0, j0) = (i.floor() as u32, j.floor() as u32);
+        let candidates = [(i0, j0), (i0 + 1, j0), (i0, j0 + 1), (i0 + 1, j0 + 1)];
+        let vertices = &self.face_vertices[face];
+        candidates
+            .iter()
+            .filter_map(|step| vertices.get(step))
+            .copied()
+            .max_by(|a, b| {
+                point
+                    .dot(self.centres[*a])
+                    .total_cmp(&point.dot(self.centres[*b]))
+            })
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug)]
+pub struct Hexbins {
+    grid: Grid,
+    /// Points (unit sphere) binned, with their values.
+    points: Vec<(Vec3, f64)>,
+    /// Total of the values of the points in each bin.
+    totals: Vec<f64>,
+    /// Range of the totals of the bins with points, mapped to the color ramp.
+    range: (f64, f64),
+    pub legend_title: String,
+}
+
+impl Default for Hexbins {
+    fn default() -> Self {
+        Self {
+            grid: Grid::new(HEXBIN_DEFAULT_RESOLUTION),
+            points: Vec::new(),
+            totals: Vec::new(),
+            range: (0.0, 0.0),
+            legend_title: HEXBIN_DEFAULT_LEGEND_TITLE.to_string(),
+        }
+    }
+}
+
+impl Hexbins {
+    /// Set the points, given as the latitude and longitude (degrees) of each in turn, with
+    /// their values, or a value of one each to count them; returns whether the number of
+    /// coordinates and values match.
+    pub fn set_points(&mut self, coordinates: &[f64], values: Option<&[f64]>) -> bool {
+        let count = coordinates.len() / 2;
+        if !coordinates.len().is_multiple_of(2)
+            || values.is_some_and(|values| values.len() != count)
+        {
+            return false;
+        }
+        self.points = coordinates
+            .chunks(2)
+            .enumerate()
+            .map(|(i, point)| {
+                let value = values.map_or(1.0, |values| values[i]);
+                (LonLat::new(point[1], point[0]).to_vec3(), value)
+            })
+            .collect();
+        self.aggregate();
+        true
+    }
+
+    /// Set the resolution of the bins, as the number of parts each edge of the underlying
+    /// icosahedron is divided into, rebinning the points.
+    pub fn set_resolution(&mut self, resolution: u32) {
+        if resolution.clamp(1, HEXBIN_MAX_RESOLUTION) != self.grid.resolution {
+            self.grid = Grid::new(resolution);
+            self.aggregate();
+        }
+    }
+
+    /// Remove all points.
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.aggregate();
+    }
+
+    fn aggregate(&mut self) {
+        self.totals = vec![0.0; self.grid.centres.len()];
+        let mut occupied = vec![false; self.totals.len()];
+        for (point, value) in &self.points {
+            let bin = self.grid.bin(*point);
+            if value.is_finite() {
+                self.totals[bin] += value;
+                occupied[bin] = true;
+            }
+        }
+        self.range = self
+            .totals
+            .iter()
+            .zip(&occupied)
+            .filter(|(_, occupied)| **occupied)
+            .fold(
+                (f64::INFINITY, f64::NEG_INFINITY),
+                |(min, max), (total, _)| (min.min(*total), max.max(*total)),
+            );
+        // Bins without points are not filled
+        for (total, occupied) in self.totals.iter_mut().zip(occupied) {
+            if !occupied {
+                *total = f64::NAN;
+            }
+        }
+    }
+
+    /// Color (red, green, blue) of a total on the ramp.
+    fn color(&self, total: f64) -> [f64; 3] {
+        let (min, max) = self.range;
+        let t = if max > min {
+            ((total - min) / (max - min)).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        [0, 1, 2].map(|i| HEXBIN_LOW_COLOR[i] + (HEXBIN_HIGH_COLOR[i] - HEXBIN_LOW_COLOR[i]) * t)
+    }
+
+    /// Legend of the color ramp over the range of the totals, when shown.
+    pub fn legend(&self) -> Option<Legend> {
+        let (min, max) = self.range;
+        if min > max {
+            return None;
+        }
+        Some(Legend {
+            title: self.legend_title.clone(),
+            symbols: LegendSymbols::Ramp {
+                low: color_style(HEXBIN_LOW_COLOR),
+                high: color_style(HEXBIN_HIGH_COLOR),
+                min: min.to_string(),
+                max: max.to_string(),
+            },
+        })
+    }
+
+    /// Fill the bins with points onto the canvas.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        for (cell, total) in self.grid.cells.iter().zip(&self.totals) {
+            if total.is_nan() {
+                continue;
+            }
+            context.begin_path();
+            for (i, corner) in cell.iter().enumerate() {
+                for point in voronoi::arc(*corner, cell[(i + 1) % cell.len()]) {
+                    let location = LonLat::from_vec3(point);
+                    let (y, z) = frame.project_to_disc(location.lon, location.lat);
+                    context.line_to(y, z);
+                }
+            }
+            context.close_path();
+            context.set_fill_style_str(&color_style(self.color(*total)));
+            context.fill();
+        }
+
+        Ok(())
+    }
+}
+
+fn color_style(color: [f64; 3]) -> String {
+    format!(
+        "rgba({:.0}, {:.0}, {:.0}, {})",
+        color[0], color[1], color[2], HEXBIN_ALPHA
+    )
+}
+
+/// Coordinates of a point along two edges of a triangle from their shared corner.
+fn barycentric(point: Vec3, edge_b: Vec3, edge_c: Vec3) -> (f64, f64) {
+    let (bb, bc, cc) = (edge_b.dot(edge_b), edge_b.dot(edge_c), edge_c.dot(edge_c));
+    let (pb, pc) = (point.dot(edge_b), point.dot(edge_c));
+    let determinant = bb * cc - bc * bc;
+    (
+        (pb * cc - pc * bc) / determinant,
+        (pc * bb - pb * bc) / determinant,
+    )
+}
+
+/// Point on the sphere at steps along the edges of a face subdivided into a resolution.
+fn face_point(face: &[Vec3; 3], resolution: u32, i: f64, j: f64) -> Vec3 {
+    let [a, b, c] = *face;
+    let resolution = resolution as f64;
+    (a + (b - a) * (i / resolution) + (c - a) * (j / resolution)).normalised()
+}
+
+/// Corners ordered anticlockwise around a centre, viewed from outside the sphere.
+fn around(centre: Vec3, mut corners: Vec<Vec3>) -> Vec<Vec3> {
+    let reference = if centre.x.abs() < 0.9 {
+        Vec3::new(1.0, 0.0, 0.0)
+    } else {
+        Vec3::new(0.0, 1.0, 0.0)
+    };
+    let first = centre.cross(reference).normalised();
+    let second = centre.cross(first);
+    corners.sort_by(|a, b| {
+        let angle = |corner: &Vec3| corner.dot(second).atan2(corner.dot(first));
+        angle(a).total_cmp(&angle(b))
+    });
+    corners
+}
+
+/// Faces of the icosahedron inscribed in the unit sphere, found as the triples of
+/// vertices each an edge's length apart.
+fn icosahedron_faces() -> Vec<[Vec3; 3]> {
+    let golden = (1.0 + 5.0_f64.sqrt()) / 2.0;
+    let mut vertices = Vec::new();
+    for a in [-1.0, 1.0] {
+        for b in [-golden, golden] {
+            vertices.push(Vec3::new(0.0, a, b).normalised());
+            vertices.push(Vec3::new(a, b, 0.0).normalised());
+            vertices.push(Vec3::new(b, 0.0, a).normalised());
+        }
+    }
+    // Neighbouring vertices are nearest each other, with the greatest dot product
+    let edge = vertices[1..]
+        .iter()
+        .map(|vertex| vertex.dot(vertices[0]))
+        .fold(f64::NEG_INFINITY, f64::max);
+    let adjacent = |a: usize, b: usize| (vertices[a].dot(vertices[b]) - edge).abs() < 1e-9;
+    let mut faces = Vec::new();
+    for a in 0..vertices.len() {
+        for b in a + 1..vertices.len() {
+            for c in b + 1..vertices.len() {
+                if adjacent(a, b) && adjacent(b, c) && adjacent(a, c) {
+                    faces.push([vertices[a], vertices[b], vertices[c]]);
+                }
+            }
+        }
+    }
+    faces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_geodesic_grids() {
+        for resolution in [1, 2, 5] {
+            let grid = Grid::new(resolution);
+            assert_eq!(grid.faces.len(), 20);
+            assert_eq!(
+                grid.centres.len(),
+                10 * (resolution * resolution) as usize + 2
+            );
+            // Bins are hexagons, except the 12 pentagons
+            let pentagons = grid.cells.iter().filter(|cell| cell.len() == 5).count();
+            assert_eq!(pentagons, 12);
+            assert!(grid.cells.iter().all(|cell| (5..=6).contains(&cell.len())));
+        }
+    }
+
+    #[test]
+    fn bins_points_with_their_nearest_centre() {
+        let grid = Grid::new(6);
+        for (lon, lat) in [(0.0, 0.0), (12.3, 45.6), (-150.0, -70.0), (90.0, 89.0)] {
+            let point = LonLat::new(lon, lat).to_vec3();
+            let bin = grid.bin(point);
+            let nearest = (0..grid.centres.len())
+                .max_by(|a, b| {
+                    point
+                        .dot(grid.centres[*a])
+                        .total_cmp(&point.dot(grid.centres[*b]))
+                })
+                .unwrap();
+            // Points near the boundaries of bins may fall either side
+            let angle = |index: usize| point.dot(grid.centres[index]).acos();
+            assert!(angle(bin) - angle(nearest) < 0.05);
+        }
+    }
+
+    #[test]
+    fn totals_values_by_bin() {
+        let mut hexbins = Hexbins::default();
+        assert!(hexbins.set_points(&[10.0, 10.0, 10.0, 10.001, -40.0, 100.0], None));
+        assert_eq!(hexbins.range, (1.0, 2.0));
+        assert!(hexbins.set_points(&[10.0, 10.0, 10.0, 10.001], Some(&[2.5, 4.0])));
+        assert_eq!(hexbins.range, (6.5, 6.5));
+        assert!(!hexbins.set_points(&[10.0, 10.0], Some(&[1.0, 2.0])));
+    }
+}
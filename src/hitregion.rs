@@ -0,0 +1,57 @@
+// Regions of the canvas taken by on-canvas controls, so pointer events over them are
+// routed to the controls rather than dragging the globe.
+
+use std::cell::RefCell;
+
+use crate::overlay::Rect;
+
+#[derive(Debug, Default)]
+pub struct HitRegions {
+    /// Rectangles of the controls drawn, by the identifiers of the controls.
+    regions: RefCell<Vec<(&'static str, Rect)>>,
+}
+
+impl HitRegions {
+    /// Set the rectangle taken by a control as it is drawn, or remove it when not drawn.
+    pub fn set(&self, control: &'static str, rect: Option<Rect>) {
+        let mut regions = self.regions.borrow_mut();
+        regions.retain(|(id, _)| *id != control);
+        if let Some(rect) = rect {
+            regions.push((control, rect));
+        }
+    }
+
+    /// Control at a position (canvas pixels), the last drawn where controls overlap.
+    pub fn hit(&self, x: f64, y: f64) -> Option<&'static str> {
+        self.regions
+            .borrow()
+            .iter()
+            .rev()
+            .find(|(_, rect)| rect.contains(x, y))
+            .map(|(id, _)| *id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hits_last_drawn_control() {
+        let regions = HitRegions::default();
+        let rect = |left| Rect {
+            left,
+            top: 0.0,
+            width: 10.0,
+            height: 10.0,
+        };
+        regions.set("legend", Some(rect(0.0)));
+        regions.set("attribution", Some(rect(5.0)));
+        assert_eq!(regions.hit(2.0, 2.0), Some("legend"));
+        assert_eq!(regions.hit(7.0, 2.0), Some("attribution"));
+        assert_eq!(regions.hit(20.0, 2.0), None);
+
+        regions.set("attribution", None);
+        assert_eq!(regions.hit(7.0, 2.0), Some("legend"));
+    }
+}
@@ -0,0 +1,107 @@
+// Clipping of filled polygons at the horizon of the orthographic view, so only the
+// parts on the front of the sphere are filled.
+
+use std::f64::consts::TAU;
+
+use crate::coords::Vec3;
+
+// Arcs along the horizon are sampled every this many radians
+const HORIZON_SAMPLE_ANGLE: f64 = 0.05;
+
+/// Outline (plotted coordinates) of the part of a ring of points (view coordinates on the
+/// unit sphere) on the front of the sphere, with its excursions behind the sphere replaced
+/// by arcs along the horizon; empty when the ring is wholly behind. Rings are clockwise
+/// seen from outside the sphere, as the outer rings of shapefiles, so the arcs run
+/// clockwise around the disc.
+pub fn clip_ring(ring: &[Vec3]) -> Vec<(f64, f64)> {
+    let count = ring.len();
+    // Starting on the front, each excursion behind is followed by a return to the front
+    let Some(start) = ring.iter().position(|point| point.x >= 0.0) else {
+        return Vec::new();
+    };
+    let mut outline = Vec::new();
+    // Angle (radians, on the plotted disc) where the ring last went behind
+    let mut exit = None;
+    for i in 0..count {
+        let (a, b) = (ring[(start + i) % count], ring[(start + i + 1) % count]);
+        if a.x >= 0.0 {
+            outline.push((a.y, a.z));
+        }
+        if (a.x >= 0.0) == (b.x >= 0.0) {
+            continue;
+        }
+        let crossing = horizon_crossing(a, b);
+        let angle = crossing.1.atan2(crossing.0);
+        if a.x >= 0.0 {
+            outline.push(crossing);
+            exit = Some(angle);
+        } else {
+            if let Some(exit) = exit.take() {
+                horizon_arc(&mut outline, exit, angle);
+            }
+            outline.push(crossing);
+        }
+    }
+    outline
+}
+
+/// Point (plotted coordinates) where the great circle arc between points on either
+/// side of the horizon crosses it.
+fn horizon_crossing(a: Vec3, b: Vec3) -> (f64, f64) {
+    let t = a.x / (a.x - b.x);
+    let point = a + (b - a) * t;
+    let r = point.y.hypot(point.z);
+    if r > 0.0 {
+        (point.y / r, point.z / r)
+    } else {
+        (1.0, 0.0)
+    }
+}
+
+/// Add the points of the arc along the horizon clockwise between angles (radians),
+/// excluding its ends.
+fn horizon_arc(outline: &mut Vec<(f64, f64)>, from: f64, to: f64) {
+    let sweep = (from - to).rem_euclid(TAU);
+    let steps = (sweep / HORIZON_SAMPLE_ANGLE).ceil() as usize;
+    for step in 1..steps {
+        let (sin, cos) = (from - sweep * step as f64 / steps as f64).sin_cos();
+        outline.push((cos, sin));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::LonLat;
+
+    fn ring(corners: &[(f64, f64)]) -> Vec<Vec3> {
+        corners
+            .iter()
+            .map(|(lon, lat)| LonLat::new(*lon, *lat).to_vec3())
+            .collect()
+    }
+
+    #[test]
+    fn keeps_rings_on_the_front() {
+        let outline = clip_ring(&ring(&[(-10.0, 10.0), (10.0, 10.0), (10.0, -10.0)]));
+        assert_eq!(outline.len(), 3);
+        assert!(clip_ring(&ring(&[(170.0, 10.0), (190.0, 10.0), (190.0, -10.0)])).is_empty());
+    }
+
+    #[test]
+    fn follows_the_horizon_behind() {
+        // Clockwise around a square straddling the horizon on the right of the disc
+        let outline = clip_ring(&ring(&[
+            (80.0, 10.0),
+            (100.0, 10.0),
+            (100.0, -10.0),
+            (80.0, -10.0),
+        ]));
+        assert!(outline.iter().all(|(y, z)| y.hypot(*z) <= 1.0 + 1e-9));
+        // The arc runs down the right of the disc rather than around the far side
+        assert!(outline.iter().all(|(y, _)| *y > 0.9));
+        assert!(outline
+            .iter()
+            .any(|(y, z)| (y - 1.0).abs() < 1e-2 && z.abs() < 0.05));
+    }
+}
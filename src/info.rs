@@ -0,0 +1,151 @@
+// Information about the datasets embedded during the build.
+
+use wasm_bindgen::JsValue;
+
+use crate::coords::LonLat;
+use crate::data;
+use crate::measure;
+use crate::projection::Projection;
+use crate::view::{Orientation, Side};
+
+// Locations around the bounding cap of a feature projected to fit it to the view
+const FIT_SAMPLES: usize = 16;
+
+#[derive(Debug)]
+pub struct DatasetInfo {
+    pub name: &'static str,
+    /// Source of the data, to be attributed.
+    pub source: &'static str,
+    pub resolution: &'static str,
+    pub version: &'static str,
+    pub features: usize,
+    pub points: usize,
+    /// Hash of the content of the source shapefile's `.shp`, `.shx`, `.dbf` and `.prj` files in
+    /// turn, as hexadecimal.
+    pub sha256: &'static str,
+}
+
+impl DatasetInfo {
+    /// Object describing the dataset, for JavaScript.
+    pub fn to_object(&self) -> Result<JsValue, JsValue> {
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| js_sys::Reflect::set(&object, &key.into(), &value);
+        set("name", self.name.into())?;
+        set("source", self.source.into())?;
+        set("resolution", self.resolution.into())?;
+        set("version", self.version.into())?;
+        set("features", (self.features as u32).into())?;
+        set("points", (self.points as u32).into())?;
+        set("sha256", self.sha256.into())?;
+        Ok(object.into())
+    }
+}
+
+/// Value of an attribute of a feature, embedded during the build.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Attribute {
+    Number(f64),
+    Text(&'static str),
+    // Only generated for missing values, which not every dataset has
+    #[allow(dead_code)]
+    Null,
+}
+
+/// Bounds of a feature, precomputed during the build.
+#[derive(Debug)]
+pub struct FeatureBounds {
+    /// Spherical centroid (degrees).
+    pub centroid: LonLat,
+    /// Radius (degrees) of the cap about the centroid containing the feature's points.
+    pub radius: f64,
+}
+
+impl FeatureBounds {
+    /// Orientation centring the feature in the view, keeping the roll.
+    pub fn centred(&self, orientation: &Orientation) -> Orientation {
+        orientation.centred_on(self.centroid)
+    }
+
+    /// Zoom factor at which the feature, centred with an orientation, fills a view of a
+    /// radius (canvas pixels) but for padding (canvas pixels) around it.
+    pub fn fit_zoom(
+        &self,
+        projection: &Projection,
+        orientation: &Orientation,
+        radius: f64,
+        padding: f64,
+    ) -> f64 {
+        // Beyond the limb of views without the back, the cap covers the whole disc
+        let angle = if projection.shows_back() {
+            self.radius.min(90.0)
+        } else {
+            self.radius
+        };
+        let extent = measure::small_circle(self.centroid, angle, FIT_SAMPLES)
+            .into_iter()
+            .map(|location| {
+                let (_, y, z) = projection.project(orientation, location.lon, location.lat);
+                y.hypot(z)
+            })
+            .fold(0.0, f64::max);
+        let available = (radius - padding.max(0.0)).max(1.0);
+        if extent > 0.0 {
+            available / (radius * extent)
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    /// Side of the view the feature is on when projected with an orientation.
+    pub fn side(&self, projection: &Projection, orientation: &Orientation) -> Side {
+        projection.cap_side(orientation, self.centroid, self.radius)
+    }
+}
+
+/// Bounds of a feature of an embedded layer, by layer identifier and feature index.
+pub fn feature_bounds(layer: &str, feature: usize) -> Option<&'static FeatureBounds> {
+    let bounds = match layer {
+        "coastline" => data::COASTLINE_BOUNDS,
+        "countries" => data::COUNTRY_BOUNDS,
+        "land" => data::LAND_BOUNDS,
+        _ => return None,
+    };
+    bounds.get(feature)
+}
+
+/// Names of the attribute fields of an embedded layer, and the attributes of each feature
+/// in the order of the fields, by layer identifier.
+pub fn layer_attributes(
+    layer: &str,
+) -> Option<(&'static [&'static str], &'static [&'static [Attribute]])> {
+    match layer {
+        "coastline" => Some((data::COASTLINE_FIELDS, data::COASTLINE_ATTRIBUTES)),
+        "countries" => Some((data::COUNTRY_FIELDS, data::COUNTRY_ATTRIBUTES)),
+        "land" => Some((data::LAND_FIELDS, data::LAND_ATTRIBUTES)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fits_features_to_the_view() {
+        let bounds = FeatureBounds {
+            centroid: LonLat::new(20.0, 10.0),
+            radius: 30.0,
+        };
+        let projection = Projection::default();
+        let orientation = bounds.centred(&Orientation::default());
+        // The cap spans half the radius of the disc, so fills it less padding at about twice
+        let zoom = bounds.fit_zoom(&projection, &orientation, 100.0, 10.0);
+        assert!((zoom - 1.8).abs() < 1e-9);
+        let whole = FeatureBounds {
+            centroid: LonLat::new(0.0, 0.0),
+            radius: 120.0,
+        };
+        let orientation = whole.centred(&Orientation::default());
+        assert!((whole.fit_zoom(&projection, &orientation, 100.0, 0.0) - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,59 @@
+// Pointer and wheel input on the canvas, read from its events on the page or from the
+// messages forwarding them to a worker drawing the globe.
+
+use web_sys::{PointerEvent, WheelEvent};
+
+use crate::controls::Position;
+
+/// Input of a pointer on the canvas.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointerInput {
+    /// Position (canvas pixels) from the top left of the canvas.
+    pub position: Position,
+    pub pointer_id: i32,
+    /// Time (milliseconds) of the input, on the clock of `now()`.
+    pub time_stamp: f64,
+    /// Type of the pointer, as "mouse", "pen" or "touch".
+    pub pointer_type: String,
+}
+
+impl PointerInput {
+    pub fn from_event(event: &PointerEvent) -> Self {
+        Self {
+            position: Position {
+                x: event.offset_x() as f64,
+                y: event.offset_y() as f64,
+            },
+            pointer_id: event.pointer_id(),
+            time_stamp: event.time_stamp(),
+            pointer_type: event.pointer_type(),
+        }
+    }
+}
+
+/// Input of the wheel over the canvas.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WheelInput {
+    /// Position (canvas pixels) from the top left of the canvas.
+    pub position: Position,
+    /// Distance scrolled downwards, in the units of the delta mode.
+    pub delta_y: f64,
+    /// Units of the distance, as pixels, lines or pages.
+    pub delta_mode: u32,
+    /// Time (milliseconds) of the input, on the clock of `now()`.
+    pub time_stamp: f64,
+}
+
+impl WheelInput {
+    pub fn from_event(event: &WheelEvent) -> Self {
+        Self {
+            position: Position {
+                x: event.offset_x() as f64,
+                y: event.offset_y() as f64,
+            },
+            delta_y: event.delta_y(),
+            delta_mode: event.delta_mode(),
+            time_stamp: event.time_stamp(),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+// Labels collected from all layers each frame and decluttered by priority before drawing.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::{font, overlay};
+
+const LABEL_FONT: &str = "12px sans-serif";
+pub(crate) const LABEL_FILL_STYLE: &str = "rgba(0, 0, 0, 1.0)";
+pub(crate) const LABEL_HALO_STYLE: &str = "rgba(255, 255, 255, 0.75)";
+pub(crate) const LABEL_HALO_WIDTH: f64 = 3.0;
+const LABEL_HEIGHT: f64 = 12.0;
+const LABEL_PADDING: f64 = 2.0;
+// Labels drawn with the vector font have capitals this high, stroked this wide
+pub(crate) const VECTOR_CAP_HEIGHT: f64 = 9.0;
+pub(crate) const VECTOR_LINE_WIDTH: f64 = 1.25;
+
+/// Font labels are drawn with.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LabelFont {
+    /// The canvas font, drawn by the browser.
+    #[default]
+    Canvas,
+    /// The stroked vector font, drawn the same by every backend.
+    Vector,
+}
+
+/// A label placed at an anchor in canvas pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Label {
+    pub text: String,
+    pub x: f64,
+    pub y: f64,
+    /// Labels with higher priority are placed first.
+    pub priority: f64,
+    /// Whether the label is drawn even when it overlaps labels already placed.
+    pub allow_overlap: bool,
+}
+
+/// Axis aligned rectangle in canvas pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Rect {
+    left: f64,
+    top: f64,
+    right: f64,
+    bottom: f64,
+}
+
+impl Rect {
+    fn intersects(&self, other: &Rect) -> bool {
+        self.left < other.right
+            && other.left < self.right
+            && self.top < other.bottom
+            && other.top < self.bottom
+    }
+}
+
+/// Decide which labels are drawn, given the label bounds; labels are placed in priority order
+/// and skipped when overlapping a label already placed, unless they allow overlap.
+fn declutter(labels: &[Label], bounds: &[Rect]) -> Vec<bool> {
+    let mut order = (0..labels.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| labels[b].priority.total_cmp(&labels[a].priority));
+
+    let mut placed = Vec::<Rect>::new();
+    let mut drawn = vec![false; labels.len()];
+    for index in order {
+        let rect = bounds[index];
+        if labels[index].allow_overlap || !placed.iter().any(|other| other.intersects(&rect)) {
+            placed.push(rect);
+            drawn[index] = true;
+        }
+    }
+    drawn
+}
+
+/// Decide which labels are drawn, given the widths (pixels) of their text.
+pub fn placed(labels: &[Label], widths: &[f64]) -> Vec<bool> {
+    let bounds = labels
+        .iter()
+        .zip(widths)
+        .map(|(label, width)| Rect {
+            left: label.x - width / 2.0 - LABEL_PADDING,
+            top: label.y - LABEL_HEIGHT - LABEL_PADDING,
+            right: label.x + width / 2.0 + LABEL_PADDING,
+            bottom: label.y + LABEL_PADDING,
+        })
+        .collect::<Vec<_>>();
+    declutter(labels, &bounds)
+}
+
+/// Declutter and draw labels onto the canvas with a font; labels are drawn centred above
+/// their anchors.
+pub fn draw(
+    context: &CanvasRenderingContext2d,
+    labels: &[Label],
+    pixel_ratio: f64,
+    font: LabelFont,
+) -> Result<(), JsValue> {
+    if labels.is_empty() {
+        return Ok(());
+    }
+
+    // Labels are drawn in canvas pixels rather than unit sphere coordinates
+    context.save();
+    overlay::canvas_pixels(context, pixel_ratio)?;
+    context.set_font(LABEL_FONT);
+    context.set_text_align("center");
+    context.set_text_baseline("bottom");
+
+    let widths = labels
+        .iter()
+        .map(|label| match font {
+            LabelFont::Canvas => Ok(context.measure_text(&label.text)?.width()),
+            LabelFont::Vector => Ok(font::width(&label.text, VECTOR_CAP_HEIGHT)),
+        })
+        .collect::<Result<Vec<_>, JsValue>>()?;
+    let drawn = labels
+        .iter()
+        .zip(placed(labels, &widths))
+        .filter_map(|(label, drawn)| drawn.then_some(label));
+
+    match font {
+        LabelFont::Canvas => {
+            context.set_line_width(LABEL_HALO_WIDTH);
+            context.set_stroke_style_str(LABEL_HALO_STYLE);
+            context.set_fill_style_str(LABEL_FILL_STYLE);
+            for label in drawn {
+                context.stroke_text(&label.text, label.x, label.y)?;
+                context.fill_text(&label.text, label.x, label.y)?;
+            }
+        }
+        LabelFont::Vector => {
+            // The strokes of all labels are batched, beneath a halo of wider strokes
+            context.begin_path();
+            for label in drawn {
+                for stroke in font::strokes(&label.text, VECTOR_CAP_HEIGHT, (label.x, label.y)) {
+                    for (i, (x, y)) in stroke.into_iter().enumerate() {
+                        if i == 0 {
+                            context.move_to(x, y);
+                        } else {
+                            context.line_to(x, y);
+                        }
+                    }
+                }
+            }
+            context.set_line_cap("round");
+            context.set_line_join("round");
+            context.set_line_width(VECTOR_LINE_WIDTH + LABEL_HALO_WIDTH);
+            context.set_stroke_style_str(LABEL_HALO_STYLE);
+            context.stroke();
+            context.set_line_width(VECTOR_LINE_WIDTH);
+            context.set_stroke_style_str(LABEL_FILL_STYLE);
+            context.stroke();
+        }
+    }
+
+    context.restore();
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+// Land filled from the embedded land polygons, clipped at the horizon.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::coords::Vec3;
+use crate::data;
+use crate::horizon;
+use crate::projection::ProjectionKind;
+use crate::view::{Frame, Side};
+
+const LAND_DEFAULT_FILL_STYLE: &str = "rgba(221, 214, 190, 1.0)";
+
+#[derive(Debug)]
+pub struct Land {
+    /// Style the land is filled with, or `None` for the land not to be filled.
+    pub fill_style: Option<String>,
+}
+
+impl Default for Land {
+    fn default() -> Self {
+        Self {
+            fill_style: Some(LAND_DEFAULT_FILL_STYLE.to_string()),
+        }
+    }
+}
+
+impl Land {
+    /// Fill the land on the front of the globe onto the canvas.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        let Some(fill_style) = &self.fill_style else {
+            return Ok(());
+        };
+
+        // The polygons are filled together, as they do not overlap
+        context.begin_path();
+        for (feature, (polygon, bounds)) in
+            data::LAND_POINTS.iter().zip(data::LAND_BOUNDS).enumerate()
+        {
+            if !frame.shows("land", feature)
+                || bounds.side(&frame.projection, &frame.orientation) == Side::Back
+            {
+                continue;
+            }
+            let points = polygon.iter().map(|point| {
+                let (lon, lat) = point.to_degrees();
+                frame.project(lon, lat)
+            });
+            // The other projections are clipped to the disc as they are drawn
+            let outline = if frame.projection.kind == ProjectionKind::Orthographic {
+                horizon::clip_ring(&points.map(Vec3::from).collect::<Vec<_>>())
+            } else {
+                points.map(|(_, y, z)| (y, z)).collect()
+            };
+            let Some(((y, z), rest)) = outline.split_first() else {
+                continue;
+            };
+            context.move_to(*y, *z);
+            for (y, z) in rest {
+                context.line_to(*y, *z);
+            }
+            context.close_path();
+        }
+        context.set_fill_style_str(fill_style);
+        context.fill();
+
+        Ok(())
+    }
+}
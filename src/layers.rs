@@ -0,0 +1,418 @@
+// Stack of the layers drawn on the globe, from the bottom up, with each layer shown or hidden
+// and drawn with an opacity. The built-in layers are drawn by their passes, which the stack
+// fills in its order, and layers added at runtime are drawn after the built-in layer beneath
+// them, so they take part in the frame's passes without passes of their own.
+
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use web_sys::{CanvasRenderingContext2d, OffscreenCanvas};
+
+use crate::overlay;
+use crate::projection::Projection;
+use crate::schedule::Pass;
+use crate::view::{Frame, Orientation};
+
+/// Layer drawn by the stack: its geometry is projected with the view of each frame, then drawn.
+pub trait Layer: fmt::Debug {
+    /// Project the layer's geometry with the view of a frame, ahead of drawing it in the frame.
+    fn project(&mut self, frame: &Frame) -> Result<(), JsValue>;
+
+    /// Draw the layer as last projected onto the canvas, in unit sphere coordinates.
+    fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue>;
+}
+
+/// Layer drawn by a script, as an object with a `draw(context, project)` method called in
+/// each frame with the context in canvas pixels and a function projecting a longitude and
+/// latitude (degrees) to `[x, y]` in canvas pixels, or to `null` on the back where the
+/// projection clips it.
+pub struct ScriptLayer {
+    object: js_sys::Object,
+    draw: js_sys::Function,
+    view: Rc<Cell<Option<ScriptView>>>,
+    project: Closure<dyn Fn(f64, f64) -> JsValue>,
+}
+
+/// View the functions of script layers project with.
+#[derive(Clone, Copy, Debug)]
+struct ScriptView {
+    orientation: Orientation,
+    projection: Projection,
+    /// Transform from unit sphere coordinates to canvas pixels.
+    transform: [f64; 6],
+}
+
+impl ScriptLayer {
+    /// Layer drawn by an object; throws when it has no `draw` method.
+    pub fn new(object: js_sys::Object) -> Result<ScriptLayer, JsValue> {
+        let draw = js_sys::Reflect::get(&object, &"draw".into())?
+            .dyn_into::<js_sys::Function>()
+            .map_err(|_| JsValue::from_str("layer has no draw method"))?;
+        let view = Rc::new(Cell::new(None::<ScriptView>));
+        let project = {
+            let view = view.clone();
+            Closure::<dyn Fn(f64, f64) -> JsValue>::new(move |lon: f64, lat: f64| {
+                let Some(view) = view.get() else {
+                    return JsValue::NULL;
+                };
+                let (x, y, z) = view.projection.project(&view.orientation, lon, lat);
+                if x < 0.0 && !view.projection.shows_back() {
+                    return JsValue::NULL;
+                }
+                let [a, b, c, d, e, f] = view.transform;
+                js_sys::Array::of2(&(a * y + c * z + e).into(), &(b * y + d * z + f).into()).into()
+            })
+        };
+        Ok(ScriptLayer {
+            object,
+            draw,
+            view,
+            project,
+        })
+    }
+}
+
+impl fmt::Debug for ScriptLayer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ScriptLayer")
+            .field("object", &self.object)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Layer for ScriptLayer {
+    fn project(&mut self, frame: &Frame) -> Result<(), JsValue> {
+        let t = &frame.transform;
+        self.view.set(Some(ScriptView {
+            orientation: frame.orientation,
+            projection: frame.projection,
+            transform: [t.a(), t.b(), t.c(), t.d(), t.e(), t.f()],
+        }));
+        Ok(())
+    }
+
+    fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        context.save();
+        overlay::canvas_pixels(context, frame.pixel_ratio)?;
+        let drawn = self
+            .draw
+            .call2(&self.object, context, self.project.as_ref().unchecked_ref());
+        context.restore();
+        // Errors in scripts are reported without stopping the frame
+        if let Err(error) = drawn {
+            web_sys::console::error_1(&error);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+enum Source {
+    BuiltIn(Pass),
+    Added(Box<dyn Layer>),
+}
+
+#[derive(Debug)]
+struct Entry {
+    id: String,
+    source: Source,
+    visible: bool,
+    /// Opacity (0 to 1) the layer is drawn with.
+    opacity: f64,
+}
+
+/// Layer drawn in place of a pass.
+#[derive(Clone, Copy, Debug)]
+pub enum Drawn<'a> {
+    /// A built-in layer, or a pass drawing no layer, drawn by its pass.
+    Pass(Pass),
+    /// A layer added at runtime.
+    Added(&'a str, &'a dyn Layer),
+}
+
+impl Drawn<'_> {
+    /// Identifier of the layer, for passes drawing a layer.
+    pub fn layer(&self) -> Option<&str> {
+        match self {
+            Drawn::Pass(pass) => pass.layer(),
+            Drawn::Added(id, _) => Some(id),
+        }
+    }
+
+    /// Whether the layer draws geographic data, clipped to the disc by projections clipping
+    /// the back; added layers do.
+    pub fn geographic(&self) -> bool {
+        match self {
+            Drawn::Pass(pass) => pass.geographic(),
+            Drawn::Added(..) => true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LayerStack {
+    /// Layers from the bottom up.
+    entries: Vec<Entry>,
+    /// Canvas translucent layers are drawn on, then drawn onto the globe's canvas with their
+    /// opacity, as passes set the opacity of the context themselves.
+    scratch: Option<(OffscreenCanvas, CanvasRenderingContext2d)>,
+}
+
+impl Default for LayerStack {
+    fn default() -> Self {
+        let entries = Pass::ALL
+            .into_iter()
+            .filter_map(|pass| {
+                Some(Entry {
+                    id: pass.layer()?.to_string(),
+                    source: Source::BuiltIn(pass),
+                    visible: true,
+                    opacity: 1.0,
+                })
+            })
+            .collect();
+        Self {
+            entries,
+            scratch: None,
+        }
+    }
+}
+
+impl LayerStack {
+    /// Add a layer beneath another layer, or on top of the others; returns whether the
+    /// identifier is new and the other layer exists.
+    pub fn add(&mut self, id: &str, layer: Box<dyn Layer>, before: Option<&str>) -> bool {
+        if self.index(id).is_some() {
+            return false;
+        }
+        let Some(index) = self.insertion_index(before) else {
+            return false;
+        };
+        self.entries.insert(
+            index,
+            Entry {
+                id: id.to_string(),
+                source: Source::Added(layer),
+                visible: true,
+                opacity: 1.0,
+            },
+        );
+        true
+    }
+
+    /// Remove a layer added at runtime, returning whether it existed; built-in layers are
+    /// hidden rather than removed.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let count = self.entries.len();
+        self.entries
+            .retain(|entry| entry.id != id || matches!(entry.source, Source::BuiltIn(_)));
+        self.entries.len() != count
+    }
+
+    /// Move a layer beneath another layer, or on top of the others; returns whether both
+    /// layers exist.
+    pub fn move_layer(&mut self, id: &str, before: Option<&str>) -> bool {
+        if before == Some(id) || before.is_some_and(|before| self.index(before).is_none()) {
+            return false;
+        }
+        let Some(index) = self.index(id) else {
+            return false;
+        };
+        let entry = self.entries.remove(index);
+        let index = self.insertion_index(before).unwrap_or(self.entries.len());
+        self.entries.insert(index, entry);
+        true
+    }
+
+    /// Show or hide a layer, returning whether it exists.
+    pub fn set_visible(&mut self, id: &str, visible: bool) -> bool {
+        self.entry(id)
+            .map(|entry| entry.visible = visible)
+            .is_some()
+    }
+
+    /// Set the opacity (0 to 1) of a layer, returning whether it exists.
+    pub fn set_opacity(&mut self, id: &str, opacity: f64) -> bool {
+        let opacity = if opacity.is_nan() { 1.0 } else { opacity };
+        self.entry(id)
+            .map(|entry| entry.opacity = opacity.clamp(0.0, 1.0))
+            .is_some()
+    }
+
+    /// Identifiers of the layers, from the bottom up.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.id.as_str())
+    }
+
+    /// Project the layers added at runtime with the view of a frame.
+    pub fn project(&mut self, frame: &Frame) -> Result<(), JsValue> {
+        for entry in &mut self.entries {
+            if let Source::Added(layer) = &mut entry.source {
+                if entry.visible {
+                    layer.project(frame)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Layers drawn in place of a pass, with their opacities: the passes of built-in layers
+    /// draw the built-in layers in the order of the stack, each with the layers added
+    /// above it, and the layers added beneath all built-in layers are drawn with the first.
+    pub fn drawn_at(&self, pass: Pass) -> Vec<(Drawn<'_>, f64)> {
+        let Some(slot) = Pass::ALL
+            .into_iter()
+            .filter(|pass| pass.layer().is_some())
+            .position(|layer_pass| layer_pass == pass)
+        else {
+            return vec![(Drawn::Pass(pass), 1.0)];
+        };
+        // Groups start at each built-in layer, except the first, which takes those before it
+        let mut group = 0;
+        let mut started = false;
+        let mut drawn = Vec::new();
+        for entry in &self.entries {
+            if matches!(entry.source, Source::BuiltIn(_)) {
+                group += usize::from(started);
+                started = true;
+            }
+            if group == slot && entry.visible && entry.opacity > 0.0 {
+                let layer = match &entry.source {
+                    Source::BuiltIn(pass) => Drawn::Pass(*pass),
+                    Source::Added(layer) => Drawn::Added(&entry.id, layer.as_ref()),
+                };
+                drawn.push((layer, entry.opacity));
+            }
+        }
+        drawn
+    }
+
+    /// Ready the canvas translucent layers are drawn on at the size (pixels) of the canvas
+    /// drawn, when any layer shown is translucent.
+    pub fn prepare(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
+        let translucent = self
+            .entries
+            .iter()
+            .any(|entry| entry.visible && entry.opacity > 0.0 && entry.opacity < 1.0);
+        if !translucent {
+            return Ok(());
+        }
+        if self.scratch.is_none() {
+            let canvas = OffscreenCanvas::new(width, height)?;
+            let context = canvas
+                .get_context("2d")?
+                .expect("should have 2d context")
+                .unchecked_into::<CanvasRenderingContext2d>();
+            self.scratch = Some((canvas, context));
+        }
+        if let Some((canvas, _)) = &self.scratch {
+            if (canvas.width(), canvas.height()) != (width, height) {
+                canvas.set_width(width);
+                canvas.set_height(height);
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw a layer onto the canvas with an opacity, through the canvas of translucent
+    /// layers when it is translucent, so it is drawn within the canvas's clip.
+    pub fn draw_with_opacity(
+        &self,
+        context: &CanvasRenderingContext2d,
+        opacity: f64,
+        draw: impl Fn(&CanvasRenderingContext2d) -> Result<(), JsValue>,
+    ) -> Result<(), JsValue> {
+        let Some((canvas, scratch)) = self.scratch.as_ref().filter(|_| opacity < 1.0) else {
+            return draw(context);
+        };
+        scratch.save();
+        scratch.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)?;
+        scratch.clear_rect(0.0, 0.0, canvas.width() as f64, canvas.height() as f64);
+        let t = context.get_transform()?;
+        scratch.set_transform(t.a(), t.b(), t.c(), t.d(), t.e(), t.f())?;
+        let drawn = draw(scratch);
+        scratch.restore();
+        drawn?;
+
+        context.save();
+        context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)?;
+        context.set_global_alpha(opacity);
+        context.draw_image_with_offscreen_canvas(canvas, 0.0, 0.0)?;
+        context.restore();
+        Ok(())
+    }
+
+    fn index(&self, id: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.id == id)
+    }
+
+    fn entry(&mut self, id: &str) -> Option<&mut Entry> {
+        self.entries.iter_mut().find(|entry| entry.id == id)
+    }
+
+    /// Index a layer is inserted at to be beneath another layer, or on top of the others.
+    fn insertion_index(&self, before: Option<&str>) -> Option<usize> {
+        match before {
+            Some(before) => self.index(before),
+            None => Some(self.entries.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Empty;
+
+    impl Layer for Empty {
+        fn project(&mut self, _frame: &Frame) -> Result<(), JsValue> {
+            Ok(())
+        }
+
+        fn draw(&self, _context: &CanvasRenderingContext2d, _frame: &Frame) -> Result<(), JsValue> {
+            Ok(())
+        }
+    }
+
+    fn ids(stack: &LayerStack, pass: Pass) -> Vec<String> {
+        stack
+            .drawn_at(pass)
+            .iter()
+            .map(|(drawn, _)| drawn.layer().unwrap_or("-").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn fills_passes_in_the_order_of_the_stack() {
+        let mut stack = LayerStack::default();
+        assert_eq!(ids(&stack, Pass::Coastlines), ["coastline"]);
+        assert_eq!(ids(&stack, Pass::Sphere), ["-"]);
+
+        assert!(stack.add("ships", Box::new(Empty), Some("coastline")));
+        assert!(!stack.add("ships", Box::new(Empty), None));
+        assert!(!stack.add("planes", Box::new(Empty), Some("clouds")));
+        assert_eq!(ids(&stack, Pass::Hexbins), ["hexbins", "ships"]);
+
+        // Moving the graticule to the top draws it in the last pass of a layer, and the
+        // layers above it in the passes before
+        assert!(stack.move_layer("graticule", None));
+        assert_eq!(ids(&stack, Pass::Graticule), ["rings"]);
+        assert_eq!(ids(&stack, Pass::Extent), ["pings"]);
+        assert_eq!(ids(&stack, Pass::Pings), ["graticule"]);
+
+        assert!(stack.set_opacity("coastline", 0.5));
+        assert!(stack.set_visible("hexbins", false));
+        assert_eq!(ids(&stack, Pass::Voronoi), ["ships"]);
+        let drawn = stack.drawn_at(Pass::Hexbins);
+        assert_eq!(drawn.len(), 1);
+        assert_eq!((drawn[0].0.layer(), drawn[0].1), (Some("coastline"), 0.5));
+
+        assert!(!stack.remove("coastline"));
+        assert!(stack.remove("ships"));
+        assert!(stack.ids().all(|id| id != "ships"));
+    }
+}
@@ -0,0 +1,388 @@
+// Legends explaining the symbology of layers, generated from their styles and drawn in a
+// corner of the canvas or into an element of the page.
+
+use std::cell::RefCell;
+
+use wasm_bindgen::JsValue;
+use web_sys::{CanvasRenderingContext2d, Element};
+
+use crate::overlay::{self, Corner, Rect};
+
+const LEGEND_TITLE_FONT: &str = "bold 12px sans-serif";
+const LEGEND_FONT: &str = "11px sans-serif";
+const LEGEND_FILL_STYLE: &str = "rgba(0, 0, 0, 0.75)";
+const LEGEND_BACKGROUND_STYLE: &str = "rgba(255, 255, 255, 0.75)";
+const LEGEND_SWATCH_STROKE_STYLE: &str = "rgba(0, 0, 0, 0.5)";
+const LEGEND_ROW_HEIGHT: f64 = 16.0;
+const LEGEND_SWATCH_SIZE: f64 = 12.0;
+const LEGEND_RAMP_WIDTH: f64 = 120.0;
+const LEGEND_SIZE_LINE_WIDTH: f64 = 3.0;
+const LEGEND_PADDING: f64 = 6.0;
+const LEGEND_MARGIN: f64 = 2.0;
+
+/// Entry of a legend, as a swatch filled with a style and the text explaining it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LegendEntry {
+    pub fill_style: String,
+    pub text: String,
+}
+
+/// Symbols of a legend.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LegendSymbols {
+    /// Continuous ramp between colors over a range of values.
+    Ramp {
+        low: String,
+        high: String,
+        min: String,
+        max: String,
+    },
+    /// Categorical swatches.
+    Swatches(Vec<LegendEntry>),
+    /// Lines of lengths (canvas pixels) stroked with a style, and the text explaining them.
+    Sizes {
+        stroke_style: String,
+        entries: Vec<(f64, String)>,
+    },
+    /// Circles of radii (canvas pixels) filled with styles, and the text explaining them.
+    Circles(Vec<(f64, LegendEntry)>),
+}
+
+/// Legend of a layer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Legend {
+    pub title: String,
+    pub symbols: LegendSymbols,
+}
+
+impl Legend {
+    /// Rows of the symbols, each of a height (canvas pixels).
+    fn row_heights(&self) -> Vec<f64> {
+        match &self.symbols {
+            LegendSymbols::Ramp { .. } => vec![LEGEND_ROW_HEIGHT, LEGEND_ROW_HEIGHT],
+            LegendSymbols::Swatches(entries) => vec![LEGEND_ROW_HEIGHT; entries.len()],
+            LegendSymbols::Sizes { entries, .. } => entries
+                .iter()
+                .map(|(size, _)| size.max(LEGEND_ROW_HEIGHT))
+                .collect(),
+            LegendSymbols::Circles(entries) => entries
+                .iter()
+                .map(|(radius, _)| (2.0 * radius).max(LEGEND_ROW_HEIGHT))
+                .collect(),
+        }
+    }
+
+    /// Width (canvas pixels) of the symbols and their text.
+    fn width(&self, context: &CanvasRenderingContext2d) -> Result<f64, JsValue> {
+        context.set_font(LEGEND_TITLE_FONT);
+        let mut width = context.measure_text(&self.title)?.width();
+        context.set_font(LEGEND_FONT);
+        match &self.symbols {
+            LegendSymbols::Ramp { .. } => width = width.max(LEGEND_RAMP_WIDTH),
+            LegendSymbols::Swatches(entries) => {
+                for entry in entries {
+                    width = width.max(
+                        LEGEND_SWATCH_SIZE
+                            + LEGEND_PADDING
+                            + context.measure_text(&entry.text)?.width(),
+                    );
+                }
+            }
+            LegendSymbols::Sizes { entries, .. } => {
+                for (_, text) in entries {
+                    width = width.max(
+                        LEGEND_SWATCH_SIZE + LEGEND_PADDING + context.measure_text(text)?.width(),
+                    );
+                }
+            }
+            LegendSymbols::Circles(entries) => {
+                let diameter = circles_diameter(entries);
+                for (_, entry) in entries {
+                    width = width.max(
+                        diameter + LEGEND_PADDING + context.measure_text(&entry.text)?.width(),
+                    );
+                }
+            }
+        }
+        Ok(width)
+    }
+
+    /// Draw the legend onto the canvas, in canvas pixels, from the top left position.
+    fn draw(&self, context: &CanvasRenderingContext2d, left: f64, top: f64) -> Result<(), JsValue> {
+        context.set_fill_style_str(LEGEND_FILL_STYLE);
+        context.set_font(LEGEND_TITLE_FONT);
+        context.fill_text(&self.title, left, top + LEGEND_ROW_HEIGHT / 2.0)?;
+
+        context.set_font(LEGEND_FONT);
+        let mut top = top + LEGEND_ROW_HEIGHT;
+        match &self.symbols {
+            LegendSymbols::Ramp {
+                low,
+                high,
+                min,
+                max,
+            } => {
+                let gradient =
+                    context.create_linear_gradient(left, 0.0, left + LEGEND_RAMP_WIDTH, 0.0);
+                gradient.add_color_stop(0.0, low)?;
+                gradient.add_color_stop(1.0, high)?;
+                let swatch_top = top + (LEGEND_ROW_HEIGHT - LEGEND_SWATCH_SIZE) / 2.0;
+                context.set_fill_style_canvas_gradient(&gradient);
+                context.fill_rect(left, swatch_top, LEGEND_RAMP_WIDTH, LEGEND_SWATCH_SIZE);
+                context.set_line_width(1.0);
+                context.set_stroke_style_str(LEGEND_SWATCH_STROKE_STYLE);
+                context.stroke_rect(left, swatch_top, LEGEND_RAMP_WIDTH, LEGEND_SWATCH_SIZE);
+
+                let middle = top + LEGEND_ROW_HEIGHT * 1.5;
+                context.set_fill_style_str(LEGEND_FILL_STYLE);
+                context.fill_text(min, left, middle)?;
+                context.set_text_align("right");
+                context.fill_text(max, left + LEGEND_RAMP_WIDTH, middle)?;
+                context.set_text_align("left");
+            }
+            LegendSymbols::Swatches(entries) => {
+                context.set_line_width(1.0);
+                context.set_stroke_style_str(LEGEND_SWATCH_STROKE_STYLE);
+                for entry in entries {
+                    let swatch_top = top + (LEGEND_ROW_HEIGHT - LEGEND_SWATCH_SIZE) / 2.0;
+                    context.set_fill_style_str(&entry.fill_style);
+                    context.fill_rect(left, swatch_top, LEGEND_SWATCH_SIZE, LEGEND_SWATCH_SIZE);
+                    context.stroke_rect(left, swatch_top, LEGEND_SWATCH_SIZE, LEGEND_SWATCH_SIZE);
+                    context.set_fill_style_str(LEGEND_FILL_STYLE);
+                    context.fill_text(
+                        &entry.text,
+                        left + LEGEND_SWATCH_SIZE + LEGEND_PADDING,
+                        top + LEGEND_ROW_HEIGHT / 2.0,
+                    )?;
+                    top += LEGEND_ROW_HEIGHT;
+                }
+            }
+            LegendSymbols::Sizes {
+                stroke_style,
+                entries,
+            } => {
+                context.set_line_width(LEGEND_SIZE_LINE_WIDTH);
+                context.set_stroke_style_str(stroke_style);
+                for (size, text) in entries {
+                    let height = size.max(LEGEND_ROW_HEIGHT);
+                    // Lines stand on the bottom of their row, like the symbols on the globe
+                    let x = left + LEGEND_SWATCH_SIZE / 2.0;
+                    context.begin_path();
+                    context.move_to(x, top + height);
+                    context.line_to(x, top + height - size);
+                    context.stroke();
+                    context.fill_text(
+                        text,
+                        left + LEGEND_SWATCH_SIZE + LEGEND_PADDING,
+                        top + height - LEGEND_ROW_HEIGHT / 2.0,
+                    )?;
+                    top += height;
+                }
+            }
+            LegendSymbols::Circles(entries) => {
+                let diameter = circles_diameter(entries);
+                context.set_line_width(1.0);
+                context.set_stroke_style_str(LEGEND_SWATCH_STROKE_STYLE);
+                for (radius, entry) in entries {
+                    let height = (2.0 * radius).max(LEGEND_ROW_HEIGHT);
+                    context.set_fill_style_str(&entry.fill_style);
+                    context.begin_path();
+                    context.arc(
+                        left + diameter / 2.0,
+                        top + height / 2.0,
+                        *radius,
+                        0.0,
+                        std::f64::consts::TAU,
+                    )?;
+                    context.fill();
+                    context.stroke();
+                    context.set_fill_style_str(LEGEND_FILL_STYLE);
+                    context.fill_text(
+                        &entry.text,
+                        left + diameter + LEGEND_PADDING,
+                        top + height / 2.0,
+                    )?;
+                    top += height;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// HTML describing the legend, for an element of the page.
+    fn to_html(&self) -> String {
+        let symbols = match &self.symbols {
+            LegendSymbols::Ramp {
+                low,
+                high,
+                min,
+                max,
+            } => format!(
+                r#"<div class="globe-legend-ramp" style="background: linear-gradient(to right, {}, {}); width: {}px; height: {}px"></div><div class="globe-legend-range"><span>{}</span> <span>{}</span></div>"#,
+                low,
+                high,
+                LEGEND_RAMP_WIDTH,
+                LEGEND_SWATCH_SIZE,
+                escape(min),
+                escape(max)
+            ),
+            LegendSymbols::Swatches(entries) => entries
+                .iter()
+                .map(|entry| {
+                    format!(
+                        r#"<div class="globe-legend-entry"><span class="globe-legend-swatch" style="display: inline-block; background: {}; width: {}px; height: {}px"></span> {}</div>"#,
+                        entry.fill_style,
+                        LEGEND_SWATCH_SIZE,
+                        LEGEND_SWATCH_SIZE,
+                        escape(&entry.text)
+                    )
+                })
+                .collect(),
+            LegendSymbols::Sizes {
+                stroke_style,
+                entries,
+            } => entries
+                .iter()
+                .map(|(size, text)| {
+                    format!(
+                        r#"<div class="globe-legend-entry"><span class="globe-legend-size" style="display: inline-block; border-left: {}px solid {}; height: {}px"></span> {}</div>"#,
+                        LEGEND_SIZE_LINE_WIDTH,
+                        stroke_style,
+                        size,
+                        escape(text)
+                    )
+                })
+                .collect(),
+            LegendSymbols::Circles(entries) => entries
+                .iter()
+                .map(|(radius, entry)| {
+                    format!(
+                        r#"<div class="globe-legend-entry"><span class="globe-legend-circle" style="display: inline-block; border-radius: 50%; background: {}; width: {}px; height: {}px"></span> {}</div>"#,
+                        entry.fill_style,
+                        2.0 * radius,
+                        2.0 * radius,
+                        escape(&entry.text)
+                    )
+                })
+                .collect(),
+        };
+        format!(
+            r#"<div class="globe-legend"><div class="globe-legend-title">{}</div>{}</div>"#,
+            escape(&self.title),
+            symbols
+        )
+    }
+}
+
+/// Width (canvas pixels) of the column of circles, at least that of a swatch.
+fn circles_diameter(entries: &[(f64, LegendEntry)]) -> f64 {
+    entries
+        .iter()
+        .map(|(radius, _)| 2.0 * radius)
+        .fold(LEGEND_SWATCH_SIZE, f64::max)
+}
+
+/// Display of the legends of the layers.
+#[derive(Debug)]
+pub struct Legends {
+    pub visible: bool,
+    pub corner: Corner,
+    /// Element of the page the legends are written into, instead of being drawn on the canvas.
+    pub element: Option<Element>,
+    /// HTML last written into the element.
+    html: RefCell<String>,
+}
+
+impl Default for Legends {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            corner: Corner::TopLeft,
+            element: None,
+            html: RefCell::new(String::new()),
+        }
+    }
+}
+
+impl Legends {
+    /// Set the element the legends are written into, clearing any previous element.
+    pub fn set_element(&mut self, element: Option<Element>) {
+        if let Some(previous) = &self.element {
+            previous.set_inner_html("");
+        }
+        self.element = element;
+        self.html.borrow_mut().clear();
+    }
+
+    /// Draw the legends onto the canvas, stacked in a corner, or write them into the element;
+    /// returns the rectangle taken on the canvas.
+    pub fn draw(
+        &self,
+        context: &CanvasRenderingContext2d,
+        legends: &[Legend],
+        pixel_ratio: f64,
+    ) -> Result<Option<Rect>, JsValue> {
+        if let Some(element) = &self.element {
+            let html = if self.visible {
+                legends.iter().map(Legend::to_html).collect()
+            } else {
+                String::new()
+            };
+            // The element is only updated when the legends change
+            if *self.html.borrow() != html {
+                element.set_inner_html(&html);
+                *self.html.borrow_mut() = html;
+            }
+            return Ok(None);
+        }
+        if !self.visible || legends.is_empty() {
+            return Ok(None);
+        }
+
+        // The legends are drawn in canvas pixels rather than unit sphere coordinates
+        context.save();
+        let canvas_size = overlay::canvas_pixels(context, pixel_ratio)?;
+        context.set_text_align("left");
+        context.set_text_baseline("middle");
+
+        let mut width: f64 = 0.0;
+        let mut height = 0.0;
+        for legend in legends {
+            width = width.max(legend.width(context)?);
+            height += LEGEND_ROW_HEIGHT + legend.row_heights().iter().sum::<f64>();
+        }
+        let size = (
+            width + 2.0 * LEGEND_PADDING,
+            height + (legends.len() + 1) as f64 * LEGEND_PADDING,
+        );
+        let (left, top) = self.corner.place(size, canvas_size, LEGEND_MARGIN);
+
+        context.set_fill_style_str(LEGEND_BACKGROUND_STYLE);
+        context.fill_rect(left, top, size.0, size.1);
+
+        let rect = Rect {
+            left,
+            top,
+            width: size.0,
+            height: size.1,
+        };
+        let mut top = top + LEGEND_PADDING;
+        for legend in legends {
+            legend.draw(context, left + LEGEND_PADDING, top)?;
+            top += LEGEND_ROW_HEIGHT + legend.row_heights().iter().sum::<f64>() + LEGEND_PADDING;
+        }
+
+        context.restore();
+
+        Ok(Some(rect))
+    }
+}
+
+/// Escape text for HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
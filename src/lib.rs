@@ -1,208 +1,3515 @@
 // A WebAssembly globe renderer.
 
-// The data module is code generated during the build.
 mod data;
 
+mod arcs;
+mod astro;
+mod atlas;
+mod attach;
+mod attract;
+mod attribution;
+mod autorotate;
+mod blob;
+mod camera;
+mod capability;
+mod cartogram;
+mod choropleth;
+mod clip;
+mod config;
+mod contour;
+mod controls;
+mod coords;
+mod crs;
+pub mod datasets;
+mod dirty;
+mod emitter;
+mod extent;
+mod fetch;
+mod filter;
+mod font;
+mod format;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+mod geo;
+mod geofence;
+mod geojson;
+mod gestures;
+mod graticule;
+mod hexbin;
+mod hitregion;
+mod horizon;
+mod info;
+mod input;
+mod labels;
+mod land;
+mod layers;
+mod legend;
+mod limb;
+mod lod;
+mod magnifier;
+mod markers;
+mod measure;
+mod names;
+mod overlay;
+mod permalink;
+mod ping;
+mod postprocess;
+mod profile;
+mod projection;
+mod quaternion;
+mod quiz;
+mod recorder;
+mod rings;
+mod route;
+mod schedule;
+mod selection;
+// Rendering without a browser, for generating thumbnails on a server
+#[cfg(feature = "server")]
+pub mod server;
+mod spikes;
+mod startup;
+mod status;
+mod stroke;
+mod style;
+mod surface;
+mod svg;
+mod swipe;
+mod symbols;
+mod telemetry;
+mod terminator;
+// Golden-image regression testing of rendering
+#[cfg(feature = "testing")]
+pub mod testing;
+mod tiles;
+mod time;
+mod tracks;
+mod view;
+mod voronoi;
+mod worker;
+mod zoom;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{CanvasRenderingContext2d, DomMatrix, HtmlCanvasElement, PointerEvent, Window};
+use web_sys::{
+    AddEventListenerOptions, CanvasRenderingContext2d, CustomEvent, CustomEventInit, DomMatrix,
+    Element, HtmlCanvasElement, HtmlImageElement, OffscreenCanvas, Path2d, PointerEvent,
+    ResizeObserverEntry, WheelEvent, Window,
+};
+
+use arcs::Arcs;
+use astro::Moon;
+use atlas::IconAtlas;
+use attach::Attachment;
+use attract::{Attract, TourStep};
+use attribution::Attribution;
+use autorotate::AutoRotate;
+use camera::CameraAnimation;
+use capability::{Capabilities, RenderSettings};
+use cartogram::Cartogram;
+use choropleth::Choropleth;
+use clip::ClipShape;
+use config::Config;
+use contour::Contours;
+use controls::{ControlData, ControlSettings, Position};
+use crs::{Crs, LayerCrs};
+use dirty::DirtyRegion;
+use emitter::{Emitter, GlobeEvent};
+use extent::Extent;
+use fetch::Fetcher;
+use filter::LayerFilters;
+use format::{CoordinateFormat, Locale};
+use geofence::{GeofenceEvent, Geofences};
+use geojson::GeoJsonLayer;
+use gestures::{Gesture, GestureKind, GestureRecognizer};
+use graticule::Graticule;
+use hexbin::Hexbins;
+use hitregion::HitRegions;
+use input::{PointerInput, WheelInput};
+use labels::LabelFont;
+use land::Land;
+use layers::{Drawn, LayerStack, ScriptLayer};
+use legend::Legends;
+use limb::LimbSmoothing;
+use lod::{CoastlineDetail, CoastlineLevel};
+use magnifier::Magnifier;
+use markers::Markers;
+use names::CountryLabels;
+use permalink::{HashSync, HashView};
+use ping::Pings;
+use profile::Profiler;
+use projection::Projection;
+use quiz::Quiz;
+use recorder::{Recorder, Recording, ReplayOutcome, View};
+use rings::DistanceRings;
+use route::Route;
+use schedule::{FrameRateCap, Pass, Scheduler};
+use selection::{Bounds, Selection};
+use startup::Startup;
+use status::{LayerStatus, LayerStatuses};
+use stroke::LineWidths;
+use surface::Surface;
+use swipe::{Half, Swipe};
+use symbols::GraduatedSymbols;
+use telemetry::{Telemetry, TelemetryEvent};
+use terminator::Terminator;
+use tiles::Tiles;
+use time::TimeWindow;
+use tracks::Tracks;
+use view::{Frame, Orientation, Side};
+use voronoi::Voronoi;
+pub use worker::WorkerCanvas;
+use zoom::{LayerZoomRanges, ZoomRange};
+
+pub use arcs::ArcStyle;
+pub use camera::Easing;
+pub use capability::RenderProfile;
+pub use controls::{ControlScheme, PoleCrossing};
+pub use coords::{LonLat, Rotation, Vec3};
+pub use geojson::GeoJsonStyle;
+pub use markers::MarkerStyle;
+pub use overlay::Corner;
+pub use ping::PingStyle;
+pub use postprocess::PostEffects;
+pub use projection::ProjectionKind;
+pub use quiz::{QuizQuestion, QuizScore};
+pub use stroke::LineWidthPolicy;
+pub use style::GlobeStyle;
+
+// Size of the canvas created at start up; globes created on canvases take their sizes
+#[cfg(feature = "autostart")]
+const CANVAS_WIDTH: u32 = 800;
+#[cfg(feature = "autostart")]
+const CANVAS_HEIGHT: u32 = 800;
+// Pixels scrolled for each line scrolled by the wheel
+const WHEEL_LINE_HEIGHT: f64 = 16.0;
+// Limits of the scale of images exported, relative to the canvas's backing store
+const MIN_EXPORT_SCALE: f64 = 0.25;
+const MAX_EXPORT_SCALE: f64 = 8.0;
+
+const SPHERE_FILL_STYLE: &str = "rgba(159, 159, 255, 1.0)";
+const COAST_FRONT_STROKE_STYLE: &str = "rgba(0, 0, 127, 1.0)";
+const COAST_BACK_STROKE_STYLE: &str = "rgba(0, 0, 0, 0.25)";
+const COAST_FRONT_LINE_WIDTH: f64 = 0.005;
+const COAST_BACK_LINE_WIDTH: f64 = 0.0025;
+
+/// State shared between the event handlers, the animation frame callback and the globe API.
+#[derive(Debug, Default)]
+struct State {
+    animation: Option<CameraAnimation>,
+    attract: Attract,
+    arcs: Arcs,
+    attribution: Attribution,
+    auto_rotate: AutoRotate,
+    /// Width and height of the canvas on the page (pixels), read from the canvas when the
+    /// globe is attached.
+    canvas_size: (u32, u32),
+    cartogram: Cartogram,
+    choropleth: Choropleth,
+    clip: Option<ClipShape>,
+    contours: Contours,
+    control: ControlData,
+    control_settings: ControlSettings,
+    country_labels: CountryLabels,
+    /// Reference systems of the locations loaded into layers.
+    crs: LayerCrs,
+    detail: CoastlineDetail,
+    /// Regions of the canvas changed by small animations, drawn without the rest.
+    dirty: DirtyRegion,
+    emitter: Emitter,
+    extent: Extent,
+    fetcher: Fetcher,
+    filters: LayerFilters,
+    frame_rate: FrameRateCap,
+    geofences: Geofences,
+    gestures: GestureRecognizer,
+    graticule: Graticule,
+    /// Synchronisation of the view with the fragment of the page's URL.
+    hash: HashSync,
+    hexbins: Hexbins,
+    hit_regions: HitRegions,
+    icons: IconAtlas,
+    label_font: LabelFont,
+    land: Land,
+    layer_status: LayerStatuses,
+    layers: LayerStack,
+    legends: Legends,
+    limb: LimbSmoothing,
+    line_widths: LineWidths,
+    locale: Locale,
+    /// Whether dragging and the wheel leave the globe, as for static embeds.
+    locked: bool,
+    magnifier: Magnifier,
+    markers: Markers,
+    moon: Moon,
+    pings: Pings,
+    post_effects: PostEffects,
+    profiler: Profiler,
+    projection: Projection,
+    quiz: Quiz,
+    recorder: Recorder,
+    render: RenderSettings,
+    /// Whether the canvas is sized by the page, the globe following its size.
+    responsive: bool,
+    rings: DistanceRings,
+    route: Route,
+    scheduler: Scheduler,
+    selection: Selection,
+    spikes: spikes::Spikes,
+    startup: Startup,
+    style: GlobeStyle,
+    swipe: Swipe,
+    symbols: GraduatedSymbols,
+    telemetry: Telemetry,
+    terminator: Terminator,
+    tiles: Tiles,
+    time_window: Option<TimeWindow>,
+    /// Whether the window of time changed since the change was last dispatched.
+    time_window_changed: bool,
+    /// Touch action of the canvas last applied.
+    touch_action: &'static str,
+    tracks: Tracks,
+    voronoi: Voronoi,
+    zoom_ranges: LayerZoomRanges,
+    redraw: bool,
+}
+
+impl State {
+    /// Transform from the unit sphere to canvas pixels, zoomed by the view.
+    fn transform(&self) -> DomMatrix {
+        self.control.zoom.apply(&canvas_transform(self.canvas_size))
+    }
+
+    /// Whether the pointer is interacting with the globe, rotating it or dragging a feature.
+    fn interacting(&self) -> bool {
+        self.control.interacting()
+            || self.route.dragging()
+            || self.selection.dragging()
+            || self.swipe.dragging()
+    }
+
+    /// Whether a frame would change nothing, so its work is skipped: no redraw is requested
+    /// or deferred, and no input, animation, timer, download or report is in progress.
+    fn at_rest(&self) -> bool {
+        !self.redraw
+            && !self.scheduler.pending()
+            && !self.dirty.pending()
+            && self.render.settled()
+            && !self.interacting()
+            && !self.control.coasting()
+            && !self.control.following()
+            && self.animation.is_none()
+            && self.auto_rotate.speed.is_none_or(|speed| speed == 0.0)
+            && !self.attract.enabled
+            && !self.tracks.heading_up()
+            && !self.detail.fading(self.control.zoom.factor)
+            && !self.choropleth.playing
+            && !self.cartogram.transitioning()
+            && !self.pings.playing()
+            && !self.quiz.revealing()
+            && !self.terminator.animating(js_sys::Date::now())
+            && !self
+                .moon
+                .animating(js_sys::Date::now(), self.terminator.time.is_none())
+            && self.fetcher.progress().is_none()
+            && !self.tiles.loading()
+            && !self.startup.shown()
+            && !self.gestures.pending()
+            && !self.geofences.pending()
+            && !self.emitter.pending()
+            && !self.hash.pending()
+            && !self.time_window_changed
+            && !self.telemetry.pending()
+            && !self.recorder.replaying()
+    }
+
+    /// Touch action of the canvas: touch screens scroll the page with vertical drags unless
+    /// they rotate the globe or drag features, when all touches are taken by the globe.
+    fn touch_action(&self) -> &'static str {
+        if self.control_settings.scheme.drags_vertically()
+            || self.route.editing
+            || self.selection.enabled
+            || self.swipe.enabled
+        {
+            "none"
+        } else {
+            "pan-y"
+        }
+    }
+
+    /// Record a change to the data of a layer, to be redrawn and reported.
+    fn layer_changed(&mut self, layer: &'static str, action: &'static str) {
+        self.redraw = true;
+        self.telemetry
+            .record(TelemetryEvent::LayerChanged { layer, action });
+    }
+}
+
+thread_local! {
+    // State of the globe created at start up
+    static STATE: RefCell<Option<Rc<RefCell<State>>>> = const { RefCell::new(None) };
+}
+
+/// Handle to a globe, used by JavaScript to add data and change settings.
+#[wasm_bindgen]
+pub struct Globe {
+    state: Rc<RefCell<State>>,
+    /// Listeners and animation of a globe created by the handle, removed with it; handles
+    /// to the globe created at start up have none.
+    _attachment: Option<Attachment>,
+}
+
+#[wasm_bindgen]
+impl Globe {
+    /// Create a globe on a canvas element, or the canvas with an identifier, at the canvas's
+    /// current width and height, with options of the `lat` and `lon` (degrees) at the centre
+    /// of the view and its `zoom`, the `theme` ("light" or "dark"), whether the `graticule`,
+    /// the `countryLabels` and the `dayNight` shading are drawn, whether the globe is
+    /// `interactive`, has `inertia` and `autoRotate`s at a speed (degrees per second), whether
+    /// it is `responsive` to the size of the canvas on the page and whether the view is
+    /// synchronised with the `hash` of the page's URL, and any style replacing the theme's.
+    /// Options may also be given as `data-*` attributes of the canvas, such as
+    /// `data-day-night`, which the options object overrides. The globe stops listening to the
+    /// canvas and drawing when freed with `free()`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        canvas: JsValue,
+        options: Option<js_sys::Object>,
+        style: Option<GlobeStyle>,
+    ) -> Result<Globe, JsValue> {
+        let canvas = match canvas.as_string() {
+            Some(canvas_id) => window()
+                .document()
+                .expect("should have document")
+                .get_element_by_id(&canvas_id)
+                .ok_or_else(|| JsValue::from_str(&format!("no element with id {:?}", canvas_id)))?
+                .dyn_into::<HtmlCanvasElement>()
+                .map_err(|_| {
+                    JsValue::from_str(&format!("element {:?} is not a canvas", canvas_id))
+                })?,
+            None => canvas
+                .dyn_into::<HtmlCanvasElement>()
+                .map_err(|_| JsValue::from_str("expected a canvas or the id of a canvas"))?,
+        };
+
+        let mut config = Config::default();
+        config.set_attributes(&canvas);
+        if let Some(options) = &options {
+            config.set_object(options);
+        }
+        let state = Rc::new(RefCell::new(initial_state(&config, style)?));
+        let attachment = attach(canvas, state.clone())?;
+        Ok(Globe {
+            state,
+            _attachment: Some(attachment),
+        })
+    }
+
+    /// Create a globe in a worker on a canvas transferred to it by a `WorkerCanvas` on the
+    /// page, with the options and style of `new Globe(canvas, options, style)`; the globe
+    /// stops drawing when freed with `free()`. Tiled basemaps are not drawn in workers.
+    pub fn offscreen(
+        canvas: OffscreenCanvas,
+        options: Option<js_sys::Object>,
+        style: Option<GlobeStyle>,
+    ) -> Result<Globe, JsValue> {
+        let mut config = Config::default();
+        if let Some(options) = &options {
+            config.set_object(options);
+        }
+        let state = Rc::new(RefCell::new(initial_state(&config, style)?));
+        let attachment = worker::attach(canvas, state.clone())?;
+        Ok(Globe {
+            state,
+            _attachment: Some(attachment),
+        })
+    }
+
+    /// Describe the datasets embedded in the build, with their names, resolutions,
+    /// versions, feature and point counts, and hashes of their source content.
+    pub fn data_info() -> Result<js_sys::Array, JsValue> {
+        let array = js_sys::Array::new();
+        for dataset in data::DATASETS {
+            array.push(&dataset.to_object()?);
+        }
+        Ok(array)
+    }
+
+    /// Add a spike extruding from the surface at a location, with height proportional
+    /// to the value; returns an identifier that can be used to remove the spike.
+    pub fn add_spike(&self, location: &LonLat, value: f64) -> u32 {
+        let mut state = self.state.borrow_mut();
+        state.layer_changed("spikes", "add");
+        state.spikes.add(*location, value)
+    }
+
+    /// Remove a spike; returns whether the spike existed.
+    pub fn remove_spike(&self, id: u32) -> bool {
+        let mut state = self.state.borrow_mut();
+        if !state.spikes.remove(id) {
+            return false;
+        }
+        state.layer_changed("spikes", "remove");
+        true
+    }
+
+    /// Remove all spikes.
+    pub fn clear_spikes(&self) {
+        let mut state = self.state.borrow_mut();
+        state.layer_changed("spikes", "clear");
+        state.spikes.clear();
+    }
+
+    /// Set the time (milliseconds) of a spike's data, for the window of time shown, or remove
+    /// it when not given so the spike is always shown; returns whether the spike exists.
+    pub fn set_spike_time(&self, id: u32, time: Option<f64>) -> bool {
+        let mut state = self.state.borrow_mut();
+        if !state.spikes.set_time(id, time) {
+            return false;
+        }
+        state.layer_changed("spikes", "time");
+        true
+    }
+
+    /// Show only the time-stamped data of layers (spikes with times, and the positions of
+    /// tracks) between times (milliseconds); a "timewindowchange" event with the window
+    /// as detail is dispatched on the canvas when the window changes. Returns whether the
+    /// window is valid, with the start no later than the end.
+    pub fn set_time_window(&self, start: f64, end: f64) -> bool {
+        let Some(window) = TimeWindow::new(start, end) else {
+            return false;
+        };
+        let mut state = self.state.borrow_mut();
+        state.time_window = Some(window);
+        state.time_window_changed = true;
+        state.redraw = true;
+        true
+    }
+
+    /// Show the time-stamped data of layers at all times.
+    pub fn clear_time_window(&self) {
+        let mut state = self.state.borrow_mut();
+        state.time_window = None;
+        state.time_window_changed = true;
+        state.redraw = true;
+    }
+
+    /// Set the label drawn at the tip of a spike, or remove it when not
+    /// set; returns whether the spike exists.
+    pub fn set_spike_label(&self, id: u32, label: Option<String>) -> bool {
+        let mut state = self.state.borrow_mut();
+        if !state.spikes.set_label(id, label) {
+            return false;
+        }
+        state.layer_changed("spikes", "label");
+        true
+    }
+
+    /// Set the height, in globe radii, of the spike with the largest value; returns whether
+    /// the height is valid, being a finite number no less than 0.
+    pub fn set_spike_max_height(&self, height: f64) -> bool {
+        if !height.is_finite() || height < 0.0 {
+            return false;
+        }
+        let mut state = self.state.borrow_mut();
+        state.redraw = true;
+        state.spikes.max_height = height;
+        true
+    }
+
+    /// Add an arc along the great circle between two locations, as for a flight route, drawn
+    /// on the front of the globe and where it shows through on the back; returns its
+    /// identifier.
+    pub fn add_arc(&self, start: &LonLat, end: &LonLat, style: &ArcStyle) -> u32 {
+        let mut state = self.state.borrow_mut();
+        state.layer_changed("arcs", "add");
+        state.arcs.add(*start, *end, style.clone())
+    }
+
+    /// Remove an arc; returns whether the arc existed.
+    pub fn remove_arc(&self, id: u32) -> bool {
+        let mut state = self.state.borrow_mut();
+        if !state.arcs.remove(id) {
+            return false;
+        }
+        state.layer_changed("arcs", "remove");
+        true
+    }
+
+    /// Remove all arcs.
+    pub fn clear_arcs(&self) {
+        let mut state = self.state.borrow_mut();
+        state.layer_changed("arcs", "clear");
+        state.arcs.clear();
+    }
+
+    /// Add a marker at a location, drawn as a dot or an icon that is dimmed or hidden when
+    /// rotated to the back of the globe; returns its identifier.
+    pub fn add_marker(&self, location: &LonLat, style: &MarkerStyle) -> u32 {
+        let mut state = self.state.borrow_mut();
+        state.layer_changed("markers", "add");
+        state.markers.add(*location, style.clone())
+    }
+
+    /// Remove a marker; returns whether the marker existed.
+    pub fn remove_marker(&self, id: u32) -> bool {
+        let mut state = self.state.borrow_mut();
+        if !state.markers.remove(id) {
+            return false;
+        }
+        state.layer_changed("markers", "remove");
+        true
+    }
+
+    /// Remove all markers.
+    pub fn clear_markers(&self) {
+        let mut state = self.state.borrow_mut();
+        state.layer_changed("markers", "clear");
+        state.markers.clear();
+    }
+
+    /// Add an icon for markers by name from a loaded image, replacing any icon of the same
+    /// name; icons are packed into an atlas, so many markers draw from a single image.
+    pub fn add_marker_icon(&self, name: &str, image: &HtmlImageElement) -> Result<(), JsValue> {
+        let mut state = self.state.borrow_mut();
+        state.icons.add(name, image)?;
+        state.redraw = true;
+        Ok(())
+    }
+
+    /// Remove all icons for markers, the markers using them being drawn as dots.
+    pub fn clear_marker_icons(&self) {
+        let mut state = self.state.borrow_mut();
+        state.icons.clear();
+        state.redraw = true;
+    }
+
+    /// Add a graduated symbol at a location, drawn as a circle with area proportional to
+    /// the value and filled by value; returns an identifier that can be used to remove it.
+    pub fn add_symbol(&self, location: &LonLat, value: f64) -> u32 {
+        let mut state = self.state.borrow_mut();
+        state.layer_changed("symbols", "add");
+        state.symbols.add(*location, value)
+    }
+
+    /// Remove a graduated symbol; returns whether the symbol existed.
+    pub fn remove_symbol(&self, id: u32) -> bool {
+        let mut state = self.state.borrow_mut();
+        if !state.symbols.remove(id) {
+            return false;
+        }
+        state.layer_changed("symbols", "remove");
+        true
+    }
+
+    /// Remove all graduated symbols.
+    pub fn clear_symbols(&self) {
+        let mut state = self.state.borrow_mut();
+        state.layer_changed("symbols", "clear");
+        state.symbols.clear();
+    }
+
+    /// Set the radius, in canvas pixels, of the graduated symbol with the largest value.
+    pub fn set_symbol_max_radius(&self, radius: f64) {
+        let mut state = self.state.borrow_mut();
+        state.redraw = true;
+        state.symbols.max_radius = radius;
+    }
+
+    /// Add a position of a moving entity at a time (milliseconds), creating its track if new;
+    /// the entity is drawn at its latest position with a fading trail of its recent positions.
+    pub fn add_track_position(&self, id: &str, time: f64, location: &LonLat) {
+        let mut state = self.state.borrow_mut();
+        if state.tracks.add(id, time, *location) {
+            state.geofences.update(id, time, *location);
+        }
+        state.layer_changed("tracks", "add");
+    }
+
+    /// Remove the track of an entity; returns whether the track existed.
+    pub fn remove_track(&self, id: &str) -> bool {
+        let mut state = self.state.borrow_mut();
+        if !state.tracks.remove(id) {
+            return false;
+        }
+        state.geofences.forget(Some(id));
+        state.layer_changed("tracks", "remove");
+        true
+    }
+
+    /// Remove all tracks.
+    pub fn clear_tracks(&self) {
+        let mut state = self.state.borrow_mut();
+        state.layer_changed("tracks", "clear");
+        state.geofences.forget(None);
+        state.tracks.clear();
+    }
+
+    /// Add a geofence, replacing any with the same identifier, as the latitude and longitude
+    /// (degrees) of each vertex of its polygon in turn, its edges along great circles; returns
+    /// whether the polygon has at least three vertices. As tracked entities move into or out
+    /// of the fence, "geofenceenter" and "geofenceexit" events are dispatched on the canvas,
+    /// with the fence, entity, time and location of the crossing position as detail.
+    pub fn add_geofence(&self, coordinates: &[f64], id: &str) -> bool {
+        self.state.borrow_mut().geofences.add(id, coordinates)
+    }
+
+    /// Remove a geofence; returns whether the fence existed.
+    pub fn remove_geofence(&self, id: &str) -> bool {
+        self.state.borrow_mut().geofences.remove(id)
+    }
+
+    /// Remove all geofences.
+    pub fn clear_geofences(&self) {
+        self.state.borrow_mut().geofences.clear();
+    }
+
+    /// Keep the latest position of the track of an entity at the centre of the view with its
+    /// heading pointing up, turning the globe as the entity moves, or stop with `None`;
+    /// returns whether the track exists. The heading is taken from the track's last move.
+    pub fn set_heading_up(&self, id: Option<String>) -> bool {
+        let mut state = self.state.borrow_mut();
+        state.redraw = true;
+        state.tracks.set_heading_up(id.as_deref())
+    }
+
+    /// Set the time (milliseconds) of history shown as the trail of each track; older
+    /// positions are pruned as new positions are added.
+    pub fn set_trail_length(&self, milliseconds: f64) {
+        let mut state = self.state.borrow_mut();
+        state.tracks.trail_length = milliseconds;
+        state.redraw = true;
+    }
+
+    /// Set whether the route is being edited: tapping the globe adds a waypoint to
+    /// the end of the route, and waypoints can be dragged to move them.
+    pub fn set_route_editing(&self, editing: bool) {
+        let mut state = self.state.borrow_mut();
+        state.route.editing = editing;
+        if !editing {
+            state.route.drag_end();
+        }
+    }
+
+    /// Remove a waypoint of the route, by index; returns whether the waypoint existed.
+    pub fn remove_route_waypoint(&self, index: u32) -> bool {
+        let mut state = self.state.borrow_mut();
+        if !state.route.remove(index as usize) {
+            return false;
+        }
+        state.layer_changed("route", "remove");
+        true
+    }
+
+    /// Remove all waypoints of the route.
+    pub fn clear_route(&self) {
+        let mut state = self.state.borrow_mut();
+        state.layer_changed("route", "clear");
+        state.route.clear();
+    }
+
+    /// Distances (kilometres) of each leg of the route, along great circles.
+    pub fn route_leg_distances(&self) -> Vec<f64> {
+        self.state.borrow().route.leg_distances()
+    }
+
+    /// Locations where the route intersects itself; crossings are marked on the globe while
+    /// the route is edited.
+    pub fn route_crossings(&self) -> Vec<LonLat> {
+        self.state.borrow().route.crossings()
+    }
+
+    /// Export the route as GeoJSON.
+    pub fn route_geojson(&self) -> String {
+        self.state.borrow().route.to_geojson()
+    }
+
+    /// Play a short animation of rings expanding from a location, to draw attention to it.
+    pub fn ping(&self, location: &LonLat, style: &PingStyle) {
+        let mut state = self.state.borrow_mut();
+        state.pings.add(*location, style.clone(), now());
+        state.redraw = true;
+    }
+
+    /// Set whether dragging selects a region between the latitudes and longitudes dragged
+    /// from and to, rather than rotating the globe; a "select" event with the bounds of the
+    /// region is dispatched on the canvas when the drag ends.
+    pub fn set_selection_mode(&self, enabled: bool) {
+        let mut state = self.state.borrow_mut();
+        state.selection.enabled = enabled;
+        if !enabled {
+            state.selection.drag_end();
+        }
+    }
+
+    /// Bounds (degrees) of the selected region, as an object with west, south, east and
+    /// north properties; the west bound is greater for regions crossing the antimeridian.
+    pub fn selection_bounds(&self) -> Result<JsValue, JsValue> {
+        match self.state.borrow().selection.bounds() {
+            Some(bounds) => bounds.to_object(),
+            None => Ok(JsValue::NULL),
+        }
+    }
+
+    /// Remove the selected region.
+    pub fn clear_selection(&self) {
+        let mut state = self.state.borrow_mut();
+        state.selection.clear();
+        state.layer_changed("selection", "clear");
+    }
+
+    /// Outline the extent between its south-west and north-east corners (eastwards from the
+    /// west), such as that of a detailed map shown elsewhere on the page, as a locator frame
+    /// turning with the globe; returns whether the extent is valid.
+    pub fn show_extent(&self, south_west: &LonLat, north_east: &LonLat) -> bool {
+        let mut state = self.state.borrow_mut();
+        let shown = state.extent.show(*south_west, *north_east);
+        if shown {
+            state.layer_changed("extent", "show");
+        }
+        shown
+    }
+
+    /// Remove the outline of the extent.
+    pub fn hide_extent(&self) {
+        let mut state = self.state.borrow_mut();
+        state.extent.hide();
+        state.layer_changed("extent", "hide");
+    }
+
+    /// Set a time series of values for the choropleth, given as the values of every country
+    /// (by index, with `NaN` for no value) for each time step in turn; returns whether the
+    /// number of values is a multiple of the number of countries.
+    pub fn set_choropleth_series(&self, values: &[f64]) -> bool {
+        let mut state = self.state.borrow_mut();
+        if !state.choropleth.set_series(values) {
+            return false;
+        }
+        state.layer_changed("choropleth", "set");
+        true
+    }
+
+    /// Play the choropleth time series, from the current position.
+    pub fn play_choropleth(&self) {
+        let mut state = self.state.borrow_mut();
+        if state.choropleth.position() >= state.choropleth.steps().saturating_sub(1) as f64 {
+            state.choropleth.seek(0.0);
+        }
+        state.choropleth.playing = true;
+    }
+
+    /// Pause the choropleth time series.
+    pub fn pause_choropleth(&self) {
+        self.state.borrow_mut().choropleth.playing = false;
+    }
+
+    /// Move to a position, in time steps, in the choropleth time series; fractional
+    /// positions interpolate between the colors of the time steps either side.
+    pub fn seek_choropleth(&self, position: f64) {
+        let mut state = self.state.borrow_mut();
+        state.choropleth.seek(position);
+        state.redraw = true;
+    }
+
+    /// Position, in time steps, in the choropleth time series.
+    pub fn choropleth_position(&self) -> f64 {
+        self.state.borrow().choropleth.position()
+    }
+
+    /// Set the time steps of the choropleth played per second.
+    pub fn set_choropleth_speed(&self, steps_per_second: f64) {
+        self.state.borrow_mut().choropleth.speed = steps_per_second;
+    }
+
+    /// Set the value of a country, by index, in the experimental cartogram, which scales
+    /// countries with values about their centroids so their areas are proportional to
+    /// their values; or remove it when not set. Returns whether the country exists.
+    pub fn set_cartogram_value(&self, country_id: u32, value: Option<f64>) -> bool {
+        let mut state = self.state.borrow_mut();
+        state.layer_changed("cartogram", "set");
+        state.cartogram.set_value(country_id as usize, value, now())
+    }
+
+    /// Remove the values of all countries in the cartogram.
+    pub fn clear_cartogram(&self) {
+        let mut state = self.state.borrow_mut();
+        state.layer_changed("cartogram", "clear");
+        state.cartogram.clear(now());
+    }
+
+    /// Partition the globe into the regions nearest to each of a set of sites, such as the
+    /// service areas of facilities, given as the latitude and longitude (degrees) of each
+    /// site in turn, or in the layer's reference system; returns whether the number of
+    /// values is even.
+    pub fn set_voronoi_sites(&self, coordinates: &[f64]) -> bool {
+        let mut state = self.state.borrow_mut();
+        let coordinates = state.crs.get("voronoi").transform(coordinates);
+        let set = state.voronoi.set_sites(&coordinates);
+        if set {
+            state.layer_changed("voronoi", "set");
+        }
+        set
+    }
+
+    /// Set the coordinate reference system of the locations loaded into a layer ("hexbins"
+    /// or "voronoi"), by an identifier such as "EPSG:3857" or the well-known text of a
+    /// shapefile's `.prj` file; locations in projected systems are given as the easting and
+    /// northing (metres) of each in turn, and are transformed as they are loaded.
+    pub fn set_layer_crs(&self, layer_id: &str, crs: &str) -> Result<(), JsValue> {
+        let crs = Crs::parse(crs).map_err(|error| JsValue::from_str(&error))?;
+        self.state
+            .borrow_mut()
+            .crs
+            .set(layer_id, crs)
+            .map_err(|error| JsValue::from_str(&error))
+    }
+
+    /// Transform locations given by pairs of coordinates in a coordinate reference system,
+    /// as for `set_layer_crs`, to the latitude and longitude (degrees) of each in turn, for
+    /// the other methods taking locations.
+    pub fn transform_coordinates(crs: &str, coordinates: &[f64]) -> Result<Vec<f64>, JsValue> {
+        let crs = Crs::parse(crs).map_err(|error| JsValue::from_str(&error))?;
+        Ok(crs.transform(coordinates).into_owned())
+    }
+
+    /// Index of the Voronoi site nearest to a location, or `None` without sites.
+    pub fn nearest_voronoi_site(&self, location: &LonLat) -> Option<u32> {
+        let state = self.state.borrow();
+        state
+            .voronoi
+            .nearest_site(*location)
+            .map(|index| index as u32)
+    }
+
+    /// Remove the Voronoi sites.
+    pub fn clear_voronoi(&self) {
+        let mut state = self.state.borrow_mut();
+        state.voronoi.clear();
+        state.layer_changed("voronoi", "clear");
+    }
+
+    /// Aggregate points into hexagonal bins filled by color according to the count of points
+    /// in each, or the total of their values when given; points are given as the latitude and
+    /// longitude (degrees) of each in turn, or in the layer's reference system. Returns
+    /// whether the numbers of coordinates and values match.
+    pub fn set_hexbin_points(&self, coordinates: &[f64], values: Option<Vec<f64>>) -> bool {
+        let mut state = self.state.borrow_mut();
+        let coordinates = state.crs.get("hexbins").transform(coordinates);
+        let set = state.hexbins.set_points(&coordinates, values.as_deref());
+        if set {
+            state.layer_changed("hexbins", "set");
+        }
+        set
+    }
+
+    /// Set the resolution of the hexagonal bins, as the number of parts each edge of the
+    /// underlying icosahedron is divided into (1 to 64); higher resolutions give smaller bins.
+    pub fn set_hexbin_resolution(&self, resolution: u32) {
+        let mut state = self.state.borrow_mut();
+        state.hexbins.set_resolution(resolution);
+        state.redraw = true;
+    }
+
+    /// Remove the points aggregated into hexagonal bins.
+    pub fn clear_hexbins(&self) {
+        let mut state = self.state.borrow_mut();
+        state.hexbins.clear();
+        state.layer_changed("hexbins", "clear");
+    }
+
+    /// Trace contour lines, labelled with their levels, through gridded values such as
+    /// temperature or pressure; values are given by row from north to south, each from west
+    /// to east, with the bounds (degrees) of the grid at its outer points. Returns whether
+    /// the number of values matches the grid.
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_contour_grid(
+        &self,
+        values: &[f64],
+        columns: u32,
+        rows: u32,
+        west: f64,
+        south: f64,
+        east: f64,
+        north: f64,
+    ) -> bool {
+        let mut state = self.state.borrow_mut();
+        let set = state.contours.set_grid(
+            values,
+            columns as usize,
+            rows as usize,
+            west,
+            south,
+            east,
+            north,
+        );
+        if set {
+            state.layer_changed("contours", "set");
+        }
+        set
+    }
+
+    /// Set the levels of the contour lines, spaced by an interval from a base level.
+    pub fn set_contour_levels(&self, interval: f64, base: f64) {
+        let mut state = self.state.borrow_mut();
+        state.contours.set_levels(interval, base);
+        state.redraw = true;
+    }
+
+    /// Value of the data of a raster layer at a location, rather than the color it
+    /// is drawn with, for tooltips and analyses: the gridded values of "contours",
+    /// interpolated between the grid points. Returns `None` where the layer has no value, and
+    /// throws when the layer has no values to sample.
+    pub fn sample(&self, layer_id: &str, location: &LonLat) -> Result<Option<f64>, JsValue> {
+        let state = self.state.borrow();
+        match layer_id {
+            "contours" => Ok(state.contours.sample(*location)),
+            _ => Err(JsValue::from_str(&format!(
+                "layer {:?} has no values to sample",
+                layer_id
+            ))),
+        }
+    }
+
+    /// Remove the gridded values and their contour lines.
+    pub fn clear_contours(&self) {
+        let mut state = self.state.borrow_mut();
+        state.contours.clear();
+        state.layer_changed("contours", "clear");
+    }
+
+    /// Show bands of distance from an origin, each of a width (kilometres).
+    pub fn set_distance_rings(&self, origin: &LonLat, interval: f64, count: u32) {
+        let mut state = self.state.borrow_mut();
+        state.rings.origin = Some(*origin);
+        state.rings.interval = interval;
+        state.rings.count = count;
+        state.layer_changed("rings", "set");
+    }
+
+    /// Remove the bands of distance.
+    pub fn clear_distance_rings(&self) {
+        let mut state = self.state.borrow_mut();
+        state.rings.origin = None;
+        state.layer_changed("rings", "clear");
+    }
+
+    /// Add the attribution of a source of data shown on the globe, such as a tile server.
+    pub fn add_attribution(&self, source: &str) {
+        let mut state = self.state.borrow_mut();
+        state.attribution.add(source);
+        state.redraw = true;
+    }
+
+    /// Remove the attribution of a source of data; returns whether it was present.
+    pub fn remove_attribution(&self, source: &str) -> bool {
+        let mut state = self.state.borrow_mut();
+        state.redraw = true;
+        state.attribution.remove(source)
+    }
+
+    /// Set whether the legends of the layers are shown, and the corner of the canvas they are
+    /// shown in.
+    pub fn set_legend_display(&self, visible: bool, corner: Corner) {
+        let mut state = self.state.borrow_mut();
+        state.legends.visible = visible;
+        state.legends.corner = corner;
+        state.redraw = true;
+    }
+
+    /// Set an element of the page the legends are written into as HTML instead of being drawn
+    /// on the canvas, or `None` to draw them on the canvas.
+    pub fn set_legend_element(&self, element: Option<Element>) {
+        let mut state = self.state.borrow_mut();
+        state.legends.set_element(element);
+        state.redraw = true;
+    }
+
+    /// Set the profile of the quality of rendering, or `None` for the profile selected by
+    /// probing the capabilities of the device.
+    pub fn set_render_profile(&self, profile: Option<RenderProfile>) {
+        let mut state = self.state.borrow_mut();
+        state.render.overridden = profile;
+        state.redraw = true;
+    }
+
+    /// Render the current view to a PNG image, drawing every pass at once onto a canvas at a
+    /// scale (by default 1, from 0.25 to 8) of the resolution of the globe's canvas, for
+    /// higher resolution images; returns a promise of the image as a `Blob`. Globes in
+    /// workers cannot be exported, and icons from other origins without CORS prevent it.
+    pub fn to_png(&self, scale: Option<f64>) -> Result<js_sys::Promise, JsValue> {
+        let canvas = {
+            let mut state = self.state.borrow_mut();
+            let scale = scale
+                .unwrap_or(1.0)
+                .clamp(MIN_EXPORT_SCALE, MAX_EXPORT_SCALE);
+            let pixel_ratio = state.render.pixel_ratio() * scale;
+            snapshot(&mut state, pixel_ratio)?
+        };
+        Ok(js_sys::Promise::new(&mut |resolve, reject| {
+            let rejected = reject.clone();
+            let callback = Closure::once_into_js(move |blob: JsValue| {
+                // Browsers give null when the image cannot be encoded
+                let _ = if blob.is_null() {
+                    reject.call1(&JsValue::NULL, &"the image could not be encoded".into())
+                } else {
+                    resolve.call1(&JsValue::NULL, &blob)
+                };
+            });
+            if let Err(error) = canvas.to_blob(callback.unchecked_ref()) {
+                let _ = rejected.call1(&JsValue::NULL, &error);
+            }
+        }))
+    }
+
+    /// Render the current view to an SVG document of the coastlines, graticule and markers,
+    /// projected with the current rotation and zoom at the size of the canvas, for figures
+    /// that stay sharp when printed. Markers with icons are written as their dots.
+    pub fn to_svg(&self) -> String {
+        let state = self.state.borrow();
+        let transform = state.transform();
+        let frame = Frame {
+            orientation: state.control.orientation,
+            projection: state.projection,
+            base_scale: std::cmp::min(state.canvas_size.0, state.canvas_size.1) as f64 / 2.0,
+            scale: transform.a(),
+            transform,
+            pixel_ratio: 1.0,
+            geometry_stride: 1,
+            line_widths: &state.line_widths,
+            filters: &state.filters,
+            zoom_ranges: &state.zoom_ranges,
+            locale: &state.locale,
+            style: &state.style,
+            time_window: state.time_window,
+            cull_back: state.render.back_culling,
+        };
+        // The level of detail mostly drawn, while cross-fading between them
+        let (coastlines, _) = state
+            .detail
+            .levels()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .expect("should draw a level of detail");
+        svg::render(
+            &frame,
+            state.canvas_size,
+            coastlines,
+            &state.graticule,
+            &state.markers,
+        )
+    }
+
+    /// Profile of the quality of rendering in use.
+    pub fn render_profile(&self) -> RenderProfile {
+        self.state.borrow().render.profile()
+    }
+
+    /// Set whether frames are redrawn supersampled, with crisper lines, while the globe is at rest.
+    pub fn set_supersampling(&self, enabled: bool) {
+        let mut state = self.state.borrow_mut();
+        state.render.supersampling = enabled;
+        state.redraw = true;
+    }
+
+    /// Set the samples along each side of a pixel, from 2 to 8, with which the pixels along
+    /// the edge of the sphere are redrawn from the coverage of the disc, smoothing the
+    /// silhouette, or `None` for the edge not to be refined.
+    pub fn set_limb_smoothing(&self, samples: Option<u32>) {
+        let mut state = self.state.borrow_mut();
+        state.limb.set_samples(samples);
+        state.redraw = true;
+    }
+
+    /// Start recording the input on the globe and the views drawn, for replaying with
+    /// `replay`, discarding any log being recorded.
+    pub fn start_recording(&self) {
+        let mut state = self.state.borrow_mut();
+        let view = View {
+            orientation: state.control.orientation,
+            zoom: state.control.zoom.factor,
+        };
+        let canvas_size = state.canvas_size;
+        state.recorder.start(now(), view, canvas_size);
+    }
+
+    /// Stop recording, returning the log recorded as JSON, or `None` when not recording.
+    pub fn stop_recording(&self) -> Result<Option<String>, JsValue> {
+        let recording = self.state.borrow_mut().recorder.stop();
+        recording.map(|recording| recording.to_json()).transpose()
+    }
+
+    /// Replay a log recorded with `stop_recording`, restoring the view it started from and
+    /// feeding its input to the globe frame by frame on the recorded clock. A "replayend"
+    /// event is dispatched on the canvas with the number of frames replayed and the index of
+    /// the first whose view differed from the recording, or null when all matched.
+    pub fn replay(&self, log: &str) -> Result<(), JsValue> {
+        let recording = Recording::from_json(log)?;
+        let mut state = self.state.borrow_mut();
+        state.animation = None;
+        state.control.stop();
+        state.control.orientation = recording.view.orientation;
+        state.control.zoom.set(recording.view.zoom);
+        if recording.canvas_size != state.canvas_size {
+            state.canvas_size = recording.canvas_size;
+            state.render.resize();
+        }
+        state.redraw = true;
+        state.recorder.replay(recording, now());
+        Ok(())
+    }
+
+    /// Set whether segments of lines on the back of the globe are skipped rather than stroked,
+    /// roughly halving the strokes drawn; the style's `culled_back_opacity` still draws the
+    /// coastlines on the back, dimmed, in a single stroke.
+    pub fn set_back_culling(&self, enabled: bool) {
+        let mut state = self.state.borrow_mut();
+        state.render.back_culling = enabled;
+        state.redraw = true;
+    }
+
+    /// Set the title of the legend of a layer ("choropleth", "hexbins", "spikes" or "symbols"); returns whether
+    /// the layer has a legend with a title that can be set.
+    pub fn set_legend_title(&self, layer: &str, title: &str) -> bool {
+        let mut state = self.state.borrow_mut();
+        match layer {
+            "choropleth" => state.choropleth.legend_title = title.to_string(),
+            "hexbins" => state.hexbins.legend_title = title.to_string(),
+            "spikes" => state.spikes.legend_title = title.to_string(),
+            "symbols" => state.symbols.legend_title = title.to_string(),
+            _ => return false,
+        }
+        state.redraw = true;
+        true
+    }
+
+    /// Set whether the attribution is shown, and the corner of the canvas it is shown in.
+    pub fn set_attribution_display(&self, visible: bool, corner: Corner) {
+        let mut state = self.state.borrow_mut();
+        state.attribution.visible = visible;
+        state.attribution.corner = corner;
+        state.redraw = true;
+    }
+
+    /// Set the post-processing effects applied to each composed frame.
+    pub fn set_post_effects(&self, effects: &PostEffects) {
+        let mut state = self.state.borrow_mut();
+        state.post_effects = *effects;
+        state.redraw = true;
+    }
+
+    /// Set the time (milliseconds) allowed for drawing in each animation frame; low
+    /// priority passes over budget are deferred to the next animation frame. Throws when the
+    /// budget is not positive.
+    pub fn set_frame_budget(&self, budget: f64) -> Result<(), JsValue> {
+        if budget.is_nan() || budget <= 0.0 {
+            return Err(JsValue::from_str(&format!(
+                "frame budget {} is not positive",
+                budget
+            )));
+        }
+        self.state.borrow_mut().scheduler.budget = budget;
+        Ok(())
+    }
+
+    /// Clip everything drawn to a circle (canvas pixels), for insetting the globe into
+    /// non-rectangular elements of the page.
+    pub fn set_clip_circle(&self, x: f64, y: f64, radius: f64) {
+        let mut state = self.state.borrow_mut();
+        state.clip = Some(ClipShape::Circle { x, y, radius });
+        state.redraw = true;
+    }
+
+    /// Clip everything drawn to a rectangle (canvas pixels, from its top left corner)
+    /// with corners rounded to a radius.
+    pub fn set_clip_rounded_rect(&self, x: f64, y: f64, width: f64, height: f64, radius: f64) {
+        let mut state = self.state.borrow_mut();
+        state.clip = Some(ClipShape::RoundedRect {
+            x,
+            y,
+            width,
+            height,
+            radius,
+        });
+        state.redraw = true;
+    }
+
+    /// Clip everything drawn to a path (canvas pixels).
+    pub fn set_clip_path(&self, path: Path2d) {
+        let mut state = self.state.borrow_mut();
+        state.clip = Some(ClipShape::Path(path));
+        state.redraw = true;
+    }
+
+    /// Remove any clipping of everything drawn.
+    pub fn clear_clip(&self) {
+        let mut state = self.state.borrow_mut();
+        state.clip = None;
+        state.redraw = true;
+    }
+
+    /// Set the number of animation frames per second at most drawn, or `None` to draw every
+    /// animation frame; the cap is lifted while the pointer interacts with the globe.
+    pub fn set_max_fps(&self, fps: Option<f64>) {
+        self.state.borrow_mut().frame_rate.max_fps = fps;
+    }
+
+    /// Set whether debugging aids are enabled, including marking the phases of
+    /// rendering on the performance timeline for browser devtools.
+    pub fn set_debug(&self, enabled: bool) {
+        self.state.borrow_mut().profiler.enabled = enabled;
+    }
+
+    /// Set a callback reporting significant interactions, called with a payload
+    /// object whose `type` property names the interaction; or remove it when not set.
+    pub fn set_telemetry_callback(&self, callback: Option<js_sys::Function>) {
+        self.state.borrow_mut().telemetry.callback = callback;
+    }
+
+    /// Set the degrees of rotation per pixel dragged; when not
+    /// set the surface under the pointer follows the pointer.
+    /// Throws when the sensitivity is negative or not finite.
+    pub fn set_rotation_sensitivity(&self, degrees_per_pixel: Option<f64>) -> Result<(), JsValue> {
+        if let Some(sensitivity) = degrees_per_pixel {
+            if !sensitivity.is_finite() || sensitivity < 0.0 {
+                return Err(JsValue::from_str(&format!(
+                    "rotation sensitivity {} is not a finite, non-negative number",
+                    sensitivity
+                )));
+            }
+        }
+        self.state.borrow_mut().control_settings.sensitivity = degrees_per_pixel;
+        Ok(())
+    }
+
+    /// Set whether horizontal and vertical drags rotate the globe in the opposite direction.
+    pub fn set_invert_axes(&self, invert_x: bool, invert_y: bool) {
+        let settings = &mut self.state.borrow_mut().control_settings;
+        settings.invert_x = invert_x;
+        settings.invert_y = invert_y;
+    }
+
+    /// Set the scheme mapping pointer drags to rotation of the globe.
+    pub fn set_control_scheme(&self, scheme: ControlScheme) {
+        self.state.borrow_mut().control_settings.scheme = scheme;
+    }
+
+    /// Set whether drags of the trackball scheme stop tilting the globe at the poles or turn
+    /// it over them.
+    pub fn set_pole_crossing(&self, pole_crossing: PoleCrossing) {
+        self.state.borrow_mut().control_settings.pole_crossing = pole_crossing;
+    }
+
+    /// Set whether a lens magnifies the globe under the pointer, drawing that region again at a
+    /// larger scale in a circular inset following the pointer.
+    pub fn set_magnifier(&self, enabled: bool) {
+        let mut state = self.state.borrow_mut();
+        state.magnifier.enabled = enabled;
+        state.redraw = true;
+    }
+
+    /// Set the radius (canvas pixels) of the magnifier's lens and the factor it magnifies by.
+    pub fn set_magnifier_size(&self, radius: f64, magnification: f64) {
+        let mut state = self.state.borrow_mut();
+        state.magnifier.set_size(radius, magnification);
+        state.redraw = true;
+    }
+
+    /// Set whether the night side of the globe is shaded, bounded by the terminator where the
+    /// sun is rising or setting; it follows the clock unless a time is set.
+    pub fn set_day_night(&self, enabled: bool) {
+        let mut state = self.state.borrow_mut();
+        state.terminator.enabled = enabled;
+        state.redraw = true;
+    }
+
+    /// Set the time (milliseconds since the Unix epoch, as from `Date.getTime`) the night side
+    /// is shaded at, freezing it there, or `None` for it to follow the clock.
+    pub fn set_day_night_time(&self, time: Option<f64>) {
+        let mut state = self.state.borrow_mut();
+        state.terminator.time = time.filter(|time| time.is_finite());
+        state.redraw = true;
+    }
+
+    /// Freeze the shading of the night side at the current time, or resume following the clock.
+    pub fn freeze_day_night(&self, frozen: bool) {
+        let mut state = self.state.borrow_mut();
+        state.terminator.time = frozen.then(js_sys::Date::now);
+        state.redraw = true;
+    }
+
+    /// Set whether the phase of the moon is shown in a widget, and the corner of the canvas it
+    /// is shown in; it follows the time the night side is shaded at, or else the clock.
+    pub fn set_moon_widget(&self, visible: bool, corner: Corner) {
+        let mut state = self.state.borrow_mut();
+        state.moon.widget = visible;
+        state.moon.corner = corner;
+        state.redraw = true;
+    }
+
+    /// Set whether the sub-lunar point, where the moon is overhead, is marked on the globe, with
+    /// the night side's layer; it follows the time the night side is shaded at, or else the
+    /// clock.
+    pub fn set_sublunar_point(&self, visible: bool) {
+        let mut state = self.state.borrow_mut();
+        state.moon.sublunar = visible;
+        state.redraw = true;
+    }
+
+    /// Phase of the moon at the time the night side is shaded at, or else now, as the fraction
+    /// of its disc lit, from 0 at new moon to 1 at full moon, and 1 while it is waxing or 0
+    /// while it is waning.
+    pub fn moon_phase(&self) -> Vec<f64> {
+        let time = self
+            .state
+            .borrow()
+            .terminator
+            .time
+            .unwrap_or_else(js_sys::Date::now);
+        let (illuminated, waxing) = astro::phase(time);
+        vec![illuminated, if waxing { 1.0 } else { 0.0 }]
+    }
+
+    /// Location (degrees) of the sub-lunar point at the time the night side is shaded at, or
+    /// else now.
+    pub fn sublunar_point(&self) -> LonLat {
+        let time = self
+            .state
+            .borrow()
+            .terminator
+            .time
+            .unwrap_or_else(js_sys::Date::now);
+        astro::sublunar_point(time)
+    }
+
+    /// Set the CSS color the night side is shaded with, translucent to show the globe beneath.
+    pub fn set_night_fill_style(&self, fill_style: &str) {
+        let mut state = self.state.borrow_mut();
+        state.terminator.fill_style = fill_style.to_string();
+        state.redraw = true;
+    }
+
+    /// Set whether a draggable divider splits the canvas to compare two sets of layers, set
+    /// by `set_swipe_layers`, one drawn to each side of it.
+    pub fn set_swipe(&self, enabled: bool) {
+        let mut state = self.state.borrow_mut();
+        state.swipe.enabled = enabled;
+        if !enabled {
+            state.swipe.drag_end();
+        }
+        state.redraw = true;
+    }
+
+    /// Set the layers (such as "choropleth" or "hexbins", for datasets of different years)
+    /// drawn only to the left and only to the right of the swipe's divider; other layers are
+    /// drawn on both sides. Throws when a layer is unknown.
+    pub fn set_swipe_layers(&self, left: Vec<String>, right: Vec<String>) -> Result<(), JsValue> {
+        let mut state = self.state.borrow_mut();
+        state
+            .swipe
+            .set_layers(&left, &right)
+            .map_err(|error| JsValue::from_str(&error))?;
+        state.redraw = true;
+        Ok(())
+    }
+
+    /// Move the swipe's divider, as a fraction of the width of the canvas from its left.
+    pub fn set_swipe_position(&self, fraction: f64) {
+        let mut state = self.state.borrow_mut();
+        state.swipe.set_position(fraction);
+        state.redraw = true;
+    }
+
+    /// Position of the swipe's divider, as a fraction of the width of the canvas.
+    pub fn swipe_position(&self) -> f64 {
+        self.state.borrow().swipe.position()
+    }
+
+    /// Set the speed (degrees per second) at which the globe turns about its polar axis once
+    /// left idle, positive as the Earth turns, or `None` for it not to turn.
+    pub fn set_auto_rotate(&self, speed: Option<f64>) {
+        self.state.borrow_mut().auto_rotate.speed = speed;
+    }
+
+    /// Set the time (seconds) the globe is left idle before it turns.
+    pub fn set_auto_rotate_delay(&self, seconds: f64) {
+        self.state.borrow_mut().auto_rotate.delay = seconds.max(0.0) * 1000.0;
+    }
+
+    /// Set whether the globe plays an attract mode for kiosks once left idle for a time
+    /// (seconds): a slow tour turning the globe and flying between the stops set by
+    /// `set_attract_stops`, stopped as soon as the globe is touched.
+    pub fn set_attract_mode(&self, enabled: bool, idle_seconds: f64) {
+        let mut state = self.state.borrow_mut();
+        state.attract.enabled = enabled;
+        state.attract.delay = idle_seconds.max(0.0) * 1000.0;
+    }
+
+    /// Set the stops of the attract mode's tour, given as the latitude and longitude
+    /// (degrees) of each stop in turn, with the speed (degrees per second) the globe turns
+    /// at and the time (seconds) spent turning before flying to the next stop; returns
+    /// whether the number of values is even.
+    pub fn set_attract_stops(&self, coordinates: &[f64], speed: f64, dwell_seconds: f64) -> bool {
+        let mut state = self.state.borrow_mut();
+        state.attract.speed = speed;
+        state.attract.dwell = dwell_seconds.max(0.0) * 1000.0;
+        state.attract.set_stops(coordinates)
+    }
+
+    /// Set whether the globe keeps rotating, slowing to a stop, when released mid-drag.
+    pub fn set_inertia(&self, enabled: bool) {
+        self.state.borrow_mut().control_settings.inertia = enabled;
+    }
+
+    /// Set the friction slowing the globe once released, as the rate (per second) at which
+    /// its velocity decays exponentially; higher values stop it sooner.
+    pub fn set_inertia_friction(&self, friction: f64) {
+        self.state.borrow_mut().control_settings.friction = friction.max(0.0);
+    }
+
+    /// Set the time constant (milliseconds, by default 30) over which the rotation of a drag
+    /// follows the pointer, so sparse pointer samples turn the globe smoothly over the frames
+    /// between them; or apply each sample at once when not set.
+    pub fn set_drag_smoothing(&self, time_constant_ms: Option<f64>) {
+        self.state.borrow_mut().control_settings.smoothing = time_constant_ms;
+    }
+
+    /// Set the zoom from which the detailed coastline is drawn, cross-fading from the coarse
+    /// coastline as the zoom crosses it; requires the detailed coastline data.
+    pub fn set_detail_zoom(&self, zoom: f64) {
+        self.state.borrow_mut().detail.zoom = zoom;
+    }
+
+    /// Set the locale numbers and coordinates are written in, by a BCP 47 language tag such
+    /// as "de-CH", for labels, legends and formatted locations; returns whether the language
+    /// is known, leaving the locale unchanged otherwise.
+    pub fn set_locale(&self, tag: &str) -> bool {
+        let mut state = self.state.borrow_mut();
+        let Some(mut locale) = Locale::from_tag(tag) else {
+            return false;
+        };
+        locale.coordinates = state.locale.coordinates;
+        state.locale = locale;
+        state.redraw = true;
+        true
+    }
+
+    /// Set how coordinates are written, as decimal degrees ("decimal") or degrees, minutes
+    /// and seconds ("dms"); returns whether the format is known.
+    pub fn set_coordinate_format(&self, format: &str) -> bool {
+        let Some(format) = CoordinateFormat::from_id(format) else {
+            return false;
+        };
+        let mut state = self.state.borrow_mut();
+        state.locale.coordinates = format;
+        state.redraw = true;
+        true
+    }
+
+    /// A location written in the locale and coordinate format, to a number of decimal places
+    /// of degrees or of seconds, for readouts on the page.
+    pub fn format_location(&self, location: &LonLat, decimals: usize) -> String {
+        self.state.borrow().locale.location(*location, decimals)
+    }
+
+    /// Set whether labels are drawn with the built-in stroked vector font rather than the
+    /// canvas font, so they look the same as in images rendered on a server.
+    pub fn set_vector_labels(&self, enabled: bool) {
+        let mut state = self.state.borrow_mut();
+        state.label_font = if enabled {
+            LabelFont::Vector
+        } else {
+            LabelFont::Canvas
+        };
+        state.redraw = true;
+    }
+
+    /// Set the URL template of a basemap of raster tiles in the XYZ scheme of web maps, such as
+    /// "https://tile.openstreetmap.org/{z}/{x}/{y}.png", with the attribution the tiles
+    /// require, or `None` for no basemap. Tiles are reprojected onto the globe, so the server
+    /// must allow cross-origin requests.
+    pub fn set_tile_source(&self, template: Option<String>, attribution: Option<String>) {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+        if let Some(previous) = state.tiles.attribution.take() {
+            state.attribution.remove(&previous);
+        }
+        if let Some(attribution) = &attribution {
+            state.attribution.add(attribution);
+        }
+        state.tiles.attribution = attribution;
+        state.tiles.set_source(template);
+        state.layer_changed("tiles", "set");
+    }
+
+    /// Set the greatest zoom of the tiles requested, as the source provides tiles to.
+    pub fn set_tile_max_zoom(&self, zoom: u8) {
+        let mut state = self.state.borrow_mut();
+        state.tiles.max_zoom = zoom;
+        state.redraw = true;
+    }
+
+    /// Set whether the canvas is sized by the page's styles, the globe resizing its backing
+    /// store and redrawing as the canvas is resized; otherwise the canvas keeps the size it
+    /// has, as when the globe was created.
+    pub fn set_responsive(&self, enabled: bool) {
+        let mut state = self.state.borrow_mut();
+        state.responsive = enabled;
+        state.render.resize();
+    }
+
+    /// Status of a layer by its identifier, as an object with the `layer`, its `status` of
+    /// "loading", "ready" or "error", and the `message` of any error; changes of status are
+    /// dispatched as "layerstatuschange" events on the canvas.
+    pub fn layer_status(&self, layer: &str) -> Result<JsValue, JsValue> {
+        let status = self
+            .state
+            .borrow()
+            .layer_status
+            .get(layer)
+            .map_err(|error| JsValue::from_str(&error))?;
+        status.to_object(layer)
+    }
+
+    /// Show or hide a badge naming the layers loading or failing, and set the corner of the
+    /// canvas it is placed in.
+    pub fn set_status_display(&self, visible: bool, corner: Corner) {
+        let mut state = self.state.borrow_mut();
+        state.layer_status.badge = visible;
+        state.layer_status.corner = corner;
+        state.redraw = true;
+    }
+
+    /// Set whether a placeholder of the sphere and graticule, with an arc of the progress of
+    /// decoding the embedded data and of the first downloads, is drawn in place of the layers
+    /// while the globe starts up, as it is by default.
+    pub fn set_startup_placeholder(&self, enabled: bool) {
+        let mut state = self.state.borrow_mut();
+        state.startup.enabled = enabled;
+        state.redraw = true;
+    }
+
+    /// Set the greatest number of requests for the data of network layers, such as tiles, in
+    /// flight at once; the default is 6.
+    pub fn set_max_requests(&self, max_requests: u32) {
+        let fetcher = self.state.borrow().fetcher.clone();
+        fetcher.set_max_requests(max_requests as usize);
+    }
+
+    /// Set whether the data of network layers is kept in IndexedDB between visits, as it is by
+    /// default.
+    pub fn set_persistent_cache(&self, enabled: bool) {
+        self.state.borrow().fetcher.set_persistent(enabled);
+    }
+
+    /// Discard the data of network layers cached in memory and in IndexedDB.
+    pub fn clear_cache(&self) -> Result<(), JsValue> {
+        let fetcher = self.state.borrow().fetcher.clone();
+        fetcher.clear()
+    }
+
+    /// Style of the base layers.
+    pub fn style(&self) -> GlobeStyle {
+        self.state.borrow().style.clone()
+    }
+
+    /// Set the style of the base layers, redrawing the globe.
+    pub fn set_style(&self, style: &GlobeStyle) {
+        let mut state = self.state.borrow_mut();
+        state.style = style.clone();
+        state.redraw = true;
+    }
+
+    /// Set the color the land is filled with, or `None` for the land not to be filled.
+    pub fn set_land_color(&self, color: Option<String>) {
+        let mut state = self.state.borrow_mut();
+        state.land.fill_style = color;
+        state.redraw = true;
+    }
+
+    /// Set a filter so a layer ("coastline", "countries" or "land") only draws features whose
+    /// attributes match an expression, such as `scalerank <= 3`, or remove the filter with an
+    /// empty expression; throws when the layer is unknown or the expression is invalid.
+    pub fn set_layer_filter(&self, layer_id: &str, expression: &str) -> Result<(), JsValue> {
+        let mut state = self.state.borrow_mut();
+        state
+            .filters
+            .set(layer_id, expression)
+            .map_err(|error| JsValue::from_str(&error))?;
+        state.layer_changed("filter", "set");
+        Ok(())
+    }
+
+    /// Set the range of zoom, relative to the unzoomed view, over which a layer (such as
+    /// "coastline", "choropleth" or "spikes") is shown, so detailed layers appear as the view
+    /// is zoomed in; `None` leaves the range unlimited. Throws when the layer is unknown.
+    pub fn set_layer_zoom_range(
+        &self,
+        layer_id: &str,
+        min_zoom: Option<f64>,
+        max_zoom: Option<f64>,
+    ) -> Result<(), JsValue> {
+        let mut state = self.state.borrow_mut();
+        state
+            .zoom_ranges
+            .set(
+                layer_id,
+                ZoomRange {
+                    min: min_zoom,
+                    max: max_zoom,
+                },
+            )
+            .map_err(|error| JsValue::from_str(&error))?;
+        state.redraw = true;
+        Ok(())
+    }
+
+    /// Add a layer drawn by a script, as an object with a `draw(context, project)` method
+    /// called in each frame with the context in canvas pixels and a function projecting a
+    /// longitude and latitude (degrees) to `[x, y]` in canvas pixels, or to `null` on the back
+    /// where the projection clips it; the layer is added beneath another layer, or on top of
+    /// the others. Throws when the identifier is taken, the other layer is unknown or the
+    /// object has no `draw` method.
+    pub fn add_layer(
+        &self,
+        layer_id: &str,
+        layer: js_sys::Object,
+        before: Option<String>,
+    ) -> Result<(), JsValue> {
+        let layer = ScriptLayer::new(layer)?;
+        let mut state = self.state.borrow_mut();
+        if !state
+            .layers
+            .add(layer_id, Box::new(layer), before.as_deref())
+        {
+            return Err(JsValue::from_str(&format!(
+                "layer {:?} exists or {:?} is unknown",
+                layer_id, before
+            )));
+        }
+        state.redraw = true;
+        Ok(())
+    }
+
+    /// Add a layer of a GeoJSON document: a geometry, feature, or collection of either, with
+    /// points, line strings and polygons, and their multi-part forms, drawn with a style. The
+    /// layer is added beneath another layer, or on top of the others, and is removed, moved
+    /// and hidden like layers added with `add_layer`. Throws when the identifier is taken, the
+    /// other layer is unknown, or the document is invalid.
+    pub fn add_geojson(
+        &self,
+        layer_id: &str,
+        json: &str,
+        style: &GeoJsonStyle,
+        before: Option<String>,
+    ) -> Result<(), JsValue> {
+        let layer = GeoJsonLayer::new(json, style.clone())?;
+        let mut state = self.state.borrow_mut();
+        if !state
+            .layers
+            .add(layer_id, Box::new(layer), before.as_deref())
+        {
+            return Err(JsValue::from_str(&format!(
+                "layer {:?} exists or {:?} is unknown",
+                layer_id, before
+            )));
+        }
+        state.redraw = true;
+        Ok(())
+    }
+
+    /// Remove a layer added with `add_layer`, returning whether it existed; built-in layers are
+    /// hidden with `set_layer_visible` rather than removed.
+    pub fn remove_layer(&self, layer_id: &str) -> bool {
+        let mut state = self.state.borrow_mut();
+        state.redraw = true;
+        state.layers.remove(layer_id)
+    }
+
+    /// Move a layer, built-in or added, beneath another layer, or on top of the others;
+    /// returns whether both layers exist.
+    pub fn move_layer(&self, layer_id: &str, before: Option<String>) -> bool {
+        let mut state = self.state.borrow_mut();
+        state.redraw = true;
+        state.layers.move_layer(layer_id, before.as_deref())
+    }
+
+    /// Show or hide a layer, returning whether it exists.
+    pub fn set_layer_visible(&self, layer_id: &str, visible: bool) -> bool {
+        let mut state = self.state.borrow_mut();
+        state.redraw = true;
+        state.layers.set_visible(layer_id, visible)
+    }
+
+    /// Set the opacity (0 to 1) a layer is drawn with, returning whether it exists.
+    pub fn set_layer_opacity(&self, layer_id: &str, opacity: f64) -> bool {
+        let mut state = self.state.borrow_mut();
+        state.redraw = true;
+        state.layers.set_opacity(layer_id, opacity)
+    }
+
+    /// Identifiers of the layers, built-in and added, in drawing order from the bottom up.
+    pub fn layer_ids(&self) -> Vec<String> {
+        self.state.borrow().layers.ids().map(String::from).collect()
+    }
+
+    /// Rotate and zoom the globe so a feature of a layer ("coastline" or "countries"), by
+    /// index, fills the view, keeping padding (canvas pixels) around it, within the limits of
+    /// the zoom; returns whether the feature exists.
+    pub fn fit_feature(&self, layer_id: &str, feature_id: u32, padding: f64) -> bool {
+        let Some(bounds) = info::feature_bounds(layer_id, feature_id as usize) else {
+            return false;
+        };
+        let mut state = self.state.borrow_mut();
+        let from = state.control.orientation;
+        let to = bounds.centred(&from);
+        let radius = std::cmp::min(state.canvas_size.0, state.canvas_size.1) as f64 / 2.0;
+        let zoom = &state.control.zoom;
+        let target = zoom.clamped(bounds.fit_zoom(&state.projection, &to, radius, padding));
+        let animation = CameraAnimation::new(from, to, now()).zooming(zoom.factor, target);
+        state.animation = Some(animation);
+        true
+    }
+
+    /// Set the projection of the globe onto the canvas.
+    pub fn set_projection(&self, kind: ProjectionKind) {
+        let mut state = self.state.borrow_mut();
+        state.projection.kind = kind;
+        state.redraw = true;
+    }
+
+    /// Rotate the globe so a location is at the centre of the view, such as the centre of the
+    /// azimuthal equidistant projection.
+    pub fn set_centre(&self, location: &LonLat) {
+        let mut state = self.state.borrow_mut();
+        state.control.stop();
+        let from = state.control.orientation;
+        state.animation = Some(CameraAnimation::new(
+            from,
+            from.centred_on(*location),
+            now(),
+        ));
+    }
+
+    /// Fly the globe so a location is at the centre of the view, turning along the
+    /// great circle over a duration (milliseconds, by default 1000) with an easing of its
+    /// progress (by default easing in and out). Returns a promise resolved with whether the
+    /// flight finished, or false once interrupted, as by dragging the globe or another flight.
+    pub fn fly_to(
+        &self,
+        location: &LonLat,
+        duration_ms: Option<f64>,
+        easing: Option<Easing>,
+    ) -> js_sys::Promise {
+        let mut resolve = None;
+        let promise = js_sys::Promise::new(&mut |resolve_promise, _reject| {
+            resolve = Some(resolve_promise);
+        });
+        let mut state = self.state.borrow_mut();
+        state.control.stop();
+        let from = state.control.orientation;
+        state.animation = Some(CameraAnimation::fly(
+            from,
+            from.centred_on(*location),
+            now(),
+            duration_ms,
+            easing.unwrap_or_default(),
+            resolve,
+        ));
+        promise
+    }
+
+    /// Set the latitude (degrees, towards the equator from the pole) at the edge
+    /// of the polar stereographic projections.
+    pub fn set_latitude_limit(&self, latitude: f64) {
+        let mut state = self.state.borrow_mut();
+        state.projection.set_latitude_limit(latitude);
+        state.redraw = true;
+    }
+
+    /// Set whether the graticule of meridians and parallels is drawn.
+    pub fn set_graticule(&self, visible: bool) {
+        let mut state = self.state.borrow_mut();
+        state.graticule.visible = visible;
+        state.redraw = true;
+    }
+
+    /// Set whether the countries are labelled with their names; requires the country data.
+    pub fn set_country_labels(&self, visible: bool) {
+        let mut state = self.state.borrow_mut();
+        state.country_labels.visible = visible;
+        state.redraw = true;
+    }
+
+    /// Set the language countries are labelled in by a BCP 47 language tag such as "fr-CA",
+    /// or `None` for their default names; returns whether names in the language are embedded,
+    /// which requires building with the `localized-names` feature.
+    pub fn set_label_language(&self, language: Option<String>) -> bool {
+        let mut state = self.state.borrow_mut();
+        let set = state.country_labels.set_language(language.as_deref());
+        if set {
+            state.redraw = true;
+        }
+        set
+    }
+
+    /// Index of the country containing a location, or `None` over the sea; requires the
+    /// country data.
+    pub fn country_at(&self, location: &LonLat) -> Option<u32> {
+        quiz::country_at(*location).map(|country| country as u32)
+    }
+
+    /// Ask for a country at random, not asked for again until every country has been, with
+    /// its name in the language countries are labelled in and the cap bounding it, to bring
+    /// it into view; requires the country data.
+    pub fn next_quiz_question(&self) -> Option<QuizQuestion> {
+        let mut state = self.state.borrow_mut();
+        let country = state.quiz.ask(js_sys::Math::random())?;
+        let bounds = &data::COUNTRY_BOUNDS[country];
+        let LonLat { lon, lat } = bounds.centroid;
+        Some(QuizQuestion {
+            country: country as u32,
+            name: state.country_labels.name(country).to_string(),
+            lat,
+            lon,
+            radius: bounds.radius,
+        })
+    }
+
+    /// Answer the question asked with a location, correct when it is within the
+    /// country asked for, and call the score callback; returns whether the answer is correct,
+    /// or `None` with no question awaiting an answer.
+    pub fn answer_quiz(&self, location: &LonLat) -> Result<Option<bool>, JsValue> {
+        let (correct, score, callback) = {
+            let mut state = self.state.borrow_mut();
+            let correct = state.quiz.answer(quiz::country_at(*location));
+            (correct, state.quiz.score, state.quiz.callback.clone())
+        };
+        // Called once the state is released, so the callback can reveal the country or ask
+        // the next question
+        if let (Some(correct), Some(callback)) = (correct, callback) {
+            callback.call2(&JsValue::NULL, &correct.into(), &score.into())?;
+        }
+        Ok(correct)
+    }
+
+    /// Highlight a country by index for a duration (milliseconds, by default 3000), fading
+    /// out at its end; returns whether the country exists.
+    pub fn reveal_country(&self, country: u32, duration_ms: Option<f64>) -> bool {
+        let mut state = self.state.borrow_mut();
+        let revealed = state.quiz.reveal(country as usize, now(), duration_ms);
+        if revealed {
+            state.redraw = true;
+        }
+        revealed
+    }
+
+    /// Answers given to the questions asked since the quiz was last reset.
+    pub fn quiz_score(&self) -> QuizScore {
+        self.state.borrow().quiz.score
+    }
+
+    /// Set a callback called after each answer with whether it is correct and the score, or
+    /// remove it when not set.
+    pub fn set_quiz_score_callback(&self, callback: Option<js_sys::Function>) {
+        self.state.borrow_mut().quiz.callback = callback;
+    }
+
+    /// Forget the questions asked, the score and any country highlighted.
+    pub fn reset_quiz(&self) {
+        let mut state = self.state.borrow_mut();
+        state.quiz.reset();
+        state.redraw = true;
+    }
+
+    /// Location at the centre of the view.
+    pub fn centre(&self) -> LonLat {
+        Rotation::from(self.state.borrow().control.orientation).centre()
+    }
+
+    /// Location on the globe at a position (canvas pixels) on the canvas, or `None` off the
+    /// globe.
+    pub fn pick(&self, x: f64, y: f64) -> Option<LonLat> {
+        let state = self.state.borrow();
+        locate(
+            &state.projection,
+            &state.control.orientation,
+            &state.transform(),
+            &Position { x, y },
+        )
+    }
+
+    /// Orientation of the globe.
+    pub fn rotation(&self) -> Rotation {
+        self.state.borrow().control.orientation.into()
+    }
+
+    /// Set the orientation of the globe, stopping any animation of the camera.
+    pub fn set_rotation(&self, rotation: &Rotation) {
+        let mut state = self.state.borrow_mut();
+        state.animation = None;
+        state.control.stop();
+        state.control.orientation = (*rotation).into();
+        state.redraw = true;
+    }
+
+    /// Rotation (degrees) of the globe about the view axis, anticlockwise on the canvas.
+    pub fn roll(&self) -> f64 {
+        self.state.borrow().control.orientation.roll
+    }
+
+    /// Set the rotation (degrees) of the globe about the view axis, anticlockwise on the canvas.
+    pub fn set_roll(&self, degrees: f64) {
+        let mut state = self.state.borrow_mut();
+        state.control.orientation.roll = degrees;
+        state.redraw = true;
+    }
+
+    /// Zoom of the view, as a factor scaling the globe.
+    pub fn zoom(&self) -> f64 {
+        self.state.borrow().control.zoom.factor
+    }
+
+    /// Set the zoom of the view, as a factor scaling the globe, within the limits of the zoom.
+    pub fn set_zoom(&self, factor: f64) {
+        let mut state = self.state.borrow_mut();
+        if state.control.zoom.set(factor) {
+            state.redraw = true;
+        }
+    }
+
+    /// Set whether the view is synchronised with the fragment of the page's URL, as
+    /// `#lat=…&lon=…&zoom=…`: the view is restored from the fragment, and the fragment follows
+    /// the view once it settles, so views can be shared and survive reloads. Returns whether
+    /// the view was restored from the fragment.
+    pub fn set_hash_sync(&self, enabled: bool) -> Result<bool, JsValue> {
+        let mut state = self.state.borrow_mut();
+        if !enabled {
+            state.hash.enabled = false;
+            return Ok(false);
+        }
+        sync_hash(&mut state)
+    }
+
+    /// Set the limits of the zoom factor reached by the wheel and pinches; returns whether the
+    /// limits are valid, with the minimum positive and no more than the maximum.
+    pub fn set_zoom_limits(&self, min: f64, max: f64) -> bool {
+        let mut state = self.state.borrow_mut();
+        if !state.control.zoom.set_limits(min, max) {
+            return false;
+        }
+        state.redraw = true;
+        true
+    }
+
+    /// Register a function called with the detail of each event of a type, as dispatched on
+    /// the canvas: "rotationchange" (with the `lat` and `lon` at the centre of the view and the
+    /// `rotation`, `tilt` and `roll`), "zoomchange" (with the `zoom`), "renderstart",
+    /// "renderend" (with the `duration` of the frame in milliseconds) or "pick" (with the `x`
+    /// and `y` clicked, its `lat` and `lon`, and the `marker` and `country` there, or null).
+    /// Throws when the type is unknown.
+    pub fn on(&self, event_type: &str, callback: js_sys::Function) -> Result<(), JsValue> {
+        if !self.state.borrow_mut().emitter.on(event_type, callback) {
+            return Err(JsValue::from_str(&format!(
+                "unknown event type {:?}",
+                event_type
+            )));
+        }
+        Ok(())
+    }
+
+    /// Remove a function registered for a type of event, returning whether it was registered.
+    pub fn off(&self, event_type: &str, callback: &js_sys::Function) -> bool {
+        self.state.borrow_mut().emitter.off(event_type, callback)
+    }
+
+    /// Set whether the globe responds to dragging and the wheel; globes that do not leave
+    /// the page to scroll, while controls drawn on the canvas still respond.
+    pub fn set_interactive(&self, enabled: bool) {
+        let mut state = self.state.borrow_mut();
+        state.locked = !enabled;
+        state.control.stop();
+    }
+
+    /// Set whether line widths are constant on screen or relative to the globe as the
+    /// view is zoomed, with limits (canvas pixels) on the resulting widths; returns whether
+    /// the limits are valid, with the minimum not negative and no more than the maximum.
+    pub fn set_line_width_policy(&self, policy: LineWidthPolicy, min: f64, max: f64) -> bool {
+        let mut state = self.state.borrow_mut();
+        if !state.line_widths.set_limits(min, max) {
+            return false;
+        }
+        state.line_widths.policy = policy;
+        state.redraw = true;
+        true
+    }
+
+    /// Set the width (device pixels, by default 1) no line is drawn thinner than, so fine
+    /// lines stay visible on every display, zoom and export resolution; 0 lets lines thin
+    /// freely.
+    pub fn set_min_line_width(&self, device_pixels: f64) {
+        let mut state = self.state.borrow_mut();
+        state.line_widths.min_device = device_pixels.max(0.0);
+        state.redraw = true;
+    }
+}
+
+/// Get a handle to the globe created at start up.
+#[wasm_bindgen]
+pub fn globe() -> Option<Globe> {
+    STATE.with(|state| {
+        state.borrow().clone().map(|state| Globe {
+            state,
+            _attachment: None,
+        })
+    })
+}
+
+/// Location where two arcs of great circles cross, each arc given by its start and end;
+/// undefined when they do not cross.
+#[wasm_bindgen]
+pub fn great_circle_intersection(
+    first_start: &LonLat,
+    first_end: &LonLat,
+    second_start: &LonLat,
+    second_end: &LonLat,
+) -> Option<LonLat> {
+    measure::intersection((*first_start, *first_end), (*second_start, *second_end))
+}
+
+/// Whether two arcs of great circles cross, each given by its start and end.
+#[wasm_bindgen]
+pub fn great_circle_arcs_cross(
+    first_start: &LonLat,
+    first_end: &LonLat,
+    second_start: &LonLat,
+    second_end: &LonLat,
+) -> bool {
+    measure::crosses((*first_start, *first_end), (*second_start, *second_end))
+}
+
+/// State of a new globe from the configuration and style it is created with, the style
+/// replacing that of the configuration's theme; throws when the theme is unknown.
+fn initial_state(config: &Config, style: Option<GlobeStyle>) -> Result<State, JsValue> {
+    let mut state = State::default();
+    if let Some(theme) = &config.theme {
+        state.style = GlobeStyle::theme(theme)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown theme {:?}", theme)))?;
+    }
+    if let Some(style) = style {
+        state.style = style;
+    }
+    if config.lat.is_some() || config.lon.is_some() {
+        state.control.orientation = state.control.orientation.centred_on(LonLat::new(
+            config.lon.unwrap_or(0.0),
+            config.lat.unwrap_or(0.0),
+        ));
+    }
+    if let Some(zoom) = config.zoom {
+        state.control.zoom.set(zoom);
+    }
+    if let Some(graticule) = config.graticule {
+        state.graticule.visible = graticule;
+    }
+    if let Some(country_labels) = config.country_labels {
+        state.country_labels.visible = country_labels;
+    }
+    if let Some(day_night) = config.day_night {
+        state.terminator.enabled = day_night;
+    }
+    if let Some(interactive) = config.interactive {
+        state.locked = !interactive;
+    }
+    if let Some(inertia) = config.inertia {
+        state.control_settings.inertia = inertia;
+    }
+    if config.auto_rotate.is_some() {
+        state.auto_rotate.speed = config.auto_rotate;
+    }
+    if let Some(responsive) = config.responsive {
+        state.responsive = responsive;
+    }
+    if config.hash == Some(true) {
+        sync_hash(&mut state)?;
+    }
+    Ok(state)
+}
+
+/// Start synchronising the view with the fragment of the page's URL, restoring the view
+/// from it; returns whether the fragment held a view.
+fn sync_hash(state: &mut State) -> Result<bool, JsValue> {
+    let window = web_sys::window()
+        .ok_or_else(|| JsValue::from_str("globes in workers have no URL to synchronise with"))?;
+    let view = HashView::parse(&window.location().hash()?);
+    if let Some(view) = view {
+        state.control.stop();
+        state.animation = None;
+        state.control.orientation = state
+            .control
+            .orientation
+            .centred_on(LonLat::new(view.lon, view.lat));
+        if let Some(zoom) = view.zoom {
+            state.control.zoom.set(zoom);
+        }
+        state.redraw = true;
+    }
+    state.hash.start(view.map(|view| view.params()));
+    Ok(view.is_some())
+}
+
+/// Write the parameters of a view to the fragment of the page's URL, replacing the page's
+/// entry in the history rather than adding one for each view.
+fn write_hash(params: &str) -> Result<(), JsValue> {
+    let Some(window) = web_sys::window() else {
+        return Ok(());
+    };
+    let location = window.location();
+    location.replace(&format!("#{}", permalink::merge(params, &location.hash()?)))
+}
+
+fn window() -> Window {
+    web_sys::window().expect("should have window")
+}
+
+/// Performance timeline of the page, or of the worker for globes drawn in a worker.
+fn performance() -> web_sys::Performance {
+    match web_sys::window() {
+        Some(window) => window.performance(),
+        None => js_sys::global()
+            .unchecked_into::<web_sys::WorkerGlobalScope>()
+            .performance(),
+    }
+    .expect("should have performance")
+}
+
+/// Current time (milliseconds), on the same clock as event time stamps.
+fn now() -> f64 {
+    // Replays run on the clock of the recording
+    recorder::clock().unwrap_or_else(|| performance().now())
+}
+
+/// Create a globe on a canvas appended to the page at start up, for pages written before
+/// globes were created with `new Globe(canvas, options)`, and for embeds configured without
+/// a script of their own: the options are read from the query string of the script marked
+/// with `data-globe` and the `data-*` attributes of the element marked with `data-globe`,
+/// which hosts the canvas in place of the page's body. The globe is reached with `globe()`.
+#[cfg(feature = "autostart")]
+#[wasm_bindgen(start)]
+pub fn main() -> Result<(), JsValue> {
+    // Workers draw on canvases transferred to them
+    let Some(window) = web_sys::window() else {
+        return Ok(());
+    };
+    let document = window.document().expect("should have document");
+
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(CANVAS_WIDTH);
+    canvas.set_height(CANVAS_HEIGHT);
+    let (config, host) = Config::from_page(&document)?;
+    match host {
+        Some(host) => host.append_child(&canvas)?,
+        None => document.body().unwrap().append_child(&canvas)?,
+    };
+
+    let state = Rc::new(RefCell::new(initial_state(&config, None)?));
+    STATE.with(|s| *s.borrow_mut() = Some(state.clone()));
+    // The globe created at start up lasts as long as the page
+    std::mem::forget(attach(canvas, state)?);
+
+    Ok(())
+}
+
+/// Draw a globe's state on a canvas at the canvas's size, adding the listeners for its
+/// controls and starting the loop of animation frames, which last until the attachment is
+/// dropped.
+fn attach(canvas: HtmlCanvasElement, state: Rc<RefCell<State>>) -> Result<Attachment, JsValue> {
+    let document = window().document().expect("should have document");
+    let (width, height) = (canvas.width(), canvas.height());
+    state.borrow_mut().canvas_size = (width, height);
+
+    let context = canvas
+        .get_context("2d")?
+        .expect("should have 2d context")
+        .dyn_into::<CanvasRenderingContext2d>()?;
+
+    let capabilities = Capabilities::probe(&window(), &document, width, height)?;
+    let surface = Rc::new(Surface::Element(canvas.clone()));
+    start(&context, &surface, &mut state.borrow_mut(), capabilities)?;
+
+    let mut attachment = Attachment::default();
+    {
+        let (state, surface) = (state.clone(), surface.clone());
+        let closure = Closure::<dyn FnMut(_)>::new(move |event: PointerEvent| {
+            pointer_down(&state, &surface, &PointerInput::from_event(&event));
+        });
+        attachment.listen(&canvas, "pointerdown", closure, None)?;
+    }
+
+    {
+        let state = state.clone();
+        let closure = Closure::<dyn FnMut(_)>::new(move |event: PointerEvent| {
+            if pointer_move(&state, &PointerInput::from_event(&event)) {
+                event.prevent_default();
+            }
+        });
+        attachment.listen(&canvas, "pointermove", closure, None)?;
+    }
+
+    {
+        let (state, surface) = (state.clone(), surface.clone());
+        let closure = Closure::<dyn FnMut(_)>::new(move |event: PointerEvent| {
+            pointer_up(&state, &surface, &PointerInput::from_event(&event));
+        });
+        attachment.listen(&document, "pointerup", closure, None)?;
+    }
+
+    {
+        let state = state.clone();
+        let closure = Closure::<dyn FnMut(_)>::new(move |_event: PointerEvent| {
+            pointer_leave(&state);
+        });
+        attachment.listen(&canvas, "pointerleave", closure, None)?;
+    }
+
+    {
+        let state = state.clone();
+        let closure = Closure::<dyn FnMut(_)>::new(move |_event: PointerEvent| {
+            pointer_cancel(&state);
+        });
+        attachment.listen(&canvas, "pointercancel", closure, None)?;
+    }
+
+    {
+        let state = state.clone();
+        let closure = Closure::<dyn FnMut(_)>::new(move |event: WheelEvent| {
+            if wheel(&state, &WheelInput::from_event(&event)) {
+                event.prevent_default();
+            }
+        });
+        // Not passive, so scrolling the page can be prevented
+        let options = AddEventListenerOptions::new();
+        options.set_passive(false);
+        attachment.listen(&canvas, "wheel", closure, Some(&options))?;
+    }
+
+    {
+        let state = state.clone();
+        let closure = Closure::<dyn FnMut(_)>::new(move |entries: js_sys::Array| {
+            let Ok(entry) = entries.get(0).dyn_into::<ResizeObserverEntry>() else {
+                return;
+            };
+            // The content box is the size of the canvas on the page, in canvas pixels
+            let rect = entry.content_rect();
+            resize(
+                &state,
+                (rect.width().round() as u32, rect.height().round() as u32),
+            );
+        });
+        attachment.observe_resize(&canvas, closure)?;
+    }
+
+    attachment.animate(move || tick(&state, &context, &surface));
+
+    Ok(attachment)
+}
+
+/// Size the canvas for the device and draw the first frame, timing it to find slow devices.
+fn start(
+    context: &CanvasRenderingContext2d,
+    surface: &Surface,
+    state: &mut State,
+    mut capabilities: Capabilities,
+) -> Result<(), JsValue> {
+    // Over browser (i.e. "auto") touch behaviour
+    state.touch_action = state.touch_action();
+    surface.set_touch_action(state.touch_action)?;
+    if let Some(ratio) = surface.device_pixel_ratio() {
+        state.render.set_device_pixel_ratio(ratio);
+    }
+    if let Some(pixel_ratio) = state.render.take_resize() {
+        size_canvas(
+            surface,
+            context,
+            state.canvas_size,
+            pixel_ratio,
+            state.responsive,
+        )?;
+    }
+    state.scheduler.start();
+    let start = now();
+    draw(context, state)?;
+    capabilities.first_frame = Some(now() - start);
+    state.render.probed = capabilities.profile();
+    Ok(())
+}
 
-const CANVAS_WIDTH: u32 = 800;
-const CANVAS_HEIGHT: u32 = 800;
+/// Press of a pointer on the canvas.
+fn pointer_down(state: &RefCell<State>, surface: &Surface, input: &PointerInput) {
+    let recorded = recorder::Input::PointerDown(input.clone());
+    state
+        .borrow_mut()
+        .recorder
+        .input(input.time_stamp, &recorded);
+    let position = input.position.clone();
+    // Presses on controls drawn on the canvas are routed to them
+    let control = state.borrow().hit_regions.hit(position.x, position.y);
+    if let Some(control) = control {
+        dispatch_control(surface, control, &position).unwrap();
+        return;
+    }
+    let mut state = state.borrow_mut();
+    let state = &mut *state;
+    if state.locked {
+        return;
+    }
+    // Pressing the swipe's divider drags it
+    if state
+        .swipe
+        .drag_start(position.x, state.canvas_size.0 as f64)
+    {
+        return;
+    }
+    let transform = state.transform();
+    // Pressing a waypoint of the route being edited drags it rather than the globe
+    if state.route.drag_start(
+        &state.projection,
+        &state.control.orientation,
+        &transform,
+        &position,
+    ) {
+        return;
+    }
+    // Dragging in selection mode sweeps out a region rather than rotating the globe
+    let location = locate(
+        &state.projection,
+        &state.control.orientation,
+        &transform,
+        &position,
+    );
+    if state.selection.drag_start(location) {
+        state.redraw = true;
+        return;
+    }
+    // The pointer takes over from any animation of the camera
+    state.animation = None;
+    state
+        .control
+        .pointer_down(input.pointer_id, position.clone());
+    // Gestures are recognised for a single pointer
+    if state.control.multiple_pointers() {
+        state.gestures.pointer_cancel();
+    } else {
+        state
+            .gestures
+            .pointer_down(position, input.time_stamp, &input.pointer_type);
+    }
+}
 
-const SPHERE_FILL_STYLE: &str = "rgba(159, 159, 255, 1.0)";
-const COAST_FRONT_STROKE_STYLE: &str = "rgba(0, 0, 127, 1.0)";
-const COAST_BACK_STROKE_STYLE: &str = "rgba(0, 0, 0, 0.25)";
-const COAST_FRONT_LINE_WIDTH: f64 = 0.005;
-const COAST_BACK_LINE_WIDTH: f64 = 0.0025;
+/// Movement of a pointer over the canvas, returning whether it was taken by the globe, so
+/// the browser's handling of it is prevented.
+fn pointer_move(state: &RefCell<State>, input: &PointerInput) -> bool {
+    let mut state = state.borrow_mut();
+    let recorded = recorder::Input::PointerMove(input.clone());
+    state.recorder.input(input.time_stamp, &recorded);
+    let transform = state.transform();
+    let position = input.position.clone();
+    // The magnifier follows the pointer
+    if state.magnifier.enabled {
+        state.magnifier.position = Some((position.x, position.y));
+        state.redraw = true;
+    }
+    // Hovering over controls drawn on the canvas is not hovering over the globe
+    if !state.interacting() && state.hit_regions.hit(position.x, position.y).is_some() {
+        state.gestures.pointer_leave();
+        return false;
+    }
+    let width = state.canvas_size.0 as f64;
+    if state.swipe.drag_to(position.x, width) {
+        state.redraw = true;
+        return true;
+    }
+    let location = locate(
+        &state.projection,
+        &state.control.orientation,
+        &transform,
+        &position,
+    );
+    if state.route.drag_to(location) || state.selection.drag_to(location) {
+        state.redraw = true;
+        return true;
+    }
+    state
+        .gestures
+        .pointer_move(position.clone(), input.time_stamp, &input.pointer_type);
+    state.control.pointer_move(input.pointer_id, position)
+}
 
-#[derive(Clone, Debug, Default, PartialEq)]
-struct Position {
-    x: f64,
-    y: f64,
+/// Release of a pointer anywhere on the page.
+fn pointer_up(state: &RefCell<State>, surface: &Surface, input: &PointerInput) {
+    let (gesture, selected) = {
+        let mut state = state.borrow_mut();
+        let recorded = recorder::Input::PointerUp(input.clone());
+        state.recorder.input(input.time_stamp, &recorded);
+        state
+            .control
+            .pointer_up(input.pointer_id, input.position.clone());
+        if state.route.drag_end() {
+            state.layer_changed("route", "move");
+        }
+        if state.swipe.drag_end() {
+            state.redraw = true;
+        }
+        let selected = state.selection.drag_end();
+        if selected.is_some() {
+            state.layer_changed("selection", "select");
+        }
+        let gesture = state.gestures.pointer_up(input.time_stamp);
+        let transform = state.transform();
+        let gesture = gesture.map(|gesture| {
+            state.telemetry.record(TelemetryEvent::gesture(&gesture));
+            let location = locate(
+                &state.projection,
+                &state.control.orientation,
+                &transform,
+                &gesture.position,
+            );
+            // Taps on the globe add waypoints to the route being edited
+            if let (GestureKind::Tap, Some(location)) = (gesture.kind, location) {
+                if state.route.editing {
+                    state.route.add(location);
+                    state.layer_changed("route", "add");
+                }
+            }
+            // Double taps recentre the azimuthal equidistant projection
+            if let (GestureKind::DoubleTap, Some(location)) = (gesture.kind, location) {
+                if state.projection.kind == ProjectionKind::AzimuthalEquidistant {
+                    let from = state.control.orientation;
+                    let to = from.centred_on(location);
+                    state.animation = Some(CameraAnimation::new(from, to, now()));
+                }
+            }
+            // Taps on the globe pick the marker and country there
+            if let (GestureKind::Tap, Some(location)) = (gesture.kind, location) {
+                let position = &gesture.position;
+                let marker = state.markers.at(
+                    &state.projection,
+                    &state.control.orientation,
+                    &transform,
+                    (position.x, position.y),
+                );
+                state.emitter.push(GlobeEvent::Pick {
+                    x: position.x,
+                    y: position.y,
+                    location,
+                    marker,
+                    country: quiz::country_at(location),
+                });
+            }
+            (gesture, location)
+        });
+        (gesture, selected)
+    };
+    emit(state, surface);
+    // Dispatched once the state is released as listeners may call back into the globe
+    if let Some((gesture, location)) = gesture {
+        dispatch_gesture(surface, &gesture, location).unwrap();
+        // Clicks on the globe are reported with their location
+        if let (GestureKind::Tap, Some(location)) = (gesture.kind, location) {
+            dispatch_click(surface, &gesture.position, location).unwrap();
+        }
+    }
+    if let Some(bounds) = selected {
+        dispatch_selection(surface, &bounds).unwrap();
+    }
 }
 
-#[derive(Debug, Default)]
-struct ControlData {
-    pressed: bool,
-    position: Position,
-    position_prev: Position,
-    rotation: f64,
+/// Pointer leaving the canvas.
+fn pointer_leave(state: &RefCell<State>) {
+    let mut state = state.borrow_mut();
+    state.recorder.input(now(), &recorder::Input::PointerLeave);
+    state.gestures.pointer_leave();
+    if state.magnifier.position.take().is_some() {
+        state.redraw = true;
+    }
 }
 
-fn window() -> Window {
-    web_sys::window().expect("should have window")
+/// Pointer cancelled by the browser, as when a touch becomes a scroll of the page.
+fn pointer_cancel(state: &RefCell<State>) {
+    let mut state = state.borrow_mut();
+    state.recorder.input(now(), &recorder::Input::PointerCancel);
+    state.gestures.pointer_cancel();
+    state.control.pointer_cancel();
+    state.route.drag_end();
+    state.selection.drag_end();
+    state.swipe.drag_end();
 }
 
-fn request_animation_frame(f: &Closure<dyn FnMut()>) {
-    window()
-        .request_animation_frame(f.as_ref().unchecked_ref())
-        .expect("should register request animation frame callback");
+/// Turn of the wheel over the canvas, returning whether it zoomed the globe, so the page is
+/// not scrolled.
+fn wheel(state: &RefCell<State>, input: &WheelInput) -> bool {
+    let mut state = state.borrow_mut();
+    let recorded = recorder::Input::Wheel(input.clone());
+    state.recorder.input(input.time_stamp, &recorded);
+    // Controls drawn on the canvas, and locked globes, leave the page to scroll
+    if state.locked
+        || state
+            .hit_regions
+            .hit(input.position.x, input.position.y)
+            .is_some()
+    {
+        return false;
+    }
+    state.auto_rotate.interrupt(input.time_stamp);
+    state.attract.interrupt(input.time_stamp);
+    // Scrolls by lines or pages are taken as pixels of a typical line or the canvas
+    let delta = match input.delta_mode {
+        WheelEvent::DOM_DELTA_LINE => input.delta_y * WHEEL_LINE_HEIGHT,
+        WheelEvent::DOM_DELTA_PAGE => input.delta_y * state.canvas_size.1 as f64,
+        _ => input.delta_y,
+    };
+    // The page scrolls once the zoom reaches its limits
+    let zoomed = state.control.zoom.wheel(delta);
+    if zoomed {
+        state.redraw = true;
+    }
+    zoomed
 }
 
-#[wasm_bindgen(start)]
-pub fn main() -> Result<(), JsValue> {
-    let document = window().document().expect("should have document");
+/// Resizing of the canvas on the page to a size (canvas pixels), followed by the globe when
+/// the canvas is sized by the page.
+fn resize(state: &RefCell<State>, size: (u32, u32)) {
+    let mut state = state.borrow_mut();
+    let recorded = recorder::Input::Resize(size.0, size.1);
+    state.recorder.input(now(), &recorded);
+    if state.responsive && size.0 > 0 && size.1 > 0 && size != state.canvas_size {
+        state.canvas_size = size;
+        state.render.resize();
+    }
+}
 
-    let canvas = document
-        .create_element("canvas")?
-        .dyn_into::<HtmlCanvasElement>()?;
-    canvas.set_width(CANVAS_WIDTH);
-    canvas.set_height(CANVAS_HEIGHT);
-    canvas.style().set_property("touch-action", "pan-y")?; // Over browser (i.e. "auto") touch behaviour
-    document.body().unwrap().append_child(&canvas)?;
+/// Advance the globe's animations and draw its changes for an animation frame, dispatching
+/// the events they raise.
+fn tick(state: &RefCell<State>, context: &CanvasRenderingContext2d, surface: &Surface) {
+    // Replays feed the input recorded before each frame, at the times it arrived
+    let replayed = state.borrow_mut().recorder.next_frame();
+    if let Some(frame) = replayed {
+        for (time, input) in &frame.inputs {
+            recorder::set_clock(Some(*time));
+            replay_input(state, surface, input);
+        }
+        recorder::set_clock(Some(frame.time));
+    }
+    // A globe at rest skips the frame, other than following the ratio of the display's
+    // pixels, which changes without an event when the window moves between monitors
+    {
+        let mut state = state.borrow_mut();
+        if let Some(ratio) = surface.device_pixel_ratio() {
+            state.render.set_device_pixel_ratio(ratio);
+        }
+        if state.at_rest() {
+            return;
+        }
+    }
+    // Animation frames skipped by the cap on the frame rate leave animations, which
+    // follow the time, to catch up on the next frame ticked
+    let ticked = {
+        let mut state = state.borrow_mut();
+        let interacting = state.interacting();
+        // Replays draw the frames recorded, which were not skipped
+        state.frame_rate.tick(now(), interacting) || state.recorder.replaying()
+    };
+    if !ticked {
+        return;
+    }
+    let (gesture, time_window, statuses, geofence_events, telemetry, replayed) = {
+        let mut state = state.borrow_mut();
+        let state = &mut *state;
+        if state.control.pinch() {
+            state.redraw = true;
+        }
+        let transform = state.transform();
+        if state.control.drag(
+            &state.control_settings,
+            &state.projection,
+            &transform,
+            now(),
+        ) {
+            state.redraw = true;
+        }
+        if state.control.twist() {
+            state.redraw = true;
+        }
+        if let Some(animation) = &state.animation {
+            // Animations of the camera take over from the globe coasting
+            state.control.stop();
+            let (orientation, finished) = animation.at(now());
+            state.control.orientation = orientation;
+            if let Some(zoom) = animation.zoom_at(now()) {
+                state.control.zoom.set(zoom);
+            }
+            if finished {
+                if let Some(animation) = state.animation.take() {
+                    animation.finish();
+                }
+            }
+            state.redraw = true;
+        } else if state.control.coast(&state.control_settings, now()) {
+            state.redraw = true;
+        }
+        if state.detail.tick(state.control.zoom.factor, now()) {
+            state.redraw = true;
+        }
+        let idle = !state.interacting()
+            && !state.control.coasting()
+            && state.animation.is_none()
+            && !state.tracks.heading_up();
+        if let Some(rotation) = state.auto_rotate.tick(now(), idle) {
+            state.control.orientation.rotation += rotation;
+            state.redraw = true;
+        }
+        // The tour's own flights between stops are not interactions
+        let flying = state.animation.is_some();
+        match state.attract.tick(now(), idle || flying, flying) {
+            Some(TourStep::Rotate(rotation)) => {
+                state.control.orientation.rotation += rotation;
+                state.redraw = true;
+            }
+            Some(TourStep::FlyTo(stop)) => {
+                let from = state.control.orientation;
+                state.animation = Some(CameraAnimation::new(from, from.centred_on(stop), now()));
+            }
+            None => {}
+        }
+        // A track followed with its heading up holds the view, other than while it is dragged
+        if !state.interacting() && state.animation.is_none() {
+            if let Some(orientation) = state.tracks.follow(state.control.orientation, now()) {
+                state.control.stop();
+                state.control.orientation = orientation;
+                state.redraw = true;
+            }
+        }
+        if state.choropleth.tick(now()) {
+            state.redraw = true;
+        }
+        if state.cartogram.animating(now()) {
+            state.redraw = true;
+        }
+        // Pings change only the regions of their rings, as drawn before they finish
+        let ping_regions =
+            state
+                .pings
+                .regions(&state.projection, &state.control.orientation, &transform);
+        if state.pings.animating(now()) {
+            for region in ping_regions {
+                state.dirty.add(region);
+            }
+        }
+        if state.quiz.animating(now()) {
+            state.redraw = true;
+        }
+        if state.terminator.animating(js_sys::Date::now()) {
+            state.redraw = true;
+        }
+        if state
+            .moon
+            .animating(js_sys::Date::now(), state.terminator.time.is_none())
+        {
+            state.redraw = true;
+        }
+        // Tiles failing to decode are logged rather than stopping the animation frames
+        match state.tiles.receive(&state.fetcher) {
+            Ok(received) => state.redraw |= received,
+            Err(error) => web_sys::console::error_1(&error),
+        }
+        if state.layer_status.set("tiles", state.tiles.status()) && state.layer_status.badge {
+            let (width, height) = state.canvas_size;
+            let region = state.layer_status.region((width as f64, height as f64));
+            state.dirty.add(region);
+        }
+        if state.startup.report("decode", startup::decode_progress()) {
+            state.redraw = true;
+        }
+        if state.startup.report("fetch", state.fetcher.progress()) {
+            state.redraw = true;
+        }
+        if state.hash.enabled {
+            let centre = Rotation::from(state.control.orientation).centre().wrapped();
+            let view = HashView {
+                lat: centre.lat,
+                lon: centre.lon,
+                zoom: Some(state.control.zoom.factor),
+            };
+            if let Some(params) = state.hash.tick(view.params(), now()) {
+                // Pages whose history cannot be replaced, as in sandboxed frames, keep the view
+                if let Err(error) = write_hash(&params) {
+                    web_sys::console::error_1(&error);
+                }
+            }
+        }
+        // Touches are taken by the globe as the controls and editing modes change
+        let touch_action = state.touch_action();
+        if touch_action != state.touch_action {
+            surface.set_touch_action(touch_action).unwrap();
+            state.touch_action = touch_action;
+        }
+        // Resuming changes reverts to fast rendering from supersampling at rest
+        state.render.settle(state.redraw);
+        if let Some(pixel_ratio) = state.render.take_resize() {
+            size_canvas(
+                surface,
+                context,
+                state.canvas_size,
+                pixel_ratio,
+                state.responsive,
+            )
+            .unwrap();
+            state.redraw = true;
+        }
+        if state.redraw {
+            state.scheduler.start();
+            state.redraw = false;
+            state.dirty.start_full();
+            state.emitter.render_start(now());
+        } else if state.scheduler.pending() {
+            // Passes deferred from the last frame are drawn in order with a whole frame
+            state.scheduler.start();
+            state.dirty.start_full();
+            state.emitter.render_start(now());
+        } else if state.dirty.pending() {
+            state.scheduler.start();
+            state.dirty.start_partial();
+            state.emitter.render_start(now());
+        }
+        if state.scheduler.drawing() {
+            draw(context, state).unwrap();
+            state.emitter.render_end(now());
+        }
+        state
+            .emitter
+            .view(state.control.orientation, state.control.zoom.factor);
+        // The embedded data is decoded a part a frame once the placeholder is drawn
+        if state.startup.shown() {
+            startup::decode_next();
+        }
+        let gesture = state.gestures.tick(now()).map(|gesture| {
+            state.telemetry.record(TelemetryEvent::gesture(&gesture));
+            let location = locate(
+                &state.projection,
+                &state.control.orientation,
+                &transform,
+                &gesture.position,
+            );
+            (gesture, location)
+        });
+        state.telemetry.view(
+            &state.control.orientation,
+            !state.control.interacting()
+                && !state.control.coasting()
+                && !state.auto_rotate.rotating()
+                && !state.attract.touring()
+                && state.animation.is_none(),
+        );
+        // The view drawn is recorded, or compared with the view recorded while replaying
+        let view = View {
+            orientation: state.control.orientation,
+            zoom: state.control.zoom.factor,
+        };
+        state.recorder.frame(now(), view);
+        let time_window =
+            std::mem::take(&mut state.time_window_changed).then_some(state.time_window);
+        (
+            gesture,
+            time_window,
+            state.layer_status.take_changes(),
+            state.geofences.take_events(),
+            state.telemetry.take(),
+            state.recorder.finish(),
+        )
+    };
+    // Dispatched once the state is released as listeners may call back into the globe
+    if let Some((gesture, location)) = gesture {
+        dispatch_gesture(surface, &gesture, location).unwrap();
+    }
+    if let Some(time_window) = time_window {
+        dispatch_time_window(surface, time_window).unwrap();
+    }
+    for (layer, status) in statuses {
+        dispatch_layer_status(surface, layer, &status).unwrap();
+    }
+    for event in geofence_events {
+        dispatch_geofence(surface, &event).unwrap();
+    }
+    if let Some((callback, events)) = telemetry {
+        // Errors thrown by the host application should not stop the globe
+        if let Err(error) = telemetry::report(&callback, &events) {
+            web_sys::console::error_1(&error);
+        }
+    }
+    if let Some(outcome) = replayed {
+        recorder::set_clock(None);
+        dispatch_replay_end(surface, &outcome).unwrap();
+    }
+    emit(state, surface);
+}
 
-    let context = canvas
-        .get_context("2d")?
-        .expect("should have 2d context")
-        .dyn_into::<CanvasRenderingContext2d>()?;
+/// Dispatch the events of changes of the globe's state on the canvas, and pass their detail
+/// to the functions registered for them, once the state is released as listeners may call
+/// back into the globe.
+fn emit(state: &RefCell<State>, surface: &Surface) {
+    let events = state.borrow_mut().emitter.take();
+    for (event, callbacks) in events {
+        let detail = event.detail().unwrap();
+        let init = CustomEventInit::new();
+        init.set_detail(&detail);
+        let custom = CustomEvent::new_with_event_init_dict(event.event_type(), &init).unwrap();
+        surface.dispatch(&custom).unwrap();
+        for callback in callbacks {
+            // Errors thrown by the host application should not stop the globe
+            if let Err(error) = callback.call1(&JsValue::NULL, &detail) {
+                web_sys::console::error_1(&error);
+            }
+        }
+    }
+}
 
-    // Position calculations for plotting, etc... are performed for a unit sphere
-    // centred at the origin; values are scaled and translated to fit on the canvas
-    context.set_transform(
+/// Feed input recorded on the globe to it again.
+fn replay_input(state: &RefCell<State>, surface: &Surface, input: &recorder::Input) {
+    match input {
+        recorder::Input::PointerDown(input) => pointer_down(state, surface, input),
+        recorder::Input::PointerMove(input) => {
+            pointer_move(state, input);
+        }
+        recorder::Input::PointerUp(input) => pointer_up(state, surface, input),
+        recorder::Input::PointerLeave => pointer_leave(state),
+        recorder::Input::PointerCancel => pointer_cancel(state),
+        recorder::Input::Wheel(input) => {
+            wheel(state, input);
+        }
+        recorder::Input::Resize(width, height) => resize(state, (*width, *height)),
+    }
+}
+
+/// Location on the globe at a canvas position.
+fn locate(
+    projection: &Projection,
+    orientation: &Orientation,
+    transform: &DomMatrix,
+    position: &Position,
+) -> Option<LonLat> {
+    let (y, z) = controls::canvas_to_unit_coords(position.x, position.y, transform);
+    projection.unproject(orientation, y, z)
+}
+
+/// Dispatch a recognised gesture as a custom event on the canvas, with the canvas
+/// position, pointer type and any location on the globe in the event detail.
+fn dispatch_gesture(
+    surface: &Surface,
+    gesture: &Gesture,
+    location: Option<LonLat>,
+) -> Result<(), JsValue> {
+    let detail = js_sys::Object::new();
+    js_sys::Reflect::set(&detail, &"x".into(), &gesture.position.x.into())?;
+    js_sys::Reflect::set(&detail, &"y".into(), &gesture.position.y.into())?;
+    js_sys::Reflect::set(
+        &detail,
+        &"pointerType".into(),
+        &gesture.pointer_type.as_str().into(),
+    )?;
+    if let Some(location) = location {
+        js_sys::Reflect::set(&detail, &"lat".into(), &location.lat.into())?;
+        js_sys::Reflect::set(&detail, &"lon".into(), &location.lon.into())?;
+    }
+
+    let init = CustomEventInit::new();
+    init.set_detail(&detail);
+    let event = CustomEvent::new_with_event_init_dict(gesture.kind.event_type(), &init)?;
+    surface.dispatch(&event)?;
+
+    Ok(())
+}
+
+/// Dispatch a "globe-click" event on the canvas for a click on the globe, with the canvas
+/// position and location on the globe as detail.
+fn dispatch_click(surface: &Surface, position: &Position, location: LonLat) -> Result<(), JsValue> {
+    let detail = js_sys::Object::new();
+    js_sys::Reflect::set(&detail, &"x".into(), &position.x.into())?;
+    js_sys::Reflect::set(&detail, &"y".into(), &position.y.into())?;
+    js_sys::Reflect::set(&detail, &"lat".into(), &location.lat.into())?;
+    js_sys::Reflect::set(&detail, &"lon".into(), &location.lon.into())?;
+
+    let init = CustomEventInit::new();
+    init.set_detail(&detail);
+    let event = CustomEvent::new_with_event_init_dict("globe-click", &init)?;
+    surface.dispatch(&event)?;
+
+    Ok(())
+}
+
+/// Dispatch a "globe-control" event on the canvas for a press on a control drawn on the
+/// canvas, with the identifier of the control and the canvas position as detail.
+fn dispatch_control(surface: &Surface, control: &str, position: &Position) -> Result<(), JsValue> {
+    let detail = js_sys::Object::new();
+    js_sys::Reflect::set(&detail, &"control".into(), &control.into())?;
+    js_sys::Reflect::set(&detail, &"x".into(), &position.x.into())?;
+    js_sys::Reflect::set(&detail, &"y".into(), &position.y.into())?;
+
+    let init = CustomEventInit::new();
+    init.set_detail(&detail);
+    let event = CustomEvent::new_with_event_init_dict("globe-control", &init)?;
+    surface.dispatch(&event)?;
+
+    Ok(())
+}
+
+/// Dispatch a "select" event on the canvas, with the bounds of the selected region as detail.
+fn dispatch_selection(surface: &Surface, bounds: &Bounds) -> Result<(), JsValue> {
+    let init = CustomEventInit::new();
+    init.set_detail(&bounds.to_object()?);
+    let event = CustomEvent::new_with_event_init_dict("select", &init)?;
+    surface.dispatch(&event)?;
+
+    Ok(())
+}
+
+/// Dispatch a "timewindowchange" event on the canvas, with the window of time as detail, or
+/// null when all times are shown.
+fn dispatch_time_window(surface: &Surface, time_window: Option<TimeWindow>) -> Result<(), JsValue> {
+    let init = CustomEventInit::new();
+    match time_window {
+        Some(time_window) => init.set_detail(&time_window.to_object()?),
+        None => init.set_detail(&JsValue::NULL),
+    }
+    let event = CustomEvent::new_with_event_init_dict("timewindowchange", &init)?;
+    surface.dispatch(&event)?;
+
+    Ok(())
+}
+
+/// Dispatch a "replayend" event on the canvas, with the number of frames replayed and the
+/// index of the first whose view differed from the recording, or null, as detail.
+fn dispatch_replay_end(surface: &Surface, outcome: &ReplayOutcome) -> Result<(), JsValue> {
+    let detail = js_sys::Object::new();
+    js_sys::Reflect::set(&detail, &"frames".into(), &outcome.frames.into())?;
+    let diverged = outcome.diverged.map_or(JsValue::NULL, JsValue::from);
+    js_sys::Reflect::set(&detail, &"divergedFrame".into(), &diverged)?;
+    let init = CustomEventInit::new();
+    init.set_detail(&detail);
+    let event = CustomEvent::new_with_event_init_dict("replayend", &init)?;
+    surface.dispatch(&event)?;
+
+    Ok(())
+}
+
+/// Dispatch an entity entering or leaving a geofence as a "geofenceenter" or "geofenceexit"
+/// event on the canvas, with the crossing as detail.
+fn dispatch_geofence(surface: &Surface, event: &GeofenceEvent) -> Result<(), JsValue> {
+    let init = CustomEventInit::new();
+    init.set_detail(&event.to_object()?);
+    let event = CustomEvent::new_with_event_init_dict(event.event_type(), &init)?;
+    surface.dispatch(&event)?;
+
+    Ok(())
+}
+
+/// Dispatch a "layerstatuschange" event on the canvas, with the status of the layer as
+/// detail.
+fn dispatch_layer_status(
+    surface: &Surface,
+    layer: &str,
+    status: &LayerStatus,
+) -> Result<(), JsValue> {
+    let init = CustomEventInit::new();
+    init.set_detail(&status.to_object(layer)?);
+    let event = CustomEvent::new_with_event_init_dict("layerstatuschange", &init)?;
+    surface.dispatch(&event)?;
+
+    Ok(())
+}
+
+/// Size the backing store of the canvas with a ratio of its pixels to canvas pixels, keeping
+/// the size of the canvas on the page, and scale the context's transform to match; canvases
+/// sized by the page are left to its styles.
+fn size_canvas(
+    surface: &Surface,
+    context: &CanvasRenderingContext2d,
+    (width, height): (u32, u32),
+    pixel_ratio: f64,
+    responsive: bool,
+) -> Result<(), JsValue> {
+    surface.set_size(
+        (
+            (width as f64 * pixel_ratio).round() as u32,
+            (height as f64 * pixel_ratio).round() as u32,
+        ),
+        (!responsive).then_some((width, height)),
+    )?;
+
+    // Sizing the canvas resets the state of the context
+    set_context_transform(context, &canvas_transform((width, height)), pixel_ratio)?;
+    context.set_line_join("round");
+
+    Ok(())
+}
+
+/// Transform from the unit sphere, on which positions are calculated for plotting, to canvas
+/// pixels: the sphere is scaled to fit the canvas and centred on it.
+fn canvas_transform((width, height): (u32, u32)) -> DomMatrix {
+    let scale = std::cmp::min(width, height) as f64 / 2.0;
+    DomMatrix::new_with_array64(&mut [
         // horizontal scale
-        std::cmp::min(CANVAS_WIDTH, CANVAS_HEIGHT) as f64 / 2.0,
+        scale,
         0.0,
         0.0,
         // vertical scale, flipped
-        std::cmp::min(CANVAS_WIDTH, CANVAS_HEIGHT) as f64 / -2.0,
+        -scale,
         // horizontal translation
-        std::cmp::min(CANVAS_WIDTH, CANVAS_HEIGHT) as f64 / 2.0,
+        width as f64 / 2.0,
         // vertical translation
-        std::cmp::min(CANVAS_WIDTH, CANVAS_HEIGHT) as f64 / 2.0,
-    )?;
-    let context_transform = context.get_transform()?;
-    context.set_line_join("round");
+        height as f64 / 2.0,
+    ])
+    .expect("should create matrix")
+}
+
+/// Set the context's transform to a transform to canvas pixels, scaled to the pixels of the
+/// backing store.
+fn set_context_transform(
+    context: &CanvasRenderingContext2d,
+    transform: &DomMatrix,
+    pixel_ratio: f64,
+) -> Result<(), JsValue> {
+    let t = transform;
+    context.set_transform(
+        t.a() * pixel_ratio,
+        t.b() * pixel_ratio,
+        t.c() * pixel_ratio,
+        t.d() * pixel_ratio,
+        t.e() * pixel_ratio,
+        t.f() * pixel_ratio,
+    )
+}
 
-    let control_data = std::rc::Rc::new(std::cell::RefCell::new(ControlData::default()));
-    draw(&context, control_data.borrow().rotation)?;
+/// Draw the passes of the frame onto the canvas that fit within the frame budget.
+fn draw(context: &CanvasRenderingContext2d, state: &mut State) -> Result<(), JsValue> {
+    let pixel_ratio = state.render.pixel_ratio();
+    // Partial frames clear and draw only the regions changed
+    let Some(rects) = state.dirty.drawing().map(<[_]>::to_vec) else {
+        return draw_at(context, state, pixel_ratio);
+    };
+    context.save();
+    dirty::clip(context, &rects, pixel_ratio)?;
+    let drawn = draw_at(context, state, pixel_ratio);
+    context.restore();
+    drawn
+}
 
-    {
-        let control_data = control_data.clone();
-        let closure = Closure::<dyn FnMut(_)>::new(move |event: PointerEvent| {
-            let mut control_data = control_data.borrow_mut();
-            control_data.pressed = true;
-            control_data.position = Position {
-                x: event.offset_x() as f64,
-                y: event.offset_y() as f64,
-            };
-            control_data.position_prev = control_data.position.clone();
-        });
-        canvas.add_event_listener_with_callback("pointerdown", closure.as_ref().unchecked_ref())?;
-        closure.forget();
-    }
+/// Draw the passes of the frame that fit within the frame budget onto a canvas with a ratio of
+/// its pixels to canvas pixels.
+fn draw_at(
+    context: &CanvasRenderingContext2d,
+    state: &mut State,
+    pixel_ratio: f64,
+) -> Result<(), JsValue> {
+    let start = now();
+    // Line widths given for the unzoomed view keep their width on screen as the view zooms
+    let transform = state.transform();
+    set_context_transform(context, &transform, pixel_ratio)?;
+    let frame = Frame {
+        orientation: state.control.orientation,
+        projection: state.projection,
+        base_scale: std::cmp::min(state.canvas_size.0, state.canvas_size.1) as f64 / 2.0,
+        scale: transform.a(),
+        transform,
+        pixel_ratio,
+        geometry_stride: state.render.geometry_stride(),
+        line_widths: &state.line_widths,
+        filters: &state.filters,
+        zoom_ranges: &state.zoom_ranges,
+        locale: &state.locale,
+        style: &state.style,
+        time_window: state.time_window,
+        cull_back: state.render.back_culling,
+    };
 
-    {
-        let control_data = control_data.clone();
-        let closure = Closure::<dyn FnMut(_)>::new(move |event: PointerEvent| {
-            let mut control_data = control_data.borrow_mut();
-            if control_data.pressed {
-                control_data.position = Position {
-                    x: event.offset_x() as f64,
-                    y: event.offset_y() as f64,
-                };
-                event.prevent_default();
+    state.profiler.start("frame")?;
+    let canvas = context.canvas().expect("should have canvas");
+    state.layers.prepare(canvas.width(), canvas.height())?;
+    state.layers.project(&frame)?;
+    while let Some(pass) = state.scheduler.next_pass(now() - start) {
+        let pass_start = now();
+        state.profiler.start(pass.name())?;
+        // The whole canvas is cleared, as the clip shape may have changed and the zoomed
+        // out globe may not cover it
+        if pass == Pass::Sphere {
+            clear_canvas(context, &frame)?;
+        }
+        // Passes of layers draw the layers of the stack in its order
+        for (drawn, opacity) in state.layers.drawn_at(pass) {
+            context.save();
+            if let Some(shape) = &state.clip {
+                shape.apply(context, &frame)?;
             }
-        });
-        canvas.add_event_listener_with_callback("pointermove", closure.as_ref().unchecked_ref())?;
-        closure.forget();
+            if drawn.geographic() && !frame.projection.shows_back() {
+                context.begin_path();
+                context.arc(0.0, 0.0, 1.0, 0.0, std::f64::consts::TAU)?;
+                context.clip();
+            }
+            // Layers outside their range of zoom, or needing data while starting up, are not
+            // drawn
+            let starting = matches!(drawn, Drawn::Pass(pass) if !state.startup.draws(pass));
+            if drawn.layer().is_none_or(|layer| frame.shows_layer(layer)) && !starting {
+                state
+                    .layers
+                    .draw_with_opacity(context, opacity, |context| {
+                        draw_swiped(context, state, &frame, drawn.layer(), || {
+                            draw_layer(context, state, &frame, drawn)
+                        })
+                    })?;
+            }
+            context.restore();
+        }
+        state.profiler.end(pass.name())?;
+        state.scheduler.record(pass, now() - pass_start);
     }
+    state.profiler.end("frame")?;
 
-    {
-        let control_data = control_data.clone();
-        let closure = Closure::<dyn FnMut(_)>::new(move |event: PointerEvent| {
-            let mut control_data = control_data.borrow_mut();
-            control_data.pressed = false;
-            control_data.position = Position {
-                x: event.offset_x() as f64,
-                y: event.offset_y() as f64,
-            };
-        });
-        document.add_event_listener_with_callback("pointerup", closure.as_ref().unchecked_ref())?;
-        closure.forget();
+    Ok(())
+}
+
+/// Draw every pass of the current frame at once onto a new canvas with a ratio of its pixels to
+/// canvas pixels, leaving the passes of the globe's own canvas to its frames.
+fn snapshot(state: &mut State, pixel_ratio: f64) -> Result<HtmlCanvasElement, JsValue> {
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| JsValue::from_str("globes in workers cannot be exported"))?;
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    let context = canvas
+        .get_context("2d")?
+        .expect("should have 2d context")
+        .dyn_into::<CanvasRenderingContext2d>()?;
+    let surface = Surface::Element(canvas.clone());
+    size_canvas(&surface, &context, state.canvas_size, pixel_ratio, false)?;
+
+    let mut scheduler = Scheduler::default();
+    scheduler.budget = f64::INFINITY;
+    scheduler.start();
+    let scheduler = std::mem::replace(&mut state.scheduler, scheduler);
+    let drawn = draw_at(&context, state, pixel_ratio);
+    state.scheduler = scheduler;
+    drawn?;
+    Ok(canvas)
+}
+
+/// Clear the canvas to the background, within any clip.
+fn clear_canvas(context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+    context.save();
+    let (width, height) = overlay::canvas_pixels(context, frame.pixel_ratio)?;
+    context.clear_rect(0.0, 0.0, width, height);
+    if let Some(background) = &frame.style.background {
+        context.set_fill_style_str(background);
+        context.fill_rect(0.0, 0.0, width, height);
     }
+    context.restore();
+    Ok(())
+}
 
-    // Tranform from canvas coordinates to unit circle
-    // coordinates by reversing the context transform
-    let canvas_to_unit_coords = |x: f64, y: f64, reverse_transform: &DomMatrix| {
-        (
-            (x - reverse_transform.e()) / reverse_transform.a(),
-            (y - reverse_transform.f()) / reverse_transform.d(),
-        )
+/// Draw the region of the frame under the pointer again, magnified, within the lens.
+fn draw_magnifier(
+    context: &CanvasRenderingContext2d,
+    state: &State,
+    frame: &Frame,
+) -> Result<(), JsValue> {
+    let Some(transform) = state.magnifier.transform(&frame.transform) else {
+        return Ok(());
+    };
+    let lens = Frame {
+        scale: transform.a(),
+        transform,
+        ..*frame
     };
 
-    // Calculate the (positive) third coordinate value on
-    // a unit sphere given the other two coordinate values
-    let third_coord_val = |first: f64, second: f64| (1.0 - first * first - second * second).sqrt();
-
-    let f = std::rc::Rc::new(std::cell::RefCell::new(None));
-    let g = f.clone();
-    *g.borrow_mut() = Some(Closure::new(move || {
-        let mut control_data = control_data.borrow_mut();
-        if control_data.position != control_data.position_prev {
-            let (y, z) = canvas_to_unit_coords(
-                control_data.position.x,
-                control_data.position.y,
-                &context_transform,
-            );
-            let x = third_coord_val(y, z);
-            if !x.is_nan() {
-                let (y_prev, z_prev) = canvas_to_unit_coords(
-                    control_data.position_prev.x,
-                    control_data.position_prev.y,
-                    &context_transform,
-                );
-                let x_prev = third_coord_val(y_prev, z_prev);
-                if !x_prev.is_nan() {
-                    let (_, phi) = cartesian_to_unit_spherical(x, y, z);
-                    let (_, phi_prev) = cartesian_to_unit_spherical(x_prev, y_prev, z_prev);
+    context.save();
+    state.magnifier.clip(context, frame.pixel_ratio)?;
+    clear_canvas(context, &lens)?;
+    set_context_transform(context, &lens.transform, lens.pixel_ratio)?;
+    // Layers added at runtime are projected for the frame, not the lens, so are not magnified
+    let passes = Pass::ALL
+        .into_iter()
+        .flat_map(|pass| state.layers.drawn_at(pass))
+        .filter_map(|(drawn, opacity)| match drawn {
+            Drawn::Pass(pass) if pass.magnified() => Some((pass, opacity)),
+            _ => None,
+        });
+    for (pass, opacity) in passes {
+        context.save();
+        if pass.geographic() && !lens.projection.shows_back() {
+            context.begin_path();
+            context.arc(0.0, 0.0, 1.0, 0.0, std::f64::consts::TAU)?;
+            context.clip();
+        }
+        if pass.layer().is_none_or(|layer| lens.shows_layer(layer)) {
+            state
+                .layers
+                .draw_with_opacity(context, opacity, |context| {
+                    draw_swiped(context, state, &lens, pass.layer(), || {
+                        // The lens shows the level of detail of the coastlines for its own zoom
+                        if pass == Pass::Coastlines {
+                            let level = state.detail.level_at(lens.zoom());
+                            draw_coastlines(context, &lens, level, &state.profiler)
+                        } else {
+                            draw_pass(context, state, &lens, pass)
+                        }
+                    })
+                })?;
+        }
+        context.restore();
+    }
+    context.restore();
 
-                    control_data.position_prev = control_data.position.clone();
-                    control_data.rotation += phi - phi_prev;
+    state.magnifier.draw_border(context, frame.pixel_ratio)
+}
 
-                    draw(&context, control_data.rotation).unwrap();
-                }
+/// Draw a layer, clipped to the halves of the canvas where the swipe shows it.
+fn draw_swiped(
+    context: &CanvasRenderingContext2d,
+    state: &State,
+    frame: &Frame,
+    layer: Option<&str>,
+    draw: impl Fn() -> Result<(), JsValue>,
+) -> Result<(), JsValue> {
+    let Some(halves) = layer.and_then(|layer| state.swipe.halves(layer)) else {
+        return draw();
+    };
+    for (half, shown) in [Half::Left, Half::Right].into_iter().zip(halves) {
+        if shown {
+            context.save();
+            state.swipe.clip(context, half, frame.pixel_ratio)?;
+            draw()?;
+            context.restore();
+        }
+    }
+    Ok(())
+}
+
+/// Draw a layer of the stack onto the canvas.
+fn draw_layer(
+    context: &CanvasRenderingContext2d,
+    state: &State,
+    frame: &Frame,
+    drawn: Drawn,
+) -> Result<(), JsValue> {
+    match drawn {
+        Drawn::Pass(pass) => draw_pass(context, state, frame, pass),
+        Drawn::Added(_, layer) => layer.draw(context, frame),
+    }
+}
+
+/// Draw a pass of the frame onto the canvas.
+fn draw_pass(
+    context: &CanvasRenderingContext2d,
+    state: &State,
+    frame: &Frame,
+    pass: Pass,
+) -> Result<(), JsValue> {
+    match pass {
+        Pass::Sphere => {
+            context.set_fill_style_str(&frame.style.sphere_fill);
+            context.begin_path();
+            context.arc(0.0, 0.0, 1.0, 0.0, std::f64::consts::TAU)?;
+            context.fill();
+        }
+        Pass::Tiles => state.tiles.draw(context, frame, &state.fetcher)?,
+        Pass::Land => state.land.draw(context, frame)?,
+        Pass::Startup => state.startup.draw(context, frame)?,
+        Pass::Graticule => state.graticule.draw(context, frame)?,
+        Pass::Rings => state.rings.draw(context, frame)?,
+        Pass::Choropleth => state.choropleth.draw(context, frame)?,
+        Pass::Cartogram => state.cartogram.draw(context, frame, now())?,
+        Pass::Voronoi => state.voronoi.draw(context, frame)?,
+        Pass::Hexbins => state.hexbins.draw(context, frame)?,
+        Pass::Coastlines => {
+            // Levels of detail are drawn together while cross-fading between them
+            for (level, alpha) in state.detail.levels() {
+                context.set_global_alpha(alpha);
+                draw_coastlines(context, frame, level, &state.profiler)?;
+            }
+            context.set_global_alpha(1.0);
+        }
+        Pass::Terminator => {
+            let clock = js_sys::Date::now();
+            state.terminator.draw(context, frame, clock)?;
+            // The sub-lunar point is shown with the night side, at the time it is shaded at
+            let time = state.terminator.time.unwrap_or(clock);
+            state.moon.draw_sublunar(context, frame, clock, time)?;
+        }
+        Pass::Contours => state.contours.draw(context, frame)?,
+        Pass::Spikes => state.spikes.draw(context, frame)?,
+        Pass::Symbols => state.symbols.draw(context, frame)?,
+        Pass::Arcs => state.arcs.draw(context, frame)?,
+        Pass::Markers => state.markers.draw(context, frame, &state.icons)?,
+        Pass::Tracks => state.tracks.draw(context, frame)?,
+        Pass::Route => state.route.draw(context, frame)?,
+        Pass::Selection => state.selection.draw(context, frame)?,
+        Pass::Extent => state.extent.draw(context, frame)?,
+        Pass::Quiz => state.quiz.draw(context, frame, now())?,
+        Pass::Pings => state.pings.draw(context, frame, now())?,
+        Pass::Limb => state.limb.apply(context, frame)?,
+        Pass::Labels => {
+            // Labels from all layers shown are decluttered together
+            let mut labels = Vec::new();
+            if frame.shows_layer("graticule") {
+                labels.extend(state.graticule.labels(frame));
+            }
+            labels.extend(state.country_labels.labels(frame));
+            if frame.shows_layer("spikes") {
+                labels.extend(state.spikes.labels(frame));
+            }
+            if frame.shows_layer("route") {
+                labels.extend(state.route.labels(frame));
+            }
+            if frame.shows_layer("contours") {
+                labels.extend(state.contours.labels(frame));
+            }
+            labels::draw(context, &labels, frame.pixel_ratio, state.label_font)?;
+        }
+        Pass::PostProcess => {
+            if state.render.post_effects() {
+                postprocess::apply(context, &state.post_effects)?;
             }
         }
-        request_animation_frame(f.borrow().as_ref().unwrap());
-    }));
-    request_animation_frame(g.borrow().as_ref().unwrap());
+        Pass::Magnifier => draw_magnifier(context, state, frame)?,
+        Pass::Swipe => state.swipe.draw(context, frame.pixel_ratio)?,
+        Pass::Legend => {
+            // Legends from all layers are stacked together
+            let legends = [
+                state.rings.legend(frame),
+                state.choropleth.legend(),
+                state.hexbins.legend(),
+                state.spikes.legend(frame),
+                state.symbols.legend(),
+            ];
+            let rect = state.legends.draw(
+                context,
+                &legends.into_iter().flatten().collect::<Vec<_>>(),
+                frame.pixel_ratio,
+            )?;
+            state.hit_regions.set("legend", rect);
+        }
+        Pass::Moon => {
+            let clock = js_sys::Date::now();
+            let time = state.terminator.time.unwrap_or(clock);
+            state
+                .moon
+                .draw_widget(context, frame.pixel_ratio, clock, time)?;
+        }
+        Pass::Attribution => {
+            let rect = state.attribution.draw(context, frame.pixel_ratio)?;
+            state.hit_regions.set("attribution", rect);
+        }
+        Pass::Status => state.layer_status.draw(context, frame.pixel_ratio)?,
+    }
 
     Ok(())
 }
 
-/// Draw data onto the canvas.
-fn draw(context: &CanvasRenderingContext2d, rotation: f64) -> Result<(), JsValue> {
-    context.clear_rect(-1.0, -1.0, 2.0, 2.0);
+/// Draw a level of detail of the coastlines onto the canvas, in phases culling, projecting
+/// and stroking them, with the segments on each side of the globe stroked as one path.
+fn draw_coastlines(
+    context: &CanvasRenderingContext2d,
+    frame: &Frame,
+    level: &CoastlineLevel,
+    profiler: &Profiler,
+) -> Result<(), JsValue> {
+    profiler.start("cull")?;
+    let sides = level
+        .bounds
+        .iter()
+        .map(|bounds| bounds.side(&frame.projection, &frame.orientation))
+        .collect::<Vec<_>>();
+    profiler.end("cull")?;
 
-    context.set_fill_style(&JsValue::from_str(SPHERE_FILL_STYLE));
-    context.begin_path();
-    context.arc(0.0, 0.0, 1.0, 0.0, std::f64::consts::TAU)?;
-    context.fill();
+    profiler.start("project")?;
+    // Features on the back are not drawn when the projection clips the back, or when it is
+    // culled and not dimmed
+    let dimmed = frame.culled_back_opacity();
+    let polylines = level
+        .points
+        .iter()
+        .zip(&sides)
+        .zip(level.attributes)
+        .map(|((polyline, side), attributes)| {
+            let shown = *side != Side::Back || frame.draws_back() || dimmed.is_some();
+            (shown && frame.filters.shows_attributes("coastline", attributes)).then(|| {
+                lod::thin(polyline, frame.geometry_stride)
+                    .map(|point| {
+                        let (lon, lat) = point.to_degrees();
+                        frame.project(lon, lat)
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect::<Vec<_>>();
+    profiler.end("project")?;
 
-    for polyline in data::COASTLINE_POINTS {
-        let mut prev_point = None;
-        for point in *polyline {
-            let (lon, lat) = point;
-            let (x, y, z) = unit_spherical_to_cartesian(90.0 - lat, lon + rotation);
-            if let Some((x_prev, y_prev, z_prev)) = prev_point {
-                if x_prev < 0.0 || x < 0.0 {
-                    context.set_line_width(COAST_BACK_LINE_WIDTH);
-                    context.set_stroke_style(&JsValue::from_str(COAST_BACK_STROKE_STYLE));
+    profiler.start("stroke")?;
+    // Segments are gathered into a path for each side, so the level is stroked twice
+    // however dense its lines
+    let (front, back) = (Path2d::new()?, Path2d::new()?);
+    for (polyline, side) in polylines.iter().zip(sides) {
+        let Some(polyline) = polyline else {
+            continue;
+        };
+        // Features clear of the limb are added as a single subpath
+        if side != Side::Both {
+            let path = if side == Side::Front { &front } else { &back };
+            for (index, (_, y, z)) in polyline.iter().enumerate() {
+                if index == 0 {
+                    path.move_to(*y, *z);
                 } else {
-                    context.set_line_width(COAST_FRONT_LINE_WIDTH);
-                    context.set_stroke_style(&JsValue::from_str(COAST_FRONT_STROKE_STYLE));
+                    path.line_to(*y, *z);
                 }
-                context.begin_path();
-                context.move_to(y_prev, z_prev);
-                context.line_to(y, z);
-                context.stroke()
             }
-            prev_point = Some((x, y, z));
+            continue;
+        }
+
+        // Runs of segments on the same side continue a subpath
+        let mut previous = None;
+        for segment in polyline.windows(2) {
+            let ((_, y_prev, z_prev), (_, y, z)) = (segment[0], segment[1]);
+            let side = frame.projection.segment_side(segment[0], segment[1]);
+            let path = match side {
+                Some(Side::Front) => &front,
+                Some(_) => &back,
+                None => {
+                    previous = None;
+                    continue;
+                }
+            };
+            if previous != side {
+                path.move_to(y_prev, z_prev);
+            }
+            path.line_to(y, z);
+            previous = side;
         }
-        context.stroke();
     }
 
+    // The back is stroked first, dimmed where it is culled, so the front covers it
+    if frame.draws_back() || dimmed.is_some() {
+        let alpha = context.global_alpha();
+        context.set_global_alpha(alpha * dimmed.unwrap_or(1.0));
+        context.set_line_width(frame.line_width(frame.style.coast_back_width));
+        context.set_stroke_style_str(&frame.style.coast_back_stroke);
+        context.stroke_with_path(&back);
+        context.set_global_alpha(alpha);
+    }
+    context.set_line_width(frame.line_width(frame.style.coast_front_width));
+    context.set_stroke_style_str(&frame.style.coast_front_stroke);
+    context.stroke_with_path(&front);
+    profiler.end("stroke")?;
+
     Ok(())
 }
 
@@ -0,0 +1,159 @@
+// Refinement of the edge of the sphere: the pixels of a thin annulus along the limb are
+// redrawn from the coverage of the disc, multi-sampled within each pixel, blending the
+// colours just inside and just outside the edge, so the silhouette does not alias.
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
+use web_sys::{CanvasRenderingContext2d, ImageData};
+
+use crate::view::Frame;
+
+// Distances are in backing store pixels
+// Pixels whose centres are within this distance of the edge are refined, covering every
+// pixel the edge passes through
+const ANNULUS_HALF_WIDTH: f64 = 1.0;
+// Distance along the radius of the pixels the colours inside and outside the edge are
+// taken from, clear of the annulus
+const NEIGHBOUR_DISTANCE: f64 = 2.0;
+const MAX_SAMPLES: u32 = 8;
+
+#[derive(Debug, Default)]
+pub struct LimbSmoothing {
+    /// Samples along each side of a pixel, or `None` for the edge not to be refined.
+    samples: Option<u32>,
+}
+
+impl LimbSmoothing {
+    /// Set the samples along each side of a pixel, from 2 to 8, or `None` for the edge not
+    /// to be refined.
+    pub fn set_samples(&mut self, samples: Option<u32>) {
+        self.samples = samples.map(|samples| samples.clamp(2, MAX_SAMPLES));
+    }
+
+    /// Refine the pixels along the edge of the sphere on the canvas.
+    pub fn apply(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        let Some(samples) = self.samples else {
+            return Ok(());
+        };
+        let canvas = context.canvas().expect("should have canvas");
+        let (cx, cy) = frame.to_canvas(0.0, 0.0);
+        let (cx, cy) = (cx * frame.pixel_ratio, cy * frame.pixel_ratio);
+        let radius = frame.scale * frame.pixel_ratio;
+
+        // Only the square about the disc, within the canvas, is read back
+        let margin = radius + ANNULUS_HALF_WIDTH + NEIGHBOUR_DISTANCE + 1.0;
+        let left = (cx - margin).floor().max(0.0);
+        let top = (cy - margin).floor().max(0.0);
+        let right = (cx + margin).ceil().min(canvas.width() as f64);
+        let bottom = (cy + margin).ceil().min(canvas.height() as f64);
+        if right <= left || bottom <= top {
+            return Ok(());
+        }
+        let (width, height) = ((right - left) as usize, (bottom - top) as usize);
+
+        let image_data = context.get_image_data(left, top, width as f64, height as f64)?;
+        let source = image_data.data();
+        let mut data = source.clone();
+        let pixel = |x: f64, y: f64| -> Option<[u8; 4]> {
+            let (x, y) = (x.floor() - left, y.floor() - top);
+            if x < 0.0 || y < 0.0 || x >= width as f64 || y >= height as f64 {
+                return None;
+            }
+            let index = (y as usize * width + x as usize) * 4;
+            source.get(index..index + 4)?.try_into().ok()
+        };
+
+        for row in 0..height {
+            let y = top + row as f64 + 0.5;
+            let dy = y - cy;
+            // Columns of the row within the annulus, either side of the centre
+            let outer = (radius + ANNULUS_HALF_WIDTH).powi(2) - dy * dy;
+            if outer < 0.0 {
+                continue;
+            }
+            let inner = (radius - ANNULUS_HALF_WIDTH).max(0.0).powi(2) - dy * dy;
+            let (outer, inner) = (outer.sqrt(), inner.max(0.0).sqrt());
+            for (from, to) in [(cx - outer, cx - inner), (cx + inner, cx + outer)] {
+                let (from, to) = ((from - left).floor().max(0.0), (to - left).ceil());
+                for column in from as usize..(to as usize).min(width) {
+                    let x = left + column as f64 + 0.5;
+                    let (dx, distance) = (x - cx, (x - cx).hypot(dy));
+                    if distance == 0.0 || (distance - radius).abs() > ANNULUS_HALF_WIDTH {
+                        continue;
+                    }
+                    let (ux, uy) = (dx / distance, dy / distance);
+                    let inside = pixel(x - ux * NEIGHBOUR_DISTANCE, y - uy * NEIGHBOUR_DISTANCE);
+                    let outside = pixel(x + ux * NEIGHBOUR_DISTANCE, y + uy * NEIGHBOUR_DISTANCE);
+                    let (Some(inside), Some(outside)) = (inside, outside) else {
+                        continue;
+                    };
+                    let coverage = coverage(x - 0.5 - cx, y - 0.5 - cy, radius, samples);
+                    let index = (row * width + column) * 4;
+                    data[index..index + 4].copy_from_slice(&blend(inside, outside, coverage));
+                }
+            }
+        }
+
+        let image_data = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(&data),
+            width as u32,
+            height as u32,
+        )?;
+        context.put_image_data(&image_data, left, top)?;
+        Ok(())
+    }
+}
+
+/// Fraction of a pixel, with its top left corner at an offset (pixels) from the centre of a
+/// disc, covered by the disc, from a grid of samples along each side of the pixel.
+fn coverage(x: f64, y: f64, radius: f64, samples: u32) -> f64 {
+    let step = 1.0 / samples as f64;
+    let offset = |i: u32| (i as f64 + 0.5) * step;
+    let covered = (0..samples)
+        .flat_map(|i| (0..samples).map(move |j| (offset(i), offset(j))))
+        .filter(|(sx, sy)| (x + sx).hypot(y + sy) <= radius)
+        .count();
+    covered as f64 / (samples * samples) as f64
+}
+
+/// Colour (RGBA, not premultiplied) of a pixel covered in part by one colour over another.
+fn blend(over: [u8; 4], under: [u8; 4], coverage: f64) -> [u8; 4] {
+    let (over_alpha, under_alpha) = (
+        over[3] as f64 * coverage,
+        under[3] as f64 * (1.0 - coverage),
+    );
+    let alpha = over_alpha + under_alpha;
+    if alpha <= 0.0 {
+        return [0, 0, 0, 0];
+    }
+    let channel = |i: usize| {
+        ((over[i] as f64 * over_alpha + under[i] as f64 * under_alpha) / alpha).round() as u8
+    };
+    [
+        channel(0),
+        channel(1),
+        channel(2),
+        alpha.round().min(255.0) as u8,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blends_by_the_coverage_of_the_disc() {
+        // Pixels well inside and outside the edge, and one straddling it
+        assert_eq!(coverage(-0.5, -0.5, 10.0, 4), 1.0);
+        assert_eq!(coverage(11.0, 0.0, 10.0, 4), 0.0);
+        assert_eq!(coverage(9.5, -0.5, 10.0, 4), 0.5);
+
+        let (sphere, transparent) = ([0, 0, 255, 255], [255, 255, 255, 0]);
+        assert_eq!(blend(sphere, transparent, 1.0), sphere);
+        assert_eq!(blend(sphere, transparent, 0.5), [0, 0, 255, 128]);
+        assert_eq!(
+            blend([0, 0, 0, 255], [255, 255, 255, 255], 0.5),
+            [128, 128, 128, 255]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+// Levels of detail of the coastline, cross-faded as the zoom of the view crosses a threshold
+// so the detailed geometry appears without popping.
+
+use crate::data::{self, Polylines};
+use crate::info::{Attribute, FeatureBounds};
+
+// Times are in milliseconds
+const DETAIL_FADE_DURATION: f64 = 300.0;
+const DETAIL_DEFAULT_ZOOM: f64 = 2.5;
+
+/// Geometry of the coastline at a level of detail.
+#[derive(Debug)]
+pub struct CoastlineLevel {
+    pub points: &'static Polylines,
+    pub bounds: &'static [FeatureBounds],
+    pub attributes: &'static [&'static [Attribute]],
+}
+
+static COARSE: CoastlineLevel = CoastlineLevel {
+    points: &data::COASTLINE_POINTS,
+    bounds: data::COASTLINE_BOUNDS,
+    attributes: data::COASTLINE_ATTRIBUTES,
+};
+
+static DETAILED: CoastlineLevel = CoastlineLevel {
+    points: &data::DETAILED_COASTLINE_POINTS,
+    bounds: data::DETAILED_COASTLINE_BOUNDS,
+    attributes: data::DETAILED_COASTLINE_ATTRIBUTES,
+};
+
+#[derive(Debug)]
+pub struct CoastlineDetail {
+    /// Zoom from which the detailed level is drawn.
+    pub zoom: f64,
+    /// Progress of the fade from the coarse level (0) to the detailed level (1).
+    fade: f64,
+    time_prev: Option<f64>,
+}
+
+impl Default for CoastlineDetail {
+    fn default() -> Self {
+        Self {
+            zoom: DETAIL_DEFAULT_ZOOM,
+            fade: 0.0,
+            time_prev: None,
+        }
+    }
+}
+
+impl CoastlineDetail {
+    /// Fade towards the level of detail for a zoom at a time, returning whether the fade
+    /// progressed; without the detailed data the coarse level is always drawn.
+    pub fn tick(&mut self, zoom: f64, time: f64) -> bool {
+        let detailed = zoom >= self.zoom && !DETAILED.points.is_empty();
+        let target = if detailed { 1.0 } else { 0.0 };
+        if self.fade == target {
+            // Fades start from their first frame, however long the globe was at rest
+            self.time_prev = None;
+            return false;
+        }
+        let interval = time - self.time_prev.unwrap_or(time);
+        self.time_prev = Some(time);
+        let step = interval.max(0.0) / DETAIL_FADE_DURATION;
+        self.fade = if target > self.fade {
+            (self.fade + step).min(target)
+        } else {
+            (self.fade - step).max(target)
+        };
+        true
+    }
+
+    /// Whether the levels are fading towards the level of detail for a zoom.
+    pub fn fading(&self, zoom: f64) -> bool {
+        let detailed = zoom >= self.zoom && !DETAILED.points.is_empty();
+        self.fade != if detailed { 1.0 } else { 0.0 }
+    }
+
+    /// Level of detail drawn at a zoom without fading, as within the magnifier.
+    pub fn level_at(&self, zoom: f64) -> &'static CoastlineLevel {
+        if zoom >= self.zoom && !DETAILED.points.is_empty() {
+            &DETAILED
+        } else {
+            &COARSE
+        }
+    }
+
+    /// Levels of detail drawn, with their opacities.
+    pub fn levels(&self) -> impl Iterator<Item = (&'static CoastlineLevel, f64)> {
+        [(&COARSE, 1.0 - self.fade), (&DETAILED, self.fade)]
+            .into_iter()
+            .filter(|(_, alpha)| *alpha > 0.0)
+    }
+}
+
+/// Simplified polyline keeping every few points, by a stride of at least one, and its ends.
+pub fn thin<T>(polyline: &[T], stride: usize) -> impl Iterator<Item = &T> {
+    let stride = stride.max(1);
+    let last = polyline.len().saturating_sub(1);
+    polyline
+        .iter()
+        .enumerate()
+        .filter(move |(i, _)| i.is_multiple_of(stride) || *i == last)
+        .map(|(_, point)| point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fades_over_duration() {
+        let mut detail = CoastlineDetail {
+            fade: 1.0,
+            ..CoastlineDetail::default()
+        };
+        detail.tick(1.0, 0.0);
+        assert!(detail.tick(1.0, DETAIL_FADE_DURATION / 2.0));
+        assert_eq!(detail.levels().count(), 2);
+        assert!(detail.tick(1.0, DETAIL_FADE_DURATION));
+        assert_eq!(detail.levels().count(), 1);
+        assert!(!detail.tick(1.0, 2.0 * DETAIL_FADE_DURATION));
+    }
+
+    #[test]
+    fn thins_keeping_ends() {
+        let points = [0, 1, 2, 3, 4, 5, 6];
+        assert_eq!(thin(&points, 3).collect::<Vec<_>>(), [&0, &3, &6]);
+        assert_eq!(thin(&points, 4).collect::<Vec<_>>(), [&0, &4, &6]);
+        assert_eq!(thin(&points, 0).count(), points.len());
+        assert_eq!(thin(&[] as &[i32], 2).count(), 0);
+    }
+}
@@ -0,0 +1,146 @@
+// Lens magnifying the globe under the pointer: the region is drawn again at a larger scale
+// into a circular inset following the pointer, without changing the zoom of the view.
+
+use wasm_bindgen::JsValue;
+use web_sys::{CanvasRenderingContext2d, DomMatrix};
+
+use crate::overlay;
+
+// Sizes are in canvas pixels
+const LENS_DEFAULT_RADIUS: f64 = 80.0;
+const LENS_DEFAULT_MAGNIFICATION: f64 = 3.0;
+const LENS_MIN_MAGNIFICATION: f64 = 1.0;
+const LENS_MAX_MAGNIFICATION: f64 = 20.0;
+const LENS_BORDER_STYLE: &str = "rgba(255, 255, 255, 0.9)";
+const LENS_BORDER_SHADOW_STYLE: &str = "rgba(0, 0, 0, 0.4)";
+const LENS_BORDER_WIDTH: f64 = 2.0;
+
+#[derive(Debug)]
+pub struct Magnifier {
+    pub enabled: bool,
+    /// Position (canvas pixels) of the pointer over the canvas, or `None` when it is not over
+    /// the canvas.
+    pub position: Option<(f64, f64)>,
+    /// Radius (canvas pixels) of the lens.
+    radius: f64,
+    /// Factor the globe is scaled by within the lens.
+    magnification: f64,
+}
+
+impl Default for Magnifier {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            position: None,
+            radius: LENS_DEFAULT_RADIUS,
+            magnification: LENS_DEFAULT_MAGNIFICATION,
+        }
+    }
+}
+
+impl Magnifier {
+    /// Set the radius (canvas pixels) and magnification of the lens, within usable ranges.
+    pub fn set_size(&mut self, radius: f64, magnification: f64) {
+        if radius.is_finite() {
+            self.radius = radius.max(0.0);
+        }
+        if !magnification.is_nan() {
+            self.magnification =
+                magnification.clamp(LENS_MIN_MAGNIFICATION, LENS_MAX_MAGNIFICATION);
+        }
+    }
+
+    /// Whether the lens is drawn.
+    pub fn shown(&self) -> bool {
+        self.enabled && self.position.is_some() && self.radius > 0.0
+    }
+
+    /// Transform from unit sphere coordinates to canvas pixels within the lens, magnifying the
+    /// view's transform about the pointer, when the lens is drawn.
+    pub fn transform(&self, transform: &DomMatrix) -> Option<DomMatrix> {
+        let centre = self.position.filter(|_| self.shown())?;
+        let t = transform;
+        let mut magnified = magnify(
+            [t.a(), t.b(), t.c(), t.d(), t.e(), t.f()],
+            centre,
+            self.magnification,
+        );
+        Some(DomMatrix::new_with_array64(&mut magnified).expect("should create matrix"))
+    }
+
+    /// Clip subsequent drawing to the lens; the clip is removed by restoring the context.
+    pub fn clip(
+        &self,
+        context: &CanvasRenderingContext2d,
+        pixel_ratio: f64,
+    ) -> Result<(), JsValue> {
+        let Some((x, y)) = self.position else {
+            return Ok(());
+        };
+        let unit = context.get_transform()?;
+        overlay::canvas_pixels(context, pixel_ratio)?;
+        context.begin_path();
+        context.arc(x, y, self.radius, 0.0, std::f64::consts::TAU)?;
+        context.clip();
+        context.set_transform(unit.a(), unit.b(), unit.c(), unit.d(), unit.e(), unit.f())
+    }
+
+    /// Draw the border of the lens onto the canvas.
+    pub fn draw_border(
+        &self,
+        context: &CanvasRenderingContext2d,
+        pixel_ratio: f64,
+    ) -> Result<(), JsValue> {
+        let Some((x, y)) = self.position else {
+            return Ok(());
+        };
+        context.save();
+        overlay::canvas_pixels(context, pixel_ratio)?;
+        // A shadow outside the border sets the lens apart from the globe behind
+        for (style, radius) in [
+            (LENS_BORDER_SHADOW_STYLE, self.radius + LENS_BORDER_WIDTH),
+            (LENS_BORDER_STYLE, self.radius),
+        ] {
+            context.set_line_width(LENS_BORDER_WIDTH);
+            context.set_stroke_style_str(style);
+            context.begin_path();
+            context.arc(x, y, radius, 0.0, std::f64::consts::TAU)?;
+            context.stroke();
+        }
+        context.restore();
+        Ok(())
+    }
+}
+
+/// Components of a transform to canvas pixels scaled by a factor about a canvas position.
+fn magnify(transform: [f64; 6], (x, y): (f64, f64), factor: f64) -> [f64; 6] {
+    let [a, b, c, d, e, f] = transform;
+    [
+        a * factor,
+        b * factor,
+        c * factor,
+        d * factor,
+        x + (e - x) * factor,
+        y + (f - y) * factor,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magnifies_about_the_pointer() {
+        let transform = [100.0, 0.0, 0.0, -100.0, 200.0, 200.0];
+        let magnified = magnify(transform, (250.0, 150.0), 2.0);
+        assert_eq!(magnified, [200.0, 0.0, 0.0, -200.0, 150.0, 250.0]);
+        // The point of the globe under the pointer stays under it
+        let under = |[a, _, _, d, e, f]: [f64; 6]| ((250.0 - e) / a, (150.0 - f) / d);
+        assert_eq!(under(transform), under(magnified));
+
+        let mut magnifier = Magnifier::default();
+        magnifier.set_size(50.0, 100.0);
+        assert_eq!(magnifier.magnification, LENS_MAX_MAGNIFICATION);
+        assert!(!magnifier.shown());
+    }
+}
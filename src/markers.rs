@@ -0,0 +1,228 @@
+// Markers plotting points at locations, drawn as dots or icons on the front of the globe and
+// dimmed or hidden on the back.
+
+use wasm_bindgen::prelude::*;
+use web_sys::{CanvasRenderingContext2d, DomMatrix};
+
+use crate::atlas::IconAtlas;
+use crate::coords::LonLat;
+use crate::overlay;
+use crate::projection::Projection;
+use crate::view::{self, Frame, Orientation};
+
+const MARKER_DEFAULT_COLOR: &str = "rgba(220, 20, 60, 1.0)";
+const MARKER_DEFAULT_OUTLINE: &str = "rgba(255, 255, 255, 1.0)";
+// Radii are in canvas pixels
+const MARKER_DEFAULT_RADIUS: f64 = 4.0;
+const MARKER_DEFAULT_BACK_OPACITY: f64 = 0.3;
+pub const MARKER_LINE_WIDTH: f64 = 0.002;
+
+/// Style of a marker.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarkerStyle {
+    /// CSS color of the dot.
+    #[wasm_bindgen(getter_with_clone)]
+    pub color: String,
+    /// CSS color of the outline of the dot, or `None` for no outline.
+    #[wasm_bindgen(getter_with_clone)]
+    pub outline: Option<String>,
+    /// Radius (canvas pixels) of the dot, or half the height of the icon.
+    pub radius: f64,
+    /// Opacity of the marker when on the back of the globe, where it shows through; 0 hides it.
+    pub back_opacity: f64,
+    /// Name of an icon added to the globe drawn in place of the dot, upright and centred on
+    /// the location, or `None` for the dot.
+    #[wasm_bindgen(getter_with_clone)]
+    pub icon: Option<String>,
+}
+
+impl Default for MarkerStyle {
+    fn default() -> Self {
+        Self {
+            color: MARKER_DEFAULT_COLOR.to_string(),
+            outline: Some(MARKER_DEFAULT_OUTLINE.to_string()),
+            radius: MARKER_DEFAULT_RADIUS,
+            back_opacity: MARKER_DEFAULT_BACK_OPACITY,
+            icon: None,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl MarkerStyle {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> MarkerStyle {
+        MarkerStyle::default()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Marker {
+    id: u32,
+    location: LonLat,
+    style: MarkerStyle,
+}
+
+#[derive(Debug, Default)]
+pub struct Markers {
+    markers: Vec<Marker>,
+    next_id: u32,
+}
+
+impl Markers {
+    /// Add a marker, returning its identifier.
+    pub fn add(&mut self, location: LonLat, style: MarkerStyle) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.markers.push(Marker {
+            id,
+            location,
+            style,
+        });
+        id
+    }
+
+    /// Remove a marker, returning whether it existed.
+    pub fn remove(&mut self, id: u32) -> bool {
+        let len = self.markers.len();
+        self.markers.retain(|marker| marker.id != id);
+        self.markers.len() != len
+    }
+
+    /// Remove all markers.
+    pub fn clear(&mut self) {
+        self.markers.clear();
+    }
+
+    /// Markers shown in a frame, with their projected positions and opacities, those on the
+    /// back first so those in front cover them.
+    pub fn projected(&self, frame: &Frame) -> Vec<(&MarkerStyle, f64, f64, f64, f64)> {
+        let mut markers = self
+            .markers
+            .iter()
+            .filter_map(|marker| {
+                let (x, y, z) = frame.project(marker.location.lon, marker.location.lat);
+                let alpha = marker_alpha(x, &marker.style, frame.projection.shows_back());
+                (alpha > 0.0 && marker.style.radius > 0.0).then_some((
+                    &marker.style,
+                    x,
+                    y,
+                    z,
+                    alpha,
+                ))
+            })
+            .collect::<Vec<_>>();
+        markers.sort_by(|a, b| a.1.total_cmp(&b.1));
+        markers
+    }
+
+    /// Marker drawn at a canvas position, projected with a projection, orientation and
+    /// transform to canvas pixels; the one in front where markers overlap.
+    pub fn at(
+        &self,
+        projection: &Projection,
+        orientation: &Orientation,
+        transform: &DomMatrix,
+        (x, y): (f64, f64),
+    ) -> Option<u32> {
+        self.markers
+            .iter()
+            .filter_map(|marker| {
+                let (depth, my, mz) =
+                    projection.project(orientation, marker.location.lon, marker.location.lat);
+                if marker_alpha(depth, &marker.style, projection.shows_back()) <= 0.0 {
+                    return None;
+                }
+                let (mx, my) = view::to_canvas(transform, my, mz);
+                ((mx - x).hypot(my - y) <= marker.style.radius).then_some((marker.id, depth))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id)
+    }
+
+    /// Draw the markers onto the canvas, those on the back first so those in front cover them,
+    /// with icons drawn from the atlas.
+    pub fn draw(
+        &self,
+        context: &CanvasRenderingContext2d,
+        frame: &Frame,
+        icons: &IconAtlas,
+    ) -> Result<(), JsValue> {
+        let markers = self.projected(frame);
+
+        // Markers are a constant size on screen, and icons upright, so they are drawn in canvas
+        // pixels
+        context.save();
+        overlay::canvas_pixels(context, frame.pixel_ratio)?;
+        context.set_line_width(frame.line_width(MARKER_LINE_WIDTH) * frame.scale);
+        for (style, _, y, z, alpha) in markers {
+            context.set_global_alpha(alpha);
+            let (x, y) = frame.to_canvas(y, z);
+            let radius = style.radius;
+            let icon = style.icon.as_deref().and_then(|icon| icons.get(icon));
+            if let Some((atlas, region)) = icon {
+                let width = 2.0 * radius * region.width as f64 / region.height as f64;
+                context
+                    .draw_image_with_html_canvas_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                        atlas,
+                        region.x as f64,
+                        region.y as f64,
+                        region.width as f64,
+                        region.height as f64,
+                        x - width / 2.0,
+                        y - radius,
+                        width,
+                        2.0 * radius,
+                    )?;
+                continue;
+            }
+            context.begin_path();
+            context.arc(x, y, radius, 0.0, std::f64::consts::TAU)?;
+            context.set_fill_style_str(&style.color);
+            context.fill();
+            if let Some(outline) = &style.outline {
+                context.set_stroke_style_str(outline);
+                context.stroke();
+            }
+        }
+        context.restore();
+
+        Ok(())
+    }
+}
+
+/// Opacity of a marker at a depth in the view: opaque in front, and dimmed on the back where
+/// the projection shows it through the globe.
+fn marker_alpha(x: f64, style: &MarkerStyle, shows_back: bool) -> f64 {
+    if x >= 0.0 {
+        1.0
+    } else if shows_back {
+        style.back_opacity.clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dims_or_hides_markers_behind() {
+        let style = MarkerStyle::default();
+        assert_eq!(marker_alpha(0.5, &style, true), 1.0);
+        assert_eq!(marker_alpha(-0.5, &style, true), style.back_opacity);
+        assert_eq!(marker_alpha(-0.5, &style, false), 0.0);
+        let hidden = MarkerStyle {
+            back_opacity: 0.0,
+            ..style
+        };
+        assert_eq!(marker_alpha(-0.5, &hidden, true), 0.0);
+
+        let mut markers = Markers::default();
+        let id = markers.add(LonLat::new(0.0, 0.0), hidden);
+        assert!(markers.remove(id));
+        assert!(!markers.remove(id));
+    }
+}
@@ -0,0 +1,118 @@
+// Measurement and interpolation along great circles.
+
+use crate::coords::{LonLat, Vec3};
+
+/// Mean radius (kilometres) of the Earth.
+pub const EARTH_RADIUS: f64 = 6371.0088;
+
+/// Arc of a great circle from one location (degrees) to another.
+pub type Arc = (LonLat, LonLat);
+
+/// Angle (degrees) between two locations (degrees).
+pub fn central_angle(from: LonLat, to: LonLat) -> f64 {
+    from.to_vec3()
+        .dot(to.to_vec3())
+        .clamp(-1.0, 1.0)
+        .acos()
+        .to_degrees()
+}
+
+/// Distance (kilometres) along the great circle between two locations (degrees).
+pub fn distance(from: LonLat, to: LonLat) -> f64 {
+    central_angle(from, to).to_radians() * EARTH_RADIUS
+}
+
+/// Location (degrees) a fraction of the way from one location to another along the great
+/// circle between them.
+pub fn interpolate(from: LonLat, to: LonLat, fraction: f64) -> LonLat {
+    let angle = central_angle(from, to).to_radians();
+    if angle == 0.0 {
+        return from;
+    }
+    let weight_from = ((1.0 - fraction) * angle).sin() / angle.sin();
+    let weight_to = (fraction * angle).sin() / angle.sin();
+    LonLat::from_vec3(from.to_vec3() * weight_from + to.to_vec3() * weight_to)
+}
+
+/// Initial bearing (degrees clockwise from north) along the great circle from one
+/// location (degrees) to another.
+pub fn bearing(from: LonLat, to: LonLat) -> f64 {
+    let (lat_from, lat_to) = (from.lat.to_radians(), to.lat.to_radians());
+    let delta_lon = (to.lon - from.lon).to_radians();
+    (delta_lon.sin() * lat_to.cos())
+        .atan2(lat_from.cos() * lat_to.sin() - lat_from.sin() * lat_to.cos() * delta_lon.cos())
+        .to_degrees()
+}
+
+/// Location (degrees) at an angle (degrees) along the great circle from a location at a
+/// bearing (degrees clockwise from north).
+pub fn destination(from: LonLat, bearing: f64, angle: f64) -> LonLat {
+    let (lat, lon) = (from.lat.to_radians(), from.lon.to_radians());
+    let (sin_angle, cos_angle) = angle.to_radians().sin_cos();
+    let (sin_bearing, cos_bearing) = bearing.to_radians().sin_cos();
+    let sin_lat = (lat.sin() * cos_angle + lat.cos() * sin_angle * cos_bearing).clamp(-1.0, 1.0);
+    let lon = lon + (sin_bearing * sin_angle * lat.cos()).atan2(cos_angle - lat.sin() * sin_lat);
+    LonLat::new(lon.to_degrees(), sin_lat.asin().to_degrees()).wrapped()
+}
+
+/// Locations (degrees) around the small circle at an angle (degrees) from a centre.
+pub fn small_circle(centre: LonLat, angle: f64, samples: usize) -> Vec<LonLat> {
+    (0..samples)
+        .map(|i| destination(centre, 360.0 * i as f64 / samples as f64, angle))
+        .collect()
+}
+
+/// Location (degrees) where two arcs of great circles, each between two locations, cross, if
+/// they do. Arcs touching at an end cross there; arcs along the same great circle, or between
+/// coincident or antipodal locations, cross nowhere.
+pub fn intersection(first: Arc, second: Arc) -> Option<LonLat> {
+    let (a, b) = (first.0.to_vec3(), first.1.to_vec3());
+    let (c, d) = (second.0.to_vec3(), second.1.to_vec3());
+    let (normal_ab, normal_cd) = (a.cross(b), c.cross(d));
+    // Planes of the great circles meet along a line through the centre of the globe, which
+    // meets the sphere at two antipodal points
+    let line = normal_ab.cross(normal_cd);
+    if normal_ab.length() < EPSILON || normal_cd.length() < EPSILON || line.length() < EPSILON {
+        return None;
+    }
+    let line = line.normalised();
+    // Points lie on an arc when the arc turns the same way from its start to the point and
+    // from the point to its end
+    let on_arc = |from: Vec3, to: Vec3, normal: Vec3, point: Vec3| {
+        from.cross(point).dot(normal) >= -EPSILON && point.cross(to).dot(normal) >= -EPSILON
+    };
+    [line, line * -1.0]
+        .into_iter()
+        .find(|&point| on_arc(a, b, normal_ab, point) && on_arc(c, d, normal_cd, point))
+        .map(LonLat::from_vec3)
+}
+
+/// Whether two arcs of great circles, each between two locations (degrees), cross.
+pub fn crosses(first: Arc, second: Arc) -> bool {
+    intersection(first, second).is_some()
+}
+
+/// Tolerance of the tests of points on arcs, in unit sphere coordinates.
+const EPSILON: f64 = 1e-12;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersects_arcs_of_great_circles() {
+        let arc = |lon1, lat1, lon2, lat2| (LonLat::new(lon1, lat1), LonLat::new(lon2, lat2));
+        // The equator and a meridian cross where they meet, but not on their far sides
+        let equator = arc(-10.0, 0.0, 10.0, 0.0);
+        let crossing = intersection(equator, arc(5.0, -10.0, 5.0, 10.0)).unwrap();
+        assert!(crossing.lat.abs() < 1e-9 && (crossing.lon - 5.0).abs() < 1e-9);
+        assert!(!crosses(equator, arc(175.0, -10.0, 175.0, 10.0)));
+        assert!(!crosses(equator, arc(5.0, 5.0, 5.0, 10.0)));
+        // Across the antimeridian
+        let crossing =
+            intersection(arc(170.0, 0.0, -170.0, 0.0), arc(180.0, -10.0, 180.0, 10.0)).unwrap();
+        assert!((crossing.lon.abs() - 180.0).abs() < 1e-9);
+        // Arcs along the same great circle
+        assert!(!crosses(equator, arc(0.0, 0.0, 20.0, 0.0)));
+    }
+}
@@ -0,0 +1,108 @@
+// Labels of the countries by name, in a chosen language when localized names are embedded.
+
+use crate::coords::LonLat;
+use crate::data;
+use crate::labels::Label;
+use crate::view::Frame;
+
+// Country labels are placed above the graticule labels and beneath those of the data layers,
+// larger countries first
+const COUNTRY_LABEL_PRIORITY: f64 = -2.0;
+
+#[derive(Debug, Default)]
+pub struct CountryLabels {
+    pub visible: bool,
+    /// Index of the language of the names in the localized names, or `None` for the
+    /// default names.
+    language: Option<usize>,
+}
+
+impl CountryLabels {
+    /// Set the language of the names by a BCP 47 language tag such as "fr-CA", or `None` for
+    /// the default names; returns whether names are embedded in the language.
+    pub fn set_language(&mut self, tag: Option<&str>) -> bool {
+        let Some(tag) = tag else {
+            self.language = None;
+            return true;
+        };
+        let language = language_of(tag);
+        match data::COUNTRY_NAME_LANGUAGES
+            .iter()
+            .position(|id| *id == language)
+        {
+            Some(index) => {
+                self.language = Some(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Name of a country in the language, falling back to the default name where it has no
+    /// name in the language.
+    pub fn name(&self, country: usize) -> &'static str {
+        self.language
+            .and_then(|language| data::COUNTRY_LOCALIZED_NAMES[language].get(country))
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&data::COUNTRY_NAMES[country])
+    }
+
+    /// Labels of the countries on the front of the globe shown by the filters, at their
+    /// centroids.
+    pub fn labels(&self, frame: &Frame) -> Vec<Label> {
+        if !self.visible {
+            return Vec::new();
+        }
+        data::COUNTRY_BOUNDS
+            .iter()
+            .enumerate()
+            .filter(|(country, _)| frame.shows("countries", *country))
+            .filter_map(|(country, bounds)| {
+                let LonLat { lon, lat } = bounds.centroid;
+                let (x, y, z) = frame.project(lon, lat);
+                if x < 0.0 {
+                    return None;
+                }
+                let (x, y) = frame.to_canvas(y, z);
+                Some(Label {
+                    text: self.name(country).to_string(),
+                    x,
+                    y,
+                    priority: COUNTRY_LABEL_PRIORITY + bounds.radius / 180.0,
+                    allow_overlap: false,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Language of the localized names for a language tag: its primary language, with Chinese in
+/// traditional characters distinguished by script or region.
+fn language_of(tag: &str) -> String {
+    let subtags = tag
+        .split(['-', '_'])
+        .map(|subtag| subtag.to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    let language = subtags.first().cloned().unwrap_or_default();
+    let traditional = subtags[1..]
+        .iter()
+        .any(|subtag| matches!(subtag.as_str(), "hant" | "tw" | "hk" | "mo"));
+    if language == "zh" && traditional {
+        "zht".to_string()
+    } else {
+        language
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_languages_of_tags() {
+        assert_eq!(language_of("fr-CA"), "fr");
+        assert_eq!(language_of("zh-Hans-CN"), "zh");
+        assert_eq!(language_of("zh_TW"), "zht");
+        assert_eq!(language_of("DE"), "de");
+    }
+}
@@ -0,0 +1,87 @@
+// Placement of overlays drawn in the corners of the canvas.
+
+use wasm_bindgen::prelude::*;
+use web_sys::CanvasRenderingContext2d;
+
+/// Corner of the canvas an overlay is placed in.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    /// Top left position (canvas pixels) of an overlay of the given size placed in
+    /// the corner of a canvas of the given size, inset by the margin.
+    pub fn place(
+        &self,
+        (width, height): (f64, f64),
+        (canvas_width, canvas_height): (f64, f64),
+        margin: f64,
+    ) -> (f64, f64) {
+        let left = margin;
+        let right = canvas_width - width - margin;
+        let top = margin;
+        let bottom = canvas_height - height - margin;
+        match self {
+            Corner::TopLeft => (left, top),
+            Corner::TopRight => (right, top),
+            Corner::BottomLeft => (left, bottom),
+            Corner::BottomRight => (right, bottom),
+        }
+    }
+}
+
+/// Rectangle (canvas pixels) taken by an overlay.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub left: f64,
+    pub top: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    /// Whether a position (canvas pixels) is within the rectangle.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        (self.left..=self.left + self.width).contains(&x)
+            && (self.top..=self.top + self.height).contains(&y)
+    }
+
+    /// Whether the rectangle overlaps or touches another.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.left <= other.left + other.width
+            && other.left <= self.left + self.width
+            && self.top <= other.top + other.height
+            && other.top <= self.top + self.height
+    }
+
+    /// Smallest rectangle containing the rectangle and another.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let left = self.left.min(other.left);
+        let top = self.top.min(other.top);
+        Rect {
+            left,
+            top,
+            width: (self.left + self.width).max(other.left + other.width) - left,
+            height: (self.top + self.height).max(other.top + other.height) - top,
+        }
+    }
+}
+
+/// Set the context to draw in canvas pixels, with the backing store having a ratio of pixels
+/// to them, returning the size of the canvas in canvas pixels.
+pub fn canvas_pixels(
+    context: &CanvasRenderingContext2d,
+    pixel_ratio: f64,
+) -> Result<(f64, f64), JsValue> {
+    let canvas = context.canvas().expect("should have canvas");
+    context.set_transform(pixel_ratio, 0.0, 0.0, pixel_ratio, 0.0, 0.0)?;
+    Ok((
+        canvas.width() as f64 / pixel_ratio,
+        canvas.height() as f64 / pixel_ratio,
+    ))
+}
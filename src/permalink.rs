@@ -0,0 +1,156 @@
+// Synchronisation of the view with the fragment of the page's URL, as
+// `#lat=…&lon=…&zoom=…`, so views can be shared as links and survive reloads: the view is
+// restored from the fragment as synchronising starts, and the fragment follows the view once
+// it settles.
+
+// Time (milliseconds) the view is unchanged for before the fragment follows it
+const SETTLE_TIME: f64 = 500.0;
+// Decimal places of the coordinates (about a metre) and the zoom
+const COORDINATE_PRECISION: usize = 5;
+const ZOOM_PRECISION: usize = 2;
+const VIEW_KEYS: [&str; 3] = ["lat", "lon", "zoom"];
+
+/// View given by the fragment of a URL.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HashView {
+    /// Location (degrees) at the centre of the view.
+    pub lat: f64,
+    pub lon: f64,
+    pub zoom: Option<f64>,
+}
+
+impl HashView {
+    /// View of a fragment, with or without its leading `#`, or `None` when it has no valid
+    /// latitude and longitude.
+    pub fn parse(fragment: &str) -> Option<HashView> {
+        let value = |key: &str| {
+            fragment
+                .trim_start_matches('#')
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(name, _)| *name == key)
+                .and_then(|(_, value)| value.parse::<f64>().ok())
+                .filter(|value| value.is_finite())
+        };
+        Some(HashView {
+            lat: value("lat").filter(|lat| lat.abs() <= 90.0)?,
+            lon: value("lon")?,
+            zoom: value("zoom").filter(|zoom| *zoom > 0.0),
+        })
+    }
+
+    /// Parameters of the view in a fragment.
+    pub fn params(&self) -> String {
+        let mut params = format!(
+            "lat={:.*}&lon={:.*}",
+            COORDINATE_PRECISION, self.lat, COORDINATE_PRECISION, self.lon
+        );
+        if let Some(zoom) = self.zoom {
+            params.push_str(&format!("&zoom={:.*}", ZOOM_PRECISION, zoom));
+        }
+        params
+    }
+}
+
+/// Fragment with the parameters of a view in place of those of an existing fragment, keeping
+/// its other parameters.
+pub fn merge(params: &str, fragment: &str) -> String {
+    let others = fragment
+        .trim_start_matches('#')
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter(|pair| {
+            let name = pair.split_once('=').map_or(*pair, |(name, _)| name);
+            !VIEW_KEYS.contains(&name)
+        });
+    std::iter::once(params)
+        .chain(others)
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[derive(Debug, Default)]
+pub struct HashSync {
+    pub enabled: bool,
+    /// Parameters of the view last written to the fragment, or read from it.
+    written: Option<String>,
+    /// Parameters of a changed view, and the time (milliseconds) they last changed, until
+    /// the view settles.
+    changed: Option<(String, f64)>,
+}
+
+impl HashSync {
+    /// Start synchronising, from the parameters of the view read from the fragment.
+    pub fn start(&mut self, params: Option<String>) {
+        self.enabled = true;
+        self.written = params;
+        self.changed = None;
+    }
+
+    /// Parameters of the view at a time (milliseconds) to write to the fragment, once they
+    /// differ from those last written and have been unchanged for the settle time.
+    pub fn tick(&mut self, params: String, time: f64) -> Option<String> {
+        if !self.enabled || self.written.as_ref() == Some(&params) {
+            self.changed = None;
+            return None;
+        }
+        match &self.changed {
+            Some((changed, since)) if *changed == params => {
+                if time - since < SETTLE_TIME {
+                    return None;
+                }
+                self.changed = None;
+                self.written = Some(params.clone());
+                Some(params)
+            }
+            _ => {
+                self.changed = Some((params, time));
+                None
+            }
+        }
+    }
+
+    /// Whether a changed view is waiting to settle before it is written.
+    pub fn pending(&self) -> bool {
+        self.changed.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_settled_views_to_the_fragment() {
+        let view = HashView::parse("#lat=51.5&lon=-0.12&zoom=2&layer=tiles").unwrap();
+        assert_eq!(
+            view,
+            HashView {
+                lat: 51.5,
+                lon: -0.12,
+                zoom: Some(2.0)
+            }
+        );
+        assert_eq!(HashView::parse("lat=91&lon=0"), None);
+        assert_eq!(HashView::parse("lon=0"), None);
+        assert_eq!(
+            merge(&view.params(), "#zoom=1&layer=tiles&lat=0"),
+            "lat=51.50000&lon=-0.12000&zoom=2.00&layer=tiles"
+        );
+
+        let mut sync = HashSync::default();
+        sync.start(Some(view.params()));
+        assert_eq!(sync.tick(view.params(), 0.0), None);
+        // Changes are written once the view is unchanged for the settle time
+        assert_eq!(sync.tick("lat=1".to_string(), 0.0), None);
+        assert_eq!(sync.tick("lat=2".to_string(), 100.0), None);
+        assert_eq!(sync.tick("lat=2".to_string(), 500.0), None);
+        assert!(sync.pending());
+        assert_eq!(
+            sync.tick("lat=2".to_string(), 600.0),
+            Some("lat=2".to_string())
+        );
+        assert_eq!(sync.tick("lat=2".to_string(), 1200.0), None);
+        assert!(!sync.pending());
+    }
+}
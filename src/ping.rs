@@ -0,0 +1,205 @@
+// Expanding rings played at a location to draw attention to it.
+
+use wasm_bindgen::prelude::*;
+use web_sys::{CanvasRenderingContext2d, DomMatrix};
+
+use crate::coords::LonLat;
+use crate::overlay::Rect;
+use crate::projection::Projection;
+use crate::view::{self, Frame, Orientation};
+
+const PING_DEFAULT_COLOR: &str = "rgba(255, 0, 0, 1.0)";
+const PING_DEFAULT_RINGS: u32 = 3;
+const PING_DEFAULT_DURATION: f64 = 1500.0;
+const PING_DEFAULT_RADIUS: f64 = 30.0;
+// Fraction of the duration over which the starts of the rings are spread
+const PING_STAGGER: f64 = 0.5;
+const PING_LINE_WIDTH: f64 = 2.0;
+
+/// Style of a ping.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PingStyle {
+    /// CSS color of the rings.
+    #[wasm_bindgen(getter_with_clone)]
+    pub color: String,
+    /// Number of overlapping rings.
+    pub rings: u32,
+    /// Time (milliseconds) from the first ring starting to the last ring fading.
+    pub duration: f64,
+    /// Radius (canvas pixels) the rings expand to.
+    pub radius: f64,
+}
+
+impl Default for PingStyle {
+    fn default() -> Self {
+        Self {
+            color: PING_DEFAULT_COLOR.to_string(),
+            rings: PING_DEFAULT_RINGS,
+            duration: PING_DEFAULT_DURATION,
+            radius: PING_DEFAULT_RADIUS,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl PingStyle {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> PingStyle {
+        PingStyle::default()
+    }
+}
+
+#[derive(Debug)]
+struct Ping {
+    /// Location (degrees).
+    location: LonLat,
+    style: PingStyle,
+    /// Time (milliseconds) the ping started.
+    start: f64,
+}
+
+impl Ping {
+    /// Expansion of each ring, from 0 to 1, at a time, for the rings that are playing.
+    fn rings(&self, time: f64) -> impl Iterator<Item = f64> + '_ {
+        let rings = self.style.rings.max(1);
+        let stagger = if rings > 1 {
+            self.style.duration * PING_STAGGER / (rings - 1) as f64
+        } else {
+            0.0
+        };
+        let ring_duration = self.style.duration - stagger * (rings - 1) as f64;
+        let elapsed = time - self.start;
+        (0..rings)
+            .map(move |i| (elapsed - i as f64 * stagger) / ring_duration)
+            .filter(|t| (0.0..1.0).contains(t))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Pings {
+    pings: Vec<Ping>,
+}
+
+impl Pings {
+    /// Start a ping at a location (degrees) at a time (milliseconds).
+    pub fn add(&mut self, location: LonLat, style: PingStyle, time: f64) {
+        self.pings.push(Ping {
+            location,
+            style,
+            start: time,
+        });
+    }
+
+    /// Whether any pings are playing, or have finished without being removed.
+    pub fn playing(&self) -> bool {
+        !self.pings.is_empty()
+    }
+
+    /// Remove finished pings, returning whether any pings are playing at a time (milliseconds)
+    /// or have just finished, so the frame needs drawing.
+    pub fn animating(&mut self, time: f64) -> bool {
+        let count = self.pings.len();
+        self.pings
+            .retain(|ping| time < ping.start + ping.style.duration);
+        !self.pings.is_empty() || self.pings.len() != count
+    }
+
+    /// Regions (canvas pixels) the rings of the pings are drawn within, as projected with a
+    /// view, so frames changing only the rings redraw only their regions.
+    pub fn regions(
+        &self,
+        projection: &Projection,
+        orientation: &Orientation,
+        transform: &DomMatrix,
+    ) -> Vec<Rect> {
+        self.pings
+            .iter()
+            .filter_map(|ping| {
+                let LonLat { lon, lat } = ping.location;
+                let (x, y, z) = projection.project(orientation, lon, lat);
+                if x < 0.0 {
+                    return None;
+                }
+                let (x, y) = view::to_canvas(transform, y, z);
+                let radius = ping.style.radius + PING_LINE_WIDTH;
+                Some(Rect {
+                    left: x - radius,
+                    top: y - radius,
+                    width: 2.0 * radius,
+                    height: 2.0 * radius,
+                })
+            })
+            .collect()
+    }
+
+    /// Draw the rings of the pings playing at a time (milliseconds) onto the canvas.
+    pub fn draw(
+        &self,
+        context: &CanvasRenderingContext2d,
+        frame: &Frame,
+        time: f64,
+    ) -> Result<(), JsValue> {
+        // Rings are a constant size on screen
+        context.set_line_width(PING_LINE_WIDTH / frame.scale);
+        for ping in &self.pings {
+            let LonLat { lon, lat } = ping.location;
+            let (x, y, z) = frame.project(lon, lat);
+            if x < 0.0 {
+                continue;
+            }
+            context.set_stroke_style_str(&ping.style.color);
+            for t in ping.rings(time) {
+                // Rings slow as they expand, and fade out
+                let expansion = 1.0 - (1.0 - t) * (1.0 - t);
+                context.set_global_alpha(1.0 - t);
+                context.begin_path();
+                context.arc(
+                    y,
+                    z,
+                    expansion * ping.style.radius / frame.scale,
+                    0.0,
+                    std::f64::consts::TAU,
+                )?;
+                context.stroke();
+            }
+        }
+        context.set_global_alpha(1.0);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expires_finished_pings() {
+        let mut pings = Pings::default();
+        let style = PingStyle::default();
+        pings.add(LonLat::new(0.0, 0.0), style.clone(), 0.0);
+        pings.add(LonLat::new(10.0, 0.0), style, 500.0);
+        assert!(pings.animating(PING_DEFAULT_DURATION - 1.0));
+        assert_eq!(pings.pings.len(), 2);
+        // The frame after the last ping finishes is drawn to clear it
+        assert!(pings.animating(PING_DEFAULT_DURATION));
+        assert_eq!(pings.pings.len(), 1);
+        assert!(pings.animating(500.0 + PING_DEFAULT_DURATION));
+        assert!(!pings.playing());
+        assert!(!pings.animating(500.0 + PING_DEFAULT_DURATION + 16.0));
+    }
+
+    #[test]
+    fn staggers_the_rings() {
+        let ping = Ping {
+            location: LonLat::new(0.0, 0.0),
+            style: PingStyle::default(),
+            start: 0.0,
+        };
+        // Later rings start as the first expands, and all finish by the end of the duration
+        assert_eq!(ping.rings(0.0).collect::<Vec<_>>(), [0.0]);
+        assert_eq!(ping.rings(PING_DEFAULT_DURATION / 2.0).count(), 2);
+        assert_eq!(ping.rings(PING_DEFAULT_DURATION).count(), 0);
+    }
+}
@@ -0,0 +1,112 @@
+// Pixel space post-processing effects applied to the composed frame.
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
+use web_sys::{CanvasRenderingContext2d, ImageData};
+
+const BLOOM_FILTER: &str = "blur(8px)";
+const GRAIN_MAX_NOISE: f64 = 64.0;
+
+/// Strengths of the post-processing effects, each from 0 (off) to 1.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PostEffects {
+    /// Darkening towards the corners of the canvas.
+    pub vignette: f64,
+    /// Random noise added to each pixel.
+    pub grain: f64,
+    /// Sepia toning.
+    pub sepia: f64,
+    /// Glow around bright areas, approximated by lightening with a blurred copy.
+    pub bloom: f64,
+}
+
+#[wasm_bindgen]
+impl PostEffects {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> PostEffects {
+        PostEffects::default()
+    }
+}
+
+impl PostEffects {
+    fn is_empty(&self) -> bool {
+        self.vignette <= 0.0 && self.grain <= 0.0 && self.sepia <= 0.0 && self.bloom <= 0.0
+    }
+}
+
+/// Apply the effects to the frame composed on the canvas.
+pub fn apply(context: &CanvasRenderingContext2d, effects: &PostEffects) -> Result<(), JsValue> {
+    if effects.is_empty() {
+        return Ok(());
+    }
+
+    let canvas = context.canvas().expect("should have canvas");
+    let (width, height) = (canvas.width() as f64, canvas.height() as f64);
+
+    // Effects are applied in canvas pixels rather than unit sphere coordinates
+    context.save();
+    context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)?;
+
+    if effects.sepia > 0.0 {
+        context.set_filter(&format!("sepia({})", effects.sepia.min(1.0)));
+        context.set_global_composite_operation("copy")?;
+        context.draw_image_with_html_canvas_element(&canvas, 0.0, 0.0)?;
+        context.set_filter("none");
+        context.set_global_composite_operation("source-over")?;
+    }
+
+    if effects.bloom > 0.0 {
+        context.set_filter(BLOOM_FILTER);
+        context.set_global_composite_operation("lighter")?;
+        context.set_global_alpha(effects.bloom.min(1.0) * 0.5);
+        context.draw_image_with_html_canvas_element(&canvas, 0.0, 0.0)?;
+        context.set_filter("none");
+        context.set_global_composite_operation("source-over")?;
+        context.set_global_alpha(1.0);
+    }
+
+    if effects.vignette > 0.0 {
+        let (cx, cy) = (width / 2.0, height / 2.0);
+        let gradient =
+            context.create_radial_gradient(cx, cy, cx.min(cy) / 2.0, cx, cy, cx.hypot(cy))?;
+        gradient.add_color_stop(0.0, "rgba(0, 0, 0, 0.0)")?;
+        gradient.add_color_stop(
+            1.0,
+            &format!("rgba(0, 0, 0, {})", effects.vignette.min(1.0)),
+        )?;
+        context.set_fill_style_canvas_gradient(&gradient);
+        context.fill_rect(0.0, 0.0, width, height);
+    }
+
+    if effects.grain > 0.0 {
+        let image_data = context.get_image_data(0.0, 0.0, width, height)?;
+        let mut data = image_data.data();
+        let amount = effects.grain.min(1.0) * GRAIN_MAX_NOISE;
+        let mut seed = (js_sys::Math::random() * u32::MAX as f64) as u32 | 1;
+        for pixel in data.chunks_exact_mut(4) {
+            // Transparent pixels are left transparent
+            if pixel[3] == 0 {
+                continue;
+            }
+            // Xorshift pseudo random numbers are plenty for noise
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            let noise = (seed as f64 / u32::MAX as f64 * 2.0 - 1.0) * amount;
+            for channel in &mut pixel[..3] {
+                *channel = (*channel as f64 + noise).clamp(0.0, 255.0) as u8;
+            }
+        }
+        let image_data = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(&data),
+            canvas.width(),
+            canvas.height(),
+        )?;
+        context.put_image_data(&image_data, 0.0, 0.0)?;
+    }
+
+    context.restore();
+
+    Ok(())
+}
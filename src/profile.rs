@@ -0,0 +1,39 @@
+// Marks of the phases of rendering on the browser's performance timeline, so frame
+// breakdowns show in the profilers of browser devtools.
+
+use wasm_bindgen::JsValue;
+
+use crate::performance;
+
+#[derive(Debug, Default)]
+pub struct Profiler {
+    pub enabled: bool,
+}
+
+impl Profiler {
+    /// Mark the start of a phase.
+    pub fn start(&self, phase: &str) -> Result<(), JsValue> {
+        if self.enabled {
+            performance().mark(&start_mark(phase))?;
+        }
+        Ok(())
+    }
+
+    /// Measure a phase from its start to now, as "globe:<phase>".
+    pub fn end(&self, phase: &str) -> Result<(), JsValue> {
+        if self.enabled {
+            let performance = performance();
+            let start = start_mark(phase);
+            let name = format!("globe:{}", phase);
+            performance.measure_with_start_mark(&name, &start)?;
+            // Entries would otherwise accumulate every frame; devtools recordings keep them
+            performance.clear_marks_with_mark_name(&start);
+            performance.clear_measures_with_measure_name(&name);
+        }
+        Ok(())
+    }
+}
+
+fn start_mark(phase: &str) -> String {
+    format!("globe:{}:start", phase)
+}
@@ -0,0 +1,289 @@
+// Projections of the globe onto the disc drawn on the canvas.
+
+use wasm_bindgen::prelude::*;
+
+use crate::coords::LonLat;
+use crate::view::{Orientation, Side};
+
+const DEFAULT_LATITUDE_LIMIT: f64 = 45.0;
+// Limits keep the polar views' scale finite
+const MIN_LATITUDE_LIMIT: f64 = -80.0;
+const MAX_LATITUDE_LIMIT: f64 = 89.0;
+// Segments longer than this, in globe radii, jump across the edge of the
+// azimuthal equidistant view around the antipode of its centre
+const MAX_SEGMENT_LENGTH: f64 = 0.5;
+// Caps this close (degrees) to the antipode of the centre may jump across the edge
+const ANTIPODE_MARGIN: f64 = 10.0;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProjectionKind {
+    /// The globe as seen from a distance.
+    Orthographic,
+    /// Conformal view centred on the north pole, out to the latitude limit.
+    NorthPolarStereographic,
+    /// Conformal view centred on the south pole, out to the latitude limit.
+    SouthPolarStereographic,
+    /// The whole globe, with true distances and bearings from the centre of the view.
+    AzimuthalEquidistant,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Projection {
+    pub kind: ProjectionKind,
+    /// Latitude (degrees, towards the equator from the pole) at the edge of the polar views.
+    latitude_limit: f64,
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Self {
+            kind: ProjectionKind::Orthographic,
+            latitude_limit: DEFAULT_LATITUDE_LIMIT,
+        }
+    }
+}
+
+impl Projection {
+    /// Set the latitude (degrees) at the edge of the polar views, clamped to a usable range.
+    pub fn set_latitude_limit(&mut self, latitude: f64) {
+        self.latitude_limit = latitude.clamp(MIN_LATITUDE_LIMIT, MAX_LATITUDE_LIMIT);
+    }
+
+    /// Whether the back of the globe shows through, rather than being clipped.
+    pub fn shows_back(&self) -> bool {
+        self.kind == ProjectionKind::Orthographic
+    }
+
+    /// Direction of the pole at the centre of a polar view, as 1 for north and -1 for south.
+    fn pole(&self) -> Option<f64> {
+        match self.kind {
+            ProjectionKind::Orthographic | ProjectionKind::AzimuthalEquidistant => None,
+            ProjectionKind::NorthPolarStereographic => Some(1.0),
+            ProjectionKind::SouthPolarStereographic => Some(-1.0),
+        }
+    }
+
+    /// Project a location (degrees) to view coordinates; (y, z) are the coordinates plotted
+    /// on the canvas, within the unit disc when x, the depth towards the viewer, is positive.
+    pub fn project(&self, orientation: &Orientation, lon: f64, lat: f64) -> (f64, f64, f64) {
+        if self.kind == ProjectionKind::AzimuthalEquidistant {
+            // The orthographic view is scaled so distance from the centre is proportional to angle
+            let (x, y, z) = orientation.project(lon, lat);
+            let angle = x.clamp(-1.0, 1.0).acos();
+            let r = angle / std::f64::consts::PI;
+            let scale = if angle > 0.0 {
+                r / angle.sin()
+            } else {
+                1.0 / std::f64::consts::PI
+            };
+            return (1.0 - r * r, y * scale, z * scale);
+        }
+        let Some(pole) = self.pole() else {
+            return orientation.project(lon, lat);
+        };
+        let r = stereographic_radius(90.0 - pole * lat) / self.limit_radius();
+        // The north polar view has the prime meridian downwards and the south polar view upwards
+        let (sin_angle, cos_angle) = (lon + orientation.rotation).to_radians().sin_cos();
+        let (y, z) = (r * sin_angle, -pole * r * cos_angle);
+        let (sin_roll, cos_roll) = orientation.roll.to_radians().sin_cos();
+        (
+            1.0 - r * r,
+            y * cos_roll - z * sin_roll,
+            y * sin_roll + z * cos_roll,
+        )
+    }
+
+    /// Location at plotted coordinates, or `None` when off the globe.
+    pub fn unproject(&self, orientation: &Orientation, y: f64, z: f64) -> Option<LonLat> {
+        if self.kind == ProjectionKind::AzimuthalEquidistant {
+            let r = y.hypot(z);
+            if r > 1.0 {
+                return None;
+            }
+            let angle = r * std::f64::consts::PI;
+            let scale = if r > 0.0 {
+                angle.sin() / r
+            } else {
+                std::f64::consts::PI
+            };
+            return Some(orientation.location(angle.cos(), y * scale, z * scale));
+        }
+        let Some(pole) = self.pole() else {
+            return orientation.unproject(y, z);
+        };
+        let (y, z) = orientation.unroll(y, z);
+        let r = y.hypot(z);
+        if r > 1.0 {
+            return None;
+        }
+        let colatitude = 2.0 * (r * self.limit_radius()).atan().to_degrees();
+        Some(
+            LonLat::new(
+                polar_angle(pole, y, z) - orientation.rotation,
+                pole * (90.0 - colatitude),
+            )
+            .wrapped(),
+        )
+    }
+
+    /// Plotted coordinates of the orthographic view of the same location, for controls
+    /// following the pointer; locations on the back of the globe are taken to its limb.
+    pub fn orthographic_equivalent(&self, y: f64, z: f64) -> (f64, f64) {
+        if self.kind != ProjectionKind::AzimuthalEquidistant {
+            return (y, z);
+        }
+        let r = y.hypot(z);
+        if r == 0.0 {
+            return (y, z);
+        }
+        let angle = (r * std::f64::consts::PI).min(std::f64::consts::FRAC_PI_2);
+        (y * angle.sin() / r, z * angle.sin() / r)
+    }
+
+    /// Rotation (degrees) about the polar axis that keeps the location under the pointer
+    /// as it moves between unrolled plotted coordinates, for the polar views.
+    pub fn polar_rotation(&self, from: (f64, f64), to: (f64, f64)) -> Option<f64> {
+        let pole = self.pole()?;
+        let delta = polar_angle(pole, to.0, to.1) - polar_angle(pole, from.0, from.1);
+        Some((delta + 180.0).rem_euclid(360.0) - 180.0)
+    }
+
+    /// Side of the view a cap (degrees, as the longitude and latitude of its centre, and
+    /// its radius) is on; for the polar views the back is beyond the latitude limit.
+    pub fn cap_side(&self, orientation: &Orientation, centre: LonLat, radius: f64) -> Side {
+        let LonLat { lon, lat } = centre;
+        if self.kind == ProjectionKind::AzimuthalEquidistant {
+            let (x, _, _) = orientation.project(lon, lat);
+            let angle = x.clamp(-1.0, 1.0).acos().to_degrees();
+            return if angle + radius < 180.0 - ANTIPODE_MARGIN {
+                Side::Front
+            } else {
+                Side::Both
+            };
+        }
+        match self.pole() {
+            None => {
+                let (x, _, _) = orientation.project(lon, lat);
+                // The cap is clear of the limb when its centre is further than its radius from it
+                let clearance = radius.min(90.0).to_radians().sin();
+                if x > clearance {
+                    Side::Front
+                } else if x < -clearance {
+                    Side::Back
+                } else {
+                    Side::Both
+                }
+            }
+            Some(pole) => {
+                if pole * lat - radius >= self.latitude_limit {
+                    Side::Front
+                } else if pole * lat + radius < self.latitude_limit {
+                    Side::Back
+                } else {
+                    Side::Both
+                }
+            }
+        }
+    }
+
+    /// Side a segment between projected points is drawn on, or `None` when it is not drawn;
+    /// segments crossing the edge of the polar views are clipped.
+    pub fn segment_side(&self, prev: (f64, f64, f64), point: (f64, f64, f64)) -> Option<Side> {
+        let ((x_prev, y_prev, z_prev), (x, y, z)) = (prev, point);
+        if self.kind == ProjectionKind::AzimuthalEquidistant
+            && (y - y_prev).hypot(z - z_prev) > MAX_SEGMENT_LENGTH
+        {
+            None
+        } else if x_prev >= 0.0 && x >= 0.0 {
+            Some(Side::Front)
+        } else if self.shows_back() {
+            Some(Side::Back)
+        } else if x_prev >= 0.0 || x >= 0.0 {
+            Some(Side::Front)
+        } else {
+            None
+        }
+    }
+
+    /// Stereographic radius at the latitude limit, which is scaled to the edge of the disc.
+    fn limit_radius(&self) -> f64 {
+        stereographic_radius(90.0 - self.latitude_limit)
+    }
+}
+
+/// Radius, on the plane tangent at a pole, of the stereographic projection of
+/// a point at a colatitude (degrees) from the pole, for a unit sphere.
+fn stereographic_radius(colatitude: f64) -> f64 {
+    (colatitude.to_radians() / 2.0).tan()
+}
+
+/// Angle (degrees) of unrolled plotted coordinates about the centre of a polar view,
+/// increasing with longitude.
+fn polar_angle(pole: f64, y: f64, z: f64) -> f64 {
+    y.atan2(-pole * z).to_degrees()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near(a: LonLat, b: LonLat) -> bool {
+        (a.lat - b.lat).abs() < 1e-9 && (a.lon - b.lon).abs() < 1e-9
+    }
+
+    #[test]
+    fn unprojects_the_polar_views() {
+        let orientation = Orientation {
+            rotation: 30.0,
+            tilt: 0.0,
+            roll: 20.0,
+        };
+        for (kind, pole) in [
+            (ProjectionKind::NorthPolarStereographic, 1.0),
+            (ProjectionKind::SouthPolarStereographic, -1.0),
+        ] {
+            let mut projection = Projection {
+                kind,
+                ..Projection::default()
+            };
+            projection.set_latitude_limit(30.0);
+            for (lon, lat) in [(0.0, 89.0), (-120.0, 60.0), (45.0, 31.0), (179.0, 75.0)] {
+                let location = LonLat::new(lon, pole * lat);
+                let (x, y, z) = projection.project(&orientation, location.lon, location.lat);
+                assert!(x > 0.0);
+                let unprojected = projection.unproject(&orientation, y, z).unwrap();
+                assert!(near(unprojected, location), "{:?}", unprojected);
+            }
+            // The latitude limit is at the edge of the disc, and beyond it is off the globe
+            let (x, y, z) = projection.project(&orientation, 10.0, pole * 30.0);
+            assert!(x.abs() < 1e-9 && (y.hypot(z) - 1.0).abs() < 1e-9);
+            let (x, _, _) = projection.project(&orientation, 10.0, pole * 20.0);
+            assert!(x < 0.0);
+            assert_eq!(projection.unproject(&orientation, 0.8, 0.8), None);
+        }
+    }
+
+    #[test]
+    fn unprojects_the_azimuthal_equidistant_view() {
+        let projection = Projection {
+            kind: ProjectionKind::AzimuthalEquidistant,
+            ..Projection::default()
+        };
+        let orientation = Orientation::default().centred_on(LonLat::new(-75.0, 40.0));
+        for (lon, lat) in [(-75.0, 40.0), (0.0, 51.5), (139.7, 35.7), (-58.4, -34.6)] {
+            let location = LonLat::new(lon, lat);
+            let (_, y, z) = projection.project(&orientation, lon, lat);
+            let unprojected = projection.unproject(&orientation, y, z).unwrap();
+            assert!(near(unprojected, location), "{:?}", unprojected);
+        }
+        // Distance from the centre is proportional to angle, so the antipode is at the edge
+        let (x, _, _) = projection.project(&orientation, 105.0, -40.0);
+        assert!(x.abs() < 1e-9);
+        let antipode = projection.unproject(&orientation, 1.0, 0.0).unwrap();
+        assert!((antipode.lat + 40.0).abs() < 1e-9);
+        let (_, y, z) = projection.project(&Orientation::default(), 179.0, 0.0);
+        assert!((y.hypot(z) - 179.0 / 180.0).abs() < 1e-9);
+        assert_eq!(projection.unproject(&orientation, 0.8, 0.8), None);
+    }
+}
@@ -0,0 +1,268 @@
+// Unit quaternions composing rotations of the globe about arbitrary axes.
+
+use crate::coords::Vec3;
+use crate::view::Orientation;
+
+// Tilts this close (degrees) to the poles leave the rotation and roll indistinguishable
+const GIMBAL_TOLERANCE: f64 = 1e-6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    /// Rotation by an angle (degrees) about an axis, anticlockwise looking along the axis
+    /// towards the origin; rotations about a zero axis are the identity.
+    pub fn from_axis_angle(axis: Vec3, angle: f64) -> Quaternion {
+        let axis = axis.normalised();
+        if axis.length() == 0.0 {
+            return Quaternion::identity();
+        }
+        let (sin, cos) = (angle.to_radians() / 2.0).sin_cos();
+        Quaternion {
+            w: cos,
+            x: axis.x * sin,
+            y: axis.y * sin,
+            z: axis.z * sin,
+        }
+    }
+
+    pub fn identity() -> Quaternion {
+        Quaternion {
+            w: 1.0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+
+    /// Shortest rotation taking the direction of one point to the direction of another.
+    pub fn between(from: Vec3, to: Vec3) -> Quaternion {
+        let (from, to) = (from.normalised(), to.normalised());
+        let axis = Vec3::new(
+            from.y * to.z - from.z * to.y,
+            from.z * to.x - from.x * to.z,
+            from.x * to.y - from.y * to.x,
+        );
+        let angle = from.dot(to).clamp(-1.0, 1.0).acos().to_degrees();
+        Quaternion::from_axis_angle(axis, angle)
+    }
+
+    /// Rotation applying another rotation, then this one.
+    pub fn then_after(self, other: Quaternion) -> Quaternion {
+        let (a, b) = (self, other);
+        Quaternion {
+            w: a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+            x: a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            y: a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            z: a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        }
+    }
+
+    pub fn normalised(self) -> Quaternion {
+        let length = (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        Quaternion {
+            w: self.w / length,
+            x: self.x / length,
+            y: self.y / length,
+            z: self.z / length,
+        }
+    }
+
+    /// Rotation a fraction of the way from this rotation to another, turning at a constant
+    /// rate about a fixed axis the shorter way round.
+    pub fn slerp(self, other: Quaternion, t: f64) -> Quaternion {
+        let mut dot = self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z;
+        // The same rotation, the shorter way round
+        let other = if dot < 0.0 {
+            dot = -dot;
+            Quaternion {
+                w: -other.w,
+                x: -other.x,
+                y: -other.y,
+                z: -other.z,
+            }
+        } else {
+            other
+        };
+        let angle = dot.clamp(-1.0, 1.0).acos();
+        // Nearly equal rotations are interpolated linearly, as the sine vanishes
+        let (a, b) = if angle.sin() < 1e-9 {
+            (1.0 - t, t)
+        } else {
+            (
+                ((1.0 - t) * angle).sin() / angle.sin(),
+                (t * angle).sin() / angle.sin(),
+            )
+        };
+        Quaternion {
+            w: a * self.w + b * other.w,
+            x: a * self.x + b * other.x,
+            y: a * self.y + b * other.y,
+            z: a * self.z + b * other.z,
+        }
+        .normalised()
+    }
+
+    /// Axis of the rotation scaled by its angle (degrees), the inverse of `from_axis_angle`.
+    pub fn rotation_vector(self) -> Vec3 {
+        let q = if self.w < 0.0 {
+            // The same rotation, the shorter way round
+            Quaternion {
+                w: -self.w,
+                x: -self.x,
+                y: -self.y,
+                z: -self.z,
+            }
+        } else {
+            self
+        };
+        let angle = 2.0 * q.w.clamp(-1.0, 1.0).acos().to_degrees();
+        Vec3::new(q.x, q.y, q.z).normalised() * angle
+    }
+
+    /// Rotate a point.
+    pub fn rotate(self, point: Vec3) -> Vec3 {
+        let m = self.matrix();
+        Vec3::new(
+            m[0][0] * point.x + m[0][1] * point.y + m[0][2] * point.z,
+            m[1][0] * point.x + m[1][1] * point.y + m[1][2] * point.z,
+            m[2][0] * point.x + m[2][1] * point.y + m[2][2] * point.z,
+        )
+    }
+
+    /// Rotation matrix, by rows.
+    fn matrix(self) -> [[f64; 3]; 3] {
+        let Quaternion { w, x, y, z } = self;
+        [
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ]
+    }
+}
+
+impl From<Orientation> for Quaternion {
+    /// Rotation from globe to view coordinates: about the polar axis, then tilting about
+    /// the horizontal axis, then rolling about the view axis.
+    fn from(orientation: Orientation) -> Self {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let y = Vec3::new(0.0, 1.0, 0.0);
+        let z = Vec3::new(0.0, 0.0, 1.0);
+        Quaternion::from_axis_angle(x, orientation.roll)
+            .then_after(Quaternion::from_axis_angle(y, orientation.tilt))
+            .then_after(Quaternion::from_axis_angle(z, orientation.rotation))
+    }
+}
+
+impl From<Quaternion> for Orientation {
+    fn from(quaternion: Quaternion) -> Self {
+        let m = quaternion.normalised().matrix();
+        let tilt = m[0][2].clamp(-1.0, 1.0).asin().to_degrees();
+        let (rotation, roll) = if 90.0 - tilt.abs() > GIMBAL_TOLERANCE {
+            (
+                (-m[0][1]).atan2(m[0][0]).to_degrees(),
+                (-m[1][2]).atan2(m[2][2]).to_degrees(),
+            )
+        } else {
+            // At the poles the roll is taken up by the rotation
+            (m[1][0].atan2(m[1][1]).to_degrees(), 0.0)
+        };
+        Orientation {
+            rotation,
+            tilt,
+            roll,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::LonLat;
+
+    fn assert_close(a: (f64, f64, f64), b: Vec3) {
+        assert!((a.0 - b.x).abs() < 1e-9 && (a.1 - b.y).abs() < 1e-9 && (a.2 - b.z).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rotates_as_orientations_project() {
+        let orientation = Orientation {
+            rotation: 30.0,
+            tilt: -50.0,
+            roll: 70.0,
+        };
+        let quaternion = Quaternion::from(orientation);
+        for (lon, lat) in [(0.0, 0.0), (45.0, 10.0), (-120.0, -60.0)] {
+            let point = LonLat::new(lon, lat).to_vec3();
+            assert_close(orientation.project(lon, lat), quaternion.rotate(point));
+        }
+    }
+
+    #[test]
+    fn converts_back_to_orientations() {
+        for orientation in [
+            Orientation {
+                rotation: 30.0,
+                tilt: -50.0,
+                roll: 70.0,
+            },
+            Orientation {
+                rotation: -100.0,
+                tilt: 90.0,
+                roll: 0.0,
+            },
+        ] {
+            let converted = Orientation::from(Quaternion::from(orientation));
+            let point = LonLat::new(20.0, 40.0);
+            assert_close(
+                orientation.project(point.lon, point.lat),
+                Vec3::from(converted.project(point.lon, point.lat)),
+            );
+        }
+    }
+
+    #[test]
+    fn converts_to_rotation_vectors() {
+        let axis = Vec3::new(0.0, 0.6, 0.8);
+        let vector = Quaternion::from_axis_angle(axis, 30.0).rotation_vector();
+        assert_close((0.0, 18.0, 24.0), vector);
+        assert_eq!(Quaternion::identity().rotation_vector().length(), 0.0);
+    }
+
+    #[test]
+    fn interpolates_along_the_shorter_arc() {
+        let z = Vec3::new(0.0, 0.0, 1.0);
+        let (from, to) = (
+            Quaternion::from_axis_angle(z, 10.0),
+            Quaternion::from_axis_angle(z, 90.0),
+        );
+        assert_close((0.0, 0.0, 50.0), from.slerp(to, 0.5).rotation_vector());
+        // Rotations past a half turn apart are reached the other way round
+        let far = Quaternion::from_axis_angle(z, -170.0);
+        assert_close((0.0, 0.0, 100.0), from.slerp(far, 0.5).rotation_vector());
+        assert_close((0.0, 0.0, 10.0), from.slerp(from, 0.3).rotation_vector());
+    }
+
+    #[test]
+    fn rotates_between_points() {
+        let (from, to) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.6, 0.8));
+        assert_close((0.0, 0.6, 0.8), Quaternion::between(from, to).rotate(from));
+    }
+}
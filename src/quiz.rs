@@ -0,0 +1,248 @@
+// Building blocks of geography games: countries are asked for at random, with the caps
+// bounding them to bring them into view, answers given as locations are checked by the
+// country they fall in, and countries are revealed by a highlight fading out over a time.
+
+use wasm_bindgen::prelude::*;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::coords::LonLat;
+use crate::data;
+use crate::measure;
+use crate::view::Frame;
+
+const REVEAL_FILL_STYLE: &str = "rgba(255, 200, 0, 0.6)";
+const REVEAL_STROKE_STYLE: &str = "rgba(255, 140, 0, 1.0)";
+const REVEAL_LINE_WIDTH: f64 = 0.004;
+// Times are in milliseconds
+const DEFAULT_REVEAL_DURATION: f64 = 3000.0;
+// Fraction of the duration of a reveal over which the highlight fades out, at its end
+const REVEAL_FADE: f64 = 0.25;
+
+/// Country asked for by a question.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuizQuestion {
+    /// Index of the country, as in the countries dataset.
+    pub country: u32,
+    /// Name of the country, in the language countries are labelled in.
+    #[wasm_bindgen(getter_with_clone)]
+    pub name: String,
+    /// Latitude (degrees) of the centre of the cap bounding the country.
+    pub lat: f64,
+    /// Longitude (degrees) of the centre of the cap bounding the country.
+    pub lon: f64,
+    /// Radius (degrees) of the cap bounding the country.
+    pub radius: f64,
+}
+
+/// Answers given to the questions.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QuizScore {
+    pub correct: u32,
+    pub answered: u32,
+}
+
+#[derive(Debug)]
+struct Reveal {
+    country: usize,
+    /// Time (milliseconds) the reveal started.
+    start: f64,
+    duration: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct Quiz {
+    /// Country asked for by the question awaiting an answer.
+    question: Option<usize>,
+    /// Countries asked for since every country was last asked for, not asked for again.
+    asked: Vec<usize>,
+    pub score: QuizScore,
+    /// Function called with whether each answer is correct and the score.
+    pub callback: Option<js_sys::Function>,
+    reveal: Option<Reveal>,
+}
+
+impl Quiz {
+    /// Ask for a country chosen by a random number (0 to 1) from the named countries not
+    /// yet asked for, starting over once all have been; returns the country, or `None`
+    /// without the country data.
+    pub fn ask(&mut self, random: f64) -> Option<usize> {
+        let named = |country: &usize| !data::COUNTRY_NAMES[*country].is_empty();
+        let mut country = pick(data::COUNTRY_NAMES.len(), &self.asked, named, random);
+        if country.is_none() && !self.asked.is_empty() {
+            self.asked.clear();
+            country = pick(data::COUNTRY_NAMES.len(), &self.asked, named, random);
+        }
+        self.question = country;
+        self.asked.extend(country);
+        country
+    }
+
+    /// Check an answer to the question awaiting one, given as the country at the location
+    /// chosen, and score it; returns whether it is correct, or `None` with no question
+    /// awaiting an answer.
+    pub fn answer(&mut self, country: Option<usize>) -> Option<bool> {
+        let question = self.question.take()?;
+        let correct = country == Some(question);
+        self.score.answered += 1;
+        if correct {
+            self.score.correct += 1;
+        }
+        Some(correct)
+    }
+
+    /// Forget the questions asked, the score and any country highlighted.
+    pub fn reset(&mut self) {
+        self.question = None;
+        self.asked.clear();
+        self.score = QuizScore::default();
+        self.reveal = None;
+    }
+
+    /// Highlight a country from a time (milliseconds) for a duration (milliseconds, by
+    /// default 3000); returns whether the country exists.
+    pub fn reveal(&mut self, country: usize, time: f64, duration: Option<f64>) -> bool {
+        if country >= data::COUNTRY_POINTS.len() {
+            return false;
+        }
+        self.reveal = Some(Reveal {
+            country,
+            start: time,
+            duration: duration.unwrap_or(DEFAULT_REVEAL_DURATION).max(0.0),
+        });
+        true
+    }
+
+    /// Whether a country is highlighted, or its highlight has finished without being removed.
+    pub fn revealing(&self) -> bool {
+        self.reveal.is_some()
+    }
+
+    /// Remove a finished highlight, returning whether a country is highlighted at a time
+    /// (milliseconds) or its highlight has just finished, so the frame needs drawing.
+    pub fn animating(&mut self, time: f64) -> bool {
+        let Some(reveal) = &self.reveal else {
+            return false;
+        };
+        if time >= reveal.start + reveal.duration {
+            self.reveal = None;
+        }
+        true
+    }
+
+    /// Draw the highlight of the country revealed at a time (milliseconds) onto the canvas.
+    pub fn draw(
+        &self,
+        context: &CanvasRenderingContext2d,
+        frame: &Frame,
+        time: f64,
+    ) -> Result<(), JsValue> {
+        let Some(reveal) = &self.reveal else {
+            return Ok(());
+        };
+        let Some(points) = data::COUNTRY_POINTS.get(reveal.country) else {
+            return Ok(());
+        };
+        let remaining = (reveal.start + reveal.duration - time) / reveal.duration;
+        context.set_global_alpha((remaining / REVEAL_FADE).clamp(0.0, 1.0));
+        context.begin_path();
+        for point in points {
+            let (lon, lat) = point.to_degrees();
+            let (y, z) = frame.project_to_disc(lon, lat);
+            context.line_to(y, z);
+        }
+        context.close_path();
+        context.set_fill_style_str(REVEAL_FILL_STYLE);
+        context.fill();
+        context.set_line_width(frame.line_width(REVEAL_LINE_WIDTH));
+        context.set_stroke_style_str(REVEAL_STROKE_STYLE);
+        context.stroke();
+        context.set_global_alpha(1.0);
+        Ok(())
+    }
+}
+
+/// Country containing a location (degrees), or `None` over the sea or without the country
+/// data.
+pub fn country_at(location: LonLat) -> Option<usize> {
+    data::COUNTRY_BOUNDS
+        .iter()
+        .enumerate()
+        .filter(|(_, bounds)| measure::central_angle(location, bounds.centroid) <= bounds.radius)
+        .find(|(country, _)| {
+            data::COUNTRY_POINTS.get(*country).is_some_and(|points| {
+                contains(points.iter().map(|point| point.to_degrees()), location)
+            })
+        })
+        .map(|(country, _)| country)
+}
+
+/// Whether rings (degrees, as longitude and latitude), one after another, contain a location
+/// by the even-odd rule. Between closed rings the edges there and back cancel, so the rings
+/// of a country's polygons are tested together.
+fn contains(points: impl Iterator<Item = (f64, f64)>, location: LonLat) -> bool {
+    let LonLat { lon, lat } = location;
+    let mut points = points.peekable();
+    let Some(&first) = points.peek() else {
+        return false;
+    };
+    let mut inside = false;
+    let mut previous = first;
+    for point in points.chain(std::iter::once(first)) {
+        let ((x0, y0), (x1, y1)) = (previous, point);
+        if (y0 > lat) != (y1 > lat) && lon < x0 + (lat - y0) / (y1 - y0) * (x1 - x0) {
+            inside = !inside;
+        }
+        previous = point;
+    }
+    inside
+}
+
+/// Index below a count chosen by a random number (0 to 1) from those not excluded and
+/// accepted by a filter.
+fn pick(
+    count: usize,
+    excluded: &[usize],
+    filter: impl Fn(&usize) -> bool,
+    random: f64,
+) -> Option<usize> {
+    let candidates = (0..count)
+        .filter(|index| !excluded.contains(index))
+        .filter(filter)
+        .collect::<Vec<_>>();
+    let index = (random.clamp(0.0, 1.0) * candidates.len() as f64) as usize;
+    candidates
+        .get(index.min(candidates.len().saturating_sub(1)))
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_locations_and_picks_unasked_countries() {
+        let square = [
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+            (0.0, 0.0),
+        ];
+        let island = [(20.0, 0.0), (25.0, 0.0), (25.0, 5.0), (20.0, 0.0)];
+        let rings = || square.iter().chain(&island).copied();
+        assert!(contains(rings(), LonLat::new(5.0, 5.0)));
+        assert!(contains(rings(), LonLat::new(24.0, 2.0)));
+        assert!(!contains(rings(), LonLat::new(15.0, 5.0)));
+        assert!(!contains(rings(), LonLat::new(5.0, 11.0)));
+        assert!(!contains(std::iter::empty(), LonLat::default()));
+
+        let all = |_: &usize| true;
+        assert_eq!(pick(4, &[], all, 0.0), Some(0));
+        assert_eq!(pick(4, &[], all, 1.0), Some(3));
+        assert_eq!(pick(4, &[1, 2], all, 0.6), Some(3));
+        assert_eq!(pick(4, &[0], |index| index % 2 == 0, 0.0), Some(2));
+        assert_eq!(pick(2, &[0, 1], all, 0.5), None);
+    }
+}
@@ -0,0 +1,467 @@
+// Recording of the input on the globe and of the views it led to, as a JSON log that can be
+// replayed frame by frame on the recorded clock, so interaction bugs can be reported and
+// reproduced exactly.
+//
+// A log holds the view and canvas size at the start of recording, then entries in time
+// order: the pointer, wheel and resize input, and a view entry for each animation frame
+// drawn. Replaying feeds each frame's input to the globe with the clock set to the times
+// recorded, and compares the views reached with those recorded.
+
+use std::cell::Cell;
+
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::controls::Position;
+use crate::input::{PointerInput, WheelInput};
+use crate::view::Orientation;
+
+const LOG_VERSION: f64 = 1.0;
+// Views within this difference (degrees, or of the zoom factor) of those recorded match
+const VIEW_TOLERANCE: f64 = 1e-6;
+
+thread_local! {
+    // Time on the recorded clock while replaying, in place of the time of the page
+    static CLOCK: Cell<Option<f64>> = const { Cell::new(None) };
+}
+
+/// Time (milliseconds) of the recorded clock while a log is replayed.
+pub fn clock() -> Option<f64> {
+    CLOCK.with(Cell::get)
+}
+
+/// Set the time (milliseconds) of the recorded clock, or return to the page's with `None`.
+pub fn set_clock(time: Option<f64>) {
+    CLOCK.with(|clock| clock.set(time));
+}
+
+/// Input on the globe.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Input {
+    PointerDown(PointerInput),
+    PointerMove(PointerInput),
+    PointerUp(PointerInput),
+    PointerLeave,
+    PointerCancel,
+    Wheel(WheelInput),
+    /// Size (canvas pixels) of the canvas on the page.
+    Resize(u32, u32),
+}
+
+impl Input {
+    /// Input with its time stamp moved by an offset (milliseconds).
+    fn shifted(&self, offset: f64) -> Input {
+        match self {
+            Input::PointerDown(input) => Input::PointerDown(shift_pointer(input, offset)),
+            Input::PointerMove(input) => Input::PointerMove(shift_pointer(input, offset)),
+            Input::PointerUp(input) => Input::PointerUp(shift_pointer(input, offset)),
+            Input::Wheel(input) => Input::Wheel(WheelInput {
+                time_stamp: input.time_stamp + offset,
+                ..input.clone()
+            }),
+            _ => self.clone(),
+        }
+    }
+}
+
+fn shift_pointer(input: &PointerInput, offset: f64) -> PointerInput {
+    PointerInput {
+        time_stamp: input.time_stamp + offset,
+        ..input.clone()
+    }
+}
+
+/// View of the globe: its orientation and zoom factor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct View {
+    pub orientation: Orientation,
+    pub zoom: f64,
+}
+
+impl View {
+    fn matches(&self, other: &View) -> bool {
+        let (a, b) = (self.orientation, other.orientation);
+        [
+            (a.rotation, b.rotation),
+            (a.tilt, b.tilt),
+            (a.roll, b.roll),
+            (self.zoom, other.zoom),
+        ]
+        .iter()
+        .all(|(a, b)| (a - b).abs() <= VIEW_TOLERANCE)
+    }
+}
+
+/// Entry of a log, at a time (milliseconds) from the start of recording.
+#[derive(Clone, Debug, PartialEq)]
+enum Entry {
+    Input(f64, Input),
+    Frame(f64, View),
+}
+
+/// Log of the input on the globe and the views drawn, with times from its start.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recording {
+    pub view: View,
+    pub canvas_size: (u32, u32),
+    entries: Vec<Entry>,
+}
+
+/// Input of a replayed frame and the time (milliseconds) of the frame, on the recorded clock.
+#[derive(Debug, PartialEq)]
+pub struct ReplayFrame {
+    pub time: f64,
+    /// Input before the frame, with the time it arrived.
+    pub inputs: Vec<(f64, Input)>,
+}
+
+/// Outcome of a replay: the frames replayed, and the first whose view differed from the
+/// view recorded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReplayOutcome {
+    pub frames: usize,
+    pub diverged: Option<usize>,
+}
+
+#[derive(Debug)]
+struct Replay {
+    recording: Recording,
+    /// Offset (milliseconds) of the recorded clock from the start of recording.
+    offset: f64,
+    /// Index of the next entry replayed.
+    next: usize,
+    /// View recorded for the frame being replayed.
+    expected: Option<View>,
+    frames: usize,
+    diverged: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+pub struct Recorder {
+    /// Log being recorded, with the time (milliseconds) recording started.
+    recording: Option<(f64, Recording)>,
+    replay: Option<Replay>,
+}
+
+impl Recorder {
+    /// Start recording at a time (milliseconds) from a view, discarding any log recorded.
+    pub fn start(&mut self, time: f64, view: View, canvas_size: (u32, u32)) {
+        let recording = Recording {
+            view,
+            canvas_size,
+            entries: Vec::new(),
+        };
+        self.recording = Some((time, recording));
+    }
+
+    /// Stop recording, returning the log recorded.
+    pub fn stop(&mut self) -> Option<Recording> {
+        self.recording.take().map(|(_, recording)| recording)
+    }
+
+    /// Record input arriving at a time (milliseconds), unless replaying.
+    pub fn input(&mut self, time: f64, input: &Input) {
+        if self.replay.is_some() {
+            return;
+        }
+        if let Some((start, recording)) = &mut self.recording {
+            recording
+                .entries
+                .push(Entry::Input(time - *start, input.shifted(-*start)));
+        }
+    }
+
+    /// Record the view drawn in the frame at a time (milliseconds), or while replaying,
+    /// compare it with the view recorded.
+    pub fn frame(&mut self, time: f64, view: View) {
+        if let Some(replay) = &mut self.replay {
+            if let Some(expected) = replay.expected.take() {
+                if replay.diverged.is_none() && !expected.matches(&view) {
+                    replay.diverged = Some(replay.frames);
+                }
+                replay.frames += 1;
+            }
+            return;
+        }
+        if let Some((start, recording)) = &mut self.recording {
+            recording.entries.push(Entry::Frame(time - *start, view));
+        }
+    }
+
+    /// Replay a log from a time (milliseconds), its recorded clock starting then.
+    pub fn replay(&mut self, recording: Recording, time: f64) {
+        self.replay = Some(Replay {
+            recording,
+            offset: time,
+            next: 0,
+            expected: None,
+            frames: 0,
+            diverged: None,
+        });
+    }
+
+    /// Whether a log is being replayed.
+    pub fn replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// Input and time of the next frame replayed, or `None` when not replaying or all the
+    /// frames have been replayed.
+    pub fn next_frame(&mut self) -> Option<ReplayFrame> {
+        let replay = self.replay.as_mut()?;
+        let mut inputs = Vec::new();
+        while let Some(entry) = replay.recording.entries.get(replay.next) {
+            replay.next += 1;
+            match entry {
+                Entry::Input(time, input) => {
+                    inputs.push((time + replay.offset, input.shifted(replay.offset)));
+                }
+                Entry::Frame(time, view) => {
+                    replay.expected = Some(*view);
+                    return Some(ReplayFrame {
+                        time: time + replay.offset,
+                        inputs,
+                    });
+                }
+            }
+        }
+        // Input after the last frame is fed in a frame of its own
+        let time = inputs.last()?.0;
+        Some(ReplayFrame { time, inputs })
+    }
+
+    /// Outcome of the replay once all its frames have been replayed, ending it.
+    pub fn finish(&mut self) -> Option<ReplayOutcome> {
+        let replay = self.replay.as_ref()?;
+        if replay.next < replay.recording.entries.len() || replay.expected.is_some() {
+            return None;
+        }
+        let replay = self.replay.take()?;
+        Some(ReplayOutcome {
+            frames: replay.frames,
+            diverged: replay.diverged,
+        })
+    }
+}
+
+impl Recording {
+    /// The log as JSON.
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        let log = js_sys::Object::new();
+        set(&log, "version", LOG_VERSION.into())?;
+        set(&log, "view", view_object(&self.view)?.into())?;
+        set(&log, "width", self.canvas_size.0.into())?;
+        set(&log, "height", self.canvas_size.1.into())?;
+        let entries = js_sys::Array::new();
+        for entry in &self.entries {
+            let object = js_sys::Object::new();
+            match entry {
+                Entry::Input(time, input) => {
+                    set(&object, "time", (*time).into())?;
+                    set_input(&object, input)?;
+                }
+                Entry::Frame(time, view) => {
+                    set(&object, "time", (*time).into())?;
+                    set(&object, "type", "frame".into())?;
+                    set(&object, "view", view_object(view)?.into())?;
+                }
+            }
+            entries.push(&object);
+        }
+        set(&log, "entries", entries.into())?;
+        js_sys::JSON::stringify(&log)?
+            .as_string()
+            .ok_or_else(|| JsValue::from_str("log should be written as JSON"))
+    }
+
+    /// Log from JSON written by `to_json`.
+    pub fn from_json(json: &str) -> Result<Recording, JsValue> {
+        let log = js_sys::JSON::parse(json)?;
+        if number(&log, "version")? != LOG_VERSION {
+            return Err(JsValue::from_str("unsupported version of recorded log"));
+        }
+        let entries = js_sys::Reflect::get(&log, &"entries".into())?
+            .dyn_into::<js_sys::Array>()
+            .map_err(|_| JsValue::from_str("recorded log should have entries"))?;
+        Ok(Recording {
+            view: parse_view(&js_sys::Reflect::get(&log, &"view".into())?)?,
+            canvas_size: (
+                number(&log, "width")? as u32,
+                number(&log, "height")? as u32,
+            ),
+            entries: entries
+                .iter()
+                .map(|entry| parse_entry(&entry))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+fn set(object: &js_sys::Object, key: &str, value: JsValue) -> Result<(), JsValue> {
+    js_sys::Reflect::set(object, &key.into(), &value).map(|_| ())
+}
+
+fn number(object: &JsValue, key: &str) -> Result<f64, JsValue> {
+    js_sys::Reflect::get(object, &key.into())?
+        .as_f64()
+        .ok_or_else(|| JsValue::from_str(&format!("recorded log should have {:?}", key)))
+}
+
+fn view_object(view: &View) -> Result<js_sys::Object, JsValue> {
+    let object = js_sys::Object::new();
+    set(&object, "rotation", view.orientation.rotation.into())?;
+    set(&object, "tilt", view.orientation.tilt.into())?;
+    set(&object, "roll", view.orientation.roll.into())?;
+    set(&object, "zoom", view.zoom.into())?;
+    Ok(object)
+}
+
+fn parse_view(object: &JsValue) -> Result<View, JsValue> {
+    Ok(View {
+        orientation: Orientation {
+            rotation: number(object, "rotation")?,
+            tilt: number(object, "tilt")?,
+            roll: number(object, "roll")?,
+        },
+        zoom: number(object, "zoom")?,
+    })
+}
+
+fn set_input(object: &js_sys::Object, input: &Input) -> Result<(), JsValue> {
+    let (kind, pointer) = match input {
+        Input::PointerDown(pointer) => ("pointerdown", Some(pointer)),
+        Input::PointerMove(pointer) => ("pointermove", Some(pointer)),
+        Input::PointerUp(pointer) => ("pointerup", Some(pointer)),
+        Input::PointerLeave => ("pointerleave", None),
+        Input::PointerCancel => ("pointercancel", None),
+        Input::Wheel(wheel) => {
+            set(object, "type", "wheel".into())?;
+            set(object, "x", wheel.position.x.into())?;
+            set(object, "y", wheel.position.y.into())?;
+            set(object, "deltaY", wheel.delta_y.into())?;
+            set(object, "deltaMode", wheel.delta_mode.into())?;
+            return set(object, "timeStamp", wheel.time_stamp.into());
+        }
+        Input::Resize(width, height) => {
+            set(object, "type", "resize".into())?;
+            set(object, "width", (*width).into())?;
+            return set(object, "height", (*height).into());
+        }
+    };
+    set(object, "type", kind.into())?;
+    if let Some(pointer) = pointer {
+        set(object, "x", pointer.position.x.into())?;
+        set(object, "y", pointer.position.y.into())?;
+        set(object, "pointerId", pointer.pointer_id.into())?;
+        set(object, "pointerType", pointer.pointer_type.as_str().into())?;
+        set(object, "timeStamp", pointer.time_stamp.into())?;
+    }
+    Ok(())
+}
+
+fn parse_entry(object: &JsValue) -> Result<Entry, JsValue> {
+    let time = number(object, "time")?;
+    let kind = js_sys::Reflect::get(object, &"type".into())?
+        .as_string()
+        .unwrap_or_default();
+    let position = || -> Result<Position, JsValue> {
+        Ok(Position {
+            x: number(object, "x")?,
+            y: number(object, "y")?,
+        })
+    };
+    let pointer = || -> Result<PointerInput, JsValue> {
+        Ok(PointerInput {
+            position: position()?,
+            pointer_id: number(object, "pointerId")? as i32,
+            time_stamp: number(object, "timeStamp")?,
+            pointer_type: js_sys::Reflect::get(object, &"pointerType".into())?
+                .as_string()
+                .unwrap_or_default(),
+        })
+    };
+    let input = match kind.as_str() {
+        "frame" => {
+            let view = parse_view(&js_sys::Reflect::get(object, &"view".into())?)?;
+            return Ok(Entry::Frame(time, view));
+        }
+        "pointerdown" => Input::PointerDown(pointer()?),
+        "pointermove" => Input::PointerMove(pointer()?),
+        "pointerup" => Input::PointerUp(pointer()?),
+        "pointerleave" => Input::PointerLeave,
+        "pointercancel" => Input::PointerCancel,
+        "wheel" => Input::Wheel(WheelInput {
+            position: position()?,
+            delta_y: number(object, "deltaY")?,
+            delta_mode: number(object, "deltaMode")? as u32,
+            time_stamp: number(object, "timeStamp")?,
+        }),
+        "resize" => Input::Resize(
+            number(object, "width")? as u32,
+            number(object, "height")? as u32,
+        ),
+        _ => {
+            return Err(JsValue::from_str(&format!(
+                "unknown entry {:?} in recorded log",
+                kind
+            )))
+        }
+    };
+    Ok(Entry::Input(time, input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_recorded_frames() {
+        let view = |rotation| View {
+            orientation: Orientation {
+                rotation,
+                ..Orientation::default()
+            },
+            zoom: 1.0,
+        };
+        let press = PointerInput {
+            position: Position { x: 10.0, y: 20.0 },
+            pointer_id: 1,
+            time_stamp: 1005.0,
+            pointer_type: "mouse".to_string(),
+        };
+        let mut recorder = Recorder::default();
+        recorder.start(1000.0, view(0.0), (800, 800));
+        recorder.input(1005.0, &Input::PointerDown(press.clone()));
+        recorder.frame(1016.0, view(0.0));
+        recorder.frame(1032.0, view(5.0));
+        recorder.input(1040.0, &Input::PointerLeave);
+        let recording = recorder.stop().unwrap();
+        assert_eq!(recording.entries.len(), 4);
+
+        recorder.replay(recording, 5000.0);
+        // Nothing is recorded while replaying
+        recorder.input(5001.0, &Input::PointerCancel);
+        let frame = recorder.next_frame().unwrap();
+        assert_eq!(frame.time, 5016.0);
+        let replayed = PointerInput {
+            time_stamp: 5005.0,
+            ..press
+        };
+        assert_eq!(frame.inputs, [(5005.0, Input::PointerDown(replayed))]);
+        recorder.frame(5016.0, view(0.0));
+        assert_eq!(recorder.finish(), None);
+
+        let frame = recorder.next_frame().unwrap();
+        assert!(frame.inputs.is_empty());
+        recorder.frame(5032.0, view(4.0));
+        let frame = recorder.next_frame().unwrap();
+        assert_eq!(frame.inputs, [(5040.0, Input::PointerLeave)]);
+        assert_eq!(
+            recorder.finish(),
+            Some(ReplayOutcome {
+                frames: 2,
+                diverged: Some(1)
+            })
+        );
+        assert!(!recorder.replaying());
+    }
+}
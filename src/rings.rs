@@ -0,0 +1,126 @@
+// Concentric bands of geodesic distance from an origin.
+
+use wasm_bindgen::JsValue;
+use web_sys::{CanvasRenderingContext2d, CanvasWindingRule};
+
+use crate::coords::LonLat;
+use crate::legend::{Legend, LegendEntry, LegendSymbols};
+use crate::measure;
+use crate::view::Frame;
+
+// Bands alternate between the fill styles, outwards from the origin
+const RING_FILL_STYLES: [&str; 2] = ["rgba(255, 127, 0, 0.35)", "rgba(255, 127, 0, 0.15)"];
+const RING_SAMPLES: usize = 180;
+const RING_DEFAULT_INTERVAL: f64 = 1000.0;
+const RING_DEFAULT_COUNT: u32 = 5;
+const RING_LEGEND_TITLE: &str = "Distance";
+
+#[derive(Debug)]
+pub struct DistanceRings {
+    /// Origin (degrees) the distances are from.
+    pub origin: Option<LonLat>,
+    /// Width (kilometres) of each band.
+    pub interval: f64,
+    pub count: u32,
+}
+
+impl Default for DistanceRings {
+    fn default() -> Self {
+        Self {
+            origin: None,
+            interval: RING_DEFAULT_INTERVAL,
+            count: RING_DEFAULT_COUNT,
+        }
+    }
+}
+
+impl DistanceRings {
+    /// Inner and outer distances (kilometres) of each band, up to the antipode of the origin.
+    fn bands(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        let max_distance = std::f64::consts::PI * measure::EARTH_RADIUS;
+        (0..self.count)
+            .map(|i| (i as f64 * self.interval, (i + 1) as f64 * self.interval))
+            .take_while(move |(inner, _)| *inner < max_distance)
+            .map(move |(inner, outer)| (inner, outer.min(max_distance)))
+    }
+
+    /// Fill the bands onto the canvas.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        let Some(origin) = self.origin else {
+            return Ok(());
+        };
+        if self.interval <= 0.0 {
+            return Ok(());
+        }
+
+        for (i, (inner, outer)) in self.bands().enumerate() {
+            // Each band is the area between its outer and inner small circles
+            context.begin_path();
+            for distance in [outer, inner] {
+                if distance <= 0.0 {
+                    continue;
+                }
+                let angle = (distance / measure::EARTH_RADIUS).to_degrees();
+                for location in measure::small_circle(origin, angle, RING_SAMPLES) {
+                    let (y, z) = frame.project_to_disc(location.lon, location.lat);
+                    context.line_to(y, z);
+                }
+                context.close_path();
+            }
+            context.set_fill_style_str(RING_FILL_STYLES[i % RING_FILL_STYLES.len()]);
+            context.fill_with_canvas_winding_rule(CanvasWindingRule::Evenodd);
+        }
+
+        Ok(())
+    }
+
+    /// Legend of the bands, with distances written in the frame's locale, when shown.
+    pub fn legend(&self, frame: &Frame) -> Option<Legend> {
+        if self.origin.is_none() || self.interval <= 0.0 {
+            return None;
+        }
+        let entries = self
+            .bands()
+            .enumerate()
+            .map(|(i, (inner, outer))| LegendEntry {
+                fill_style: RING_FILL_STYLES[i % RING_FILL_STYLES.len()].to_string(),
+                text: format!(
+                    "{}\u{2013}{} km",
+                    frame.locale.number(inner, 0),
+                    frame.locale.number(outer, 0)
+                ),
+            })
+            .collect();
+        Some(Legend {
+            title: RING_LEGEND_TITLE.to_string(),
+            symbols: LegendSymbols::Swatches(entries),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bands_stop_at_the_antipode() {
+        let mut rings = DistanceRings {
+            origin: Some(LonLat::new(0.0, 0.0)),
+            interval: 1000.0,
+            count: 3,
+        };
+        assert_eq!(
+            rings.bands().collect::<Vec<_>>(),
+            [(0.0, 1000.0), (1000.0, 2000.0), (2000.0, 3000.0)]
+        );
+        // Half the circumference is about 20,000 km, where the last band is cut short
+        rings.interval = 8000.0;
+        let max_distance = std::f64::consts::PI * measure::EARTH_RADIUS;
+        assert_eq!(
+            rings.bands().collect::<Vec<_>>(),
+            [(0.0, 8000.0), (8000.0, 16000.0), (16000.0, max_distance)]
+        );
+        rings.count = 5;
+        assert_eq!(rings.bands().count(), 3);
+    }
+}
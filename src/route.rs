@@ -0,0 +1,324 @@
+// Interactive editing of a multi-leg great-circle route between waypoints.
+
+use wasm_bindgen::JsValue;
+use web_sys::{CanvasRenderingContext2d, DomMatrix};
+
+use crate::controls::Position;
+use crate::coords::LonLat;
+use crate::labels::Label;
+use crate::measure;
+use crate::projection::Projection;
+use crate::view::{self, Frame, Orientation, Side};
+
+const ROUTE_FRONT_STROKE_STYLE: &str = "rgba(200, 0, 0, 1.0)";
+const ROUTE_BACK_STROKE_STYLE: &str = "rgba(0, 0, 0, 0.25)";
+const ROUTE_FRONT_LINE_WIDTH: f64 = 0.005;
+const ROUTE_BACK_LINE_WIDTH: f64 = 0.0025;
+const WAYPOINT_FILL_STYLE: &str = "rgba(255, 255, 255, 1.0)";
+const CROSSING_STROKE_STYLE: &str = "rgba(255, 160, 0, 1.0)";
+// Sizes are in canvas pixels and angles in degrees
+const WAYPOINT_RADIUS: f64 = 5.0;
+const WAYPOINT_HIT_DISTANCE: f64 = 10.0;
+const CROSSING_SIZE: f64 = 6.0;
+const LEG_SAMPLE_ANGLE: f64 = 2.0;
+const LEG_LABEL_PRIORITY: f64 = 0.0;
+
+#[derive(Debug, Default)]
+pub struct Route {
+    /// Whether taps add waypoints and waypoints can be dragged.
+    pub editing: bool,
+    /// Waypoints (degrees), in order.
+    waypoints: Vec<LonLat>,
+    /// Index of the waypoint being dragged.
+    dragging: Option<usize>,
+}
+
+impl Route {
+    /// Add a waypoint to the end of the route.
+    pub fn add(&mut self, location: LonLat) {
+        self.waypoints.push(location);
+    }
+
+    /// Remove a waypoint, returning whether it existed.
+    pub fn remove(&mut self, index: usize) -> bool {
+        if index >= self.waypoints.len() {
+            return false;
+        }
+        self.waypoints.remove(index);
+        self.dragging = None;
+        true
+    }
+
+    /// Remove all waypoints.
+    pub fn clear(&mut self) {
+        self.waypoints.clear();
+        self.dragging = None;
+    }
+
+    /// Distances (kilometres) of each leg of the route.
+    pub fn leg_distances(&self) -> Vec<f64> {
+        self.waypoints
+            .windows(2)
+            .map(|leg| measure::distance(leg[0], leg[1]))
+            .collect()
+    }
+
+    /// Locations (degrees) where legs of the route cross legs other than those next to them,
+    /// as the route intersects itself.
+    pub fn crossings(&self) -> Vec<LonLat> {
+        let legs = self
+            .waypoints
+            .windows(2)
+            .map(|leg| (leg[0], leg[1]))
+            .collect::<Vec<_>>();
+        let mut crossings = Vec::new();
+        for (i, first) in legs.iter().enumerate() {
+            // Legs next to each other meet at their shared waypoint
+            for second in legs.iter().skip(i + 2) {
+                crossings.extend(measure::intersection(*first, *second));
+            }
+        }
+        crossings
+    }
+
+    /// Start dragging the waypoint under a canvas position, if editing,
+    /// returning whether a waypoint is being dragged.
+    pub fn drag_start(
+        &mut self,
+        projection: &Projection,
+        orientation: &Orientation,
+        transform: &DomMatrix,
+        position: &Position,
+    ) -> bool {
+        if !self.editing {
+            return false;
+        }
+        self.dragging = self
+            .waypoints
+            .iter()
+            .enumerate()
+            .filter_map(|(index, waypoint)| {
+                let (x, y, z) = projection.project(orientation, waypoint.lon, waypoint.lat);
+                let (canvas_x, canvas_y) = view::to_canvas(transform, y, z);
+                let distance = (canvas_x - position.x).hypot(canvas_y - position.y);
+                (x >= 0.0 && distance <= WAYPOINT_HIT_DISTANCE).then_some((index, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index);
+        self.dragging.is_some()
+    }
+
+    /// Move the waypoint being dragged to a location (degrees), returning whether one is dragged.
+    pub fn drag_to(&mut self, location: Option<LonLat>) -> bool {
+        let Some(index) = self.dragging else {
+            return false;
+        };
+        // Locations off the globe leave the waypoint where it was
+        if let Some(location) = location {
+            self.waypoints[index] = location;
+        }
+        true
+    }
+
+    /// Whether a waypoint is being dragged.
+    pub fn dragging(&self) -> bool {
+        self.dragging.is_some()
+    }
+
+    /// Stop dragging, returning whether a waypoint was being dragged.
+    pub fn drag_end(&mut self) -> bool {
+        self.dragging.take().is_some()
+    }
+
+    /// Draw the legs of the route along great circles, and the waypoints, onto the canvas.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        for leg in self.waypoints.windows(2) {
+            let samples = leg_samples(leg[0], leg[1]);
+            let points = (0..=samples)
+                .map(|i| {
+                    let location = measure::interpolate(leg[0], leg[1], i as f64 / samples as f64);
+                    frame.project(location.lon, location.lat)
+                })
+                .collect::<Vec<_>>();
+            for segment in points.windows(2) {
+                let ((_, y_prev, z_prev), (_, y, z)) = (segment[0], segment[1]);
+                match frame.segment_side(segment[0], segment[1]) {
+                    Some(Side::Front) => {
+                        context.set_line_width(frame.line_width(ROUTE_FRONT_LINE_WIDTH));
+                        context.set_stroke_style_str(ROUTE_FRONT_STROKE_STYLE);
+                    }
+                    Some(_) => {
+                        context.set_line_width(frame.line_width(ROUTE_BACK_LINE_WIDTH));
+                        context.set_stroke_style_str(ROUTE_BACK_STROKE_STYLE);
+                    }
+                    None => continue,
+                }
+                context.begin_path();
+                context.move_to(y_prev, z_prev);
+                context.line_to(y, z);
+                context.stroke();
+            }
+        }
+
+        // Waypoints are a constant size on screen
+        let radius = WAYPOINT_RADIUS / frame.scale;
+        context.set_line_width(frame.line_width(ROUTE_FRONT_LINE_WIDTH));
+        context.set_stroke_style_str(ROUTE_FRONT_STROKE_STYLE);
+        context.set_fill_style_str(WAYPOINT_FILL_STYLE);
+        for waypoint in &self.waypoints {
+            let (x, y, z) = frame.project(waypoint.lon, waypoint.lat);
+            if x < 0.0 {
+                continue;
+            }
+            context.begin_path();
+            context.arc(y, z, radius, 0.0, std::f64::consts::TAU)?;
+            context.fill();
+            context.stroke();
+        }
+
+        // Crossings of legs are marked as warnings while editing
+        if self.editing {
+            let size = CROSSING_SIZE / frame.scale;
+            context.set_stroke_style_str(CROSSING_STROKE_STYLE);
+            for crossing in self.crossings() {
+                let (x, y, z) = frame.project(crossing.lon, crossing.lat);
+                if x < 0.0 {
+                    continue;
+                }
+                context.begin_path();
+                context.move_to(y - size, z - size);
+                context.line_to(y + size, z + size);
+                context.move_to(y - size, z + size);
+                context.line_to(y + size, z - size);
+                context.stroke();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Labels of the distance of each leg, at the middle of legs on the front of the globe.
+    pub fn labels(&self, frame: &Frame) -> Vec<Label> {
+        self.waypoints
+            .windows(2)
+            .filter_map(|leg| {
+                let middle = measure::interpolate(leg[0], leg[1], 0.5);
+                let (x, y, z) = frame.project(middle.lon, middle.lat);
+                if x < 0.0 {
+                    return None;
+                }
+                let (x, y) = frame.to_canvas(y, z);
+                Some(Label {
+                    text: format!(
+                        "{} km",
+                        frame.locale.number(measure::distance(leg[0], leg[1]), 0)
+                    ),
+                    x,
+                    y,
+                    priority: LEG_LABEL_PRIORITY,
+                    allow_overlap: false,
+                })
+            })
+            .collect()
+    }
+
+    /// The route as a GeoJSON feature collection: the legs as a line string densified
+    /// along their great circles, with the leg and total distances (kilometres), and
+    /// the waypoints as points with their indices.
+    pub fn to_geojson(&self) -> String {
+        let mut coordinates = Vec::new();
+        for (i, leg) in self.waypoints.windows(2).enumerate() {
+            let samples = leg_samples(leg[0], leg[1]);
+            // Each leg starts where the previous one ended
+            let first = if i == 0 { 0 } else { 1 };
+            for j in first..=samples {
+                coordinates.push(measure::interpolate(
+                    leg[0],
+                    leg[1],
+                    j as f64 / samples as f64,
+                ));
+            }
+        }
+
+        let leg_distances = self.leg_distances();
+        let mut features = Vec::new();
+        if !coordinates.is_empty() {
+            features.push(format!(
+                r#"{{"type":"Feature","geometry":{{"type":"LineString","coordinates":[{}]}},"properties":{{"legDistances":[{}],"distance":{}}}}}"#,
+                coordinates
+                    .iter()
+                    .map(|location| format!("[{},{}]", location.lon, location.lat))
+                    .collect::<Vec<_>>()
+                    .join(","),
+                leg_distances
+                    .iter()
+                    .map(|distance| distance.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+                leg_distances.iter().sum::<f64>(),
+            ));
+        }
+        for (index, waypoint) in self.waypoints.iter().enumerate() {
+            features.push(format!(
+                r#"{{"type":"Feature","geometry":{{"type":"Point","coordinates":[{},{}]}},"properties":{{"index":{}}}}}"#,
+                waypoint.lon, waypoint.lat, index
+            ));
+        }
+
+        format!(
+            r#"{{"type":"FeatureCollection","features":[{}]}}"#,
+            features.join(",")
+        )
+    }
+}
+
+/// Number of segments sampling a leg along its great circle.
+fn leg_samples(from: LonLat, to: LonLat) -> usize {
+    ((measure::central_angle(from, to) / LEG_SAMPLE_ANGLE).ceil() as usize).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_waypoints() {
+        let mut route = Route::default();
+        route.add(LonLat::new(0.0, 0.0));
+        route.add(LonLat::new(90.0, 0.0));
+        route.add(LonLat::new(90.0, 90.0));
+        let distances = route.leg_distances();
+        let quarter = std::f64::consts::FRAC_PI_2 * measure::EARTH_RADIUS;
+        assert_eq!(distances.len(), 2);
+        assert!(distances
+            .iter()
+            .all(|distance| (distance - quarter).abs() < 1e-6));
+
+        // Dragging moves the waypoint, but not off the globe
+        route.dragging = Some(1);
+        assert!(route.drag_to(Some(LonLat::new(45.0, 0.0))));
+        assert!(route.drag_to(None));
+        assert_eq!(route.waypoints[1], LonLat::new(45.0, 0.0));
+        assert!(route.drag_end());
+        assert!(!route.drag_to(Some(LonLat::new(0.0, 0.0))));
+        assert_eq!(route.waypoints[1], LonLat::new(45.0, 0.0));
+
+        assert!(route.remove(0));
+        assert!(!route.remove(2));
+        assert_eq!(route.leg_distances().len(), 1);
+    }
+
+    #[test]
+    fn finds_where_the_route_crosses_itself() {
+        let mut route = Route::default();
+        for (lon, lat) in [(-10.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)] {
+            route.add(LonLat::new(lon, lat));
+        }
+        assert_eq!(route.crossings(), []);
+        // Doubling back across the first leg crosses it on the equator
+        route.add(LonLat::new(0.0, -10.0));
+        let crossings = route.crossings();
+        assert_eq!(crossings.len(), 1);
+        assert!(crossings[0].lon.abs() < 1e-9 && crossings[0].lat.abs() < 1e-9);
+    }
+}
@@ -0,0 +1,329 @@
+// Frame budget aware scheduling of the passes drawing a frame.
+
+// Times are in milliseconds
+const DEFAULT_FRAME_BUDGET: f64 = 12.0;
+const COST_SMOOTHING: f64 = 0.2;
+// Animation frames arriving this early are still ticked, as their times jitter
+const FRAME_INTERVAL_TOLERANCE: f64 = 1.0;
+
+/// Passes drawing a frame, in drawing order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pass {
+    Sphere,
+    Tiles,
+    Land,
+    Startup,
+    Graticule,
+    Rings,
+    Choropleth,
+    Cartogram,
+    Voronoi,
+    Hexbins,
+    Coastlines,
+    Terminator,
+    Contours,
+    Spikes,
+    Symbols,
+    Arcs,
+    Markers,
+    Tracks,
+    Route,
+    Selection,
+    Extent,
+    Quiz,
+    Pings,
+    Limb,
+    Labels,
+    PostProcess,
+    Magnifier,
+    Swipe,
+    Legend,
+    Moon,
+    Attribution,
+    Status,
+}
+
+impl Pass {
+    pub const ALL: [Pass; 32] = [
+        Pass::Sphere,
+        Pass::Tiles,
+        Pass::Land,
+        Pass::Startup,
+        Pass::Graticule,
+        Pass::Rings,
+        Pass::Choropleth,
+        Pass::Cartogram,
+        Pass::Voronoi,
+        Pass::Hexbins,
+        Pass::Coastlines,
+        Pass::Terminator,
+        Pass::Contours,
+        Pass::Spikes,
+        Pass::Symbols,
+        Pass::Arcs,
+        Pass::Markers,
+        Pass::Tracks,
+        Pass::Route,
+        Pass::Selection,
+        Pass::Extent,
+        Pass::Quiz,
+        Pass::Pings,
+        Pass::Limb,
+        Pass::Labels,
+        Pass::PostProcess,
+        Pass::Magnifier,
+        Pass::Swipe,
+        Pass::Legend,
+        Pass::Moon,
+        Pass::Attribution,
+        Pass::Status,
+    ];
+
+    /// Name of the pass, as a phase of rendering.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pass::Sphere => "sphere",
+            Pass::Tiles => "tiles",
+            Pass::Land => "land",
+            Pass::Startup => "startup",
+            Pass::Graticule => "graticule",
+            Pass::Rings => "rings",
+            Pass::Choropleth => "choropleth",
+            Pass::Cartogram => "cartogram",
+            Pass::Voronoi => "voronoi",
+            Pass::Hexbins => "hexbins",
+            Pass::Coastlines => "coastlines",
+            Pass::Terminator => "terminator",
+            Pass::Contours => "contours",
+            Pass::Spikes => "spikes",
+            Pass::Symbols => "symbols",
+            Pass::Arcs => "arcs",
+            Pass::Markers => "markers",
+            Pass::Tracks => "tracks",
+            Pass::Route => "route",
+            Pass::Selection => "selection",
+            Pass::Extent => "extent",
+            Pass::Quiz => "quiz",
+            Pass::Pings => "pings",
+            Pass::Limb => "limb",
+            Pass::Labels => "labels",
+            Pass::PostProcess => "composite",
+            Pass::Magnifier => "magnifier",
+            Pass::Swipe => "swipe",
+            Pass::Legend => "legend",
+            Pass::Moon => "moon",
+            Pass::Attribution => "attribution",
+            Pass::Status => "status",
+        }
+    }
+
+    /// Identifier of the layer drawn by the pass, for passes drawing a layer.
+    pub fn layer(&self) -> Option<&'static str> {
+        match self {
+            Pass::Tiles => Some("tiles"),
+            Pass::Land => Some("land"),
+            Pass::Graticule => Some("graticule"),
+            Pass::Rings => Some("rings"),
+            Pass::Choropleth => Some("choropleth"),
+            Pass::Cartogram => Some("cartogram"),
+            Pass::Voronoi => Some("voronoi"),
+            Pass::Hexbins => Some("hexbins"),
+            Pass::Coastlines => Some("coastline"),
+            Pass::Terminator => Some("terminator"),
+            Pass::Contours => Some("contours"),
+            Pass::Spikes => Some("spikes"),
+            Pass::Symbols => Some("symbols"),
+            Pass::Arcs => Some("arcs"),
+            Pass::Markers => Some("markers"),
+            Pass::Tracks => Some("tracks"),
+            Pass::Route => Some("route"),
+            Pass::Selection => Some("selection"),
+            Pass::Extent => Some("extent"),
+            Pass::Pings => Some("pings"),
+            _ => None,
+        }
+    }
+
+    /// Whether the pass draws geographic data, clipped to the disc by projections clipping the back.
+    pub fn geographic(&self) -> bool {
+        matches!(
+            self,
+            Pass::Tiles
+                | Pass::Land
+                | Pass::Graticule
+                | Pass::Rings
+                | Pass::Choropleth
+                | Pass::Cartogram
+                | Pass::Voronoi
+                | Pass::Hexbins
+                | Pass::Coastlines
+                | Pass::Terminator
+                | Pass::Contours
+                | Pass::Spikes
+                | Pass::Symbols
+                | Pass::Arcs
+                | Pass::Markers
+                | Pass::Tracks
+                | Pass::Route
+                | Pass::Selection
+                | Pass::Extent
+                | Pass::Quiz
+                | Pass::Pings
+        )
+    }
+
+    /// Whether the pass is drawn again within the magnifier: the sphere and the geographic
+    /// layers, except the tiles, whose requests follow the view.
+    pub fn magnified(&self) -> bool {
+        *self == Pass::Sphere || (self.geographic() && *self != Pass::Tiles)
+    }
+
+    /// Whether the pass is low priority and may be deferred to the next animation frame.
+    fn deferrable(&self) -> bool {
+        matches!(
+            self,
+            Pass::Spikes | Pass::Limb | Pass::Labels | Pass::PostProcess
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct Scheduler {
+    /// Time allowed for drawing in each animation frame.
+    pub budget: f64,
+    /// Smoothed cost of each pass.
+    costs: [f64; Pass::ALL.len()],
+    /// Index of the next pass of the frame to draw.
+    next: usize,
+    /// Deferrable passes left out of the frame being drawn for overrunning the budget.
+    deferred: [bool; Pass::ALL.len()],
+    /// Passes deferred from the last frame, drawn in this one whatever the budget.
+    owed: [bool; Pass::ALL.len()],
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            budget: DEFAULT_FRAME_BUDGET,
+            costs: [0.0; Pass::ALL.len()],
+            next: Pass::ALL.len(),
+            deferred: [false; Pass::ALL.len()],
+            owed: [false; Pass::ALL.len()],
+        }
+    }
+}
+
+impl Scheduler {
+    /// Start drawing a new frame, owing it the passes deferred from the last.
+    pub fn start(&mut self) {
+        self.next = 0;
+        self.owed = std::mem::take(&mut self.deferred);
+    }
+
+    /// Whether passes of the frame are still to be drawn.
+    pub fn drawing(&self) -> bool {
+        self.next < Pass::ALL.len()
+    }
+
+    /// Whether passes were deferred from the last frame, so another is to be drawn.
+    pub fn pending(&self) -> bool {
+        self.deferred.contains(&true)
+    }
+
+    /// Next pass to draw in this animation frame, given the time already spent drawing in it.
+    ///
+    /// Deferrable passes expected to overrun the budget are skipped and drawn in the next
+    /// frame whatever its budget, so none is starved while frames restart; the other passes
+    /// are always drawn.
+    pub fn next_pass(&mut self, elapsed: f64) -> Option<Pass> {
+        while let Some(&pass) = Pass::ALL.get(self.next) {
+            let index = self.next;
+            self.next += 1;
+            if pass.deferrable() && !self.owed[index] && elapsed + self.costs[index] > self.budget {
+                self.deferred[index] = true;
+                continue;
+            }
+            return Some(pass);
+        }
+        None
+    }
+
+    /// Record the time taken to draw a pass.
+    pub fn record(&mut self, pass: Pass, cost: f64) {
+        if let Some(index) = Pass::ALL.iter().position(|p| *p == pass) {
+            self.costs[index] += COST_SMOOTHING * (cost - self.costs[index]);
+        }
+    }
+}
+
+/// Cap on the rate of animation frames ticked, for globes where following the
+/// display's refresh rate is wasteful.
+#[derive(Debug, Default)]
+pub struct FrameRateCap {
+    /// Animation frames per second ticked at most, or `None` for no cap.
+    pub max_fps: Option<f64>,
+    /// Time of the last animation frame ticked.
+    last: Option<f64>,
+}
+
+impl FrameRateCap {
+    /// Whether the animation frame at a time is ticked; the cap is lifted while
+    /// interacting so the globe follows the pointer without lag.
+    pub fn tick(&mut self, time: f64, interacting: bool) -> bool {
+        let due = match (self.max_fps, self.last) {
+            (Some(max_fps), Some(last)) if !interacting && max_fps > 0.0 => {
+                time - last >= 1000.0 / max_fps - FRAME_INTERVAL_TOLERANCE
+            }
+            _ => true,
+        };
+        if due {
+            self.last = Some(time);
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defers_passes_once_the_budget_is_spent() {
+        let mut scheduler = Scheduler::default();
+        scheduler.record(Pass::Spikes, 5.0 / COST_SMOOTHING);
+        scheduler.start();
+        // Only the spikes overrun, and the passes after them are still drawn
+        let drawn: Vec<_> = std::iter::from_fn(|| scheduler.next_pass(10.0)).collect();
+        assert!(!drawn.contains(&Pass::Spikes));
+        assert_eq!(drawn.len(), Pass::ALL.len() - 1);
+        assert!(drawn.contains(&Pass::Attribution));
+        assert!(!scheduler.drawing());
+        assert!(scheduler.pending());
+        // The next frame draws the deferred spikes even if they overrun
+        scheduler.start();
+        let drawn: Vec<_> = std::iter::from_fn(|| scheduler.next_pass(10.0)).collect();
+        assert!(drawn.contains(&Pass::Spikes));
+        assert!(!scheduler.pending());
+    }
+
+    #[test]
+    fn draws_the_passes_that_cannot_be_deferred_whatever_the_budget() {
+        let mut scheduler = Scheduler::default();
+        for pass in Pass::ALL {
+            scheduler.record(pass, 1000.0);
+        }
+        // Frames restarting each animation frame draw every pass at least every other frame
+        let mut drawn = Vec::new();
+        for _ in 0..2 {
+            scheduler.start();
+            let frame: Vec<_> = std::iter::from_fn(|| scheduler.next_pass(f64::INFINITY)).collect();
+            for pass in Pass::ALL.iter().filter(|pass| !pass.deferrable()) {
+                assert!(frame.contains(pass));
+            }
+            drawn.extend(frame);
+        }
+        for pass in Pass::ALL {
+            assert!(drawn.contains(&pass));
+        }
+    }
+}
@@ -0,0 +1,216 @@
+// Selection of a region of interest by dragging out its latitude and longitude bounds.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::coords::LonLat;
+use crate::view::{Frame, Side};
+
+const SELECTION_FILL_STYLE: &str = "rgba(0, 127, 255, 0.2)";
+const SELECTION_STROKE_STYLE: &str = "rgba(0, 127, 255, 1.0)";
+const SELECTION_LINE_WIDTH: f64 = 0.004;
+// Edges are sampled every this many degrees
+const EDGE_SAMPLE_ANGLE: f64 = 2.0;
+
+/// Bounds (degrees) of a region; the west bound is greater than the east
+/// bound for regions crossing the antimeridian.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub west: f64,
+    pub south: f64,
+    pub east: f64,
+    pub north: f64,
+}
+
+impl Bounds {
+    /// Bounds of the region between two corners, spanning the shorter way between
+    /// their longitudes.
+    fn between(from: LonLat, to: LonLat) -> Self {
+        let span = (to.lon - from.lon + 180.0).rem_euclid(360.0) - 180.0;
+        let (west, east) = if span >= 0.0 {
+            (from.lon, to.lon)
+        } else {
+            (to.lon, from.lon)
+        };
+        Self {
+            west,
+            south: from.lat.min(to.lat),
+            east,
+            north: from.lat.max(to.lat),
+        }
+    }
+
+    /// The bounds as an object with west, south, east and north properties.
+    pub fn to_object(self) -> Result<JsValue, JsValue> {
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| js_sys::Reflect::set(&object, &key.into(), &value);
+        set("west", self.west.into())?;
+        set("south", self.south.into())?;
+        set("east", self.east.into())?;
+        set("north", self.north.into())?;
+        Ok(object.into())
+    }
+
+    /// Width (degrees) of the region in longitude.
+    fn width(&self) -> f64 {
+        (self.east - self.west).rem_euclid(360.0)
+    }
+
+    /// Boundary of the region (degrees, as longitude and latitude), along its southern
+    /// parallel eastwards and its northern parallel westwards, joined by meridians.
+    pub fn boundary(&self) -> Vec<(f64, f64)> {
+        let parallel_samples = ((self.width() / EDGE_SAMPLE_ANGLE).ceil() as usize).max(1);
+        let meridian_samples =
+            (((self.north - self.south) / EDGE_SAMPLE_ANGLE).ceil() as usize).max(1);
+        let lon = |i: usize| self.west + self.width() * i as f64 / parallel_samples as f64;
+        let lat =
+            |i: usize| self.south + (self.north - self.south) * i as f64 / meridian_samples as f64;
+
+        let mut points = Vec::new();
+        points.extend((0..parallel_samples).map(|i| (lon(i), self.south)));
+        points.extend((0..meridian_samples).map(|i| (lon(parallel_samples), lat(i))));
+        points.extend((1..=parallel_samples).rev().map(|i| (lon(i), self.north)));
+        points.extend((1..=meridian_samples).rev().map(|i| (lon(0), lat(i))));
+        points
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Selection {
+    /// Whether drags select a region rather than rotating the globe.
+    pub enabled: bool,
+    /// Corner the region was dragged from.
+    anchor: Option<LonLat>,
+    bounds: Option<Bounds>,
+    dragging: bool,
+}
+
+impl Selection {
+    /// Bounds of the selected region.
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.bounds
+    }
+
+    /// Remove the selected region.
+    pub fn clear(&mut self) {
+        self.anchor = None;
+        self.bounds = None;
+        self.dragging = false;
+    }
+
+    /// Start selecting from a location, if enabled, returning whether selecting.
+    pub fn drag_start(&mut self, location: Option<LonLat>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let Some(location) = location else {
+            return false;
+        };
+        self.anchor = Some(location);
+        self.bounds = Some(Bounds::between(location, location));
+        self.dragging = true;
+        true
+    }
+
+    /// Sweep the region to a location, returning whether selecting.
+    pub fn drag_to(&mut self, location: Option<LonLat>) -> bool {
+        if !self.dragging {
+            return false;
+        }
+        // Locations off the globe leave the region as it was
+        if let (Some(anchor), Some(location)) = (self.anchor, location) {
+            self.bounds = Some(Bounds::between(anchor, location));
+        }
+        true
+    }
+
+    /// Whether a region is being selected.
+    pub fn dragging(&self) -> bool {
+        self.dragging
+    }
+
+    /// Stop selecting, returning the bounds of the region when one was being selected.
+    pub fn drag_end(&mut self) -> Option<Bounds> {
+        if !std::mem::take(&mut self.dragging) {
+            return None;
+        }
+        self.bounds
+    }
+
+    /// Highlight the selected region onto the canvas.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        let Some(bounds) = self.bounds else {
+            return Ok(());
+        };
+        let boundary = bounds.boundary();
+
+        context.begin_path();
+        for (lon, lat) in &boundary {
+            let (y, z) = frame.project_to_disc(*lon, *lat);
+            context.line_to(y, z);
+        }
+        context.close_path();
+        context.set_fill_style_str(SELECTION_FILL_STYLE);
+        context.fill();
+
+        let points = boundary
+            .iter()
+            .chain(boundary.first())
+            .map(|(lon, lat)| frame.project(*lon, *lat))
+            .collect::<Vec<_>>();
+        context.set_line_width(frame.line_width(SELECTION_LINE_WIDTH));
+        context.set_stroke_style_str(SELECTION_STROKE_STYLE);
+        for segment in points.windows(2) {
+            let ((_, y_prev, z_prev), (_, y, z)) = (segment[0], segment[1]);
+            if frame.segment_side(segment[0], segment[1]) != Some(Side::Front) {
+                continue;
+            }
+            context.begin_path();
+            context.move_to(y_prev, z_prev);
+            context.line_to(y, z);
+            context.stroke();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_across_the_antimeridian() {
+        let mut selection = Selection {
+            enabled: true,
+            ..Selection::default()
+        };
+        assert!(selection.drag_start(Some(LonLat::new(170.0, -10.0))));
+        assert!(selection.drag_to(Some(LonLat::new(-170.0, 20.0))));
+        // Off the globe the region stays as it was
+        assert!(selection.drag_to(None));
+        let bounds = selection.drag_end().unwrap();
+        assert_eq!(
+            bounds,
+            Bounds {
+                west: 170.0,
+                south: -10.0,
+                east: -170.0,
+                north: 20.0
+            }
+        );
+        assert_eq!(bounds.width(), 20.0);
+        // The boundary runs east from the west bound, through the antimeridian
+        let boundary = bounds.boundary();
+        assert_eq!(boundary[0], (170.0, -10.0));
+        assert!(boundary
+            .iter()
+            .all(|(lon, _)| (170.0..=190.0).contains(lon)));
+        assert_eq!(selection.drag_end(), None);
+
+        // Dragging westwards selects the same region
+        assert!(selection.drag_start(Some(LonLat::new(-170.0, 20.0))));
+        selection.drag_to(Some(LonLat::new(170.0, -10.0)));
+        assert_eq!(selection.drag_end(), Some(bounds));
+    }
+}
@@ -0,0 +1,494 @@
+// Rendering static views of the globe to SVG or PNG buffers without a browser, for
+// generating thumbnails on a server with the geometry shared with the canvas renderer.
+
+use crate::coords::LonLat;
+use crate::data;
+use crate::font;
+use crate::labels::{
+    self, Label, LABEL_FILL_STYLE, LABEL_HALO_STYLE, LABEL_HALO_WIDTH, VECTOR_CAP_HEIGHT,
+    VECTOR_LINE_WIDTH,
+};
+use crate::projection::{Projection, ProjectionKind};
+use crate::view::{Orientation, Side};
+use crate::{
+    COAST_BACK_LINE_WIDTH, COAST_BACK_STROKE_STYLE, COAST_FRONT_LINE_WIDTH,
+    COAST_FRONT_STROKE_STYLE, SPHERE_FILL_STYLE,
+};
+
+/// View of the globe to render.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StaticView {
+    /// Size (pixels) of the image.
+    pub width: u32,
+    pub height: u32,
+    /// Location (degrees) at the centre of the view.
+    pub lat: f64,
+    pub lon: f64,
+    pub projection: ProjectionKind,
+}
+
+/// Label of a location in a rendered view, drawn with the vector font.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StaticLabel {
+    /// Location (degrees) of the label.
+    pub lat: f64,
+    pub lon: f64,
+    pub text: String,
+}
+
+/// Line of a rendered view, in image pixels, with its style.
+struct Line {
+    points: Vec<(f64, f64)>,
+    stroke_style: &'static str,
+    width: f64,
+}
+
+impl StaticView {
+    /// Whether lines are clipped to the disc, as the projection clips the back of the globe.
+    fn clipped(&self) -> bool {
+        let mut projection = Projection::default();
+        projection.kind = self.projection;
+        !projection.shows_back()
+    }
+
+    /// Image pixels per globe radius.
+    fn scale(&self) -> f64 {
+        self.width.min(self.height) as f64 / 2.0
+    }
+
+    /// Image position of plotted unit sphere coordinates.
+    fn to_image(self, y: f64, z: f64) -> (f64, f64) {
+        let scale = self.scale();
+        (scale + y * scale, scale - z * scale)
+    }
+
+    /// Lines of the coastlines, split where they change side of the globe.
+    fn coastlines(&self) -> Vec<Line> {
+        let mut projection = Projection::default();
+        projection.kind = self.projection;
+        let orientation = Orientation::default().centred_on(LonLat::new(self.lon, self.lat));
+        let mut lines = Vec::new();
+        for polyline in data::COASTLINE_POINTS.iter() {
+            let points = polyline
+                .iter()
+                .map(|point| {
+                    let (lon, lat) = point.to_degrees();
+                    projection.project(&orientation, lon, lat)
+                })
+                .collect::<Vec<_>>();
+            let mut line: Option<(Side, Line)> = None;
+            for segment in points.windows(2) {
+                let side = projection.segment_side(segment[0], segment[1]);
+                let (_, y_prev, z_prev) = segment[0];
+                let (_, y, z) = segment[1];
+                match (&mut line, side) {
+                    (Some((line_side, line)), Some(side)) if *line_side == side => {
+                        line.points.push(self.to_image(y, z));
+                    }
+                    _ => {
+                        lines.extend(line.take().map(|(_, line)| line));
+                        line = side.map(|side| {
+                            let (stroke_style, width) = match side {
+                                Side::Front => (COAST_FRONT_STROKE_STYLE, COAST_FRONT_LINE_WIDTH),
+                                _ => (COAST_BACK_STROKE_STYLE, COAST_BACK_LINE_WIDTH),
+                            };
+                            (
+                                side,
+                                Line {
+                                    points: vec![
+                                        self.to_image(y_prev, z_prev),
+                                        self.to_image(y, z),
+                                    ],
+                                    stroke_style,
+                                    width: width * self.scale(),
+                                },
+                            )
+                        });
+                    }
+                }
+            }
+            lines.extend(line.map(|(_, line)| line));
+        }
+        // Lines on the back are drawn first, beneath those on the front
+        lines.sort_by_key(|line| line.stroke_style == COAST_FRONT_STROKE_STYLE);
+        lines
+    }
+
+    /// Strokes (image pixels) of the labels visible in the view, decluttered as on the canvas,
+    /// with labels placed earlier taking priority.
+    fn label_strokes(&self, labels: &[StaticLabel]) -> Vec<Vec<(f64, f64)>> {
+        let mut projection = Projection::default();
+        projection.kind = self.projection;
+        let orientation = Orientation::default().centred_on(LonLat::new(self.lon, self.lat));
+        let labels = labels
+            .iter()
+            .enumerate()
+            .filter_map(|(i, label)| {
+                let (x, y, z) = projection.project(&orientation, label.lon, label.lat);
+                // Labels on the back of the globe are hidden, as on the canvas
+                if x < 0.0 {
+                    return None;
+                }
+                let (x, y) = self.to_image(y, z);
+                Some(Label {
+                    text: label.text.clone(),
+                    x,
+                    y,
+                    priority: -(i as f64),
+                    allow_overlap: false,
+                })
+            })
+            .collect::<Vec<_>>();
+        let widths = labels
+            .iter()
+            .map(|label| font::width(&label.text, VECTOR_CAP_HEIGHT))
+            .collect::<Vec<_>>();
+        labels
+            .iter()
+            .zip(labels::placed(&labels, &widths))
+            .filter(|(_, drawn)| *drawn)
+            .flat_map(|(label, _)| {
+                font::strokes(&label.text, VECTOR_CAP_HEIGHT, (label.x, label.y))
+            })
+            .collect()
+    }
+}
+
+/// Render a view to an SVG document.
+pub fn render_svg(view: &StaticView) -> String {
+    render_svg_with_labels(view, &[])
+}
+
+/// Render a view to an SVG document, with labels drawn as strokes of the vector font.
+pub fn render_svg_with_labels(view: &StaticView, labels: &[StaticLabel]) -> String {
+    let scale = view.scale();
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        view.width, view.height, view.width, view.height
+    );
+    svg.push_str(&format!(
+        r#"<clipPath id="disc"><circle cx="{}" cy="{}" r="{}"/></clipPath>"#,
+        scale, scale, scale
+    ));
+    svg.push_str(&format!(
+        r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+        scale, scale, scale, SPHERE_FILL_STYLE
+    ));
+    svg.push_str(if view.clipped() {
+        r#"<g clip-path="url(#disc)">"#
+    } else {
+        "<g>"
+    });
+    for line in view.coastlines() {
+        let points = line
+            .points
+            .iter()
+            .map(|(x, y)| format!("{:.2},{:.2}", x, y))
+            .collect::<Vec<_>>()
+            .join(" ");
+        svg.push_str(&format!(
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{:.2}" stroke-linejoin="round"/>"#,
+            points, line.stroke_style, line.width
+        ));
+    }
+    svg.push_str("</g>");
+    let strokes = view.label_strokes(labels);
+    if !strokes.is_empty() {
+        for (stroke_style, width) in [
+            (LABEL_HALO_STYLE, VECTOR_LINE_WIDTH + LABEL_HALO_WIDTH),
+            (LABEL_FILL_STYLE, VECTOR_LINE_WIDTH),
+        ] {
+            svg.push_str(&format!(
+                r#"<g fill="none" stroke="{}" stroke-width="{:.2}" stroke-linecap="round" stroke-linejoin="round">"#,
+                stroke_style, width
+            ));
+            for stroke in &strokes {
+                let points = stroke
+                    .iter()
+                    .map(|(x, y)| format!("{:.2},{:.2}", x, y))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                svg.push_str(&format!(r#"<polyline points="{}"/>"#, points));
+            }
+            svg.push_str("</g>");
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Render a view to a PNG image, with antialiased shapes.
+pub fn render_png(view: &StaticView) -> Vec<u8> {
+    render_png_with_labels(view, &[])
+}
+
+/// Render a view to a PNG image, with labels drawn as strokes of the vector font.
+pub fn render_png_with_labels(view: &StaticView, labels: &[StaticLabel]) -> Vec<u8> {
+    let mut image = Image::new(view.width, view.height);
+    let scale = view.scale();
+    image.fill_disc((scale, scale), scale, parse_rgba(SPHERE_FILL_STYLE));
+    if view.clipped() {
+        image.clip = Some(((scale, scale), scale));
+    }
+    for line in view.coastlines() {
+        let color = parse_rgba(line.stroke_style);
+        for segment in line.points.windows(2) {
+            image.stroke_segment(segment[0], segment[1], line.width, color);
+        }
+    }
+    // Labels are drawn over the whole image, as on the canvas
+    image.clip = None;
+    let strokes = view.label_strokes(labels);
+    for (stroke_style, width) in [
+        (LABEL_HALO_STYLE, VECTOR_LINE_WIDTH + LABEL_HALO_WIDTH),
+        (LABEL_FILL_STYLE, VECTOR_LINE_WIDTH),
+    ] {
+        let color = parse_rgba(stroke_style);
+        for stroke in &strokes {
+            for segment in stroke.windows(2) {
+                image.stroke_segment(segment[0], segment[1], width, color);
+            }
+        }
+    }
+    image.encode_png()
+}
+
+/// Color (red, green, blue, alpha) of a CSS `rgba()` style, each from 0 to 1.
+fn parse_rgba(style: &str) -> [f64; 4] {
+    let values = style
+        .trim_start_matches("rgba(")
+        .trim_end_matches(')')
+        .split(',')
+        .map(|value| value.trim().parse::<f64>().unwrap_or(0.0))
+        .collect::<Vec<_>>();
+    match values.as_slice() {
+        [r, g, b, a] => [r / 255.0, g / 255.0, b / 255.0, *a],
+        _ => [0.0, 0.0, 0.0, 1.0],
+    }
+}
+
+/// Image with pixels of premultiplied colors (red, green, blue, alpha), each from 0 to 1.
+struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<[f64; 4]>,
+    /// Disc (pixels, as centre and radius) outside which nothing is drawn.
+    clip: Option<((f64, f64), f64)>,
+}
+
+impl Image {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0.0; 4]; (width * height) as usize],
+            clip: None,
+        }
+    }
+
+    /// Blend a color over a pixel, with a coverage from 0 to 1.
+    fn blend(&mut self, x: i64, y: i64, color: [f64; 4], coverage: f64) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 || coverage <= 0.0 {
+            return;
+        }
+        // The clip disc is hard edged, at pixel centres
+        if let Some(((cx, cy), radius)) = self.clip {
+            if (x as f64 + 0.5 - cx).hypot(y as f64 + 0.5 - cy) > radius {
+                return;
+            }
+        }
+        let alpha = color[3] * coverage.min(1.0);
+        let pixel = &mut self.pixels[(y * self.width as i64 + x) as usize];
+        for i in 0..3 {
+            pixel[i] = color[i] * alpha + pixel[i] * (1.0 - alpha);
+        }
+        pixel[3] = alpha + pixel[3] * (1.0 - alpha);
+    }
+
+    /// Blend a shape over the pixels within bounds, with the coverage of each pixel given by
+    /// the signed distance (pixels) from its centre to the shape's edge, negative inside.
+    fn fill_shape(
+        &mut self,
+        (left, top, right, bottom): (f64, f64, f64, f64),
+        color: [f64; 4],
+        distance: impl Fn(f64, f64) -> f64,
+    ) {
+        for y in top.floor() as i64..=bottom.ceil() as i64 {
+            for x in left.floor() as i64..=right.ceil() as i64 {
+                let coverage = 0.5 - distance(x as f64 + 0.5, y as f64 + 0.5);
+                self.blend(x, y, color, coverage.clamp(0.0, 1.0));
+            }
+        }
+    }
+
+    fn fill_disc(&mut self, (cx, cy): (f64, f64), radius: f64, color: [f64; 4]) {
+        let bounds = (cx - radius, cy - radius, cx + radius, cy + radius);
+        self.fill_shape(bounds, color, |x, y| (x - cx).hypot(y - cy) - radius);
+    }
+
+    fn stroke_segment(&mut self, a: (f64, f64), b: (f64, f64), width: f64, color: [f64; 4]) {
+        // Thin lines are drawn a pixel wide and fainter
+        let half_width = width.max(1.0) / 2.0;
+        let color = [color[0], color[1], color[2], color[3] * width.min(1.0)];
+        let bounds = (
+            a.0.min(b.0) - half_width,
+            a.1.min(b.1) - half_width,
+            a.0.max(b.0) + half_width,
+            a.1.max(b.1) + half_width,
+        );
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let length_squared = dx * dx + dy * dy;
+        self.fill_shape(bounds, color, |x, y| {
+            let t = if length_squared > 0.0 {
+                (((x - a.0) * dx + (y - a.1) * dy) / length_squared).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            (x - a.0 - t * dx).hypot(y - a.1 - t * dy) - half_width
+        });
+    }
+
+    /// Encode the image as a PNG, with the image data stored uncompressed.
+    fn encode_png(&self) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(self.pixels.len() * 4);
+        for pixel in &self.pixels {
+            let alpha = pixel[3];
+            let unpremultiply = |value: f64| {
+                if alpha > 0.0 {
+                    (value / alpha * 255.0).round().clamp(0.0, 255.0) as u8
+                } else {
+                    0
+                }
+            };
+            rgba.extend([
+                unpremultiply(pixel[0]),
+                unpremultiply(pixel[1]),
+                unpremultiply(pixel[2]),
+                (alpha * 255.0).round() as u8,
+            ]);
+        }
+        encode_png(self.width, self.height, &rgba)
+    }
+}
+
+/// Encode pixels (red, green, blue and alpha, not premultiplied) as a PNG, with the image data
+/// stored uncompressed.
+pub(crate) fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let mut raw = Vec::with_capacity(((width * 4 + 1) * height) as usize);
+    for row in rgba.chunks((width * 4) as usize) {
+        // Each row starts with its filter type, none
+        raw.push(0);
+        raw.extend(row);
+    }
+
+    let mut header = Vec::new();
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    // Bit depth 8, color type RGBA, default compression, filtering and no interlacing
+    header.extend([8, 6, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+/// Data in a zlib stream of uncompressed blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 65535;
+    let mut stream = vec![0x78, 0x01];
+    let blocks = data.chunks(MAX_BLOCK).collect::<Vec<_>>();
+    if blocks.is_empty() {
+        stream.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    for (i, block) in blocks.iter().enumerate() {
+        let last = (i + 1 == blocks.len()) as u8;
+        let length = block.len() as u16;
+        stream.push(last);
+        stream.extend(length.to_le_bytes());
+        stream.extend((!length).to_le_bytes());
+        stream.extend(*block);
+    }
+    stream.extend(adler32(data).to_be_bytes());
+    stream
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffff_u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1_u32, 0_u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VIEW: StaticView = StaticView {
+        width: 64,
+        height: 64,
+        lat: 0.0,
+        lon: 0.0,
+        projection: ProjectionKind::Orthographic,
+    };
+
+    #[test]
+    fn renders_svg_document() {
+        let svg = render_svg(&VIEW);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("<circle"));
+        assert!(svg.ends_with("</svg>"));
+    }
+
+    #[test]
+    fn renders_labels_on_front_of_globe() {
+        let label = |lon: f64| StaticLabel {
+            lat: 0.0,
+            lon,
+            text: "Null Island".to_string(),
+        };
+        assert!(!VIEW.label_strokes(&[label(0.0)]).is_empty());
+        assert!(VIEW.label_strokes(&[label(180.0)]).is_empty());
+        assert!(render_svg_with_labels(&VIEW, &[label(0.0)]).contains(r#"stroke-linecap="round""#));
+    }
+
+    #[test]
+    fn renders_png_image() {
+        let png = render_png(&VIEW);
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert!(png.ends_with(&[0xae, 0x42, 0x60, 0x82]));
+    }
+
+    #[test]
+    fn checksums_match_reference_values() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+}
@@ -0,0 +1,287 @@
+// Altitude-extruded point spikes, drawn as lines from the surface radially outwards.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::coords::LonLat;
+use crate::labels::Label;
+use crate::legend::{Legend, LegendSymbols};
+use crate::time::TimeWindow;
+use crate::view::Frame;
+
+const SPIKE_FRONT_STROKE_STYLE: &str = "rgba(255, 95, 0, 1.0)";
+const SPIKE_BACK_STROKE_STYLE: &str = "rgba(0, 0, 0, 0.25)";
+const SPIKE_FRONT_LINE_WIDTH: f64 = 0.006;
+const SPIKE_BACK_LINE_WIDTH: f64 = 0.0025;
+const SPIKE_DEFAULT_MAX_HEIGHT: f64 = 0.25;
+const SPIKE_DEFAULT_LEGEND_TITLE: &str = "Value";
+
+#[derive(Clone, Debug, PartialEq)]
+struct Spike {
+    id: u32,
+    location: LonLat,
+    value: f64,
+    label: Option<String>,
+    /// Time (milliseconds) of the spike's data, for the window of time shown.
+    time: Option<f64>,
+}
+
+/// Projected spike, as the surface point in view coordinates and the scales
+/// along the spike where it emerges from behind the sphere and of its tip.
+struct Projected {
+    y: f64,
+    z: f64,
+    visible_from: f64,
+    tip: f64,
+}
+
+#[derive(Debug)]
+pub struct Spikes {
+    spikes: Vec<Spike>,
+    next_id: u32,
+    /// Height, in globe radii, of the spike with the largest absolute value.
+    pub max_height: f64,
+    pub legend_title: String,
+}
+
+impl Default for Spikes {
+    fn default() -> Self {
+        Self {
+            spikes: Vec::new(),
+            next_id: 0,
+            max_height: SPIKE_DEFAULT_MAX_HEIGHT,
+            legend_title: SPIKE_DEFAULT_LEGEND_TITLE.to_string(),
+        }
+    }
+}
+
+impl Spikes {
+    /// Add a spike, returning its identifier.
+    pub fn add(&mut self, location: LonLat, value: f64) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.spikes.push(Spike {
+            id,
+            location,
+            value,
+            label: None,
+            time: None,
+        });
+        id
+    }
+
+    /// Remove a spike, returning whether it existed.
+    pub fn remove(&mut self, id: u32) -> bool {
+        let len = self.spikes.len();
+        self.spikes.retain(|spike| spike.id != id);
+        self.spikes.len() != len
+    }
+
+    /// Remove all spikes.
+    pub fn clear(&mut self) {
+        self.spikes.clear();
+    }
+
+    /// Set the label drawn at the tip of a spike, returning whether the spike exists.
+    pub fn set_label(&mut self, id: u32, label: Option<String>) -> bool {
+        match self.spikes.iter_mut().find(|spike| spike.id == id) {
+            Some(spike) => {
+                spike.label = label;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the time (milliseconds) of a spike's data, or remove it when not
+    /// given, returning whether the spike exists.
+    pub fn set_time(&mut self, id: u32, time: Option<f64>) -> bool {
+        match self.spikes.iter_mut().find(|spike| spike.id == id) {
+            Some(spike) => {
+                spike.time = time;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Spikes shown in a window of time, paired with the scales along them of their tips:
+    /// 1 at the surface, and more by the maximum height for the largest absolute value.
+    fn extruded(
+        &self,
+        time_window: Option<TimeWindow>,
+    ) -> impl Iterator<Item = (&Spike, f64)> + '_ {
+        let max_value = self
+            .spikes
+            .iter()
+            .map(|spike| spike.value.abs())
+            .fold(0.0, f64::max);
+        let max_height = self.max_height;
+        // Spikes without times are shown whatever the window of time
+        let shown = move |spike: &&Spike| {
+            spike
+                .time
+                .is_none_or(|time| time_window.is_none_or(|window| window.contains(time)))
+        };
+        self.spikes.iter().filter(shown).map(move |spike| {
+            let tip = if max_value > 0.0 {
+                1.0 + max_height * spike.value.abs() / max_value
+            } else {
+                1.0
+            };
+            (spike, tip)
+        })
+    }
+
+    /// Project the spikes, paired with their projections.
+    ///
+    /// Spikes are projected as lines from the surface point to the elevated point. The part
+    /// of a spike rooted on the back hemisphere that rises above the limb is still visible.
+    fn project<'a>(
+        &'a self,
+        frame: &'a Frame,
+    ) -> impl Iterator<Item = (&'a Spike, Projected)> + 'a {
+        self.extruded(frame.time_window).map(move |(spike, tip)| {
+            let (x, y, z) = frame.project(spike.location.lon, spike.location.lat);
+            let visible_from = if x >= 0.0 {
+                1.0
+            } else if !frame.projection.shows_back() {
+                f64::INFINITY
+            } else {
+                let r = (y * y + z * z).sqrt();
+                if r > 0.0 {
+                    (1.0 / r).max(1.0)
+                } else {
+                    f64::INFINITY
+                }
+            };
+            (
+                spike,
+                Projected {
+                    y,
+                    z,
+                    visible_from,
+                    tip,
+                },
+            )
+        })
+    }
+
+    /// Draw the spikes onto the canvas.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        for (
+            _,
+            Projected {
+                y,
+                z,
+                visible_from,
+                tip,
+            },
+        ) in self.project(frame)
+        {
+            if visible_from > 1.0 {
+                let end = visible_from.min(tip);
+                context.set_line_width(frame.line_width(SPIKE_BACK_LINE_WIDTH));
+                context.set_stroke_style_str(SPIKE_BACK_STROKE_STYLE);
+                context.begin_path();
+                context.move_to(y, z);
+                context.line_to(y * end, z * end);
+                context.stroke();
+            }
+            if visible_from < tip {
+                context.set_line_width(frame.line_width(SPIKE_FRONT_LINE_WIDTH));
+                context.set_stroke_style_str(SPIKE_FRONT_STROKE_STYLE);
+                context.begin_path();
+                context.move_to(y * visible_from, z * visible_from);
+                context.line_to(y * tip, z * tip);
+                context.stroke();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Legend of the heights of spikes of the largest absolute value and half of it, when shown.
+    pub fn legend(&self, frame: &Frame) -> Option<Legend> {
+        let max_value = self
+            .spikes
+            .iter()
+            .map(|spike| spike.value.abs())
+            .fold(0.0, f64::max);
+        if max_value <= 0.0 {
+            return None;
+        }
+        let max_length = self.max_height * frame.scale;
+        Some(Legend {
+            title: self.legend_title.clone(),
+            symbols: LegendSymbols::Sizes {
+                stroke_style: SPIKE_FRONT_STROKE_STYLE.to_string(),
+                entries: vec![
+                    (max_length, max_value.to_string()),
+                    (max_length / 2.0, (max_value / 2.0).to_string()),
+                ],
+            },
+        })
+    }
+
+    /// Labels at the tips of visible spikes, prioritised by value.
+    pub fn labels(&self, frame: &Frame) -> Vec<Label> {
+        self.project(frame)
+            .filter(|(_, projected)| projected.visible_from < projected.tip)
+            .filter_map(|(spike, Projected { y, z, tip, .. })| {
+                let text = spike.label.clone()?;
+                let (x, y) = frame.to_canvas(y * tip, z * tip);
+                Some(Label {
+                    text,
+                    x,
+                    y,
+                    priority: spike.value.abs(),
+                    allow_overlap: false,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extrudes_spikes_in_the_time_window() {
+        let mut spikes = Spikes::default();
+        let largest = spikes.add(LonLat::new(0.0, 0.0), -4.0);
+        let half = spikes.add(LonLat::new(10.0, 10.0), 2.0);
+        let later = spikes.add(LonLat::new(20.0, 20.0), 1.0);
+        spikes.set_time(half, Some(100.0));
+        spikes.set_time(later, Some(300.0));
+        assert!(!spikes.set_time(99, Some(0.0)));
+
+        // Heights are proportional to the absolute values
+        let tips = |window| {
+            spikes
+                .extruded(window)
+                .map(|(spike, tip)| (spike.id, tip))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            tips(None),
+            [
+                (largest, 1.0 + SPIKE_DEFAULT_MAX_HEIGHT),
+                (half, 1.0 + SPIKE_DEFAULT_MAX_HEIGHT / 2.0),
+                (later, 1.0 + SPIKE_DEFAULT_MAX_HEIGHT / 4.0)
+            ]
+        );
+        // Spikes without times are always shown
+        let window = TimeWindow::new(0.0, 200.0);
+        assert_eq!(
+            tips(window).iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            [largest, half]
+        );
+        let window = TimeWindow::new(300.0, 300.0);
+        assert_eq!(
+            tips(window).iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            [largest, later]
+        );
+    }
+}
@@ -0,0 +1,172 @@
+// Placeholder drawn while the globe starts up: until the embedded data is decoded and the
+// first downloads arrive, only the sphere, shaded, and the graticule are drawn, with an arc
+// of the progress of the work, so the first frame is drawn at once.
+
+use std::f64::consts::{FRAC_PI_2, TAU};
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::data::{self, Polylines};
+use crate::schedule::Pass;
+use crate::view::Frame;
+
+const SHADING_LIGHT_STYLE: &str = "rgba(255, 255, 255, 0.25)";
+const SHADING_DARK_STYLE: &str = "rgba(0, 0, 0, 0.2)";
+const PROGRESS_TRACK_STYLE: &str = "rgba(128, 128, 128, 0.3)";
+const PROGRESS_STYLE: &str = "rgba(70, 130, 220, 0.9)";
+// Sizes are relative to the radius of the globe
+const PROGRESS_GAP: f64 = 0.04;
+const PROGRESS_LINE_WIDTH: f64 = 0.012;
+
+/// Embedded polylines decoded during startup, one a frame.
+static EMBEDDED: [&Polylines; 4] = [
+    &data::COASTLINE_POINTS,
+    &data::DETAILED_COASTLINE_POINTS,
+    &data::COUNTRY_POINTS,
+    &data::LAND_POINTS,
+];
+
+#[derive(Debug)]
+pub struct Startup {
+    pub enabled: bool,
+    /// Progress (0 to 1) of each source of work in progress.
+    progress: Vec<(&'static str, f64)>,
+    /// Whether the work of starting up has finished.
+    finished: bool,
+}
+
+impl Default for Startup {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            progress: Vec::new(),
+            finished: false,
+        }
+    }
+}
+
+impl Startup {
+    /// Report the progress (0 to 1) of a source of work, or `None` when it has none in
+    /// progress; returns whether the placeholder changed. Startup finishes once no source has
+    /// work in progress.
+    pub fn report(&mut self, source: &'static str, progress: Option<f64>) -> bool {
+        if self.finished {
+            return false;
+        }
+        let previous = self.progress.clone();
+        self.progress.retain(|(reported, _)| *reported != source);
+        if let Some(progress) = progress {
+            self.progress.push((source, progress.clamp(0.0, 1.0)));
+        }
+        self.finished = self.progress.is_empty();
+        self.finished || self.progress != previous
+    }
+
+    /// Whether the placeholder is drawn in place of the layers.
+    pub fn shown(&self) -> bool {
+        self.enabled && !self.finished
+    }
+
+    /// Whether a pass is drawn while the placeholder is shown: passes of layers needing the
+    /// data are not.
+    pub fn draws(&self, pass: Pass) -> bool {
+        !self.shown()
+            || matches!(
+                pass,
+                Pass::Sphere
+                    | Pass::Tiles
+                    | Pass::Graticule
+                    | Pass::Startup
+                    | Pass::Attribution
+                    | Pass::Status
+            )
+    }
+
+    /// Progress (0 to 1) of the work of starting up, with each source weighted equally.
+    pub fn progress(&self) -> f64 {
+        if self.progress.is_empty() {
+            return 1.0;
+        }
+        self.progress
+            .iter()
+            .map(|(_, progress)| progress)
+            .sum::<f64>()
+            / self.progress.len() as f64
+    }
+
+    /// Draw the shading of the sphere and the arc of progress around it onto the canvas.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        if !self.shown() {
+            return Ok(());
+        }
+        // Lit from the upper left
+        let shading = context.create_radial_gradient(-0.4, 0.4, 0.0, -0.4, 0.4, 1.6)?;
+        shading.add_color_stop(0.0, SHADING_LIGHT_STYLE)?;
+        shading.add_color_stop(1.0, SHADING_DARK_STYLE)?;
+        context.set_fill_style_canvas_gradient(&shading);
+        context.begin_path();
+        context.arc(0.0, 0.0, 1.0, 0.0, TAU)?;
+        context.fill();
+
+        let radius = 1.0 + PROGRESS_GAP;
+        context.set_line_width(frame.line_width(PROGRESS_LINE_WIDTH));
+        context.set_stroke_style_str(PROGRESS_TRACK_STYLE);
+        context.begin_path();
+        context.arc(0.0, 0.0, radius, 0.0, TAU)?;
+        context.stroke();
+        // Clockwise on the canvas from the top, as the plotted coordinates are flipped
+        context.set_stroke_style_str(PROGRESS_STYLE);
+        context.begin_path();
+        context.arc_with_anticlockwise(
+            0.0,
+            0.0,
+            radius,
+            FRAC_PI_2,
+            FRAC_PI_2 - self.progress() * TAU,
+            true,
+        )?;
+        context.stroke();
+        Ok(())
+    }
+}
+
+/// Decode the next of the embedded polylines not yet decoded.
+pub fn decode_next() {
+    if let Some(polylines) = EMBEDDED.iter().find(|polylines| !polylines.decoded()) {
+        // Reaching the polylines decodes them
+        let _ = polylines.len();
+    }
+}
+
+/// Progress (0 to 1) of decoding the embedded polylines, or `None` once all are decoded.
+pub fn decode_progress() -> Option<f64> {
+    let decoded = EMBEDDED
+        .iter()
+        .filter(|polylines| polylines.decoded())
+        .count();
+    (decoded < EMBEDDED.len()).then(|| decoded as f64 / EMBEDDED.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finishes_once_no_work_is_in_progress() {
+        let mut startup = Startup::default();
+        assert!(startup.report("decode", Some(0.5)));
+        assert!(startup.report("fetch", Some(0.0)));
+        assert!(!startup.report("fetch", Some(0.0)));
+        assert_eq!(startup.progress(), 0.25);
+        assert!(startup.shown() && !startup.draws(Pass::Coastlines));
+        assert!(startup.draws(Pass::Graticule));
+
+        assert!(startup.report("decode", None));
+        assert!(startup.report("fetch", None));
+        assert!(!startup.shown() && startup.draws(Pass::Coastlines));
+        // Later work does not bring the placeholder back
+        assert!(!startup.report("fetch", Some(0.0)));
+        assert!(!startup.shown());
+    }
+}
@@ -0,0 +1,207 @@
+// Status of the layers loading their data, reported to the page as it changes and optionally
+// shown as a badge in a corner of the canvas.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::overlay::{self, Corner, Rect};
+use crate::zoom::LAYERS;
+
+const STATUS_FONT: &str = "11px sans-serif";
+const STATUS_LOADING_FILL_STYLE: &str = "rgba(0, 0, 0, 0.75)";
+const STATUS_ERROR_FILL_STYLE: &str = "rgba(160, 0, 0, 1.0)";
+const STATUS_BACKGROUND_STYLE: &str = "rgba(255, 255, 255, 0.75)";
+const STATUS_HEIGHT: f64 = 11.0;
+const STATUS_PADDING: f64 = 3.0;
+const STATUS_MARGIN: f64 = 2.0;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum LayerStatus {
+    #[default]
+    Ready,
+    Loading,
+    /// Loading failed, with a message describing the failure.
+    Error(String),
+}
+
+impl LayerStatus {
+    fn id(&self) -> &'static str {
+        match self {
+            LayerStatus::Ready => "ready",
+            LayerStatus::Loading => "loading",
+            LayerStatus::Error(_) => "error",
+        }
+    }
+
+    /// Object of the status of a layer for JavaScript, with its `layer`, `status` and any
+    /// `message`.
+    pub fn to_object(&self, layer: &str) -> Result<JsValue, JsValue> {
+        let object = js_sys::Object::new();
+        js_sys::Reflect::set(&object, &"layer".into(), &layer.into())?;
+        js_sys::Reflect::set(&object, &"status".into(), &self.id().into())?;
+        if let LayerStatus::Error(message) = self {
+            js_sys::Reflect::set(&object, &"message".into(), &message.into())?;
+        }
+        Ok(object.into())
+    }
+}
+
+#[derive(Debug)]
+pub struct LayerStatuses {
+    /// Statuses of the layers not ready.
+    statuses: Vec<(&'static str, LayerStatus)>,
+    /// Layers whose statuses changed since the changes were last taken.
+    changed: Vec<&'static str>,
+    /// Whether the badge is drawn.
+    pub badge: bool,
+    pub corner: Corner,
+}
+
+impl Default for LayerStatuses {
+    fn default() -> Self {
+        Self {
+            statuses: Vec::new(),
+            changed: Vec::new(),
+            badge: false,
+            corner: Corner::TopRight,
+        }
+    }
+}
+
+impl LayerStatuses {
+    /// Status of a layer by its identifier.
+    pub fn get(&self, layer: &str) -> Result<LayerStatus, String> {
+        if !LAYERS.contains(&layer) {
+            return Err(format!("unknown layer {:?}", layer));
+        }
+        Ok(self
+            .statuses
+            .iter()
+            .find(|(id, _)| *id == layer)
+            .map(|(_, status)| status.clone())
+            .unwrap_or_default())
+    }
+
+    /// Set the status of a layer, returning whether it changed.
+    pub fn set(&mut self, layer: &'static str, status: LayerStatus) -> bool {
+        let previous = self.get(layer).unwrap_or_default();
+        if previous == status {
+            return false;
+        }
+        self.statuses.retain(|(id, _)| *id != layer);
+        if status != LayerStatus::Ready {
+            self.statuses.push((layer, status));
+        }
+        if !self.changed.contains(&layer) {
+            self.changed.push(layer);
+        }
+        true
+    }
+
+    /// Take the layers whose statuses changed, with their statuses.
+    pub fn take_changes(&mut self) -> Vec<(&'static str, LayerStatus)> {
+        std::mem::take(&mut self.changed)
+            .into_iter()
+            .map(|layer| (layer, self.get(layer).unwrap_or_default()))
+            .collect()
+    }
+
+    /// Region (canvas pixels) of a canvas of a size the badge is drawn within: the band of its
+    /// corner across the canvas, as its width follows its text.
+    pub fn region(&self, canvas_size: (f64, f64)) -> Rect {
+        let height = STATUS_HEIGHT + 2.0 * STATUS_PADDING;
+        let (_, top) = self
+            .corner
+            .place((canvas_size.0, height), canvas_size, STATUS_MARGIN);
+        Rect {
+            left: 0.0,
+            top: top - STATUS_MARGIN,
+            width: canvas_size.0,
+            height: height + 2.0 * STATUS_MARGIN,
+        }
+    }
+
+    /// Text of the badge: the layers failing, or else those loading.
+    fn text(&self) -> Option<(String, bool)> {
+        let layers = |error: bool| {
+            self.statuses
+                .iter()
+                .filter(|(_, status)| matches!(status, LayerStatus::Error(_)) == error)
+                .map(|(layer, _)| *layer)
+                .collect::<Vec<_>>()
+        };
+        let failed = layers(true);
+        if !failed.is_empty() {
+            return Some((format!("Failed to load {}", failed.join(", ")), true));
+        }
+        let loading = layers(false);
+        if !loading.is_empty() {
+            return Some((format!("Loading {}…", loading.join(", ")), false));
+        }
+        None
+    }
+
+    /// Draw the badge onto the canvas, if shown and any layer is not ready.
+    pub fn draw(
+        &self,
+        context: &CanvasRenderingContext2d,
+        pixel_ratio: f64,
+    ) -> Result<(), JsValue> {
+        if !self.badge {
+            return Ok(());
+        }
+        let Some((text, error)) = self.text() else {
+            return Ok(());
+        };
+
+        // The badge is drawn in canvas pixels rather than unit sphere coordinates
+        context.save();
+        let canvas_size = overlay::canvas_pixels(context, pixel_ratio)?;
+        context.set_font(STATUS_FONT);
+        context.set_text_align("left");
+        context.set_text_baseline("top");
+
+        let size = (
+            context.measure_text(&text)?.width() + 2.0 * STATUS_PADDING,
+            STATUS_HEIGHT + 2.0 * STATUS_PADDING,
+        );
+        let (left, top) = self.corner.place(size, canvas_size, STATUS_MARGIN);
+
+        context.set_fill_style_str(STATUS_BACKGROUND_STYLE);
+        context.fill_rect(left, top, size.0, size.1);
+        context.set_fill_style_str(if error {
+            STATUS_ERROR_FILL_STYLE
+        } else {
+            STATUS_LOADING_FILL_STYLE
+        });
+        context.fill_text(&text, left + STATUS_PADDING, top + STATUS_PADDING)?;
+
+        context.restore();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_changes_of_status() {
+        let mut statuses = LayerStatuses::default();
+        assert!(statuses.set("tiles", LayerStatus::Loading));
+        assert!(!statuses.set("tiles", LayerStatus::Loading));
+        assert!(statuses.set("tiles", LayerStatus::Error("404".to_string())));
+        assert_eq!(
+            statuses.take_changes(),
+            vec![("tiles", LayerStatus::Error("404".to_string()))]
+        );
+        assert_eq!(
+            statuses.text(),
+            Some(("Failed to load tiles".to_string(), true))
+        );
+        assert!(statuses.set("tiles", LayerStatus::Ready));
+        assert_eq!(statuses.get("tiles"), Ok(LayerStatus::Ready));
+        assert!(statuses.get("roads").is_err());
+    }
+}
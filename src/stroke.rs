@@ -0,0 +1,92 @@
+// Policy for the widths of stroked lines as the view is zoomed, and across the resolutions of
+// displays and exports, which lines are kept from thinning below on the device.
+
+use wasm_bindgen::prelude::*;
+
+// Lines thinner than about a device pixel fade or vanish, depending on the display
+const DEFAULT_MIN_DEVICE_WIDTH: f64 = 1.0;
+
+/// Space in which line widths are held constant as the view is zoomed.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineWidthPolicy {
+    /// Lines keep the same width on screen.
+    Screen,
+    /// Lines keep the same width relative to the globe, growing as the view is zoomed in.
+    Data,
+}
+
+#[derive(Debug)]
+pub struct LineWidths {
+    pub policy: LineWidthPolicy,
+    /// Limits (canvas pixels) applied to widths after the policy.
+    pub min: f64,
+    pub max: f64,
+    /// Width (device pixels) no line is drawn thinner than, whatever its limits.
+    pub min_device: f64,
+}
+
+impl Default for LineWidths {
+    fn default() -> Self {
+        Self {
+            policy: LineWidthPolicy::Screen,
+            min: 0.0,
+            max: f64::INFINITY,
+            min_device: DEFAULT_MIN_DEVICE_WIDTH,
+        }
+    }
+}
+
+impl LineWidths {
+    /// Set the limits (canvas pixels) of widths; returns whether the limits are valid, with
+    /// the minimum not negative and no more than the maximum.
+    pub fn set_limits(&mut self, min: f64, max: f64) -> bool {
+        if min.is_nan() || min < 0.0 || max.is_nan() || max < min {
+            return false;
+        }
+        (self.min, self.max) = (min, max);
+        true
+    }
+
+    /// Width, in unit sphere coordinates, for a line with a base width given for the unzoomed
+    /// view; scales are in canvas pixels per globe radius when unzoomed and currently, and the
+    /// pixel ratio is of device pixels to canvas pixels. Lines without width stay hidden.
+    pub fn width(&self, base: f64, base_scale: f64, scale: f64, pixel_ratio: f64) -> f64 {
+        if base <= 0.0 {
+            return 0.0;
+        }
+        let pixels = match self.policy {
+            LineWidthPolicy::Screen => base * base_scale,
+            LineWidthPolicy::Data => base * scale,
+        };
+        let min_device = self.min_device / pixel_ratio;
+        pixels.clamp(self.min, self.max).max(min_device) / scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_lines_above_a_device_pixel() {
+        let widths = LineWidths::default();
+        // A line of a third of a canvas pixel widens to a device pixel
+        assert_eq!(widths.width(0.001, 300.0, 300.0, 1.0) * 300.0, 1.0);
+        assert_eq!(widths.width(0.001, 300.0, 300.0, 2.0) * 300.0, 0.5);
+        // Exports at higher resolutions keep more of the widths given
+        let pixels = widths.width(0.001, 300.0, 300.0, 4.0) * 300.0;
+        assert!((pixels - 0.3).abs() < 1e-9);
+        assert_eq!(widths.width(0.0, 300.0, 300.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn rejects_invalid_limits() {
+        let mut widths = LineWidths::default();
+        assert!(widths.set_limits(0.5, f64::INFINITY));
+        assert!(!widths.set_limits(-1.0, 2.0));
+        assert!(!widths.set_limits(3.0, 2.0));
+        assert!(!widths.set_limits(f64::NAN, 2.0));
+        assert_eq!((widths.min, widths.max), (0.5, f64::INFINITY));
+    }
+}
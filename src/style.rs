@@ -0,0 +1,89 @@
+// Colors and line widths of the base layers of the globe, changeable at runtime.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    COAST_BACK_LINE_WIDTH, COAST_BACK_STROKE_STYLE, COAST_FRONT_LINE_WIDTH,
+    COAST_FRONT_STROKE_STYLE, SPHERE_FILL_STYLE,
+};
+
+// Colors of the dark theme
+const DARK_BACKGROUND: &str = "rgba(16, 18, 28, 1.0)";
+const DARK_SPHERE_FILL_STYLE: &str = "rgba(24, 36, 64, 1.0)";
+const DARK_COAST_FRONT_STROKE_STYLE: &str = "rgba(200, 220, 255, 1.0)";
+const DARK_COAST_BACK_STROKE_STYLE: &str = "rgba(200, 220, 255, 0.2)";
+
+/// Style of the base layers: colors are CSS colors and line widths are relative to the
+/// radius of the globe.
+#[wasm_bindgen(getter_with_clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlobeStyle {
+    /// Fill of the canvas behind the globe, or `None` for the canvas to be transparent.
+    pub background: Option<String>,
+    pub sphere_fill: String,
+    pub coast_front_stroke: String,
+    pub coast_back_stroke: String,
+    pub coast_front_width: f64,
+    pub coast_back_width: f64,
+    /// Opacity (0 to 1) the coastlines on the back are still drawn with, dimmed, when the
+    /// back is culled, or `None` for them not to be drawn.
+    pub culled_back_opacity: Option<f64>,
+}
+
+impl Default for GlobeStyle {
+    fn default() -> Self {
+        Self {
+            background: None,
+            sphere_fill: SPHERE_FILL_STYLE.to_string(),
+            coast_front_stroke: COAST_FRONT_STROKE_STYLE.to_string(),
+            coast_back_stroke: COAST_BACK_STROKE_STYLE.to_string(),
+            coast_front_width: COAST_FRONT_LINE_WIDTH,
+            coast_back_width: COAST_BACK_LINE_WIDTH,
+            culled_back_opacity: None,
+        }
+    }
+}
+
+impl GlobeStyle {
+    /// Style of a theme by name, "light" (the default style) or "dark".
+    pub fn theme(name: &str) -> Option<GlobeStyle> {
+        match name {
+            "light" => Some(GlobeStyle::default()),
+            "dark" => Some(GlobeStyle {
+                background: Some(DARK_BACKGROUND.to_string()),
+                sphere_fill: DARK_SPHERE_FILL_STYLE.to_string(),
+                coast_front_stroke: DARK_COAST_FRONT_STROKE_STYLE.to_string(),
+                coast_back_stroke: DARK_COAST_BACK_STROKE_STYLE.to_string(),
+                ..GlobeStyle::default()
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl GlobeStyle {
+    /// The default style.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> GlobeStyle {
+        GlobeStyle::default()
+    }
+
+    /// Style from a plain object with any of the fields of the style, the others taking
+    /// their defaults.
+    #[cfg(feature = "serde")]
+    pub fn from_object(object: JsValue) -> Result<GlobeStyle, JsValue> {
+        serde_wasm_bindgen::from_value(object).map_err(JsValue::from)
+    }
+
+    /// The style as a plain object, such as for saving as JSON.
+    #[cfg(feature = "serde")]
+    pub fn to_object(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(self).map_err(JsValue::from)
+    }
+}
@@ -0,0 +1,88 @@
+// Canvas a globe is drawn on: an element on the page, or a canvas transferred to a worker,
+// whose events and styles are posted back to the page to be applied to the element.
+
+use wasm_bindgen::JsValue;
+use web_sys::{CustomEvent, HtmlCanvasElement, OffscreenCanvas};
+
+use crate::window;
+use crate::worker;
+
+pub enum Surface {
+    /// Canvas element on the page, drawn on the main thread.
+    Element(HtmlCanvasElement),
+    /// Canvas transferred to the worker drawing the globe.
+    Offscreen(OffscreenCanvas),
+}
+
+impl Surface {
+    /// Dispatch an event on the canvas; events in a worker are also posted to the page, to be
+    /// dispatched on the canvas element.
+    pub fn dispatch(&self, event: &CustomEvent) -> Result<(), JsValue> {
+        match self {
+            Surface::Element(canvas) => {
+                canvas.dispatch_event(event)?;
+            }
+            Surface::Offscreen(canvas) => {
+                canvas.dispatch_event(event)?;
+                worker::post_event(&event.type_(), &event.detail())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Set how touches on the canvas element are handled by the browser.
+    pub fn set_touch_action(&self, touch_action: &str) -> Result<(), JsValue> {
+        match self {
+            Surface::Element(canvas) => canvas.style().set_property("touch-action", touch_action),
+            Surface::Offscreen(_) => worker::post_style("touch-action", touch_action),
+        }
+    }
+
+    /// Size the backing store (pixels) of the canvas, with the size (canvas pixels) of the
+    /// canvas element on the page, or `None` to leave that to the page's styles.
+    pub fn set_size(
+        &self,
+        (width, height): (u32, u32),
+        page_size: Option<(u32, u32)>,
+    ) -> Result<(), JsValue> {
+        let styles = [
+            ("width", page_size.map(|(width, _)| format!("{}px", width))),
+            (
+                "height",
+                page_size.map(|(_, height)| format!("{}px", height)),
+            ),
+        ];
+        match self {
+            Surface::Element(canvas) => {
+                canvas.set_width(width);
+                canvas.set_height(height);
+                for (property, value) in styles {
+                    match value {
+                        Some(value) => canvas.style().set_property(property, &value)?,
+                        None => {
+                            canvas.style().remove_property(property)?;
+                        }
+                    }
+                }
+            }
+            Surface::Offscreen(canvas) => {
+                canvas.set_width(width);
+                canvas.set_height(height);
+                // Empty values remove the styles
+                for (property, value) in styles {
+                    worker::post_style(property, value.as_deref().unwrap_or(""))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Ratio of device pixels to canvas pixels, for canvases on the page; workers are sent
+    /// it with the size of the canvas.
+    pub fn device_pixel_ratio(&self) -> Option<f64> {
+        match self {
+            Surface::Element(_) => Some(window().device_pixel_ratio()),
+            Surface::Offscreen(_) => None,
+        }
+    }
+}
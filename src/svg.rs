@@ -0,0 +1,223 @@
+// Export of the current view as an SVG document: the coastlines, graticule and markers are
+// projected with the orientation and zoom of the view and written as paths in canvas pixels,
+// for figures that stay sharp at any size. Icons of markers are written as their dots.
+
+use std::fmt::Write;
+
+use crate::graticule::{self, Graticule};
+use crate::lod::CoastlineLevel;
+use crate::markers::{self, Markers};
+use crate::view::{Frame, Side};
+
+/// Paths of lines, in canvas pixels, on the front and back of the globe.
+#[derive(Debug, Default)]
+struct Paths {
+    front: String,
+    back: String,
+}
+
+impl Paths {
+    /// Add a line of projected points, each segment added to the path of the side it is drawn
+    /// on, if any, and taken to canvas pixels.
+    fn add(
+        &mut self,
+        line: &[(f64, f64, f64)],
+        side: impl Fn((f64, f64, f64), (f64, f64, f64)) -> Option<Side>,
+        to_canvas: impl Fn(f64, f64) -> (f64, f64),
+    ) {
+        let mut previous = None;
+        for segment in line.windows(2) {
+            let ((_, y_prev, z_prev), (_, y, z)) = (segment[0], segment[1]);
+            let segment_side = side(segment[0], segment[1]);
+            let path = match segment_side {
+                Some(Side::Front) => &mut self.front,
+                Some(_) => &mut self.back,
+                None => {
+                    previous = None;
+                    continue;
+                }
+            };
+            if previous != segment_side {
+                let (x, y) = to_canvas(y_prev, z_prev);
+                let _ = write!(path, "M{:.2} {:.2}", x, y);
+            }
+            let (x, y) = to_canvas(y, z);
+            let _ = write!(path, "L{:.2} {:.2}", x, y);
+            previous = segment_side;
+        }
+    }
+}
+
+/// Render a frame of a canvas size (canvas pixels) to an SVG document, with the coastlines at
+/// a level of detail, the graticule when visible, and the markers.
+pub fn render(
+    frame: &Frame,
+    size: (u32, u32),
+    coastlines: &CoastlineLevel,
+    graticule: &Graticule,
+    markers: &Markers,
+) -> String {
+    let to_canvas = |y, z| frame.to_canvas(y, z);
+    // Widths of lines are given in unit sphere coordinates, which the frame's scale takes to
+    // canvas pixels
+    let width = |base| frame.line_width(base) * frame.scale;
+    let (width_px, height_px) = size;
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        width_px, height_px, width_px, height_px
+    );
+    if let Some(background) = &frame.style.background {
+        let _ = write!(
+            svg,
+            r#"<rect width="100%" height="100%" fill="{}"/>"#,
+            escape(background)
+        );
+    }
+    let (cx, cy) = to_canvas(0.0, 0.0);
+    let disc = format!(r#"cx="{:.2}" cy="{:.2}" r="{:.2}""#, cx, cy, frame.scale);
+    let _ = write!(svg, r#"<clipPath id="disc"><circle {}/></clipPath>"#, disc);
+    let _ = write!(
+        svg,
+        r#"<circle {} fill="{}"/>"#,
+        disc,
+        escape(&frame.style.sphere_fill)
+    );
+    svg.push_str(if frame.projection.shows_back() {
+        r#"<g fill="none" stroke-linejoin="round">"#
+    } else {
+        r#"<g fill="none" stroke-linejoin="round" clip-path="url(#disc)">"#
+    });
+
+    let stroke = |svg: &mut String, path: &str, style: &str, width: f64, opacity: f64| {
+        if !path.is_empty() {
+            let _ = write!(
+                svg,
+                r#"<path d="{}" stroke="{}" stroke-width="{:.2}" opacity="{}"/>"#,
+                path,
+                escape(style),
+                width,
+                opacity
+            );
+        }
+    };
+
+    if graticule.visible {
+        let mut paths = Paths::default();
+        for line in graticule::lines() {
+            let line = line
+                .into_iter()
+                .map(|(lon, lat)| frame.project(lon, lat))
+                .collect::<Vec<_>>();
+            paths.add(&line, |a, b| frame.segment_side(a, b), to_canvas);
+        }
+        stroke(
+            &mut svg,
+            &paths.back,
+            graticule::GRATICULE_BACK_STROKE_STYLE,
+            width(graticule::GRATICULE_BACK_LINE_WIDTH),
+            1.0,
+        );
+        stroke(
+            &mut svg,
+            &paths.front,
+            graticule::GRATICULE_FRONT_STROKE_STYLE,
+            width(graticule::GRATICULE_FRONT_LINE_WIDTH),
+            1.0,
+        );
+    }
+
+    // The back of the coastlines is written dimmed where it is culled, as it is drawn
+    let mut paths = Paths::default();
+    for (polyline, attributes) in coastlines.points.iter().zip(coastlines.attributes) {
+        if !frame.filters.shows_attributes("coastline", attributes) {
+            continue;
+        }
+        let line = polyline
+            .iter()
+            .map(|point| {
+                let (lon, lat) = point.to_degrees();
+                frame.project(lon, lat)
+            })
+            .collect::<Vec<_>>();
+        paths.add(&line, |a, b| frame.projection.segment_side(a, b), to_canvas);
+    }
+    let dimmed = frame.culled_back_opacity();
+    if frame.draws_back() || dimmed.is_some() {
+        stroke(
+            &mut svg,
+            &paths.back,
+            &frame.style.coast_back_stroke,
+            width(frame.style.coast_back_width),
+            dimmed.unwrap_or(1.0),
+        );
+    }
+    stroke(
+        &mut svg,
+        &paths.front,
+        &frame.style.coast_front_stroke,
+        width(frame.style.coast_front_width),
+        1.0,
+    );
+    svg.push_str("</g>");
+
+    let line_width = width(markers::MARKER_LINE_WIDTH);
+    for (style, _, y, z, alpha) in markers.projected(frame) {
+        let (x, y) = to_canvas(y, z);
+        let _ = write!(
+            svg,
+            r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="{}" opacity="{}""#,
+            x,
+            y,
+            style.radius,
+            escape(&style.color),
+            alpha
+        );
+        if let Some(outline) = &style.outline {
+            let _ = write!(
+                svg,
+                r#" stroke="{}" stroke-width="{:.2}""#,
+                escape(outline),
+                line_width
+            );
+        }
+        svg.push_str("/>");
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Text escaped for an attribute value.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_lines_between_sides() {
+        let side = |a: (f64, f64, f64), b: (f64, f64, f64)| match (a.0 >= 0.0, b.0 >= 0.0) {
+            (true, true) => Some(Side::Front),
+            (false, false) => Some(Side::Back),
+            _ => None,
+        };
+        let pixels = |y: f64, z: f64| (10.0 * y, 10.0 * z);
+        let mut paths = Paths::default();
+        let line = [
+            (1.0, 0.0, 0.0),
+            (1.0, 0.5, 0.0),
+            (1.0, 0.5, 0.5),
+            (-1.0, 0.0, 0.5),
+            (-1.0, 0.0, 1.0),
+        ];
+        paths.add(&line, side, pixels);
+        assert_eq!(paths.front, "M0.00 0.00L5.00 0.00L5.00 5.00");
+        assert_eq!(paths.back, "M0.00 5.00L0.00 10.00");
+
+        assert_eq!(escape(r#"url("a&b")"#), "url(&quot;a&amp;b&quot;)");
+    }
+}
@@ -0,0 +1,207 @@
+// Comparison of two sets of layers by swiping: a draggable divider splits the canvas, with
+// the layers of one set drawn to the left of it and those of the other to the right.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::overlay;
+use crate::zoom::LAYERS;
+
+const DIVIDER_STROKE_STYLE: &str = "rgba(255, 255, 255, 0.9)";
+const DIVIDER_SHADOW_STYLE: &str = "rgba(0, 0, 0, 0.4)";
+const HANDLE_FILL_STYLE: &str = "rgba(255, 255, 255, 0.9)";
+const HANDLE_STROKE_STYLE: &str = "rgba(0, 0, 0, 0.6)";
+// Sizes are in canvas pixels
+const DIVIDER_WIDTH: f64 = 2.0;
+const HANDLE_RADIUS: f64 = 12.0;
+const HANDLE_ARROW_SIZE: f64 = 4.0;
+// Presses this close to the divider drag it
+const DIVIDER_HIT_DISTANCE: f64 = 10.0;
+
+/// Half of the canvas on either side of the divider.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Half {
+    Left,
+    Right,
+}
+
+#[derive(Debug)]
+pub struct Swipe {
+    pub enabled: bool,
+    /// Position of the divider, as a fraction of the width of the canvas from its left.
+    position: f64,
+    /// Layers drawn to the left and to the right of the divider; other layers are drawn on
+    /// both sides.
+    left: Vec<&'static str>,
+    right: Vec<&'static str>,
+    dragging: bool,
+}
+
+impl Default for Swipe {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            position: 0.5,
+            left: Vec::new(),
+            right: Vec::new(),
+            dragging: false,
+        }
+    }
+}
+
+impl Swipe {
+    /// Set the layers, by their identifiers, drawn on each side of the divider.
+    pub fn set_layers(&mut self, left: &[String], right: &[String]) -> Result<(), String> {
+        let layers = |ids: &[String]| {
+            ids.iter()
+                .map(|id| {
+                    LAYERS
+                        .iter()
+                        .find(|layer| **layer == id.as_str())
+                        .copied()
+                        .ok_or_else(|| format!("unknown layer {:?}", id))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        (self.left, self.right) = (layers(left)?, layers(right)?);
+        Ok(())
+    }
+
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    /// Set the position of the divider, as a fraction of the width of the canvas.
+    pub fn set_position(&mut self, position: f64) {
+        if !position.is_nan() {
+            self.position = position.clamp(0.0, 1.0);
+        }
+    }
+
+    /// Halves of the canvas a layer is drawn in, by whether it is drawn in the left and in the
+    /// right half, or `None` when it is drawn over the whole canvas.
+    pub fn halves(&self, layer: &str) -> Option<[bool; 2]> {
+        let halves = [self.left.contains(&layer), self.right.contains(&layer)];
+        (self.enabled && halves != [false, false]).then_some(halves)
+    }
+
+    /// Clip subsequent drawing to a half of the canvas; the clip is removed by restoring the
+    /// context.
+    pub fn clip(
+        &self,
+        context: &CanvasRenderingContext2d,
+        half: Half,
+        pixel_ratio: f64,
+    ) -> Result<(), JsValue> {
+        let unit = context.get_transform()?;
+        let (width, height) = overlay::canvas_pixels(context, pixel_ratio)?;
+        let x = self.position * width;
+        context.begin_path();
+        match half {
+            Half::Left => context.rect(0.0, 0.0, x, height),
+            Half::Right => context.rect(x, 0.0, width - x, height),
+        }
+        context.clip();
+        context.set_transform(unit.a(), unit.b(), unit.c(), unit.d(), unit.e(), unit.f())
+    }
+
+    /// Start dragging the divider when pressed near it, returning whether it was.
+    pub fn drag_start(&mut self, x: f64, width: f64) -> bool {
+        self.dragging = self.enabled && (x - self.position * width).abs() <= DIVIDER_HIT_DISTANCE;
+        self.dragging
+    }
+
+    /// Drag the divider to a position (canvas pixels), returning whether it is being dragged.
+    pub fn drag_to(&mut self, x: f64, width: f64) -> bool {
+        if self.dragging && width > 0.0 {
+            self.set_position(x / width);
+        }
+        self.dragging
+    }
+
+    /// Whether the divider is being dragged.
+    pub fn dragging(&self) -> bool {
+        self.dragging
+    }
+
+    pub fn drag_end(&mut self) -> bool {
+        std::mem::take(&mut self.dragging)
+    }
+
+    /// Draw the divider and its handle onto the canvas.
+    pub fn draw(
+        &self,
+        context: &CanvasRenderingContext2d,
+        pixel_ratio: f64,
+    ) -> Result<(), JsValue> {
+        if !self.enabled {
+            return Ok(());
+        }
+        context.save();
+        let (width, height) = overlay::canvas_pixels(context, pixel_ratio)?;
+        let (x, centre_y) = (self.position * width, height / 2.0);
+        for (style, line_width) in [
+            (DIVIDER_SHADOW_STYLE, DIVIDER_WIDTH * 2.0),
+            (DIVIDER_STROKE_STYLE, DIVIDER_WIDTH),
+        ] {
+            context.set_line_width(line_width);
+            context.set_stroke_style_str(style);
+            context.begin_path();
+            context.move_to(x, 0.0);
+            context.line_to(x, height);
+            context.stroke();
+        }
+
+        // The handle shows the divider can be dragged either way
+        context.set_line_width(1.0);
+        context.set_fill_style_str(HANDLE_FILL_STYLE);
+        context.set_stroke_style_str(HANDLE_STROKE_STYLE);
+        context.begin_path();
+        context.arc(x, centre_y, HANDLE_RADIUS, 0.0, std::f64::consts::TAU)?;
+        context.fill();
+        context.stroke();
+        context.set_fill_style_str(HANDLE_STROKE_STYLE);
+        for direction in [-1.0, 1.0] {
+            let tip = x + direction * (HANDLE_RADIUS - HANDLE_ARROW_SIZE);
+            context.begin_path();
+            context.move_to(tip, centre_y);
+            context.line_to(
+                tip - direction * HANDLE_ARROW_SIZE,
+                centre_y - HANDLE_ARROW_SIZE,
+            );
+            context.line_to(
+                tip - direction * HANDLE_ARROW_SIZE,
+                centre_y + HANDLE_ARROW_SIZE,
+            );
+            context.close_path();
+            context.fill();
+        }
+        context.restore();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_layers_between_halves() {
+        let mut swipe = Swipe::default();
+        swipe
+            .set_layers(&["choropleth".to_string()], &["hexbins".to_string()])
+            .unwrap();
+        assert_eq!(swipe.halves("choropleth"), None);
+        swipe.enabled = true;
+        assert_eq!(swipe.halves("choropleth"), Some([true, false]));
+        assert_eq!(swipe.halves("hexbins"), Some([false, true]));
+        assert_eq!(swipe.halves("coastline"), None);
+        assert!(swipe.set_layers(&["roads".to_string()], &[]).is_err());
+
+        assert!(!swipe.drag_start(100.0, 400.0));
+        assert!(swipe.drag_start(195.0, 400.0));
+        assert!(swipe.drag_to(300.0, 400.0));
+        assert_eq!(swipe.position(), 0.75);
+        assert!(swipe.drag_end());
+    }
+}
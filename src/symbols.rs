@@ -0,0 +1,195 @@
+// Graduated symbols, drawn as circles with areas proportional to their values and filled by value.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::coords::LonLat;
+use crate::legend::{Legend, LegendEntry, LegendSymbols};
+use crate::view::Frame;
+
+const SYMBOL_LOW_COLOR: [f64; 3] = [254.0, 217.0, 118.0];
+const SYMBOL_HIGH_COLOR: [f64; 3] = [189.0, 0.0, 38.0];
+const SYMBOL_ALPHA: f64 = 0.8;
+const SYMBOL_STROKE_STYLE: &str = "rgba(255, 255, 255, 0.9)";
+const SYMBOL_LINE_WIDTH: f64 = 0.002;
+// Radii are in canvas pixels
+const SYMBOL_DEFAULT_MAX_RADIUS: f64 = 20.0;
+const SYMBOL_DEFAULT_LEGEND_TITLE: &str = "Value";
+
+#[derive(Clone, Debug, PartialEq)]
+struct Symbol {
+    id: u32,
+    location: LonLat,
+    value: f64,
+}
+
+#[derive(Debug)]
+pub struct GraduatedSymbols {
+    symbols: Vec<Symbol>,
+    next_id: u32,
+    /// Radius, in canvas pixels, of the symbol with the largest absolute value.
+    pub max_radius: f64,
+    pub legend_title: String,
+}
+
+impl Default for GraduatedSymbols {
+    fn default() -> Self {
+        Self {
+            symbols: Vec::new(),
+            next_id: 0,
+            max_radius: SYMBOL_DEFAULT_MAX_RADIUS,
+            legend_title: SYMBOL_DEFAULT_LEGEND_TITLE.to_string(),
+        }
+    }
+}
+
+impl GraduatedSymbols {
+    /// Add a symbol, returning its identifier.
+    pub fn add(&mut self, location: LonLat, value: f64) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.symbols.push(Symbol {
+            id,
+            location,
+            value,
+        });
+        id
+    }
+
+    /// Remove a symbol, returning whether it existed.
+    pub fn remove(&mut self, id: u32) -> bool {
+        let len = self.symbols.len();
+        self.symbols.retain(|symbol| symbol.id != id);
+        self.symbols.len() != len
+    }
+
+    /// Remove all symbols.
+    pub fn clear(&mut self) {
+        self.symbols.clear();
+    }
+
+    /// Range of the finite values of the symbols, or `None` when there are none.
+    fn range(&self) -> Option<(f64, f64)> {
+        let (min, max) = self
+            .symbols
+            .iter()
+            .map(|symbol| symbol.value)
+            .filter(|value| value.is_finite())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                (min.min(value), max.max(value))
+            });
+        (min <= max).then_some((min, max))
+    }
+
+    /// Radius (canvas pixels) of a value, scaled by its square root so the
+    /// areas of the symbols are proportional to their values.
+    fn radius(&self, value: f64, (min, max): (f64, f64)) -> f64 {
+        let max_value = min.abs().max(max.abs());
+        if max_value > 0.0 {
+            self.max_radius * (value.abs() / max_value).sqrt()
+        } else {
+            0.0
+        }
+    }
+
+    /// Legend of the sizes of the symbols at the largest absolute value and a quarter of it,
+    /// filled with their colors, when shown.
+    pub fn legend(&self) -> Option<Legend> {
+        let range = self.range()?;
+        let max_value = range.0.abs().max(range.1.abs());
+        if max_value <= 0.0 {
+            return None;
+        }
+        let entry = |value: f64| {
+            (
+                self.radius(value, range),
+                LegendEntry {
+                    fill_style: color_style(color(value, range)),
+                    text: value.to_string(),
+                },
+            )
+        };
+        let sign = if range.1.abs() >= range.0.abs() {
+            1.0
+        } else {
+            -1.0
+        };
+        Some(Legend {
+            title: self.legend_title.clone(),
+            symbols: LegendSymbols::Circles(vec![
+                entry(sign * max_value),
+                entry(sign * max_value / 4.0),
+            ]),
+        })
+    }
+
+    /// Draw the symbols on the front of the globe onto the canvas,
+    /// largest first so smaller symbols are not hidden beneath them.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        let Some(range) = self.range() else {
+            return Ok(());
+        };
+        let mut symbols = self
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.value.is_finite())
+            .map(|symbol| (symbol, self.radius(symbol.value, range)))
+            .collect::<Vec<_>>();
+        symbols.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        context.set_line_width(frame.line_width(SYMBOL_LINE_WIDTH));
+        context.set_stroke_style_str(SYMBOL_STROKE_STYLE);
+        for (symbol, radius) in symbols {
+            let (x, y, z) = frame.project(symbol.location.lon, symbol.location.lat);
+            if x < 0.0 || radius <= 0.0 {
+                continue;
+            }
+            // Symbols are a constant size on screen
+            context.begin_path();
+            context.arc(y, z, radius / frame.scale, 0.0, std::f64::consts::TAU)?;
+            context.set_fill_style_str(&color_style(color(symbol.value, range)));
+            context.fill();
+            context.stroke();
+        }
+
+        Ok(())
+    }
+}
+
+/// Color (red, green, blue) of a value on the ramp over the range of values.
+fn color(value: f64, (min, max): (f64, f64)) -> [f64; 3] {
+    let t = if max > min {
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    [0, 1, 2].map(|i| SYMBOL_LOW_COLOR[i] + (SYMBOL_HIGH_COLOR[i] - SYMBOL_LOW_COLOR[i]) * t)
+}
+
+fn color_style(color: [f64; 3]) -> String {
+    format!(
+        "rgba({:.0}, {:.0}, {:.0}, {})",
+        color[0], color[1], color[2], SYMBOL_ALPHA
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_radii_by_square_root() {
+        let mut symbols = GraduatedSymbols::default();
+        symbols.add(LonLat::new(0.0, 0.0), 100.0);
+        symbols.add(LonLat::new(10.0, 10.0), 25.0);
+        let range = symbols.range().unwrap();
+        assert_eq!(symbols.radius(100.0, range), SYMBOL_DEFAULT_MAX_RADIUS);
+        assert_eq!(symbols.radius(25.0, range), SYMBOL_DEFAULT_MAX_RADIUS / 2.0);
+    }
+
+    #[test]
+    fn ramps_colors_over_range() {
+        assert_eq!(color(0.0, (0.0, 10.0)), SYMBOL_LOW_COLOR);
+        assert_eq!(color(10.0, (0.0, 10.0)), SYMBOL_HIGH_COLOR);
+    }
+}
@@ -0,0 +1,103 @@
+// Hook points reporting significant interactions to the host application.
+
+use wasm_bindgen::JsValue;
+
+use crate::gestures::Gesture;
+use crate::view::Orientation;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TelemetryEvent {
+    /// The view settled at a new centre (degrees).
+    ViewChanged { lat: f64, lon: f64 },
+    /// A gesture was recognised at a canvas position.
+    Gesture { kind: &'static str, x: f64, y: f64 },
+    /// The data of a layer was changed.
+    LayerChanged {
+        layer: &'static str,
+        action: &'static str,
+    },
+}
+
+impl TelemetryEvent {
+    pub fn gesture(gesture: &Gesture) -> Self {
+        TelemetryEvent::Gesture {
+            kind: gesture.kind.event_type(),
+            x: gesture.position.x,
+            y: gesture.position.y,
+        }
+    }
+
+    /// Structured payload passed to the callback, with the event type in the `type` property.
+    fn payload(&self) -> Result<JsValue, JsValue> {
+        let payload = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| js_sys::Reflect::set(&payload, &key.into(), &value);
+        match self {
+            TelemetryEvent::ViewChanged { lat, lon } => {
+                set("type", "view-changed".into())?;
+                set("lat", (*lat).into())?;
+                set("lon", (*lon).into())?;
+            }
+            TelemetryEvent::Gesture { kind, x, y } => {
+                set("type", "gesture".into())?;
+                set("kind", (*kind).into())?;
+                set("x", (*x).into())?;
+                set("y", (*y).into())?;
+            }
+            TelemetryEvent::LayerChanged { layer, action } => {
+                set("type", "layer-changed".into())?;
+                set("layer", (*layer).into())?;
+                set("action", (*action).into())?;
+            }
+        }
+        Ok(payload.into())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Telemetry {
+    pub callback: Option<js_sys::Function>,
+    pending: Vec<TelemetryEvent>,
+    view: Orientation,
+}
+
+impl Telemetry {
+    /// Record an event to be reported, when a callback is set.
+    pub fn record(&mut self, event: TelemetryEvent) {
+        if self.callback.is_some() {
+            self.pending.push(event);
+        }
+    }
+
+    /// Record a change of view once the view has settled.
+    pub fn view(&mut self, orientation: &Orientation, settled: bool) {
+        if settled && *orientation != self.view {
+            self.view = *orientation;
+            self.record(TelemetryEvent::ViewChanged {
+                lat: orientation.tilt,
+                lon: (180.0 - orientation.rotation).rem_euclid(360.0) - 180.0,
+            });
+        }
+    }
+
+    /// Whether events are waiting to be reported.
+    pub fn pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Take the callback and pending events, to report once the globe state is released.
+    pub fn take(&mut self) -> Option<(js_sys::Function, Vec<TelemetryEvent>)> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let events = std::mem::take(&mut self.pending);
+        self.callback.clone().map(|callback| (callback, events))
+    }
+}
+
+/// Report events to the callback.
+pub fn report(callback: &js_sys::Function, events: &[TelemetryEvent]) -> Result<(), JsValue> {
+    for event in events {
+        callback.call1(&JsValue::NULL, &event.payload()?)?;
+    }
+    Ok(())
+}
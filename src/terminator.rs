@@ -0,0 +1,194 @@
+// Shading of the night side of the globe, bounded by the terminator: the great circle a
+// quarter turn from the sub-solar point, where the sun is overhead.
+
+use std::cell::Cell;
+
+use wasm_bindgen::JsValue;
+use web_sys::{CanvasRenderingContext2d, CanvasWindingRule};
+
+use crate::coords::{LonLat, Vec3};
+use crate::horizon;
+use crate::measure;
+use crate::projection::ProjectionKind;
+use crate::view::Frame;
+
+const NIGHT_DEFAULT_FILL_STYLE: &str = "rgba(0, 0, 40, 0.45)";
+const TERMINATOR_SAMPLES: usize = 180;
+// The sun moves by about 0.004 degrees a second, so the shading is redrawn at most this often
+// (milliseconds) while following the clock
+const TERMINATOR_UPDATE_INTERVAL: f64 = 1000.0;
+// Extent (plotted coordinates) of the area filled around the night side when it surrounds the
+// plotted terminator, beyond the edge of any projection's disc
+const OUTER_EXTENT: f64 = 1.0e6;
+const MILLISECONDS_PER_DAY: f64 = 86_400_000.0;
+// Julian date of the Unix epoch and of the J2000 epoch
+const UNIX_EPOCH_JULIAN_DATE: f64 = 2_440_587.5;
+const J2000_JULIAN_DATE: f64 = 2_451_545.0;
+
+#[derive(Debug)]
+pub struct Terminator {
+    pub enabled: bool,
+    pub fill_style: String,
+    /// Time (milliseconds since the Unix epoch) the sun is shown at, or `None` to follow the
+    /// clock.
+    pub time: Option<f64>,
+    /// Time of the clock the shading was last drawn at.
+    drawn: Cell<Option<f64>>,
+}
+
+impl Default for Terminator {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            fill_style: NIGHT_DEFAULT_FILL_STYLE.to_string(),
+            time: None,
+            drawn: Cell::new(None),
+        }
+    }
+}
+
+impl Terminator {
+    /// Whether the shading follows the clock and has moved since it was drawn.
+    pub fn animating(&self, clock: f64) -> bool {
+        self.enabled
+            && self.time.is_none()
+            && self
+                .drawn
+                .get()
+                .is_none_or(|drawn| clock - drawn >= TERMINATOR_UPDATE_INTERVAL)
+    }
+
+    /// Fill the night side onto the canvas, at the set time or else the time of the clock.
+    pub fn draw(
+        &self,
+        context: &CanvasRenderingContext2d,
+        frame: &Frame,
+        clock: f64,
+    ) -> Result<(), JsValue> {
+        if !self.enabled {
+            return Ok(());
+        }
+        self.drawn.set(Some(clock));
+        let subsolar = subsolar_point(self.time.unwrap_or(clock));
+        let antisolar = LonLat::new(subsolar.lon + 180.0, -subsolar.lat).wrapped();
+        let ring = measure::small_circle(antisolar, 90.0, TERMINATOR_SAMPLES);
+
+        context.begin_path();
+        let outline = if frame.projection.kind == ProjectionKind::Orthographic {
+            // Arcs along the horizon close the part of the night side on the front
+            let points = ring
+                .iter()
+                .map(|location| Vec3::from(frame.project(location.lon, location.lat)))
+                .collect::<Vec<_>>();
+            horizon::clip_ring(&points)
+        } else {
+            let outline = ring
+                .iter()
+                .map(|location| {
+                    let (_, y, z) = frame.project(location.lon, location.lat);
+                    (y, z)
+                })
+                .collect::<Vec<_>>();
+            // The night side is outside the plotted terminator when the centre of the view is
+            // on the other side of it from the centre of the disc
+            let centre = frame
+                .projection
+                .unproject(&frame.orientation, 0.0, 0.0)
+                .map(|centre| measure::central_angle(centre, antisolar));
+            if centre.is_some_and(|angle| (angle < 90.0) != encloses(&outline, (0.0, 0.0))) {
+                context.rect(
+                    -OUTER_EXTENT,
+                    -OUTER_EXTENT,
+                    2.0 * OUTER_EXTENT,
+                    2.0 * OUTER_EXTENT,
+                );
+            }
+            outline
+        };
+        if let Some(((y, z), rest)) = outline.split_first() {
+            context.move_to(*y, *z);
+            for (y, z) in rest {
+                context.line_to(*y, *z);
+            }
+            context.close_path();
+        }
+        context.set_fill_style_str(&self.fill_style);
+        context.fill_with_canvas_winding_rule(CanvasWindingRule::Evenodd);
+
+        Ok(())
+    }
+}
+
+/// Location (degrees) where the sun is overhead at a time (milliseconds since the Unix epoch),
+/// from the low precision solar coordinates of the Astronomical Almanac, good to about a
+/// hundredth of a degree this century.
+pub fn subsolar_point(time: f64) -> LonLat {
+    let days = days_since_j2000(time);
+    overhead_point(days, solar_longitude(days), 0.0)
+}
+
+/// Days since the J2000 epoch at a time (milliseconds since the Unix epoch).
+pub fn days_since_j2000(time: f64) -> f64 {
+    time / MILLISECONDS_PER_DAY + UNIX_EPOCH_JULIAN_DATE - J2000_JULIAN_DATE
+}
+
+/// Ecliptic longitude (degrees) of the sun a number of days since the J2000 epoch.
+pub fn solar_longitude(days: f64) -> f64 {
+    let mean_anomaly = (357.529 + 0.985_600_28 * days).to_radians();
+    let mean_longitude = 280.459 + 0.985_647_36 * days;
+    mean_longitude + 1.915 * mean_anomaly.sin() + 0.020 * (2.0 * mean_anomaly).sin()
+}
+
+/// Location (degrees) where a body at an ecliptic longitude and latitude (degrees) is
+/// overhead a number of days since the J2000 epoch.
+pub fn overhead_point(days: f64, ecliptic_longitude: f64, ecliptic_latitude: f64) -> LonLat {
+    let obliquity = (23.439 - 0.000_000_36 * days).to_radians();
+    let (sin_lon, cos_lon) = ecliptic_longitude.to_radians().sin_cos();
+    let (sin_lat, cos_lat) = ecliptic_latitude.to_radians().sin_cos();
+    let declination =
+        (sin_lat * obliquity.cos() + cos_lat * obliquity.sin() * sin_lon).clamp(-1.0, 1.0);
+    let right_ascension =
+        (cos_lat * sin_lon * obliquity.cos() - sin_lat * obliquity.sin()).atan2(cos_lat * cos_lon);
+    // The body is overhead where the sidereal time of the meridian matches its right ascension
+    let sidereal_time = 280.460_618_37 + 360.985_647_366_29 * days;
+    let lon = right_ascension.to_degrees() - sidereal_time;
+    LonLat::new(lon, declination.asin().to_degrees()).wrapped()
+}
+
+/// Whether a point is within a closed outline (plotted coordinates), by the even-odd rule.
+fn encloses(outline: &[(f64, f64)], (y, z): (f64, f64)) -> bool {
+    let mut inside = false;
+    for (i, &(y1, z1)) in outline.iter().enumerate() {
+        let (y0, z0) = outline[(i + outline.len() - 1) % outline.len()];
+        if (z0 > z) != (z1 > z) && y < y0 + (z - z0) * (y1 - y0) / (z1 - z0) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_sun_overhead() {
+        let near = |location: LonLat, expected: LonLat| {
+            (location.lat - expected.lat).abs() < 0.1 && (location.lon - expected.lon).abs() < 0.2
+        };
+        // The March equinox of 2024, at 03:06 UTC, when the sun runs about 7.5 minutes late
+        assert!(near(
+            subsolar_point(1_710_903_960_000.0),
+            LonLat::new(135.4, 0.0)
+        ));
+        // The June solstice of 2024, at 20:51 UTC, when it runs about 1.5 minutes late
+        assert!(near(
+            subsolar_point(1_718_916_660_000.0),
+            LonLat::new(-132.4, 23.44)
+        ));
+
+        let square = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+        assert!(encloses(&square, (0.0, 0.0)));
+        assert!(!encloses(&square, (2.0, 0.0)));
+    }
+}
@@ -0,0 +1,338 @@
+// Golden-image regression testing of rendering: canonical views of the globe are rendered to
+// pixels, in the browser by drawing the passes of a frame and natively by the server renderer,
+// and compared with the golden images checked in under `tests/golden` within a tolerance.
+
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+use crate::coords::LonLat;
+use crate::projection::ProjectionKind;
+use crate::server::{self, StaticView};
+use crate::surface::Surface;
+use crate::{draw, size_canvas, window, State};
+
+include!(concat!(env!("OUT_DIR"), "/golden.rs"));
+
+/// Width and height (pixels) of the views rendered.
+pub const GOLDEN_SIZE: u32 = 128;
+
+/// View of the globe rendered for a golden image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GoldenView {
+    pub name: &'static str,
+    /// Location (degrees) at the centre of the view.
+    pub lat: f64,
+    pub lon: f64,
+    pub zoom: f64,
+    pub projection: ProjectionKind,
+    pub graticule: bool,
+}
+
+/// Views covering the projections, the poles, the antimeridian and zooming.
+pub const GOLDEN_VIEWS: &[GoldenView] = &[
+    GoldenView {
+        name: "greenwich",
+        lat: 51.5,
+        lon: 0.0,
+        zoom: 1.0,
+        projection: ProjectionKind::Orthographic,
+        graticule: false,
+    },
+    GoldenView {
+        name: "antimeridian-graticule",
+        lat: -20.0,
+        lon: 180.0,
+        zoom: 1.0,
+        projection: ProjectionKind::Orthographic,
+        graticule: true,
+    },
+    GoldenView {
+        name: "north-polar",
+        lat: 90.0,
+        lon: 0.0,
+        zoom: 1.0,
+        projection: ProjectionKind::NorthPolarStereographic,
+        graticule: true,
+    },
+    GoldenView {
+        name: "azimuthal",
+        lat: 40.0,
+        lon: -100.0,
+        zoom: 1.0,
+        projection: ProjectionKind::AzimuthalEquidistant,
+        graticule: false,
+    },
+    GoldenView {
+        name: "japan-zoomed",
+        lat: 36.0,
+        lon: 138.0,
+        zoom: 4.0,
+        projection: ProjectionKind::Orthographic,
+        graticule: true,
+    },
+];
+
+impl GoldenView {
+    /// Name of the golden image of the view rendered by the server renderer.
+    pub fn static_name(&self) -> String {
+        format!("server-{}", self.name)
+    }
+
+    /// The view for the server renderer, which draws neither the graticule nor zoomed views.
+    fn static_view(&self) -> StaticView {
+        StaticView {
+            width: GOLDEN_SIZE,
+            height: GOLDEN_SIZE,
+            lat: self.lat,
+            lon: self.lon,
+            projection: self.projection,
+        }
+    }
+}
+
+/// Pixels of an image, as red, green, blue and alpha not premultiplied.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pixels {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Pixels {
+    pub fn to_png(&self) -> Vec<u8> {
+        server::encode_png(self.width, self.height, &self.data)
+    }
+
+    /// Pixels of a PNG as written by `to_png`, with 8-bit RGBA data in unfiltered rows stored
+    /// uncompressed; returns `None` for other PNGs.
+    pub fn from_png(png: &[u8]) -> Option<Self> {
+        let mut rest = png.strip_prefix(b"\x89PNG\r\n\x1a\n")?;
+        let (mut width, mut height) = (0, 0);
+        let mut stream = Vec::new();
+        while rest.len() >= 12 {
+            let length = u32::from_be_bytes(rest[0..4].try_into().ok()?) as usize;
+            let kind = &rest[4..8];
+            let data = rest.get(8..8 + length)?;
+            match kind {
+                b"IHDR" => {
+                    width = u32::from_be_bytes(data.get(0..4)?.try_into().ok()?);
+                    height = u32::from_be_bytes(data.get(4..8)?.try_into().ok()?);
+                    if data.get(8..13)? != [8, 6, 0, 0, 0] {
+                        return None;
+                    }
+                }
+                b"IDAT" => stream.extend(data),
+                _ => {}
+            }
+            rest = rest.get(12 + length..)?;
+        }
+
+        // The zlib stream is a header, uncompressed blocks and a checksum
+        let mut raw = Vec::new();
+        let mut blocks = stream.get(2..)?;
+        loop {
+            let last = *blocks.first()? & 1 == 1;
+            if *blocks.first()? & 0b110 != 0 {
+                return None;
+            }
+            let length = u16::from_le_bytes(blocks.get(1..3)?.try_into().ok()?) as usize;
+            raw.extend(blocks.get(5..5 + length)?);
+            blocks = &blocks[5 + length..];
+            if last {
+                break;
+            }
+        }
+
+        let stride = width as usize * 4;
+        let mut data = Vec::with_capacity(stride * height as usize);
+        for row in raw.chunks(stride + 1) {
+            if row.len() != stride + 1 || row[0] != 0 {
+                return None;
+            }
+            data.extend(&row[1..]);
+        }
+        (data.len() == stride * height as usize).then_some(Self {
+            width,
+            height,
+            data,
+        })
+    }
+}
+
+/// Differences allowed between a rendering and its golden image, for the antialiasing of
+/// browsers differing slightly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tolerance {
+    /// Difference in any channel of a pixel within which the pixel matches.
+    pub channel: u8,
+    /// Fraction of the pixels allowed to differ by more.
+    pub pixels: f64,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            channel: 24,
+            pixels: 0.005,
+        }
+    }
+}
+
+/// How a rendering differs from its golden image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    /// Pixels differing by more than the tolerance, of all of them.
+    pub differing: usize,
+    pub total: usize,
+    /// Greatest difference in any channel.
+    pub max_difference: u8,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} pixels differ, by up to {}",
+            self.differing, self.total, self.max_difference
+        )
+    }
+}
+
+/// Compare a rendering with its golden image; images of different sizes differ in every pixel.
+pub fn compare(actual: &Pixels, golden: &Pixels, tolerance: Tolerance) -> Result<(), Mismatch> {
+    let total = (actual.width * actual.height) as usize;
+    if (actual.width, actual.height) != (golden.width, golden.height) {
+        return Err(Mismatch {
+            differing: total,
+            total,
+            max_difference: u8::MAX,
+        });
+    }
+    let mut differing = 0;
+    let mut max_difference = 0;
+    for (a, b) in actual.data.chunks(4).zip(golden.data.chunks(4)) {
+        let difference = a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap_or(0);
+        max_difference = max_difference.max(difference);
+        if difference > tolerance.channel {
+            differing += 1;
+        }
+    }
+    if differing as f64 > tolerance.pixels * total as f64 {
+        Err(Mismatch {
+            differing,
+            total,
+            max_difference,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Golden image checked in with a name, when the crate is built with it.
+pub fn golden(name: &str) -> Option<Pixels> {
+    GOLDEN_IMAGES
+        .iter()
+        .find(|(image, _)| *image == name)
+        .and_then(|(_, png)| Pixels::from_png(png))
+}
+
+/// Render a view in the browser, drawing every pass of a frame onto a new canvas; overlays
+/// with text, which browsers draw differently, are hidden.
+pub fn render(view: &GoldenView) -> Result<Pixels, JsValue> {
+    let canvas = window()
+        .document()
+        .expect("should have document")
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    let context = canvas
+        .get_context("2d")?
+        .expect("should have 2d context")
+        .dyn_into::<CanvasRenderingContext2d>()?;
+
+    let mut state = State {
+        canvas_size: (GOLDEN_SIZE, GOLDEN_SIZE),
+        ..State::default()
+    };
+    state.control.orientation = state
+        .control
+        .orientation
+        .centred_on(LonLat::new(view.lon, view.lat));
+    state.control.zoom.set(view.zoom);
+    state.projection.kind = view.projection;
+    state.graticule.visible = view.graticule;
+    state.attribution.visible = false;
+    state.startup.enabled = false;
+    // Every pass is drawn in the one frame
+    state.scheduler.budget = f64::INFINITY;
+
+    let pixel_ratio = state.render.pixel_ratio();
+    let surface = Surface::Element(canvas.clone());
+    size_canvas(&surface, &context, state.canvas_size, pixel_ratio, false)?;
+    state.scheduler.start();
+    draw(&context, &mut state)?;
+
+    let (width, height) = (canvas.width(), canvas.height());
+    let data = context
+        .get_image_data(0.0, 0.0, width as f64, height as f64)?
+        .data()
+        .0;
+    Ok(Pixels {
+        width,
+        height,
+        data,
+    })
+}
+
+/// Render a view with the server renderer.
+pub fn render_static(view: &GoldenView) -> Pixels {
+    Pixels::from_png(&server::render_png(&view.static_view()))
+        .expect("should decode rendered image")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_encoded_pixels() {
+        let pixels = Pixels {
+            width: 3,
+            height: 2,
+            data: (0..24).collect(),
+        };
+        assert_eq!(Pixels::from_png(&pixels.to_png()), Some(pixels));
+        assert_eq!(Pixels::from_png(b"GIF89a"), None);
+    }
+
+    #[test]
+    fn compares_within_tolerance() {
+        let golden = Pixels {
+            width: 10,
+            height: 10,
+            data: vec![100; 400],
+        };
+        let mut actual = golden.clone();
+        actual.data[0] = 110;
+        let tolerance = Tolerance {
+            channel: 8,
+            pixels: 0.0,
+        };
+        assert_eq!(
+            compare(&actual, &golden, tolerance),
+            Err(Mismatch {
+                differing: 1,
+                total: 100,
+                max_difference: 10,
+            })
+        );
+        assert!(compare(&actual, &golden, Tolerance::default()).is_ok());
+    }
+}
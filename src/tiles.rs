@@ -0,0 +1,485 @@
+// Basemap of raster tiles in the XYZ scheme of web maps, fetched for the region in view and
+// reprojected from Web Mercator onto the globe.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{
+    Blob, CanvasRenderingContext2d, Event, HtmlCanvasElement, HtmlImageElement, ImageData, Url,
+};
+
+use crate::fetch::{Body, Fetcher};
+use crate::status::LayerStatus;
+use crate::view::Frame;
+use crate::{controls, overlay, window};
+
+// Size (pixels) of tiles in the XYZ scheme, by which the zoom of the tiles is chosen
+const TILE_SIZE: f64 = 256.0;
+const TILE_DEFAULT_MAX_ZOOM: u8 = 19;
+// Decoded tiles kept
+const TILE_CACHE_SIZE: usize = 128;
+// Client of the fetcher the tiles are requested as
+const TILE_CLIENT: &str = "tiles";
+// Latitude (degrees) beyond which Web Mercator tiles have no data
+const MERCATOR_MAX_LAT: f64 = 85.051_128_78;
+
+/// Tile by its zoom and column and row at that zoom.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct TileKey {
+    z: u8,
+    x: u32,
+    y: u32,
+}
+
+impl TileKey {
+    /// Tile at a zoom containing a position in Web Mercator coordinates, each from 0 to 1.
+    fn at(z: u8, (mx, my): (f64, f64)) -> Self {
+        let n = (1_u32 << z) as f64;
+        let index = |m: f64| ((m * n).floor() as u32).min((1 << z) - 1);
+        Self {
+            z,
+            x: index(mx),
+            y: index(my),
+        }
+    }
+
+    /// URL of the tile from a template with `{z}`, `{x}` and `{y}` placeholders.
+    fn url(&self, template: &str) -> String {
+        template
+            .replace("{z}", &self.z.to_string())
+            .replace("{x}", &self.x.to_string())
+            .replace("{y}", &self.y.to_string())
+    }
+}
+
+/// Pixels (red, green, blue and alpha) of a decoded tile, with its size.
+struct Pixels {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl Pixels {
+    /// Color of the pixel of a tile at a position in Web Mercator coordinates.
+    fn sample(&self, key: TileKey, (mx, my): (f64, f64)) -> &[u8] {
+        let n = (1_u32 << key.z) as f64;
+        let column = ((mx * n - key.x as f64) * self.width as f64) as u32;
+        let row = ((my * n - key.y as f64) * self.height as f64) as u32;
+        let index =
+            (row.min(self.height - 1) * self.width + column.min(self.width - 1)) as usize * 4;
+        &self.data[index..index + 4]
+    }
+}
+
+enum Tile {
+    Loading,
+    /// Fetched, and being decoded.
+    Decoding,
+    Ready(Pixels),
+    Failed,
+}
+
+struct CachedTile {
+    tile: Tile,
+    /// Frame the tile was last drawn in, for evicting the least recently used.
+    used: u64,
+}
+
+/// Tiles loaded since they were last received, by the generation of the source they were
+/// requested from, with their pixels or `None` when they failed to load.
+type Inbox = Rc<RefCell<Vec<(u32, TileKey, Option<Pixels>)>>>;
+
+#[derive(Default)]
+struct TileCache {
+    tiles: HashMap<TileKey, CachedTile>,
+    /// Tiles requested by their URLs, until their responses are received.
+    requested: HashMap<String, TileKey>,
+    frame: u64,
+    /// Canvases tiles are decoded on and the view of the tiles composed on.
+    decoder: Option<(HtmlCanvasElement, CanvasRenderingContext2d)>,
+    composer: Option<(HtmlCanvasElement, CanvasRenderingContext2d)>,
+}
+
+pub struct Tiles {
+    /// URL template of the tiles, or `None` for no basemap.
+    template: Option<String>,
+    /// Attribution of the source of the tiles.
+    pub attribution: Option<String>,
+    pub max_zoom: u8,
+    /// Generation of the source, so tiles requested from earlier sources are ignored.
+    generation: u32,
+    /// Message of the failure of the tile last loaded, if it failed.
+    error: Option<String>,
+    cache: RefCell<TileCache>,
+    inbox: Inbox,
+}
+
+impl Default for Tiles {
+    fn default() -> Self {
+        Self {
+            template: None,
+            attribution: None,
+            max_zoom: TILE_DEFAULT_MAX_ZOOM,
+            generation: 0,
+            error: None,
+            cache: RefCell::new(TileCache::default()),
+            inbox: Inbox::default(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Tiles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tiles")
+            .field("template", &self.template)
+            .field("max_zoom", &self.max_zoom)
+            .field("tiles", &self.cache.borrow().tiles.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Tiles {
+    /// Set the URL template of the tiles, or `None` for no basemap, discarding the tiles of
+    /// any previous source.
+    pub fn set_source(&mut self, template: Option<String>) {
+        self.template = template;
+        self.generation = self.generation.wrapping_add(1);
+        self.error = None;
+        let cache = self.cache.get_mut();
+        cache.tiles.clear();
+        cache.requested.clear();
+    }
+
+    /// Decode the tiles fetched since last received and take those decoded into the cache,
+    /// returning whether any tiles were taken.
+    pub fn receive(&mut self, fetcher: &Fetcher) -> Result<bool, JsValue> {
+        let mut received = false;
+        for (url, body) in fetcher.receive(TILE_CLIENT) {
+            let cache = self.cache.get_mut();
+            let Some(key) = cache.requested.remove(&url) else {
+                continue;
+            };
+            let Some(cached) = cache.tiles.get_mut(&key) else {
+                continue;
+            };
+            match body {
+                Ok(body) => {
+                    cached.tile = Tile::Decoding;
+                    self.decode(key, &body)?;
+                }
+                Err(message) => {
+                    cached.tile = Tile::Failed;
+                    self.error = Some(message);
+                    received = true;
+                }
+            }
+        }
+
+        let loaded = std::mem::take(&mut *self.inbox.borrow_mut());
+        let cache = self.cache.get_mut();
+        for (generation, key, pixels) in loaded {
+            if generation != self.generation {
+                continue;
+            }
+            if let Some(cached) = cache.tiles.get_mut(&key) {
+                self.error = match pixels {
+                    Some(_) => None,
+                    None => Some(format!(
+                        "failed to decode tile {}/{}/{}",
+                        key.z, key.x, key.y
+                    )),
+                };
+                cached.tile = pixels.map_or(Tile::Failed, Tile::Ready);
+                received = true;
+            }
+        }
+        Ok(received)
+    }
+
+    /// Status of the tiles: failed when the tile last loaded failed, or else loading while
+    /// tiles in view are loading.
+    pub fn status(&self) -> LayerStatus {
+        if self.template.is_none() {
+            return LayerStatus::Ready;
+        }
+        if let Some(message) = &self.error {
+            return LayerStatus::Error(message.clone());
+        }
+        if self.loading() {
+            LayerStatus::Loading
+        } else {
+            LayerStatus::Ready
+        }
+    }
+
+    /// Whether tiles are loading or decoding.
+    pub fn loading(&self) -> bool {
+        self.cache
+            .borrow()
+            .tiles
+            .values()
+            .any(|cached| matches!(cached.tile, Tile::Loading | Tile::Decoding))
+    }
+
+    /// Zoom of the tiles whose pixels best match the canvas pixels at the centre of the view.
+    fn zoom(&self, frame: &Frame) -> u8 {
+        let zoom = (std::f64::consts::TAU * frame.scale / TILE_SIZE)
+            .log2()
+            .round();
+        zoom.clamp(0.0, self.max_zoom as f64) as u8
+    }
+
+    /// Draw the tiles reprojected onto the globe, sampling each canvas pixel from the tile at
+    /// the zoom of the view or the nearest loaded tile containing it at a lower zoom, and
+    /// request the tiles missing, those covering more of the view first.
+    pub fn draw(
+        &self,
+        context: &CanvasRenderingContext2d,
+        frame: &Frame,
+        fetcher: &Fetcher,
+    ) -> Result<(), JsValue> {
+        let Some(template) = &self.template else {
+            fetcher.sweep(TILE_CLIENT);
+            return Ok(());
+        };
+        let mut cache = self.cache.borrow_mut();
+        cache.frame += 1;
+
+        // The globe is sampled over its bounds within the canvas, in canvas pixels
+        let canvas = context.canvas().expect("should have canvas");
+        let (cx, cy) = (frame.transform.e(), frame.transform.f());
+        let left = (cx - frame.scale).floor().max(0.0);
+        let top = (cy - frame.scale).floor().max(0.0);
+        let right = (cx + frame.scale)
+            .ceil()
+            .min(canvas.width() as f64 / frame.pixel_ratio);
+        let bottom = (cy + frame.scale)
+            .ceil()
+            .min(canvas.height() as f64 / frame.pixel_ratio);
+        if right <= left || bottom <= top {
+            return Ok(());
+        }
+        let (width, height) = ((right - left) as u32, (bottom - top) as u32);
+
+        let zoom = self.zoom(frame);
+        let mut data = vec![0_u8; (width * height * 4) as usize];
+        let mut used = Vec::new();
+        // Runs of neighbouring pixels in the same tile, to count the pixels of each tile
+        let mut runs: Vec<(TileKey, u32)> = Vec::new();
+        // Neighbouring pixels mostly fall in the same tile, which is found once for them
+        let mut previous: Option<(TileKey, Option<(TileKey, &Pixels)>)> = None;
+        for row in 0..height {
+            for column in 0..width {
+                let (x, y) = (left + column as f64 + 0.5, top + row as f64 + 0.5);
+                let (y, z) = controls::canvas_to_unit_coords(x, y, &frame.transform);
+                let Some(location) = frame.projection.unproject(&frame.orientation, y, z) else {
+                    continue;
+                };
+                let position = mercator(location.lon, location.lat);
+                let key = TileKey::at(zoom, position);
+                let found = match previous {
+                    Some((previous_key, found)) if previous_key == key => found,
+                    _ => {
+                        let found = find(&cache.tiles, key, position);
+                        if let Some((found_key, _)) = found {
+                            used.push(found_key);
+                        }
+                        runs.push((key, 0));
+                        previous = Some((key, found));
+                        found
+                    }
+                };
+                if let Some((_, pixels)) = runs.last_mut() {
+                    *pixels += 1;
+                }
+                if let Some((found_key, pixels)) = found {
+                    let index = ((row * width + column) * 4) as usize;
+                    data[index..index + 4].copy_from_slice(pixels.sample(found_key, position));
+                }
+            }
+        }
+
+        let (composer, composer_context) = scratch(&mut cache.composer)?;
+        composer.set_width(width);
+        composer.set_height(height);
+        let image = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&data), width, height)?;
+        composer_context.put_image_data(&image, 0.0, 0.0)?;
+        context.save();
+        overlay::canvas_pixels(context, frame.pixel_ratio)?;
+        context.draw_image_with_html_canvas_element_and_dw_and_dh(
+            &composer,
+            left,
+            top,
+            width as f64,
+            height as f64,
+        )?;
+        context.restore();
+
+        let frame_index = cache.frame;
+        for key in used {
+            if let Some(cached) = cache.tiles.get_mut(&key) {
+                cached.used = frame_index;
+            }
+        }
+        // Tiles still wanted are requested again each frame, and the others cancelled
+        let mut visible: HashMap<TileKey, u32> = HashMap::new();
+        for (key, pixels) in runs {
+            *visible.entry(key).or_default() += pixels;
+        }
+        for (&key, &pixels) in &visible {
+            let cached = cache.tiles.entry(key).or_insert(CachedTile {
+                tile: Tile::Loading,
+                used: frame_index,
+            });
+            if matches!(cached.tile, Tile::Loading) {
+                let url = key.url(template);
+                fetcher.request(TILE_CLIENT, &url, pixels as f64);
+                cache.requested.insert(url, key);
+            }
+        }
+        fetcher.sweep(TILE_CLIENT);
+        cache.tiles.retain(|key, cached| {
+            !matches!(cached.tile, Tile::Loading) || visible.contains_key(key)
+        });
+        cache.requested.retain(|_, key| visible.contains_key(key));
+        evict(&mut cache.tiles);
+
+        Ok(())
+    }
+
+    /// Decode a fetched tile into the inbox.
+    fn decode(&self, key: TileKey, body: &Body) -> Result<(), JsValue> {
+        let blob = Blob::new_with_u8_array_sequence(&js_sys::Array::of1(
+            &js_sys::Uint8Array::from(&body[..]),
+        ))?;
+        let url = Url::create_object_url_with_blob(&blob)?;
+        let image = HtmlImageElement::new()?;
+        let (decoder, decoder_context) = scratch(&mut self.cache.borrow_mut().decoder)?;
+        let inbox = self.inbox.clone();
+        let generation = self.generation;
+        let loaded = image.clone();
+        let object_url = url.clone();
+        // Called once, for either the load or the error
+        let callback = Closure::once_into_js(move |event: Event| {
+            let pixels = if event.type_() == "load" {
+                decode(&loaded, &decoder, &decoder_context).ok()
+            } else {
+                None
+            };
+            let _ = Url::revoke_object_url(&object_url);
+            inbox.borrow_mut().push((generation, key, pixels));
+        });
+        image.set_onload(Some(callback.unchecked_ref()));
+        image.set_onerror(Some(callback.unchecked_ref()));
+        image.set_src(&url);
+        Ok(())
+    }
+}
+
+/// The tile containing a position, or the nearest loaded tile containing it at a lower zoom.
+fn find(
+    tiles: &HashMap<TileKey, CachedTile>,
+    key: TileKey,
+    position: (f64, f64),
+) -> Option<(TileKey, &Pixels)> {
+    (0..=key.z).rev().find_map(|z| {
+        let key = TileKey::at(z, position);
+        match tiles.get(&key) {
+            Some(CachedTile {
+                tile: Tile::Ready(pixels),
+                ..
+            }) => Some((key, pixels)),
+            _ => None,
+        }
+    })
+}
+
+/// Remove the least recently used tiles beyond the size of the cache, keeping those loading.
+fn evict(tiles: &mut HashMap<TileKey, CachedTile>) {
+    while tiles.len() > TILE_CACHE_SIZE {
+        let Some(key) = tiles
+            .iter()
+            .filter(|(_, cached)| !matches!(cached.tile, Tile::Loading | Tile::Decoding))
+            .min_by_key(|(_, cached)| cached.used)
+            .map(|(key, _)| *key)
+        else {
+            return;
+        };
+        tiles.remove(&key);
+    }
+}
+
+/// Canvas for drawing off screen, created when first needed.
+pub fn scratch(
+    canvas: &mut Option<(HtmlCanvasElement, CanvasRenderingContext2d)>,
+) -> Result<(HtmlCanvasElement, CanvasRenderingContext2d), JsValue> {
+    if canvas.is_none() {
+        let element = window()
+            .document()
+            .expect("should have document")
+            .create_element("canvas")?
+            .dyn_into::<HtmlCanvasElement>()?;
+        let context = element
+            .get_context("2d")?
+            .expect("should have 2d context")
+            .dyn_into::<CanvasRenderingContext2d>()?;
+        *canvas = Some((element, context));
+    }
+    Ok(canvas.clone().unwrap())
+}
+
+/// Pixels of a loaded image, read back from drawing it on a canvas.
+fn decode(
+    image: &HtmlImageElement,
+    canvas: &HtmlCanvasElement,
+    context: &CanvasRenderingContext2d,
+) -> Result<Pixels, JsValue> {
+    let (width, height) = (image.natural_width(), image.natural_height());
+    if width == 0 || height == 0 {
+        return Err(JsValue::from_str("empty tile"));
+    }
+    canvas.set_width(width);
+    canvas.set_height(height);
+    context.draw_image_with_html_image_element(image, 0.0, 0.0)?;
+    let data = context
+        .get_image_data(0.0, 0.0, width as f64, height as f64)?
+        .data()
+        .0;
+    Ok(Pixels {
+        width,
+        height,
+        data,
+    })
+}
+
+/// Web Mercator coordinates, each from 0 to 1, of a location (degrees), from the west and the
+/// north.
+fn mercator(lon: f64, lat: f64) -> (f64, f64) {
+    let lat = lat.clamp(-MERCATOR_MAX_LAT, MERCATOR_MAX_LAT).to_radians();
+    let x = ((lon + 180.0) / 360.0).rem_euclid(1.0);
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / std::f64::consts::PI) / 2.0;
+    (x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locates_tiles_in_web_mercator() {
+        assert_eq!(mercator(0.0, 0.0), (0.5, 0.5));
+        let (_, y) = mercator(0.0, MERCATOR_MAX_LAT);
+        assert!(y.abs() < 1e-9);
+
+        let key = TileKey::at(2, mercator(-0.1, 51.5));
+        assert_eq!(key, TileKey { z: 2, x: 1, y: 1 });
+        assert_eq!(
+            key.url("https://tile.example/{z}/{x}/{y}.png"),
+            "https://tile.example/2/1/1.png"
+        );
+        assert_eq!(TileKey::at(1, (1.0, 1.0)), TileKey { z: 1, x: 1, y: 1 });
+    }
+}
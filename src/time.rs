@@ -0,0 +1,52 @@
+// Window of time shown by the layers with time-stamped data.
+
+use wasm_bindgen::JsValue;
+
+/// Window of time (milliseconds), including its ends.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeWindow {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl TimeWindow {
+    /// Window between times, or `None` unless the start is no later than the end.
+    pub fn new(start: f64, end: f64) -> Option<Self> {
+        if start.is_nan() || end.is_nan() || end < start {
+            return None;
+        }
+        Some(Self { start, end })
+    }
+
+    pub fn contains(&self, time: f64) -> bool {
+        (self.start..=self.end).contains(&time)
+    }
+
+    /// The window as an object with start and end properties.
+    pub fn to_object(self) -> Result<JsValue, JsValue> {
+        let object = js_sys::Object::new();
+        js_sys::Reflect::set(&object, &"start".into(), &self.start.into())?;
+        js_sys::Reflect::set(&object, &"end".into(), &self.end.into())?;
+        Ok(object.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_reversed_windows() {
+        assert_eq!(
+            TimeWindow::new(1.0, 2.0),
+            Some(TimeWindow {
+                start: 1.0,
+                end: 2.0
+            })
+        );
+        assert!(TimeWindow::new(2.0, 2.0).is_some());
+        assert_eq!(TimeWindow::new(2.0, 1.0), None);
+        assert_eq!(TimeWindow::new(f64::NAN, 1.0), None);
+        assert_eq!(TimeWindow::new(1.0, f64::NAN), None);
+    }
+}
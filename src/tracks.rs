@@ -0,0 +1,244 @@
+// Moving entities, drawn at their latest positions with fading trails of their recent history.
+
+use std::collections::VecDeque;
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::camera;
+use crate::coords::LonLat;
+use crate::measure;
+use crate::view::{Frame, Orientation, Side};
+
+const TRACK_COLOR: [u8; 3] = [0, 95, 191];
+const TRACK_LINE_WIDTH: f64 = 0.004;
+const TRACK_POSITION_FILL_STYLE: &str = "rgba(0, 95, 191, 1.0)";
+const TRACK_POSITION_STROKE_STYLE: &str = "rgba(255, 255, 255, 1.0)";
+// Sizes are in canvas pixels and times in milliseconds
+const TRACK_POSITION_RADIUS: f64 = 4.0;
+const TRACK_DEFAULT_TRAIL_LENGTH: f64 = 60_000.0;
+// Positions kept for each track, however short their history
+const TRACK_MAX_POSITIONS: usize = 1000;
+// Time over which the view closes most of the way on the followed track's heading
+const HEADING_UP_SMOOTHING: f64 = 250.0;
+
+/// Position of an entity at a time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TrackPosition {
+    time: f64,
+    location: LonLat,
+}
+
+#[derive(Debug)]
+struct Track {
+    id: String,
+    /// Positions, in time order.
+    positions: VecDeque<TrackPosition>,
+}
+
+#[derive(Debug)]
+pub struct Tracks {
+    tracks: Vec<Track>,
+    /// Time (milliseconds) of history shown as a trail behind the latest position of each track.
+    pub trail_length: f64,
+    /// Identifier of the track kept at the centre of the view with its heading up, and the
+    /// time the view last followed it.
+    heading_up: Option<(String, Option<f64>)>,
+}
+
+impl Default for Tracks {
+    fn default() -> Self {
+        Self {
+            tracks: Vec::new(),
+            trail_length: TRACK_DEFAULT_TRAIL_LENGTH,
+            heading_up: None,
+        }
+    }
+}
+
+impl Tracks {
+    /// Add a position (degrees) of an entity at a time (milliseconds), creating its track if new;
+    /// positions older than its trail are pruned. Returns whether the position is the latest
+    /// of the track.
+    pub fn add(&mut self, id: &str, time: f64, location: LonLat) -> bool {
+        let index = match self.tracks.iter().position(|track| track.id == id) {
+            Some(index) => index,
+            None => {
+                self.tracks.push(Track {
+                    id: id.to_string(),
+                    positions: VecDeque::new(),
+                });
+                self.tracks.len() - 1
+            }
+        };
+        let positions = &mut self.tracks[index].positions;
+
+        // Positions may arrive out of order
+        let at = positions.partition_point(|position| position.time <= time);
+        positions.insert(at, TrackPosition { time, location });
+        let latest = at == positions.len() - 1;
+
+        let latest_time = positions.back().map_or(time, |position| position.time);
+        while positions.len() > TRACK_MAX_POSITIONS
+            || positions
+                .front()
+                .is_some_and(|position| position.time < latest_time - self.trail_length)
+        {
+            positions.pop_front();
+        }
+        latest
+    }
+
+    /// Remove the track of an entity, returning whether it existed.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let count = self.tracks.len();
+        self.tracks.retain(|track| track.id != id);
+        self.tracks.len() != count
+    }
+
+    /// Remove all tracks.
+    pub fn clear(&mut self) {
+        self.tracks.clear();
+    }
+
+    /// Keep the track of an entity at the centre of the view with its heading pointing up, or
+    /// stop with `None`; returns whether the track exists.
+    pub fn set_heading_up(&mut self, id: Option<&str>) -> bool {
+        self.heading_up = id.map(|id| (id.to_string(), None));
+        id.is_none_or(|id| self.tracks.iter().any(|track| track.id == id))
+    }
+
+    /// Whether the view follows a track with its heading up.
+    pub fn heading_up(&self) -> bool {
+        self.heading_up.is_some()
+    }
+
+    /// Orientation with the latest position of a track at the centre and its heading, from
+    /// its previous distinct position, pointing up; `None` until the track has moved.
+    fn heading_up_orientation(&self, id: &str) -> Option<Orientation> {
+        let track = self.tracks.iter().find(|track| track.id == id)?;
+        let latest = track.positions.back()?;
+        let previous = track
+            .positions
+            .iter()
+            .rev()
+            .find(|position| position.location != latest.location)?;
+        let heading = measure::bearing(previous.location, latest.location);
+        Some(Orientation {
+            rotation: -latest.location.lon,
+            tilt: latest.location.lat,
+            // Rolling anticlockwise by the heading, clockwise from north, brings it up
+            roll: heading,
+        })
+    }
+
+    /// Orientation of the view closing on the followed track and its heading at a time
+    /// (milliseconds), or `None` when no track is followed or the view is already on it.
+    pub fn follow(&mut self, orientation: Orientation, time: f64) -> Option<Orientation> {
+        let (id, last) = self.heading_up.as_ref()?;
+        let target = self.heading_up_orientation(id)?;
+        // Smoothed over time, so the view turns evenly whatever the frame rate
+        let fraction = last.map_or(1.0, |last| {
+            1.0 - (-(time - last).max(0.0) / HEADING_UP_SMOOTHING).exp()
+        });
+        if let Some((_, last)) = &mut self.heading_up {
+            *last = Some(time);
+        }
+        let followed = camera::approach(orientation, target, fraction);
+        (followed != orientation).then_some(followed)
+    }
+
+    /// Positions of a track within the window of time shown.
+    fn shown<'a>(track: &'a Track, frame: &'a Frame) -> impl Iterator<Item = &'a TrackPosition> {
+        track
+            .positions
+            .iter()
+            .filter(|position| frame.in_time_window(position.time))
+    }
+
+    /// Draw the trails, fading with age, and the latest positions within the window of
+    /// time shown onto the canvas.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        context.set_line_width(frame.line_width(TRACK_LINE_WIDTH));
+        for track in &self.tracks {
+            let Some(latest) = Self::shown(track, frame).last() else {
+                continue;
+            };
+            let points = Self::shown(track, frame)
+                .map(|position| {
+                    (
+                        position.time,
+                        frame.project(position.location.lon, position.location.lat),
+                    )
+                })
+                .collect::<Vec<_>>();
+            for segment in points.windows(2) {
+                let ((time, prev), (_, point)) = (segment[0], segment[1]);
+                if frame.segment_side(prev, point) != Some(Side::Front) {
+                    continue;
+                }
+                let age = (latest.time - time) / self.trail_length;
+                let alpha = (1.0 - age).clamp(0.0, 1.0);
+                context.set_stroke_style_str(&format!(
+                    "rgba({}, {}, {}, {})",
+                    TRACK_COLOR[0], TRACK_COLOR[1], TRACK_COLOR[2], alpha
+                ));
+                context.begin_path();
+                context.move_to(prev.1, prev.2);
+                context.line_to(point.1, point.2);
+                context.stroke();
+            }
+        }
+
+        // Positions are a constant size on screen
+        let radius = TRACK_POSITION_RADIUS / frame.scale;
+        context.set_line_width(frame.line_width(TRACK_LINE_WIDTH));
+        context.set_fill_style_str(TRACK_POSITION_FILL_STYLE);
+        context.set_stroke_style_str(TRACK_POSITION_STROKE_STYLE);
+        for latest in self
+            .tracks
+            .iter()
+            .filter_map(|track| Self::shown(track, frame).last())
+        {
+            let (x, y, z) = frame.project(latest.location.lon, latest.location.lat);
+            if x < 0.0 {
+                continue;
+            }
+            context.begin_path();
+            context.arc(y, z, radius, 0.0, std::f64::consts::TAU)?;
+            context.fill();
+            context.stroke();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_the_heading_up() {
+        let mut tracks = Tracks::default();
+        assert!(!tracks.set_heading_up(Some("ship")));
+        tracks.add("ship", 0.0, LonLat::new(20.0, 10.0));
+        assert_eq!(tracks.follow(Orientation::default(), 0.0), None);
+
+        // Heading east along the equator
+        tracks.add("ship", 1000.0, LonLat::new(20.0, 0.0));
+        tracks.add("ship", 2000.0, LonLat::new(21.0, 0.0));
+        let orientation = tracks.follow(Orientation::default(), 0.0).unwrap();
+        assert_eq!((orientation.rotation, orientation.tilt), (-21.0, 0.0));
+        assert!((orientation.roll - 90.0).abs() < 1e-9);
+        // A location ahead is plotted straight up
+        let (x, y, z) = orientation.project(22.0, 0.0);
+        assert!(x > 0.0 && y.abs() < 1e-9 && z > 0.0);
+
+        // Later frames close part of the way on the heading
+        tracks.add("ship", 3000.0, LonLat::new(21.0, 1.0));
+        let next = tracks.follow(orientation, 100.0).unwrap();
+        assert!(next.roll < 90.0 && next.roll > 0.0);
+        assert!(tracks.set_heading_up(None) && tracks.follow(next, 200.0).is_none());
+    }
+}
@@ -0,0 +1,185 @@
+// Orientation of the globe relative to the viewer.
+
+use web_sys::DomMatrix;
+
+use crate::coords::LonLat;
+use crate::filter::LayerFilters;
+use crate::format::Locale;
+use crate::projection::Projection;
+use crate::stroke::LineWidths;
+use crate::style::GlobeStyle;
+use crate::time::TimeWindow;
+use crate::zoom::LayerZoomRanges;
+use crate::{cartesian_to_unit_spherical, unit_spherical_to_cartesian};
+
+/// Orientation of the globe (degrees).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Orientation {
+    /// Rotation about the polar axis.
+    pub rotation: f64,
+    /// Latitude at the centre of the view.
+    pub tilt: f64,
+    /// Rotation about the view axis, anticlockwise on the canvas.
+    pub roll: f64,
+}
+
+impl Orientation {
+    /// Project a location (degrees) to view coordinates; x points towards
+    /// the viewer and (y, z) are the coordinates plotted on the canvas.
+    pub fn project(&self, lon: f64, lat: f64) -> (f64, f64, f64) {
+        let (x, y, z) = unit_spherical_to_cartesian(90.0 - lat, lon + self.rotation);
+        let (sin_tilt, cos_tilt) = self.tilt.to_radians().sin_cos();
+        let (x, z) = (x * cos_tilt + z * sin_tilt, z * cos_tilt - x * sin_tilt);
+        let (sin_roll, cos_roll) = self.roll.to_radians().sin_cos();
+        (x, y * cos_roll - z * sin_roll, y * sin_roll + z * cos_roll)
+    }
+
+    /// Orientation with a location (degrees) at the centre of the view, keeping the roll.
+    pub fn centred_on(&self, location: LonLat) -> Orientation {
+        Orientation {
+            rotation: -location.lon,
+            tilt: location.lat,
+            roll: self.roll,
+        }
+    }
+
+    /// Reverse the roll of plotted coordinates, giving coordinates aligned with the globe's tilt.
+    pub fn unroll(&self, y: f64, z: f64) -> (f64, f64) {
+        let (sin_roll, cos_roll) = self.roll.to_radians().sin_cos();
+        (y * cos_roll + z * sin_roll, z * cos_roll - y * sin_roll)
+    }
+
+    /// Location on the front of the globe at plotted coordinates, or `None` when off the globe.
+    pub fn unproject(&self, y: f64, z: f64) -> Option<LonLat> {
+        if y * y + z * z > 1.0 {
+            return None;
+        }
+        let x = (1.0 - y * y - z * z).max(0.0).sqrt();
+        Some(self.location(x, y, z))
+    }
+
+    /// Location at view coordinates on the unit sphere.
+    pub fn location(&self, x: f64, y: f64, z: f64) -> LonLat {
+        let (y, z) = self.unroll(y, z);
+        let (sin_tilt, cos_tilt) = self.tilt.to_radians().sin_cos();
+        let (x, z) = (x * cos_tilt - z * sin_tilt, x * sin_tilt + z * cos_tilt);
+
+        let (theta, phi) = cartesian_to_unit_spherical(x, y, z.clamp(-1.0, 1.0));
+        // Longitude is undefined at the poles
+        let phi = if phi.is_nan() { 0.0 } else { phi };
+        LonLat::new(phi - self.rotation, 90.0 - theta).wrapped()
+    }
+}
+
+/// Side of the globe, relative to the viewer, that a feature is on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Front,
+    Back,
+    /// The feature may cross the limb.
+    Both,
+}
+
+/// View parameters for drawing a frame.
+#[derive(Debug)]
+pub struct Frame<'a> {
+    pub orientation: Orientation,
+    pub projection: Projection,
+    /// Canvas pixels per globe radius when unzoomed.
+    pub base_scale: f64,
+    /// Canvas pixels per globe radius.
+    pub scale: f64,
+    /// Transform from unit sphere coordinates to canvas pixels.
+    pub transform: DomMatrix,
+    /// Ratio of backing store pixels to canvas pixels.
+    pub pixel_ratio: f64,
+    /// Step between the points of the coastlines drawn.
+    pub geometry_stride: usize,
+    pub line_widths: &'a LineWidths,
+    pub filters: &'a LayerFilters,
+    pub zoom_ranges: &'a LayerZoomRanges,
+    /// Conventions numbers and coordinates are written with.
+    pub locale: &'a Locale,
+    pub style: &'a GlobeStyle,
+    /// Window of time shown by layers with time-stamped data, or `None` for all times.
+    pub time_window: Option<TimeWindow>,
+    /// Whether segments on the back of the globe are skipped.
+    pub cull_back: bool,
+}
+
+impl Frame<'_> {
+    /// Project a location (degrees) to view coordinates with the frame's projection.
+    pub fn project(&self, lon: f64, lat: f64) -> (f64, f64, f64) {
+        self.projection.project(&self.orientation, lon, lat)
+    }
+
+    /// Project a location (degrees) to plotted coordinates, with locations that are not visible
+    /// taken to the edge of the disc, so filled areas are clipped to the visible globe.
+    pub fn project_to_disc(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let (x, y, z) = self.project(lon, lat);
+        let r = y.hypot(z);
+        if x < 0.0 && r > 0.0 {
+            (y / r, z / r)
+        } else {
+            (y, z)
+        }
+    }
+
+    /// Whether a feature of an embedded layer passes the layer's filter.
+    pub fn shows(&self, layer: &str, feature: usize) -> bool {
+        self.filters.shows(layer, feature)
+    }
+
+    /// Zoom of the view, relative to the unzoomed view.
+    pub fn zoom(&self) -> f64 {
+        self.scale / self.base_scale
+    }
+
+    /// Whether a layer is shown at the zoom of the view.
+    pub fn shows_layer(&self, layer: &str) -> bool {
+        self.zoom_ranges.shows(layer, self.zoom())
+    }
+
+    /// Whether time-stamped data at a time (milliseconds) is within the window of time shown.
+    pub fn in_time_window(&self, time: f64) -> bool {
+        self.time_window.is_none_or(|window| window.contains(time))
+    }
+
+    /// Side a segment between projected points is drawn on, or `None` when it is not drawn.
+    pub fn segment_side(&self, prev: (f64, f64, f64), point: (f64, f64, f64)) -> Option<Side> {
+        self.projection
+            .segment_side(prev, point)
+            .filter(|side| !self.cull_back || *side != Side::Back)
+    }
+
+    /// Whether the back of the globe is drawn, as it shows through and is not culled.
+    pub fn draws_back(&self) -> bool {
+        self.projection.shows_back() && !self.cull_back
+    }
+
+    /// Opacity the coastlines on the culled back are drawn with, dimmed, where they are.
+    pub fn culled_back_opacity(&self) -> Option<f64> {
+        (self.projection.shows_back() && self.cull_back)
+            .then_some(self.style.culled_back_opacity)
+            .flatten()
+            .map(|opacity| opacity.clamp(0.0, 1.0))
+    }
+
+    /// Width, in unit sphere coordinates, to stroke a line
+    /// with a base width given for the unzoomed view.
+    pub fn line_width(&self, base: f64) -> f64 {
+        self.line_widths
+            .width(base, self.base_scale, self.scale, self.pixel_ratio)
+    }
+
+    /// Transform plotted unit sphere coordinates to canvas pixels.
+    pub fn to_canvas(&self, y: f64, z: f64) -> (f64, f64) {
+        to_canvas(&self.transform, y, z)
+    }
+}
+
+/// Transform plotted unit sphere coordinates to canvas pixels with a context transform.
+pub fn to_canvas(transform: &DomMatrix, y: f64, z: f64) -> (f64, f64) {
+    let t = transform;
+    (t.a() * y + t.c() * z + t.e(), t.b() * y + t.d() * z + t.f())
+}
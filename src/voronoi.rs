@@ -0,0 +1,254 @@
+// Spherical Voronoi diagram partitioning the globe into the regions nearest to each of a set of
+// sites, such as the service areas of facilities.
+//
+// The cell of each site is the intersection of the hemispheres nearer to it than to each other
+// site, bounded by the great circles bisecting the sites. As cells larger than a hemisphere
+// are not convex, each is built from its parts within the octants of the sphere.
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::coords::{LonLat, Vec3};
+use crate::view::{Frame, Side};
+
+// Cells cycle through the fill styles by site
+const VORONOI_FILL_STYLES: [&str; 6] = [
+    "rgba(27, 158, 119, 0.35)",
+    "rgba(217, 95, 2, 0.35)",
+    "rgba(117, 112, 179, 0.35)",
+    "rgba(231, 41, 138, 0.35)",
+    "rgba(102, 166, 30, 0.35)",
+    "rgba(230, 171, 2, 0.35)",
+];
+const VORONOI_STROKE_STYLE: &str = "rgba(63, 63, 63, 1.0)";
+const VORONOI_LINE_WIDTH: f64 = 0.003;
+// Edges are sampled every this many degrees
+const EDGE_SAMPLE_ANGLE: f64 = 2.0;
+
+/// Vertex of a convex spherical polygon, with the site on the other side of the edge
+/// from it to the next vertex, or `None` for edges between octants.
+#[derive(Clone, Copy, Debug)]
+struct Vertex {
+    point: Vec3,
+    neighbour: Option<usize>,
+}
+
+/// Part of the cell of a site within an octant of the sphere.
+#[derive(Debug)]
+struct Piece {
+    site: usize,
+    vertices: Vec<Vertex>,
+}
+
+#[derive(Debug, Default)]
+pub struct Voronoi {
+    sites: Vec<Vec3>,
+    pieces: Vec<Piece>,
+}
+
+impl Voronoi {
+    /// Set the sites, given as the latitude and longitude (degrees) of each in turn, returning
+    /// whether the number of values is even.
+    pub fn set_sites(&mut self, coordinates: &[f64]) -> bool {
+        if !coordinates.len().is_multiple_of(2) {
+            return false;
+        }
+        self.sites = coordinates
+            .chunks(2)
+            .map(|site| LonLat::new(site[1], site[0]).to_vec3())
+            .collect();
+        self.pieces = cells(&self.sites);
+        true
+    }
+
+    /// Remove all sites.
+    pub fn clear(&mut self) {
+        self.sites.clear();
+        self.pieces.clear();
+    }
+
+    /// Index of the site nearest to a location (degrees), or `None` without sites.
+    pub fn nearest_site(&self, location: LonLat) -> Option<usize> {
+        let point = location.to_vec3();
+        self.sites
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.dot(point).total_cmp(&b.dot(point)))
+            .map(|(index, _)| index)
+    }
+
+    /// Fill the cells and stroke the edges between them onto the canvas.
+    pub fn draw(&self, context: &CanvasRenderingContext2d, frame: &Frame) -> Result<(), JsValue> {
+        for piece in &self.pieces {
+            context.begin_path();
+            for (i, vertex) in piece.vertices.iter().enumerate() {
+                let next = piece.vertices[(i + 1) % piece.vertices.len()];
+                for point in arc(vertex.point, next.point) {
+                    let location = LonLat::from_vec3(point);
+                    let (y, z) = frame.project_to_disc(location.lon, location.lat);
+                    context.line_to(y, z);
+                }
+            }
+            context.close_path();
+            context.set_fill_style_str(VORONOI_FILL_STYLES[piece.site % VORONOI_FILL_STYLES.len()]);
+            context.fill();
+        }
+
+        context.set_line_width(frame.line_width(VORONOI_LINE_WIDTH));
+        context.set_stroke_style_str(VORONOI_STROKE_STYLE);
+        for piece in &self.pieces {
+            for (i, vertex) in piece.vertices.iter().enumerate() {
+                // Edges are shared by the cells either side, and stroked once
+                if vertex
+                    .neighbour
+                    .is_none_or(|neighbour| neighbour < piece.site)
+                {
+                    continue;
+                }
+                let next = piece.vertices[(i + 1) % piece.vertices.len()];
+                let points = arc(vertex.point, next.point)
+                    .chain(std::iter::once(next.point))
+                    .map(|point| {
+                        let location = LonLat::from_vec3(point);
+                        frame.project(location.lon, location.lat)
+                    })
+                    .collect::<Vec<_>>();
+                for segment in points.windows(2) {
+                    let ((_, y_prev, z_prev), (_, y, z)) = (segment[0], segment[1]);
+                    if frame.segment_side(segment[0], segment[1]) != Some(Side::Front) {
+                        continue;
+                    }
+                    context.begin_path();
+                    context.move_to(y_prev, z_prev);
+                    context.line_to(y, z);
+                    context.stroke();
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Pieces of the cells of the sites within each octant of the sphere.
+fn cells(sites: &[Vec3]) -> Vec<Piece> {
+    // A single site's cell is the whole sphere, which has no boundary to draw
+    if sites.len() < 2 {
+        return Vec::new();
+    }
+    let mut pieces = Vec::new();
+    for octant in 0..8 {
+        let sign = |bit: usize| if octant & bit == 0 { 1.0 } else { -1.0 };
+        let corners = [
+            Vec3::new(sign(1), 0.0, 0.0),
+            Vec3::new(0.0, sign(2), 0.0),
+            Vec3::new(0.0, 0.0, sign(4)),
+        ];
+        for (site, point) in sites.iter().enumerate() {
+            let mut vertices = corners
+                .iter()
+                .map(|corner| Vertex {
+                    point: *corner,
+                    neighbour: None,
+                })
+                .collect::<Vec<_>>();
+            for (other, other_point) in sites.iter().enumerate() {
+                if other == site || vertices.len() < 3 {
+                    continue;
+                }
+                vertices = clip(&vertices, *point - *other_point, other);
+            }
+            if vertices.len() >= 3 {
+                pieces.push(Piece { site, vertices });
+            }
+        }
+    }
+    pieces
+}
+
+/// Clip a convex spherical polygon to the hemisphere on the side of a great circle
+/// its plane's normal points to, labelling new edges along the circle with a neighbour.
+fn clip(vertices: &[Vertex], normal: Vec3, neighbour: usize) -> Vec<Vertex> {
+    let mut clipped = Vec::new();
+    for (i, vertex) in vertices.iter().enumerate() {
+        let next = vertices[(i + 1) % vertices.len()];
+        let (d, d_next) = (normal.dot(vertex.point), normal.dot(next.point));
+        if d >= 0.0 {
+            clipped.push(*vertex);
+        }
+        if (d >= 0.0) != (d_next >= 0.0) {
+            // Point on the great circle between the vertices
+            let point = (vertex.point * d_next.abs() + next.point * d.abs()).normalised();
+            clipped.push(Vertex {
+                point,
+                neighbour: if d >= 0.0 {
+                    Some(neighbour)
+                } else {
+                    vertex.neighbour
+                },
+            });
+        }
+    }
+    clipped
+}
+
+/// Points sampled along the great circle arc from a point to another, excluding the last.
+pub fn arc(from: Vec3, to: Vec3) -> impl Iterator<Item = Vec3> {
+    let angle = from.dot(to).clamp(-1.0, 1.0).acos().to_degrees();
+    let samples = ((angle / EDGE_SAMPLE_ANGLE).ceil() as usize).max(1);
+    (0..samples).map(move |i| {
+        let t = i as f64 / samples as f64;
+        (from * (1.0 - t) + to * t).normalised()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Area of a convex spherical polygon, from the excess of its triangles from its first vertex.
+    fn area(vertices: &[Vertex]) -> f64 {
+        let a = vertices[0].point;
+        vertices[1..]
+            .windows(2)
+            .map(|pair| {
+                let (b, c) = (pair[0].point, pair[1].point);
+                let triple = a.x * (b.y * c.z - b.z * c.y) - a.y * (b.x * c.z - b.z * c.x)
+                    + a.z * (b.x * c.y - b.y * c.x);
+                2.0 * triple.abs().atan2(1.0 + a.dot(b) + b.dot(c) + c.dot(a))
+            })
+            .sum()
+    }
+
+    #[test]
+    fn partitions_the_sphere() {
+        let mut voronoi = Voronoi::default();
+        assert!(voronoi.set_sites(&[0.0, 0.0, 40.0, 100.0, -60.0, -30.0, 80.0, -170.0]));
+        let total = voronoi
+            .pieces
+            .iter()
+            .map(|piece| area(&piece.vertices))
+            .sum::<f64>();
+        assert!((total - 4.0 * std::f64::consts::PI).abs() < 1e-6);
+    }
+
+    #[test]
+    fn splits_antipodal_sites_at_the_equator() {
+        let mut voronoi = Voronoi::default();
+        assert!(voronoi.set_sites(&[90.0, 0.0, -90.0, 0.0]));
+        let north = voronoi
+            .pieces
+            .iter()
+            .filter(|piece| piece.site == 0)
+            .map(|piece| area(&piece.vertices))
+            .sum::<f64>();
+        assert!((north - 2.0 * std::f64::consts::PI).abs() < 1e-6);
+        assert_eq!(voronoi.nearest_site(LonLat::new(50.0, 10.0)), Some(0));
+        assert_eq!(voronoi.nearest_site(LonLat::new(50.0, -10.0)), Some(1));
+    }
+
+    #[test]
+    fn rejects_odd_coordinates() {
+        assert!(!Voronoi::default().set_sites(&[0.0, 0.0, 1.0]));
+    }
+}
@@ -0,0 +1,353 @@
+// Drawing of a globe in a dedicated worker, so heavy drawing never holds up the page: the
+// page transfers the canvas to the worker and forwards the input on it as messages, and the
+// worker posts back the events of the globe and the styles of the canvas element.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    AddEventListenerOptions, CanvasRenderingContext2d, CustomEvent, CustomEventInit,
+    DedicatedWorkerGlobalScope, EventTarget, HtmlCanvasElement, MessageEvent, OffscreenCanvas,
+    PointerEvent, ResizeObserverEntry, WheelEvent, Worker,
+};
+
+use crate::attach::Attachment;
+use crate::capability::Capabilities;
+use crate::controls::Position;
+use crate::input::{PointerInput, WheelInput};
+use crate::surface::Surface;
+use crate::{performance, window, State};
+
+// Messages of the globe are objects with their kind under this key, so they can share the
+// worker's messages with those of the host application
+const MESSAGE_KEY: &str = "wasmGlobe";
+
+/// Forwarder of the input on a canvas on the page to a globe drawn on it in a worker.
+#[wasm_bindgen]
+pub struct WorkerCanvas {
+    _attachment: Attachment,
+}
+
+#[wasm_bindgen]
+impl WorkerCanvas {
+    /// Transfer a canvas to a worker, posting it as the `canvas` property of a message for
+    /// the worker to create the globe with `Globe.offscreen(canvas, options)`, and forward
+    /// the input on the canvas to the worker. The globe's events are dispatched on the
+    /// canvas as for a globe drawn on the page, though the page is not scrolled by the wheel
+    /// over the canvas, as the worker cannot answer in time. Forwarding stops when freed
+    /// with `free()`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas: HtmlCanvasElement, worker: Worker) -> Result<WorkerCanvas, JsValue> {
+        let offscreen = canvas.transfer_control_to_offscreen()?;
+        let transfer = message("canvas");
+        js_sys::Reflect::set(&transfer, &"canvas".into(), &offscreen)?;
+        worker.post_message_with_transfer(&transfer, &js_sys::Array::of1(&offscreen))?;
+
+        let document = window().document().expect("should have document");
+        let mut attachment = Attachment::default();
+        for kind in [
+            "pointerdown",
+            "pointermove",
+            "pointerleave",
+            "pointercancel",
+        ] {
+            let worker = worker.clone();
+            let closure = Closure::<dyn FnMut(_)>::new(move |event: PointerEvent| {
+                let input = PointerInput::from_event(&event);
+                worker.post_message(&pointer_message(kind, &input)).unwrap();
+            });
+            attachment.listen(&canvas, kind, closure, None)?;
+        }
+
+        {
+            let worker = worker.clone();
+            let closure = Closure::<dyn FnMut(_)>::new(move |event: PointerEvent| {
+                let input = PointerInput::from_event(&event);
+                worker
+                    .post_message(&pointer_message("pointerup", &input))
+                    .unwrap();
+            });
+            attachment.listen(&document, "pointerup", closure, None)?;
+        }
+
+        {
+            let worker = worker.clone();
+            let closure = Closure::<dyn FnMut(_)>::new(move |event: WheelEvent| {
+                event.prevent_default();
+                let input = WheelInput::from_event(&event);
+                worker.post_message(&wheel_message(&input)).unwrap();
+            });
+            // Not passive, so scrolling the page can be prevented
+            let options = AddEventListenerOptions::new();
+            options.set_passive(false);
+            attachment.listen(&canvas, "wheel", closure, Some(&options))?;
+        }
+
+        {
+            let worker = worker.clone();
+            let closure = Closure::<dyn FnMut(_)>::new(move |entries: js_sys::Array| {
+                let Ok(entry) = entries.get(0).dyn_into::<ResizeObserverEntry>() else {
+                    return;
+                };
+                let rect = entry.content_rect();
+                let size = (rect.width().round() as u32, rect.height().round() as u32);
+                worker.post_message(&resize_message(size)).unwrap();
+            });
+            attachment.observe_resize(&canvas, closure)?;
+        }
+
+        {
+            // The ratio of device pixels changes with the page's zoom, which resizes the window
+            let (worker, canvas) = (worker.clone(), canvas.clone());
+            let closure = Closure::<dyn FnMut(_)>::new(move |_event: web_sys::Event| {
+                let size = (canvas.client_width() as u32, canvas.client_height() as u32);
+                worker.post_message(&resize_message(size)).unwrap();
+            });
+            attachment.listen(&window(), "resize", closure, None)?;
+        }
+
+        {
+            let canvas = canvas.clone();
+            let closure = Closure::<dyn FnMut(_)>::new(move |event: MessageEvent| {
+                receive_from_worker(&canvas, &event.data()).unwrap();
+            });
+            attachment.listen(&worker, "message", closure, None)?;
+        }
+
+        Ok(WorkerCanvas {
+            _attachment: attachment,
+        })
+    }
+}
+
+/// Draw a globe's state on a canvas transferred to the worker, handling the input forwarded
+/// from the page and starting the loop of animation frames, which last until the attachment
+/// is dropped.
+pub fn attach(canvas: OffscreenCanvas, state: Rc<RefCell<State>>) -> Result<Attachment, JsValue> {
+    let (width, height) = (canvas.width(), canvas.height());
+    state.borrow_mut().canvas_size = (width, height);
+
+    // The offscreen context has the methods of the context of a canvas element, which are
+    // called on it by name
+    let context = canvas
+        .get_context("2d")?
+        .expect("should have 2d context")
+        .unchecked_into::<CanvasRenderingContext2d>();
+
+    let surface = Rc::new(Surface::Offscreen(canvas));
+    crate::start(
+        &context,
+        &surface,
+        &mut state.borrow_mut(),
+        Capabilities::unprobed(),
+    )?;
+
+    let mut attachment = Attachment::default();
+    {
+        let (state, surface) = (state.clone(), surface.clone());
+        let closure = Closure::<dyn FnMut(_)>::new(move |event: MessageEvent| {
+            let message = event.data();
+            match kind(&message).as_deref() {
+                Some("pointerdown") => {
+                    crate::pointer_down(&state, &surface, &pointer_input(&message));
+                }
+                Some("pointermove") => {
+                    crate::pointer_move(&state, &pointer_input(&message));
+                }
+                Some("pointerup") => {
+                    crate::pointer_up(&state, &surface, &pointer_input(&message));
+                }
+                Some("pointerleave") => crate::pointer_leave(&state),
+                Some("pointercancel") => crate::pointer_cancel(&state),
+                Some("wheel") => {
+                    crate::wheel(&state, &wheel_input(&message));
+                }
+                Some("resize") => {
+                    if let Some(ratio) = number(&message, "devicePixelRatio") {
+                        state.borrow_mut().render.set_device_pixel_ratio(ratio);
+                    }
+                    let size = (number(&message, "width"), number(&message, "height"));
+                    if let (Some(width), Some(height)) = size {
+                        crate::resize(&state, (width as u32, height as u32));
+                    }
+                }
+                _ => {}
+            }
+        });
+        let scope = js_sys::global().unchecked_into::<EventTarget>();
+        attachment.listen(&scope, "message", closure, None)?;
+    }
+
+    attachment.animate(move || crate::tick(&state, &context, &surface));
+
+    Ok(attachment)
+}
+
+/// Post an event of the globe to the page, to be dispatched on the canvas element.
+pub fn post_event(event_type: &str, detail: &JsValue) -> Result<(), JsValue> {
+    let message = message("event");
+    js_sys::Reflect::set(&message, &"type".into(), &event_type.into())?;
+    js_sys::Reflect::set(&message, &"detail".into(), detail)?;
+    post(&message)
+}
+
+/// Post a style of the canvas element to the page, removed when empty.
+pub fn post_style(property: &str, value: &str) -> Result<(), JsValue> {
+    let message = message("style");
+    js_sys::Reflect::set(&message, &"property".into(), &property.into())?;
+    js_sys::Reflect::set(&message, &"value".into(), &value.into())?;
+    post(&message)
+}
+
+fn post(message: &JsValue) -> Result<(), JsValue> {
+    js_sys::global()
+        .unchecked_into::<DedicatedWorkerGlobalScope>()
+        .post_message(message)
+}
+
+/// Apply a message from the worker to the canvas element.
+fn receive_from_worker(canvas: &HtmlCanvasElement, message: &JsValue) -> Result<(), JsValue> {
+    let field = |key: &str| js_sys::Reflect::get(message, &key.into());
+    match kind(message).as_deref() {
+        Some("event") => {
+            let init = CustomEventInit::new();
+            init.set_detail(&field("detail")?);
+            let event_type = field("type")?.as_string().unwrap_or_default();
+            let event = CustomEvent::new_with_event_init_dict(&event_type, &init)?;
+            canvas.dispatch_event(&event)?;
+        }
+        Some("style") => {
+            let property = field("property")?.as_string().unwrap_or_default();
+            match field("value")?
+                .as_string()
+                .filter(|value| !value.is_empty())
+            {
+                Some(value) => canvas.style().set_property(&property, &value)?,
+                None => {
+                    canvas.style().remove_property(&property)?;
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Message of the globe of a kind.
+fn message(kind: &str) -> js_sys::Object {
+    let message = js_sys::Object::new();
+    js_sys::Reflect::set(&message, &MESSAGE_KEY.into(), &kind.into())
+        .expect("should set property of object");
+    message
+}
+
+/// Kind of a message of the globe, or `None` for other messages.
+fn kind(message: &JsValue) -> Option<String> {
+    if !message.is_object() {
+        return None;
+    }
+    js_sys::Reflect::get(message, &MESSAGE_KEY.into())
+        .ok()?
+        .as_string()
+}
+
+fn number(message: &JsValue, key: &str) -> Option<f64> {
+    js_sys::Reflect::get(message, &key.into()).ok()?.as_f64()
+}
+
+fn set_numbers(message: &js_sys::Object, fields: &[(&str, f64)]) {
+    for (key, value) in fields {
+        js_sys::Reflect::set(message, &(*key).into(), &(*value).into())
+            .expect("should set property of object");
+    }
+}
+
+// Times are posted from the time origin of one thread's clock and read on another's, so
+// are sent as times since the Unix epoch
+
+fn to_epoch(time_stamp: f64) -> f64 {
+    performance().time_origin() + time_stamp
+}
+
+fn from_epoch(time: f64) -> f64 {
+    time - performance().time_origin()
+}
+
+fn pointer_message(kind: &str, input: &PointerInput) -> js_sys::Object {
+    let message = message(kind);
+    set_numbers(
+        &message,
+        &[
+            ("x", input.position.x),
+            ("y", input.position.y),
+            ("pointerId", input.pointer_id as f64),
+            ("time", to_epoch(input.time_stamp)),
+        ],
+    );
+    js_sys::Reflect::set(
+        &message,
+        &"pointerType".into(),
+        &input.pointer_type.as_str().into(),
+    )
+    .expect("should set property of object");
+    message
+}
+
+fn pointer_input(message: &JsValue) -> PointerInput {
+    PointerInput {
+        position: Position {
+            x: number(message, "x").unwrap_or_default(),
+            y: number(message, "y").unwrap_or_default(),
+        },
+        pointer_id: number(message, "pointerId").unwrap_or_default() as i32,
+        time_stamp: number(message, "time").map_or_else(crate::now, from_epoch),
+        pointer_type: js_sys::Reflect::get(message, &"pointerType".into())
+            .ok()
+            .and_then(|value| value.as_string())
+            .unwrap_or_default(),
+    }
+}
+
+fn wheel_message(input: &WheelInput) -> js_sys::Object {
+    let message = message("wheel");
+    set_numbers(
+        &message,
+        &[
+            ("x", input.position.x),
+            ("y", input.position.y),
+            ("deltaY", input.delta_y),
+            ("deltaMode", input.delta_mode as f64),
+            ("time", to_epoch(input.time_stamp)),
+        ],
+    );
+    message
+}
+
+fn wheel_input(message: &JsValue) -> WheelInput {
+    WheelInput {
+        position: Position {
+            x: number(message, "x").unwrap_or_default(),
+            y: number(message, "y").unwrap_or_default(),
+        },
+        delta_y: number(message, "deltaY").unwrap_or_default(),
+        delta_mode: number(message, "deltaMode").unwrap_or_default() as u32,
+        time_stamp: number(message, "time").map_or_else(crate::now, from_epoch),
+    }
+}
+
+/// Message of the size (canvas pixels) of the canvas on the page, with the ratio of device
+/// pixels to canvas pixels.
+fn resize_message((width, height): (u32, u32)) -> js_sys::Object {
+    let message = message("resize");
+    set_numbers(
+        &message,
+        &[
+            ("width", width as f64),
+            ("height", height as f64),
+            ("devicePixelRatio", window().device_pixel_ratio()),
+        ],
+    );
+    message
+}
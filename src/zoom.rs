@@ -0,0 +1,189 @@
+// Zoom of the view, and ranges of zoom over which layers are shown, so detailed layers
+// appear as the view is zoomed in and disappear as it is zoomed out.
+
+use web_sys::DomMatrix;
+
+const DEFAULT_MIN_ZOOM: f64 = 0.5;
+const DEFAULT_MAX_ZOOM: f64 = 20.0;
+// Zoom is multiplied by e for each this many pixels scrolled by the wheel
+const WHEEL_PIXELS_PER_E: f64 = 500.0;
+
+/// Layers that may be shown over a range of zoom, by the identifiers used in the API.
+pub const LAYERS: [&str; 20] = [
+    "tiles",
+    "land",
+    "graticule",
+    "rings",
+    "choropleth",
+    "cartogram",
+    "voronoi",
+    "hexbins",
+    "coastline",
+    "terminator",
+    "contours",
+    "spikes",
+    "symbols",
+    "arcs",
+    "markers",
+    "tracks",
+    "route",
+    "selection",
+    "extent",
+    "pings",
+];
+
+/// Zoom of the view, as a factor scaling the globe about the centre of the canvas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Zoom {
+    pub factor: f64,
+    /// Limits of the factor.
+    min: f64,
+    max: f64,
+}
+
+impl Default for Zoom {
+    fn default() -> Self {
+        Self {
+            factor: 1.0,
+            min: DEFAULT_MIN_ZOOM,
+            max: DEFAULT_MAX_ZOOM,
+        }
+    }
+}
+
+impl Zoom {
+    /// Set the zoom factor, within the limits, returning whether it changed.
+    pub fn set(&mut self, factor: f64) -> bool {
+        let factor = self.clamped(factor);
+        if factor.is_nan() || factor == self.factor {
+            return false;
+        }
+        self.factor = factor;
+        true
+    }
+
+    /// Zoom factor within the limits.
+    pub fn clamped(&self, factor: f64) -> f64 {
+        factor.clamp(self.min, self.max)
+    }
+
+    /// Multiply the zoom factor by a ratio, within the limits, returning whether it changed.
+    pub fn by(&mut self, ratio: f64) -> bool {
+        self.set(self.factor * ratio)
+    }
+
+    /// Zoom by scrolling the wheel a distance (pixels), in for upward scrolls, returning
+    /// whether the zoom changed.
+    pub fn wheel(&mut self, delta: f64) -> bool {
+        self.by((-delta / WHEEL_PIXELS_PER_E).exp())
+    }
+
+    /// Set the limits of the zoom factor, bringing the factor within them; returns whether
+    /// the limits are valid.
+    pub fn set_limits(&mut self, min: f64, max: f64) -> bool {
+        if min.is_nan() || min <= 0.0 || max.is_nan() || max < min {
+            return false;
+        }
+        (self.min, self.max) = (min, max);
+        self.factor = self.factor.clamp(min, max);
+        true
+    }
+
+    /// Transform from unit sphere coordinates to canvas pixels of the zoomed view, given the
+    /// transform of the unzoomed view centred on the origin of the unit sphere.
+    pub fn apply(&self, transform: &DomMatrix) -> DomMatrix {
+        let t = transform;
+        DomMatrix::new_with_array64(&mut [
+            t.a() * self.factor,
+            t.b() * self.factor,
+            t.c() * self.factor,
+            t.d() * self.factor,
+            t.e(),
+            t.f(),
+        ])
+        .expect("should create matrix")
+    }
+}
+
+/// Range of zoom, relative to the unzoomed view, over which a layer is shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ZoomRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl ZoomRange {
+    /// Whether a zoom is within the range, inclusive of its limits.
+    pub fn contains(&self, zoom: f64) -> bool {
+        self.min.is_none_or(|min| zoom >= min) && self.max.is_none_or(|max| zoom <= max)
+    }
+}
+
+/// Ranges of zoom of the layers; layers without a range are shown at any zoom.
+#[derive(Debug, Default)]
+pub struct LayerZoomRanges {
+    ranges: Vec<(&'static str, ZoomRange)>,
+}
+
+impl LayerZoomRanges {
+    /// Set the range of zoom of a layer, removing it when unlimited.
+    pub fn set(&mut self, layer: &str, range: ZoomRange) -> Result<(), String> {
+        let layer = *LAYERS
+            .iter()
+            .find(|id| **id == layer)
+            .ok_or_else(|| format!("unknown layer {:?}", layer))?;
+        self.ranges.retain(|(id, _)| *id != layer);
+        if range != ZoomRange::default() {
+            self.ranges.push((layer, range));
+        }
+        Ok(())
+    }
+
+    /// Whether a layer is shown at a zoom.
+    pub fn shows(&self, layer: &str, zoom: f64) -> bool {
+        self.ranges
+            .iter()
+            .find(|(id, _)| *id == layer)
+            .is_none_or(|(_, range)| range.contains(zoom))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_layers_within_their_ranges() {
+        let mut ranges = LayerZoomRanges::default();
+        ranges
+            .set(
+                "coastline",
+                ZoomRange {
+                    min: Some(2.0),
+                    max: None,
+                },
+            )
+            .unwrap();
+        assert!(!ranges.shows("coastline", 1.0));
+        assert!(ranges.shows("coastline", 2.0));
+        assert!(ranges.shows("graticule", 1.0));
+
+        ranges.set("coastline", ZoomRange::default()).unwrap();
+        assert!(ranges.shows("coastline", 1.0));
+        assert!(ranges.set("admin", ZoomRange::default()).is_err());
+    }
+
+    #[test]
+    fn limits_zoom() {
+        let mut zoom = Zoom::default();
+        assert!(zoom.by(2.0));
+        assert_eq!(zoom.factor, 2.0);
+        assert!(zoom.wheel(-1e6));
+        assert_eq!(zoom.factor, DEFAULT_MAX_ZOOM);
+        assert!(!zoom.wheel(-1.0));
+
+        assert!(!zoom.set_limits(2.0, 1.0));
+        assert!(zoom.set_limits(1.0, 4.0));
+        assert_eq!(zoom.factor, 4.0);
+    }
+}
@@ -0,0 +1,60 @@
+//! Property-based tests of the geometry of the globe, built with the `fuzzing` feature and run
+//! natively with `cargo test --features fuzzing`. Locations are drawn as often from the poles,
+//! the antimeridian and duplicates of their neighbours as from anywhere on the globe.
+#![cfg(all(feature = "fuzzing", not(target_arch = "wasm32")))]
+
+use proptest::prelude::*;
+use proptest::sample::select;
+use wasm_globe::fuzzing::{self, PROJECTIONS, SPECIAL_LATITUDES, SPECIAL_LONGITUDES};
+
+fn longitude() -> impl Strategy<Value = f64> {
+    prop_oneof![select(SPECIAL_LONGITUDES.to_vec()), -180.0..=180.0]
+}
+
+fn latitude() -> impl Strategy<Value = f64> {
+    prop_oneof![select(SPECIAL_LATITUDES.to_vec()), -90.0..=90.0]
+}
+
+fn location() -> impl Strategy<Value = (f64, f64)> {
+    (longitude(), latitude())
+}
+
+/// Rings with some points repeated.
+fn ring() -> impl Strategy<Value = Vec<(f64, f64)>> {
+    prop::collection::vec((location(), 1..3_usize), 0..16).prop_map(|points| {
+        points
+            .into_iter()
+            .flat_map(|(point, count)| std::iter::repeat_n(point, count))
+            .collect()
+    })
+}
+
+proptest! {
+    #[test]
+    fn clipped_rings_stay_on_the_disc(ring in ring(), (lon, lat) in location()) {
+        fuzzing::clip_ring(&ring, fuzzing::location(lon, lat));
+    }
+
+    #[test]
+    fn thinned_polylines_keep_their_ends(polyline in ring(), stride in 0..8_usize) {
+        fuzzing::thin(&polyline, stride);
+    }
+
+    #[test]
+    fn projections_invert(
+        kind in select(PROJECTIONS.to_vec()),
+        (centre_lon, centre_lat) in location(),
+        (lon, lat) in location(),
+    ) {
+        fuzzing::project(
+            kind,
+            fuzzing::location(centre_lon, centre_lat),
+            fuzzing::location(lon, lat),
+        );
+    }
+
+    #[test]
+    fn arbitrary_bytes_are_handled(data in prop::collection::vec(any::<u8>(), 0..256)) {
+        fuzzing::fuzz_geometry(&data);
+    }
+}
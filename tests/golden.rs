@@ -0,0 +1,91 @@
+//! Golden-image regression tests of rendering, built with the `testing` feature.
+//!
+//! The server renderer is tested natively with `cargo test --features testing`, and setting
+//! `UPDATE_GOLDEN=1` writes its renderings as the golden images instead. Drawing in the browser
+//! is tested with `wasm-pack test --headless --firefox -- --features testing`; renderings
+//! without golden images are logged as data URLs, to be saved under `tests/golden`.
+#![cfg(feature = "testing")]
+
+use wasm_globe::testing::{self, Tolerance, GOLDEN_VIEWS};
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn server_renderings_match_golden_images() {
+    let directory = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut failures = Vec::new();
+    for view in GOLDEN_VIEWS {
+        let name = view.static_name();
+        let actual = testing::render_static(view);
+        let path = directory.join(format!("{}.png", name));
+        if update {
+            std::fs::write(&path, actual.to_png()).unwrap();
+            continue;
+        }
+        match std::fs::read(&path)
+            .ok()
+            .and_then(|png| testing::Pixels::from_png(&png))
+        {
+            Some(golden) => {
+                if let Err(mismatch) = testing::compare(&actual, &golden, Tolerance::default()) {
+                    failures.push(format!("{}: {}", name, mismatch));
+                }
+            }
+            None => failures.push(format!("{}: no golden image", name)),
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[cfg(target_arch = "wasm32")]
+mod browser {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn drawings_match_golden_images() {
+        let mut failures = Vec::new();
+        for view in GOLDEN_VIEWS {
+            let actual = testing::render(view).unwrap();
+            match testing::golden(view.name) {
+                Some(golden) => {
+                    if let Err(mismatch) = testing::compare(&actual, &golden, Tolerance::default())
+                    {
+                        failures.push(format!("{}: {}", view.name, mismatch));
+                    }
+                }
+                None => {
+                    console_log!(
+                        "{}.png: data:image/png;base64,{}",
+                        view.name,
+                        base64(&actual.to_png())
+                    );
+                    failures.push(format!("{}: no golden image", view.name));
+                }
+            }
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    /// Standard base64 encoding of bytes, for data URLs.
+    fn base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8; 64] =
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut encoded = String::new();
+        for chunk in bytes.chunks(3) {
+            let value = chunk.iter().enumerate().fold(0_u32, |value, (i, byte)| {
+                value | (*byte as u32) << (16 - 8 * i)
+            });
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    encoded.push(ALPHABET[(value >> (18 - 6 * i) & 63) as usize] as char);
+                } else {
+                    encoded.push('=');
+                }
+            }
+        }
+        encoded
+    }
+}